@@ -1,6 +1,5 @@
-use fotos_core::{run_import_pipeline, PhotoCoreConfig, PhotoIndex};
+use fotos_core::{run_import_pipeline, ImportOptions, PhotoCoreConfig, PhotoIndex};
 use std::fs;
-use std::path::PathBuf;
 
 fn main() {
     let temp_dir = std::env::temp_dir().join("fotos_demo");
@@ -29,7 +28,7 @@ fn main() {
     };
 
     println!("Starting import pipeline...");
-    let result = run_import_pipeline(src_dir.to_string_lossy().to_string(), index.clone(), config).expect("Pipeline failed");
+    let result = run_import_pipeline(src_dir.to_string_lossy().to_string(), index.clone(), config, ImportOptions::default()).expect("Pipeline failed");
 
     println!("Import Results:");
     println!("  Success: {}", result.success);
@@ -1,7 +1,105 @@
 use serde::{Serialize, Deserialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
 pub struct PhotoCoreConfig {
     pub thumbnail_dir: String,
     pub thumbnail_size: u32,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct ImportOptions {
+    /// Only scan and report what an import would do (`ImportResult::plan`); nothing is written.
+    pub dry_run: bool,
+    /// Re-import photos that were deliberately deleted from the library (skipped by default)
+    pub reimport_deleted: bool,
+    /// Threads reading metadata, hashing and thumbnailing in parallel; 0 or 1
+    /// imports one file at a time. Index inserts stay batched either way.
+    pub concurrency: u32,
+    /// Only files with these extensions (any case, no dot), e.g. the RAW
+    /// ones; empty imports every supported format
+    #[uniffi(default = [])]
+    pub extensions: Vec<String>,
+    /// Look in subfolders too (the default); off imports only the folder's own files
+    #[uniffi(default = true)]
+    pub recursive: bool,
+    /// Only photos taken at or after this time, in local seconds since 1970
+    /// like `PhotoMetadata::taken_at`. Photos without a date are left out
+    /// when either bound is set.
+    #[uniffi(default = None)]
+    pub taken_after: Option<i64>,
+    /// Only photos taken before this time (exclusive), as `taken_after`
+    #[uniffi(default = None)]
+    pub taken_before: Option<i64>,
+    /// Only files of at least this many bytes, to leave out icons and the like
+    #[uniffi(default = 0)]
+    pub min_file_size: u64,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            dry_run: false,
+            reimport_deleted: false,
+            concurrency: 0,
+            extensions: Vec::new(),
+            recursive: true,
+            taken_after: None,
+            taken_before: None,
+            min_file_size: 0,
+        }
+    }
+}
+
+impl ImportOptions {
+    /// Whether a scanned file of `size` bytes passes the extension and size filters.
+    pub fn accepts_file(&self, path: &std::path::Path, size: u64) -> bool {
+        if size < self.min_file_size {
+            return false;
+        }
+        self.extensions.is_empty()
+            || path.extension().and_then(|e| e.to_str()).is_some_and(|extension| {
+                self.extensions.iter().any(|wanted| wanted.trim_start_matches('.').eq_ignore_ascii_case(extension))
+            })
+    }
+
+    /// Whether a photo taken at `taken_at` passes the date range.
+    pub fn accepts_date(&self, taken_at: Option<i64>) -> bool {
+        if self.taken_after.is_none() && self.taken_before.is_none() {
+            return true;
+        }
+        taken_at.is_some_and(|t| self.taken_after.is_none_or(|after| t >= after) && self.taken_before.is_none_or(|before| t < before))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_import_filters_accept() {
+        let raw_2023 = ImportOptions {
+            extensions: vec!["cr3".to_string(), ".NEF".to_string()],
+            taken_after: Some(1_672_531_200),
+            taken_before: Some(1_704_067_200),
+            min_file_size: 100,
+            ..Default::default()
+        };
+        assert!(raw_2023.accepts_file(Path::new("/a/IMG_1.CR3"), 100));
+        assert!(raw_2023.accepts_file(Path::new("/a/DSC_1.nef"), 5000));
+        assert!(!raw_2023.accepts_file(Path::new("/a/IMG_1.jpg"), 5000));
+        assert!(!raw_2023.accepts_file(Path::new("/a/IMG_1.cr3"), 99));
+
+        assert!(raw_2023.accepts_date(Some(1_672_531_200)));
+        assert!(!raw_2023.accepts_date(Some(1_704_067_200)));
+        assert!(!raw_2023.accepts_date(None));
+
+        let everything = ImportOptions::default();
+        assert!(everything.recursive);
+        assert!(everything.accepts_file(Path::new("/a/README"), 0));
+        assert!(everything.accepts_date(None));
+    }
+}
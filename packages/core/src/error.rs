@@ -1,3 +1,8 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
 #[derive(Debug, thiserror::Error, uniffi::Error)]
 pub enum CoreError {
     #[error("IO error: {0}")]
@@ -8,11 +13,26 @@ pub enum CoreError {
     Database(String),
     #[error("Invalid input: {0}")]
     InvalidInput(String),
+    #[error("Not found: {0}")]
+    NotFound(String),
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+    #[error("Disk full: {0}")]
+    StorageFull(String),
+    /// The thumbnail or preview cache can't be written to (see `check_cache_dir`)
+    #[error("Cache unavailable: {0}")]
+    CacheUnavailable(String),
 }
 
 impl From<std::io::Error> for CoreError {
     fn from(err: std::io::Error) -> Self {
-        CoreError::Io(err.to_string())
+        use std::io::ErrorKind;
+        match err.kind() {
+            ErrorKind::NotFound => CoreError::NotFound(err.to_string()),
+            ErrorKind::PermissionDenied | ErrorKind::ReadOnlyFilesystem => CoreError::PermissionDenied(err.to_string()),
+            ErrorKind::StorageFull | ErrorKind::QuotaExceeded => CoreError::StorageFull(err.to_string()),
+            _ => CoreError::Io(err.to_string()),
+        }
     }
 }
 
@@ -21,3 +41,72 @@ impl From<rusqlite::Error> for CoreError {
         CoreError::Database(err.to_string())
     }
 }
+
+/// An error as the user should see it: a stable message key plus parameters,
+/// so frontends can show "Disk full while copying IMG_2012.CR3" in the user's
+/// language instead of raw IO jargon.
+///
+/// Keys: `error.not_found`, `error.permission_denied`, `error.storage_full`,
+/// `error.decode`, `error.database`, `error.invalid_input`, `error.io`,
+/// `error.cache_unavailable`.
+/// Parameters: `file` (file name only, when known) and `detail` (the
+/// untranslated technical message, for the curious and for bug reports).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct UserMessage {
+    pub key: String,
+    pub params: HashMap<String, String>,
+}
+
+impl CoreError {
+    /// Presentable form of this error. `file` is the photo or file being
+    /// worked on, if the caller knows it.
+    pub fn user_message(&self, file: Option<&Path>) -> UserMessage {
+        let (key, detail) = match self {
+            CoreError::NotFound(detail) => ("error.not_found", Some(detail)),
+            CoreError::PermissionDenied(detail) => ("error.permission_denied", Some(detail)),
+            CoreError::StorageFull(detail) => ("error.storage_full", Some(detail)),
+            CoreError::ImageDecode => ("error.decode", None),
+            CoreError::Database(detail) => ("error.database", Some(detail)),
+            CoreError::InvalidInput(detail) => ("error.invalid_input", Some(detail)),
+            CoreError::Io(detail) => ("error.io", Some(detail)),
+            CoreError::CacheUnavailable(detail) => ("error.cache_unavailable", Some(detail)),
+        };
+        let mut params = HashMap::new();
+        if let Some(name) = file.and_then(|f| f.file_name()) {
+            params.insert("file".to_string(), name.to_string_lossy().into_owned());
+        }
+        if let Some(detail) = detail {
+            params.insert("detail".to_string(), detail.clone());
+        }
+        UserMessage { key: key.to_string(), params }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_io_errors_keep_their_kind() {
+        let err = CoreError::from(std::io::Error::from(std::io::ErrorKind::StorageFull));
+        assert!(matches!(err, CoreError::StorageFull(_)));
+        let err = CoreError::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert!(matches!(err, CoreError::NotFound(_)));
+        let err = CoreError::from(std::io::Error::from(std::io::ErrorKind::UnexpectedEof));
+        assert!(matches!(err, CoreError::Io(_)));
+    }
+
+    #[test]
+    fn test_user_message() {
+        let err = CoreError::StorageFull("No space left on device".to_string());
+        let message = err.user_message(Some(Path::new("/Volumes/Card/DCIM/IMG_2012.CR3")));
+        assert_eq!(message.key, "error.storage_full");
+        assert_eq!(message.params.get("file").map(String::as_str), Some("IMG_2012.CR3"));
+        assert_eq!(message.params.get("detail").map(String::as_str), Some("No space left on device"));
+
+        let message = CoreError::ImageDecode.user_message(None);
+        assert_eq!(message.key, "error.decode");
+        assert!(message.params.is_empty());
+    }
+}
@@ -0,0 +1,111 @@
+//! The file extensions imported as photos: a built-in set the decoders are
+//! known to handle, plus extras the user opts into (e.g. "heic" or "jxl").
+//! Scanning, sniffing and file pickers all go by `supported_extensions`.
+
+use std::path::Path;
+use std::sync::RwLock;
+
+use crate::error::CoreError;
+
+/// RAW formats, read through their embedded previews
+pub const RAW_EXTENSIONS: &[&str] = &[
+    "cr2", "cr3", "nef", "nrw", "arw", "srf", "sr2", "dng", "raf", "orf", "rw2", "pef", "raw",
+];
+
+/// Video formats, imported alongside photos (see `metadata::video`)
+pub const VIDEO_EXTENSIONS: &[&str] = &["mp4", "mov", "avi", "mkv"];
+
+/// Formats always imported, RAW ones and videos included
+pub const DEFAULT_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "webp", "tif", "tiff",
+    "cr2", "cr3", "nef", "nrw", "arw", "srf", "sr2", "dng", "raf", "orf", "rw2", "pef", "raw",
+    "mp4", "mov", "avi", "mkv",
+];
+
+/// Extensions added with `set_extra_extensions`, for the whole process
+static EXTRA_EXTENSIONS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Imports files with `extensions` too, on top of `DEFAULT_EXTENSIONS`,
+/// replacing the extras set before. Extensions are matched without case and
+/// may be given with a leading dot. Apps restore the user's choice at launch.
+/// Returns the full list, as `supported_extensions`.
+#[uniffi::export]
+pub fn set_extra_extensions(extensions: Vec<String>) -> Result<Vec<String>, CoreError> {
+    let mut extra = Vec::new();
+    for extension in extensions {
+        let extension = extension.trim().trim_start_matches('.').to_lowercase();
+        if extension.is_empty() || extension.len() > 10 || !extension.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(CoreError::InvalidInput(format!("Not a file extension: {:?}", extension)));
+        }
+        if !DEFAULT_EXTENSIONS.contains(&extension.as_str()) && !extra.contains(&extension) {
+            extra.push(extension);
+        }
+    }
+    *EXTRA_EXTENSIONS.write().unwrap_or_else(|e| e.into_inner()) = extra;
+    Ok(supported_extensions())
+}
+
+/// Every extension imported as a photo: the defaults, then the user's extras.
+#[uniffi::export]
+pub fn supported_extensions() -> Vec<String> {
+    let mut extensions: Vec<String> = DEFAULT_EXTENSIONS.iter().map(|e| e.to_string()).collect();
+    if let Ok(extra) = EXTRA_EXTENSIONS.read() {
+        extensions.extend(extra.iter().cloned());
+    }
+    extensions
+}
+
+/// Whether files with `extension` (any case, no dot) are imported.
+pub fn is_supported_extension(extension: &str) -> bool {
+    let extension = extension.to_lowercase();
+    DEFAULT_EXTENSIONS.contains(&extension.as_str())
+        || EXTRA_EXTENSIONS.read().is_ok_and(|extra| extra.contains(&extension))
+}
+
+/// Whether the file extension is one the importer handles.
+pub fn is_supported_image(path: &Path) -> bool {
+    path.extension().and_then(|s| s.to_str()).is_some_and(is_supported_extension)
+}
+
+/// Whether the file is a RAW format, going by its extension.
+pub fn is_raw_extension(path: &Path) -> bool {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .is_some_and(|e| RAW_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+/// Whether the file is a video, going by its extension.
+pub fn is_video_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .is_some_and(|e| VIDEO_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extra_extensions() {
+        assert!(is_supported_image(Path::new("/a/IMG_1.JPG")));
+        assert!(is_supported_image(Path::new("/a/IMG_1.cr3")));
+        assert!(is_supported_image(Path::new("/a/IMG_1.MOV")));
+        assert!(is_video_file(Path::new("/a/IMG_1.MOV")) && !is_video_file(Path::new("/a/IMG_1.jpg")));
+        assert!(!is_supported_image(Path::new("/a/scan.zzimg")));
+        assert!(!is_supported_image(Path::new("/a/README")));
+
+        let all = set_extra_extensions(vec![".ZZIMG".to_string(), "jpg".to_string(), " zzimg ".to_string()]).unwrap();
+        assert_eq!(all.len(), DEFAULT_EXTENSIONS.len() + 1);
+        assert_eq!(all.last().map(String::as_str), Some("zzimg"));
+        assert!(is_supported_image(Path::new("/a/scan.ZzImg")));
+
+        assert!(set_extra_extensions(vec!["tar.gz".to_string()]).is_err());
+        assert!(set_extra_extensions(vec![String::new()]).is_err());
+        // A rejected list leaves the extras as they were
+        assert!(is_supported_extension("zzimg"));
+
+        set_extra_extensions(Vec::new()).unwrap();
+        assert!(!is_supported_image(Path::new("/a/scan.zzimg")));
+        assert_eq!(supported_extensions().len(), DEFAULT_EXTENSIONS.len());
+    }
+}
@@ -1,3 +1,11 @@
+pub mod formats;
+pub mod path;
 pub mod scan;
+pub mod temp;
+pub mod writable;
 
-pub use scan::scan_photos;
+pub use path::{encode_path, decode_path};
+pub use formats::{is_supported_image, is_supported_extension, supported_extensions, set_extra_extensions};
+pub use temp::{TempManager, TempJob};
+pub use writable::check_cache_dir;
+pub use scan::{scan_photos, scan_photos_with, scan_photos_counted, count_unsupported, file_stats};
@@ -0,0 +1,99 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// Marks an index path that had to be escaped. Indexed paths are absolute,
+/// so a real UTF-8 path starting with it is rare - and is escaped too, which
+/// keeps the encoding reversible.
+const ESCAPED: char = '%';
+
+/// Index form of a path: the path itself when it is valid UTF-8, which is
+/// almost always; otherwise `%` followed by the percent-encoded path bytes.
+/// Lets files with non-UTF-8 names be imported instead of skipped.
+pub fn encode_path(path: &Path) -> String {
+    if let Some(text) = path.to_str() {
+        if !text.starts_with(ESCAPED) {
+            return text.to_string();
+        }
+    }
+    let mut encoded = String::from(ESCAPED);
+    for &byte in path.as_os_str().as_encoded_bytes() {
+        if byte.is_ascii_graphic() && byte != b'%' || byte == b' ' {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Inverse of `encode_path`. Strings that aren't valid escapes are taken as
+/// plain paths.
+pub fn decode_path(encoded: &str) -> PathBuf {
+    encoded
+        .strip_prefix(ESCAPED)
+        .and_then(percent_decode)
+        .and_then(os_string_from_bytes)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(encoded))
+}
+
+fn percent_decode(text: &str) -> Option<Vec<u8>> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    Some(decoded)
+}
+
+/// Unix paths are arbitrary bytes. Elsewhere OS strings can't be rebuilt
+/// from bytes safely, so only UTF-8 is accepted.
+#[cfg(unix)]
+fn os_string_from_bytes(bytes: Vec<u8>) -> Option<OsString> {
+    use std::os::unix::ffi::OsStringExt;
+    Some(OsString::from_vec(bytes))
+}
+
+#[cfg(not(unix))]
+fn os_string_from_bytes(bytes: Vec<u8>) -> Option<OsString> {
+    String::from_utf8(bytes).ok().map(OsString::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utf8_paths_are_stored_as_is() {
+        let path = Path::new("/Photos/2024/Fête d'été.jpg");
+        assert_eq!(encode_path(path), "/Photos/2024/Fête d'été.jpg");
+        assert_eq!(decode_path("/Photos/2024/Fête d'été.jpg"), path);
+    }
+
+    #[test]
+    fn test_leading_escape_round_trips() {
+        let path = Path::new("%20 odd.jpg");
+        let encoded = encode_path(path);
+        assert_eq!(encoded, "%%2520 odd.jpg");
+        assert_eq!(decode_path(&encoded), path);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_non_utf8_round_trip() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = Path::new(std::ffi::OsStr::from_bytes(b"/scans/caf\xe9 100%.jpg"));
+        let encoded = encode_path(path);
+        assert_eq!(encoded, "%/scans/caf%E9 100%25.jpg");
+        assert_eq!(decode_path(&encoded), path);
+    }
+}
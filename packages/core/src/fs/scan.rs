@@ -1,7 +1,9 @@
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
+use crate::config::ImportOptions;
 use crate::error::CoreError;
+use super::formats::is_supported_image;
 
 /// Scans the given directory for supported photo files.
 /// 
@@ -15,35 +17,73 @@ use crate::error::CoreError;
 /// - Be mindful of the frequency of calls in low-memory environments.
 /// - Future versions may provide an iterator-based or paged implementation.
 pub fn scan_photos(root: &Path) -> Result<Vec<PathBuf>, CoreError> {
+    scan_photos_with(root, &ImportOptions::default())
+}
+
+/// `scan_photos`, keeping only files that pass the extension, size and
+/// subfolder filters of `options`. The date range needs each photo's
+/// metadata, so it's left to the import.
+pub fn scan_photos_with(root: &Path, options: &ImportOptions) -> Result<Vec<PathBuf>, CoreError> {
+    Ok(walk_photos(root, options, false).0)
+}
+
+/// `scan_photos_with`, also counting the photos its filters left out
+/// (those in subfolders included), in the same walk.
+pub fn scan_photos_counted(root: &Path, options: &ImportOptions) -> Result<(Vec<PathBuf>, u32), CoreError> {
+    Ok(walk_photos(root, options, true))
+}
+
+fn walk_photos(root: &Path, options: &ImportOptions, count_filtered: bool) -> (Vec<PathBuf>, u32) {
     let mut result = Vec::new();
+    let mut filtered = 0;
+    // Subfolders are only walked into for the count when they're left out
+    let walk = if options.recursive || count_filtered { WalkDir::new(root) } else { WalkDir::new(root).max_depth(1) };
 
-    for entry in WalkDir::new(root).into_iter().filter_map(Result::ok) {
+    for entry in walk.into_iter().filter_map(Result::ok) {
         let path = entry.path();
 
         if path.is_file() && is_supported_image(path) {
             // Stability filters
             if let Ok(metadata) = entry.metadata() {
-                if metadata.len() > 0 {
+                if metadata.len() == 0 {
+                    continue;
+                }
+                if (options.recursive || entry.depth() <= 1) && options.accepts_file(path, metadata.len()) {
                     result.push(path.to_path_buf());
+                } else {
+                    filtered += 1;
                 }
             }
         }
     }
 
-    Ok(result)
+    (result, filtered)
 }
 
-fn is_supported_image(path: &Path) -> bool {
-    matches!(
-        path.extension()
-            .and_then(|s| s.to_str())
-            .map(|s| s.to_lowercase())
-            .as_deref(),
-        Some("jpg" | "jpeg" | "png" | "webp" |
-             // RAW formats
-             "cr2" | "cr3" | "nef" | "nrw" | "arw" | "srf" | "sr2" |
-             "dng" | "raf" | "orf" | "rw2" | "pef" | "raw")
-    )
+/// Size and modification time (Unix milliseconds) of a file, as rescans
+/// compare them to tell whether it changed. `None` if it can't be read.
+pub fn file_stats(path: &Path) -> Option<(u64, i64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?;
+    let millis = match modified.duration_since(std::time::UNIX_EPOCH) {
+        Ok(since) => since.as_millis() as i64,
+        Err(before) => -(before.duration().as_millis() as i64),
+    };
+    Some((metadata.len(), millis))
+}
+
+/// Counts files under `root` that `scan_photos` skips for their format (hidden files excluded).
+pub fn count_unsupported(root: &Path) -> Result<u32, CoreError> {
+    let count = WalkDir::new(root)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .filter(|entry| !is_supported_image(entry.path()))
+        // Takeout's per-photo JSON is read along with the photo
+        .filter(|entry| !crate::metadata::takeout::is_sidecar(entry.path()))
+        .count();
+    Ok(count as u32)
 }
 
 #[cfg(test)]
@@ -76,6 +116,11 @@ mod tests {
         assert!(results.iter().any(|p| p.ends_with("valid.jpg")));
         assert!(!results.iter().any(|p| p.ends_with("zero.png")));
         assert!(!results.iter().any(|p| p.ends_with("doc.txt")));
+        assert_eq!(count_unsupported(&temp_dir).unwrap(), 1);
+
+        // Left out by the size filter; the empty file isn't a photo at all
+        let too_small = ImportOptions { min_file_size: 100, ..Default::default() };
+        assert_eq!(scan_photos_counted(&temp_dir, &too_small).unwrap(), (Vec::new(), 1));
 
         fs::remove_dir_all(&temp_dir).unwrap();
     }
@@ -0,0 +1,195 @@
+//! Scratch space for work that writes files before they're ready, e.g. a
+//! preview being rendered or a download in progress.
+//!
+//! Each job gets its own directory under one temp root inside the app's
+//! cache directory, the one place every platform's sandbox lets the app
+//! write freely. A job's directory goes when the job is dropped, whether it
+//! finished or failed, and whatever a crash left behind goes the next time
+//! the manager is opened. Finished files are moved into place with
+//! `TempJob::persist`, so readers never see them half-written.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::error::CoreError;
+
+/// Allocates per-job temp directories under one root, see the module docs.
+#[derive(uniffi::Object)]
+pub struct TempManager {
+    root: PathBuf,
+    next: AtomicU64,
+    /// Directories of the jobs not dropped yet
+    active: Arc<Mutex<BTreeSet<PathBuf>>>,
+    /// Leftovers of earlier runs removed when opened
+    swept: u32,
+}
+
+/// A job's temp directory, removed with everything in it when dropped.
+#[derive(uniffi::Object)]
+pub struct TempJob {
+    dir: PathBuf,
+    active: Arc<Mutex<BTreeSet<PathBuf>>>,
+}
+
+#[uniffi::export]
+impl TempManager {
+    /// Opens the temp root at `root` (e.g. `<cache dir>/tmp`), creating it,
+    /// and clears out jobs an earlier run of the app left behind.
+    #[uniffi::constructor]
+    pub fn new(root: String) -> Result<Arc<Self>, CoreError> {
+        Ok(Arc::new(Self::open(Path::new(&root))?))
+    }
+
+    /// A new directory for a job; `label` (e.g. "raw-preview") only makes
+    /// it easier to recognize.
+    pub fn begin_job(&self, label: String) -> Result<Arc<TempJob>, CoreError> {
+        self.job(&label).map(Arc::new)
+    }
+
+    /// Directories of the jobs in progress, e.g. for diagnostics
+    pub fn active_jobs(&self) -> Vec<String> {
+        self.active.lock().map(|active| active.iter().map(|dir| dir.to_string_lossy().into_owned()).collect()).unwrap_or_default()
+    }
+
+    /// How many leftovers of earlier runs were cleared out when the root
+    /// was opened, a sign the app didn't shut down cleanly.
+    pub fn swept(&self) -> u32 {
+        self.swept
+    }
+}
+
+impl TempManager {
+    pub fn open(root: &Path) -> Result<Self, CoreError> {
+        std::fs::create_dir_all(root)?;
+        // Jobs of this process are named after it; anything else is left
+        // over from an earlier run
+        let own = job_prefix();
+        let mut swept = 0;
+        for entry in std::fs::read_dir(root)?.filter_map(Result::ok) {
+            if entry.file_name().to_string_lossy().starts_with(&own) {
+                continue;
+            }
+            let path = entry.path();
+            let removed = if path.is_dir() { std::fs::remove_dir_all(&path) } else { std::fs::remove_file(&path) };
+            swept += removed.is_ok() as u32;
+        }
+        Ok(Self { root: root.to_path_buf(), next: AtomicU64::new(0), active: Arc::default(), swept })
+    }
+
+    pub fn job(&self, label: &str) -> Result<TempJob, CoreError> {
+        let label: String = label.chars().filter(|c| c.is_ascii_alphanumeric() || *c == '-').take(32).collect();
+        let dir = self.root.join(format!("{}{}-{}", job_prefix(), self.next.fetch_add(1, Ordering::Relaxed), label));
+        std::fs::create_dir_all(&dir)?;
+        if let Ok(mut active) = self.active.lock() {
+            active.insert(dir.clone());
+        }
+        Ok(TempJob { dir, active: self.active.clone() })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+#[uniffi::export]
+impl TempJob {
+    /// The job's directory
+    pub fn dir(&self) -> String {
+        self.dir.to_string_lossy().into_owned()
+    }
+
+    /// Path for a file named `name` in the job's directory (not created).
+    pub fn file(&self, name: String) -> String {
+        self.path(&name).to_string_lossy().into_owned()
+    }
+
+    /// Moves the job's file `name` to `dest`, replacing what's there.
+    pub fn persist_file(&self, name: String, dest: String) -> Result<(), CoreError> {
+        self.persist(&name, Path::new(&dest))
+    }
+}
+
+impl TempJob {
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    pub fn persist(&self, name: &str, dest: &Path) -> Result<(), CoreError> {
+        let source = self.path(name);
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        if std::fs::rename(&source, dest).is_ok() {
+            return Ok(());
+        }
+        // The cache may be on another volume than `dest`: copy next to
+        // it first, so the last step is still a rename
+        let staged = dest.with_file_name(format!(".{}.partial", dest.file_name().unwrap_or_default().to_string_lossy()));
+        std::fs::copy(&source, &staged)?;
+        std::fs::rename(&staged, dest).inspect_err(|_| {
+            let _ = std::fs::remove_file(&staged);
+        })?;
+        Ok(())
+    }
+}
+
+impl Drop for TempJob {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+        if let Ok(mut active) = self.active.lock() {
+            active.remove(&self.dir);
+        }
+    }
+}
+
+/// Start of this process's job directory names
+fn job_prefix() -> String {
+    format!("{}-", std::process::id())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_temp_jobs_are_cleaned_up() {
+        let root = std::env::temp_dir().join("fotos_temp_manager_test");
+        if root.exists() { fs::remove_dir_all(&root).unwrap(); }
+        // Left by a crashed run
+        fs::create_dir_all(root.join("1-0-preview")).unwrap();
+        fs::write(root.join("1-0-preview").join("half.jpg"), b"partial").unwrap();
+
+        let temp = TempManager::open(&root).unwrap();
+        assert_eq!(fs::read_dir(&root).unwrap().count(), 0);
+        assert_eq!(temp.swept(), 1);
+
+        let dest = root.join("out").join("done.jpg");
+        let job = temp.job("raw preview/../x").unwrap();
+        assert!(job.dir.starts_with(&root) && job.dir.file_name().unwrap().to_string_lossy().ends_with("-rawpreviewx"));
+        fs::write(job.path("preview.jpg"), b"finished").unwrap();
+        job.persist("preview.jpg", &dest).unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), b"finished");
+        assert_eq!(temp.active_jobs().len(), 1);
+
+        // A failed job is cleaned up as well
+        let failed = temp.job("download").unwrap();
+        fs::write(failed.path("tile.png"), b"half").unwrap();
+        let dirs = [job.dir.clone(), failed.dir.clone()];
+        drop(job);
+        drop(failed);
+        assert!(dirs.iter().all(|dir| !dir.exists()));
+        assert!(temp.active_jobs().is_empty());
+
+        // A job in progress survives another manager opening the root
+        let running = temp.job("running").unwrap();
+        // Only `out`, which isn't a job, is swept
+        assert_eq!(TempManager::open(&root).unwrap().swept(), 1);
+        assert!(running.dir.exists());
+
+        drop(running);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,75 @@
+//! Whether thumbnails and previews can be cached where the app wants them.
+//!
+//! A cache on a read-only or full volume otherwise only shows up as every
+//! thumbnail failing to encode. Apps check their cache directories when the
+//! library is opened and switch to a fallback location, or tell the user,
+//! instead.
+
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+
+use crate::types::{CacheProblem, CacheUnavailable};
+
+/// Bytes written by the probe; enough to need a free block on most volumes
+const PROBE_SIZE: usize = 64 * 1024;
+
+/// Checks that `dir` exists or can be created, and takes writes. `None`
+/// means it's usable.
+#[uniffi::export]
+pub fn check_cache_dir(dir: String) -> Option<CacheUnavailable> {
+    let dir = Path::new(&dir);
+    probe(dir).err().map(|e| CacheUnavailable {
+        path: dir.to_string_lossy().into_owned(),
+        problem: cache_problem(e.kind()).unwrap_or(CacheProblem::Unusable),
+        detail: e.to_string(),
+    })
+}
+
+fn probe(dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let file = dir.join(format!(".write-test-{}", std::process::id()));
+    let written = std::fs::File::create(&file).and_then(|mut f| {
+        f.write_all(&[0; PROBE_SIZE])?;
+        f.sync_all()
+    });
+    let _ = std::fs::remove_file(&file);
+    written
+}
+
+/// The cache problem an IO error while writing a cache file points to, if any.
+pub(crate) fn cache_problem(kind: ErrorKind) -> Option<CacheProblem> {
+    match kind {
+        ErrorKind::ReadOnlyFilesystem | ErrorKind::PermissionDenied => Some(CacheProblem::ReadOnly),
+        ErrorKind::StorageFull | ErrorKind::QuotaExceeded => Some(CacheProblem::Full),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_check_cache_dir() {
+        let root = std::env::temp_dir().join("fotos_writable_test");
+        if root.exists() { fs::remove_dir_all(&root).unwrap(); }
+
+        // Created when missing, and left without the probe file
+        let cache = root.join("cache").join("thumbnails");
+        assert_eq!(check_cache_dir(cache.to_string_lossy().into_owned()), None);
+        assert_eq!(fs::read_dir(&cache).unwrap().count(), 0);
+
+        // Where a file is in the way, as when a drive's mount point is gone
+        fs::write(root.join("unplugged"), b"").unwrap();
+        let unusable = check_cache_dir(root.join("unplugged").join("cache").to_string_lossy().into_owned()).unwrap();
+        assert_eq!(unusable.problem, CacheProblem::Unusable);
+        assert!(unusable.path.ends_with("cache") && !unusable.detail.is_empty());
+
+        assert_eq!(cache_problem(ErrorKind::ReadOnlyFilesystem), Some(CacheProblem::ReadOnly));
+        assert_eq!(cache_problem(ErrorKind::StorageFull), Some(CacheProblem::Full));
+        assert_eq!(cache_problem(ErrorKind::InvalidData), None);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
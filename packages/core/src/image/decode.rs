@@ -1,4 +1,5 @@
 use std::path::Path;
+use image::DynamicImage;
 use crate::error::CoreError;
 
 pub struct ImageDimensions {
@@ -6,8 +7,17 @@ pub struct ImageDimensions {
     pub height: u32,
 }
 
+/// Decodes the image at `path`, TIFFs included, which the `image` crate
+/// isn't built to read.
+pub fn open_image(path: &Path) -> Result<DynamicImage, CoreError> {
+    if super::tiff::is_tiff_file(path) {
+        return super::tiff::decode(path);
+    }
+    image::open(path).map_err(|_| CoreError::ImageDecode)
+}
+
 pub fn get_dimensions(path: &Path) -> Result<ImageDimensions, CoreError> {
-    let img = image::open(path).map_err(|_| CoreError::ImageDecode)?;
+    let img = open_image(path)?;
     let (width, height) = image::GenericImageView::dimensions(&img);
     Ok(ImageDimensions { width, height })
 }
@@ -0,0 +1,231 @@
+use std::path::Path;
+use image::{DynamicImage, RgbImage};
+use serde::{Deserialize, Serialize};
+use crate::error::CoreError;
+use super::thumbnail::{apply_orientation_to_image, apply_rotation, extract_raw_preview, is_raw_file, read_exif_orientation};
+
+/// Auto-enhance adjustments computed from an image.
+/// Pure data so callers can store it (see `PhotoIndex::set_enhance`), toggle
+/// it, or re-apply it later.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct AutoEnhance {
+    /// Luminance level mapped to black (0-255)
+    pub black_point: u8,
+    /// Luminance level mapped to white (0-255)
+    pub white_point: u8,
+    /// Gray-world white balance gains
+    pub gain_r: f32,
+    pub gain_g: f32,
+    pub gain_b: f32,
+    /// Saturation multiplier (1.0 = unchanged)
+    pub saturation: f32,
+}
+
+impl Default for AutoEnhance {
+    fn default() -> Self {
+        Self { black_point: 0, white_point: 255, gain_r: 1.0, gain_g: 1.0, gain_b: 1.0, saturation: 1.0 }
+    }
+}
+
+/// Analysis is done on a downscaled copy - histogram statistics don't need full resolution
+const ANALYSIS_SIZE: u32 = 256;
+/// Longest side of a rendered preview, enough for the viewer on a large display
+const PREVIEW_SIZE: u32 = 2048;
+/// Fraction of pixels clipped at each end when picking levels
+const CLIP_FRACTION: f64 = 0.005;
+/// Keep white balance corrections mild so we never produce a strong color cast
+const MAX_GAIN: f32 = 1.5;
+const MIN_GAIN: f32 = 1.0 / MAX_GAIN;
+/// Saturation boost applied to flat images, scaled down for already colorful ones
+const MAX_SATURATION_BOOST: f32 = 0.15;
+
+/// Computes auto levels, gray-world white balance and a mild saturation boost.
+/// RAW files are analysed from their embedded preview.
+pub fn compute_auto_enhance(path: &Path) -> Result<AutoEnhance, CoreError> {
+    let img = load_preview_image(path, ANALYSIS_SIZE)?;
+    Ok(analyze(&img.to_rgb8()))
+}
+
+/// Renders `source` with the given adjustments into a JPEG preview at `dest`,
+/// upright like its thumbnail: the EXIF orientation and then the user's
+/// `rotation` (degrees clockwise) are applied.
+pub fn apply_auto_enhance(source: &Path, dest: &Path, enhance: &AutoEnhance, rotation: u32) -> Result<(), CoreError> {
+    let img = load_preview_image(source, PREVIEW_SIZE)?;
+    let img = match read_exif_orientation(source) {
+        Some(orientation) if orientation > 1 => apply_orientation_to_image(img, orientation),
+        _ => img,
+    };
+    let mut img = apply_rotation(img, rotation).to_rgb8();
+    apply_to_image(&mut img, enhance);
+    img.save_with_format(dest, image::ImageFormat::Jpeg)
+        .map_err(|e| CoreError::Io(e.to_string()))
+}
+
+/// The image at `path` (a RAW's embedded preview) scaled down to fit `max_size`
+fn load_preview_image(path: &Path, max_size: u32) -> Result<DynamicImage, CoreError> {
+    let img = if is_raw_file(path) {
+        let preview = extract_raw_preview(path).map_err(|_| CoreError::ImageDecode)?;
+        image::load_from_memory(&preview).map_err(|_| CoreError::ImageDecode)?
+    } else {
+        super::decode::open_image(path)?
+    };
+    if img.width() <= max_size && img.height() <= max_size {
+        return Ok(img);
+    }
+    Ok(img.thumbnail(max_size, max_size))
+}
+
+fn analyze(img: &RgbImage) -> AutoEnhance {
+    let pixel_count = (img.width() as u64 * img.height() as u64).max(1);
+
+    let mut histogram = [0u64; 256];
+    let (mut sum_r, mut sum_g, mut sum_b) = (0u64, 0u64, 0u64);
+    let mut sum_saturation = 0f64;
+
+    for p in img.pixels() {
+        let [r, g, b] = p.0;
+        histogram[luma(r, g, b) as usize] += 1;
+        sum_r += r as u64;
+        sum_g += g as u64;
+        sum_b += b as u64;
+
+        let max = r.max(g).max(b) as f64;
+        let min = r.min(g).min(b) as f64;
+        if max > 0.0 {
+            sum_saturation += (max - min) / max;
+        }
+    }
+
+    // Auto levels: clip a small fraction of pixels at each end
+    let clip = (pixel_count as f64 * CLIP_FRACTION) as u64;
+    let mut black_point = 0u8;
+    let mut acc = 0u64;
+    for (level, count) in histogram.iter().enumerate() {
+        acc += count;
+        if acc > clip {
+            black_point = level as u8;
+            break;
+        }
+    }
+    let mut white_point = 255u8;
+    acc = 0;
+    for (level, count) in histogram.iter().enumerate().rev() {
+        acc += count;
+        if acc > clip {
+            white_point = level as u8;
+            break;
+        }
+    }
+    if white_point <= black_point {
+        // Flat image - levels would blow up, leave them alone
+        black_point = 0;
+        white_point = 255;
+    }
+
+    // Gray-world: scale each channel so the channel means match
+    let mean_r = sum_r as f32 / pixel_count as f32;
+    let mean_g = sum_g as f32 / pixel_count as f32;
+    let mean_b = sum_b as f32 / pixel_count as f32;
+    let gray = (mean_r + mean_g + mean_b) / 3.0;
+    let gain = |mean: f32| if mean > 0.0 { (gray / mean).clamp(MIN_GAIN, MAX_GAIN) } else { 1.0 };
+
+    // Mild saturation: boost flat images more than colorful ones
+    let mean_saturation = (sum_saturation / pixel_count as f64) as f32;
+    let saturation = 1.0 + MAX_SATURATION_BOOST * (1.0 - mean_saturation).clamp(0.0, 1.0);
+
+    AutoEnhance {
+        black_point,
+        white_point,
+        gain_r: gain(mean_r),
+        gain_g: gain(mean_g),
+        gain_b: gain(mean_b),
+        saturation,
+    }
+}
+
+fn apply_to_image(img: &mut RgbImage, enhance: &AutoEnhance) {
+    let black = enhance.black_point as f32;
+    let range = (enhance.white_point as f32 - black).max(1.0);
+    let gains = [enhance.gain_r, enhance.gain_g, enhance.gain_b];
+
+    for p in img.pixels_mut() {
+        let mut c = [0f32; 3];
+        for i in 0..3 {
+            let balanced = p.0[i] as f32 * gains[i];
+            c[i] = ((balanced - black) / range * 255.0).clamp(0.0, 255.0);
+        }
+
+        // Saturation around the pixel's luminance
+        let l = luma_f(c[0], c[1], c[2]);
+        for (i, v) in c.iter().enumerate() {
+            p.0[i] = (l + (v - l) * enhance.saturation).round().clamp(0.0, 255.0) as u8;
+        }
+    }
+}
+
+fn luma(r: u8, g: u8, b: u8) -> u8 {
+    luma_f(r as f32, g as f32, b as f32).round().clamp(0.0, 255.0) as u8
+}
+
+fn luma_f(r: f32, g: f32, b: f32) -> f32 {
+    0.299 * r + 0.587 * g + 0.114 * b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    #[test]
+    fn test_gray_world_corrects_color_cast() {
+        // Warm-tinted gradient: red channel too strong, blue too weak
+        let img = RgbImage::from_fn(64, 64, |x, _| {
+            let v = (x * 3) as u8;
+            Rgb([v.saturating_add(40), v, v / 2])
+        });
+
+        let enhance = analyze(&img);
+        assert!(enhance.gain_r < 1.0, "red should be reduced: {:?}", enhance);
+        assert!(enhance.gain_b > 1.0, "blue should be boosted: {:?}", enhance);
+        assert!(enhance.black_point < enhance.white_point);
+        assert!(enhance.saturation >= 1.0);
+    }
+
+    #[test]
+    fn test_flat_image_keeps_neutral_levels() {
+        let img = RgbImage::from_pixel(16, 16, Rgb([128, 128, 128]));
+        let enhance = analyze(&img);
+        assert_eq!(enhance.black_point, 0);
+        assert_eq!(enhance.white_point, 255);
+        assert!((enhance.gain_r - 1.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_apply_writes_enhanced_jpeg() {
+        let temp_dir = std::env::temp_dir().join("fotos_enhance_test");
+        if temp_dir.exists() { std::fs::remove_dir_all(&temp_dir).unwrap(); }
+        std::fs::create_dir_all(&temp_dir).unwrap();
+
+        let src = temp_dir.join("src.png");
+        let img = RgbImage::from_fn(32, 32, |x, y| Rgb([(x * 4) as u8 + 60, (y * 4) as u8 + 60, 90]));
+        img.save(&src).unwrap();
+
+        let enhance = compute_auto_enhance(&src).unwrap();
+        let dest = temp_dir.join("enhanced.jpg");
+        apply_auto_enhance(&src, &dest, &enhance, 0).unwrap();
+
+        let out = image::open(&dest).unwrap();
+        assert_eq!(out.width(), 32);
+        assert_eq!(out.height(), 32);
+
+        // Large originals are scaled down to a preview, turned like the thumbnail
+        let large = temp_dir.join("large.png");
+        RgbImage::from_pixel(PREVIEW_SIZE * 2, PREVIEW_SIZE, Rgb([90, 120, 150])).save(&large).unwrap();
+        apply_auto_enhance(&large, &dest, &enhance, 90).unwrap();
+        let out = image::open(&dest).unwrap();
+        assert_eq!((out.width(), out.height()), (PREVIEW_SIZE / 2, PREVIEW_SIZE));
+
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+    }
+}
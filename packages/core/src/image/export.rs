@@ -0,0 +1,66 @@
+use std::path::Path;
+use crate::error::CoreError;
+use super::thumbnail::{apply_orientation_to_image, apply_rotation, extract_raw_preview, is_raw_file, read_exif_orientation};
+
+/// Renders a photo as PNG bytes, upright and optionally downscaled so its
+/// longest side is at most `max_dimension` - for handing full images to
+/// other apps (clipboard, share targets) that don't understand EXIF or RAW.
+/// `rotation` is the user's rotation from the index (degrees clockwise).
+/// RAW files are rendered from their embedded preview.
+pub fn render_png(path: &Path, rotation: u32, max_dimension: Option<u32>) -> Result<Vec<u8>, CoreError> {
+    let img = if is_raw_file(path) {
+        // The RAW preview is already orientation-corrected
+        let preview = extract_raw_preview(path).map_err(|_| CoreError::ImageDecode)?;
+        image::load_from_memory(&preview).map_err(|_| CoreError::ImageDecode)?
+    } else {
+        let img = super::decode::open_image(path)?;
+        match read_exif_orientation(path) {
+            Some(orient) if orient > 1 => apply_orientation_to_image(img, orient),
+            _ => img,
+        }
+    };
+
+    let img = apply_rotation(img, rotation);
+
+    let img = match max_dimension {
+        Some(max) if img.width().max(img.height()) > max => {
+            img.resize(max, max, image::imageops::FilterType::Lanczos3)
+        }
+        _ => img,
+    };
+
+    let mut output = Vec::new();
+    img.write_to(&mut std::io::Cursor::new(&mut output), image::ImageFormat::Png)
+        .map_err(|e| CoreError::Io(e.to_string()))?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_png_downscales_large_images() {
+        let temp_dir = std::env::temp_dir().join("fotos_export_test");
+        if temp_dir.exists() { std::fs::remove_dir_all(&temp_dir).unwrap(); }
+        std::fs::create_dir_all(&temp_dir).unwrap();
+
+        let src = temp_dir.join("wide.jpg");
+        image::RgbImage::new(400, 200).save(&src).unwrap();
+
+        let png = render_png(&src, 0, Some(100)).unwrap();
+        let img = image::load_from_memory_with_format(&png, image::ImageFormat::Png).unwrap();
+        assert_eq!((img.width(), img.height()), (100, 50));
+
+        // Small enough already - left at full size
+        let png = render_png(&src, 0, Some(1000)).unwrap();
+        let img = image::load_from_memory(&png).unwrap();
+        assert_eq!((img.width(), img.height()), (400, 200));
+
+        let png = render_png(&src, 90, Some(100)).unwrap();
+        let img = image::load_from_memory(&png).unwrap();
+        assert_eq!((img.width(), img.height()), (50, 100));
+
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+    }
+}
@@ -5,7 +5,13 @@ use crate::error::CoreError;
 
 /// Compute perceptual hash of an image.
 /// Optimized to use EXIF embedded thumbnail when available (much faster for camera photos).
+/// Motion photo clips appended after the JPEG never contribute to the hash.
+/// Videos get the file-based hash: their cover art may be shared by many.
 pub fn compute_hash(path: &Path) -> Result<String, CoreError> {
+    if crate::fs::formats::is_video_file(path) {
+        return compute_file_hash(path);
+    }
+
     let hasher = HasherConfig::new()
         .hash_alg(HashAlg::Gradient)
         .hash_size(8, 8)
@@ -19,22 +25,55 @@ pub fn compute_hash(path: &Path) -> Result<String, CoreError> {
         }
     }
 
+    // TIFF scans rarely carry a thumbnail, but decode well enough to hash
+    // what they show, so they keep their identity when moved
+    if super::tiff::is_tiff_file(path) {
+        if let Ok(img) = super::tiff::decode(path) {
+            return Ok(hasher.hash_image(&img).to_base64());
+        }
+    }
+
     // For files without EXIF thumbnail (RAW or regular images),
     // use file-based hash to avoid slow full image decode.
     // File hash is sufficient for deduplication (same file = same hash).
     compute_file_hash(path)
 }
 
+/// Checksum of a file's exact bytes, for exact duplicates and for telling
+/// whether an original changed at all (see `PhotoIndex::audit_originals`).
+/// Streams the whole file.
+pub fn compute_checksum(path: &Path) -> Result<String, CoreError> {
+    let mut hasher = blake3::Hasher::new();
+    std::io::copy(&mut std::fs::File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// The 64 bits of a perceptual hash from `compute_hash`, for Hamming
+/// distances. `None` for the file-based fallback, which says nothing about
+/// what the image looks like.
+pub fn hash_bits(hash: &str) -> Option<u64> {
+    if hash.starts_with("file:") {
+        return None;
+    }
+    let hash = image_hasher::ImageHash::<Box<[u8]>>::from_base64(hash).ok()?;
+    Some(u64::from_be_bytes(hash.as_bytes().try_into().ok()?))
+}
+
 /// Compute a simple file-based hash for files that can't be decoded
 fn compute_file_hash(path: &Path) -> Result<String, CoreError> {
     use std::collections::hash_map::DefaultHasher;
     use std::hash::{Hash, Hasher};
 
     let metadata = std::fs::metadata(path).map_err(|_| CoreError::Io("metadata failed".into()))?;
-    let file_size = metadata.len();
+    // Motion photos: only the still counts, so re-muxed or trimmed clips still dedupe
+    let file_size = crate::metadata::find_motion_video_offset(path)
+        .ok()
+        .flatten()
+        .unwrap_or(metadata.len());
 
     let mut hasher = DefaultHasher::new();
-    path.to_string_lossy().hash(&mut hasher);
+    // Encoded so distinct non-UTF-8 names don't collide (UTF-8 paths hash as before)
+    crate::fs::encode_path(path).hash(&mut hasher);
     file_size.hash(&mut hasher);
 
     Ok(format!("file:{:016x}", hasher.finish()))
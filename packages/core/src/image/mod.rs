@@ -1,6 +1,14 @@
 pub mod decode;
 pub mod thumbnail;
 pub mod hash;
+pub mod enhance;
+pub mod export;
+pub mod orient;
+pub mod tiff;
 
-pub use thumbnail::{Thumbnailer, ThumbnailSpec, ThumbnailError, extract_raw_preview};
-pub use hash::compute_hash;
+pub use thumbnail::{Thumbnailer, ThumbnailSpec, ThumbnailError, extract_raw_preview, extract_raw_preview_with, PreviewQuality, RawPreview};
+pub use hash::{compute_hash, compute_checksum, hash_bits};
+pub use enhance::{AutoEnhance, compute_auto_enhance, apply_auto_enhance};
+pub use export::render_png;
+pub use orient::normalize_jpeg_orientation;
+pub use decode::open_image;
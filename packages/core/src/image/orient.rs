@@ -0,0 +1,157 @@
+use std::path::Path;
+use crate::error::CoreError;
+use super::thumbnail::{apply_orientation_to_image, read_exif_orientation};
+
+const TAG_ORIENTATION: u16 = 0x0112;
+/// Re-encode quality for normalized JPEGs - high enough that one pass is invisible
+const JPEG_QUALITY: u8 = 95;
+
+/// Rewrites a JPEG so its pixels are stored upright and its EXIF orientation is 1.
+/// The original metadata segments (EXIF, XMP, ICC, ...) are carried over; the
+/// embedded EXIF thumbnail is unlinked since it still has the old orientation.
+/// The file is replaced atomically. Returns `Ok(false)` if nothing needed doing.
+pub fn normalize_jpeg_orientation(path: &Path) -> Result<bool, CoreError> {
+    let orientation = match read_exif_orientation(path) {
+        Some(orientation) if orientation > 1 => orientation,
+        _ => return Ok(false),
+    };
+
+    let bytes = std::fs::read(path)?;
+    if !bytes.starts_with(&[0xFF, 0xD8]) {
+        return Err(CoreError::InvalidInput(format!("Not a JPEG: {}", path.display())));
+    }
+
+    let img = image::load_from_memory_with_format(&bytes, image::ImageFormat::Jpeg)
+        .map_err(|_| CoreError::ImageDecode)?;
+    let upright = apply_orientation_to_image(img, orientation).to_rgb8();
+    let mut encoded = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut encoded, JPEG_QUALITY)
+        .encode_image(&upright)
+        .map_err(|e| CoreError::Io(e.to_string()))?;
+
+    // SOI + original metadata (orientation patched) + new image data
+    let mut output = vec![0xFF, 0xD8];
+    for mut segment in metadata_segments(&bytes) {
+        if segment.len() > 10 && segment[1] == 0xE1 && &segment[4..10] == b"Exif\0\0" {
+            patch_exif(&mut segment[10..]);
+        }
+        output.extend_from_slice(&segment);
+    }
+    let image_start = 2 + metadata_segments(&encoded).iter().map(Vec::len).sum::<usize>();
+    output.extend_from_slice(&encoded[image_start..]);
+
+    let temp = path.with_extension("orient.tmp");
+    std::fs::write(&temp, &output)?;
+    std::fs::rename(&temp, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp);
+    })?;
+    Ok(true)
+}
+
+/// APPn and COM segments (marker included) that directly follow SOI.
+fn metadata_segments(jpeg: &[u8]) -> Vec<Vec<u8>> {
+    let mut segments = Vec::new();
+    let mut pos = 2;
+    while pos + 4 <= jpeg.len() && jpeg[pos] == 0xFF {
+        let marker = jpeg[pos + 1];
+        if !((0xE0..=0xEF).contains(&marker) || marker == 0xFE) {
+            break;
+        }
+        let length = u16::from_be_bytes([jpeg[pos + 2], jpeg[pos + 3]]) as usize;
+        let end = pos + 2 + length;
+        if length < 2 || end > jpeg.len() {
+            break;
+        }
+        segments.push(jpeg[pos..end].to_vec());
+        pos = end;
+    }
+    segments
+}
+
+/// In a TIFF/EXIF block: sets IFD0's Orientation to 1 and unlinks IFD1
+/// (the embedded thumbnail) so readers don't show a sideways thumbnail.
+fn patch_exif(tiff: &mut [u8]) -> Option<()> {
+    let little_endian = match tiff.get(0..2)? {
+        b"II" => true,
+        b"MM" => false,
+        _ => return None,
+    };
+    let u16_at = |data: &[u8], pos: usize| -> Option<u16> {
+        let b = data.get(pos..pos + 2)?;
+        Some(if little_endian { u16::from_le_bytes([b[0], b[1]]) } else { u16::from_be_bytes([b[0], b[1]]) })
+    };
+    let u32_at = |data: &[u8], pos: usize| -> Option<u32> {
+        let b = data.get(pos..pos + 4)?;
+        Some(if little_endian {
+            u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+        } else {
+            u32::from_be_bytes([b[0], b[1], b[2], b[3]])
+        })
+    };
+
+    let ifd0 = u32_at(tiff, 4)? as usize;
+    let count = u16_at(tiff, ifd0)? as usize;
+    for i in 0..count {
+        let entry = ifd0 + 2 + i * 12;
+        if u16_at(tiff, entry)? == TAG_ORIENTATION {
+            let one = if little_endian { 1u16.to_le_bytes() } else { 1u16.to_be_bytes() };
+            tiff.get_mut(entry + 8..entry + 10)?.copy_from_slice(&one);
+        }
+    }
+    tiff.get_mut(ifd0 + 2 + count * 12..ifd0 + 6 + count * 12)?.copy_from_slice(&[0; 4]);
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 40x20 JPEG tagged with EXIF orientation 6 (rotate 90 CW to display)
+    fn sideways_jpeg() -> Vec<u8> {
+        let mut encoded = Vec::new();
+        image::RgbImage::new(40, 20)
+            .write_to(&mut std::io::Cursor::new(&mut encoded), image::ImageFormat::Jpeg)
+            .unwrap();
+
+        let mut tiff = Vec::new();
+        tiff.extend_from_slice(b"II*\0");
+        tiff.extend_from_slice(&8u32.to_le_bytes());
+        tiff.extend_from_slice(&1u16.to_le_bytes());
+        tiff.extend_from_slice(&TAG_ORIENTATION.to_le_bytes());
+        tiff.extend_from_slice(&3u16.to_le_bytes());
+        tiff.extend_from_slice(&1u32.to_le_bytes());
+        tiff.extend_from_slice(&[6, 0, 0, 0]);
+        tiff.extend_from_slice(&0u32.to_le_bytes());
+
+        let mut app1 = vec![0xFF, 0xE1];
+        app1.extend_from_slice(&((2 + 6 + tiff.len()) as u16).to_be_bytes());
+        app1.extend_from_slice(b"Exif\0\0");
+        app1.extend_from_slice(&tiff);
+
+        let mut jpeg = vec![0xFF, 0xD8];
+        jpeg.extend_from_slice(&app1);
+        jpeg.extend_from_slice(&encoded[2..]);
+        jpeg
+    }
+
+    #[test]
+    fn test_normalize_jpeg_orientation() {
+        let temp_dir = std::env::temp_dir().join("fotos_orient_test");
+        if temp_dir.exists() { std::fs::remove_dir_all(&temp_dir).unwrap(); }
+        std::fs::create_dir_all(&temp_dir).unwrap();
+
+        let path = temp_dir.join("scan.jpg");
+        std::fs::write(&path, sideways_jpeg()).unwrap();
+        assert_eq!(read_exif_orientation(&path), Some(6));
+
+        assert!(normalize_jpeg_orientation(&path).unwrap());
+        let img = image::open(&path).unwrap();
+        assert_eq!((img.width(), img.height()), (20, 40));
+        assert_eq!(read_exif_orientation(&path), Some(1));
+
+        // Already upright - left alone
+        assert!(!normalize_jpeg_orientation(&path).unwrap());
+
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+    }
+}
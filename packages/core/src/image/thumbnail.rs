@@ -5,6 +5,8 @@ use thiserror::Error;
 pub struct ThumbnailSpec {
     pub width: u32,
     pub height: u32,
+    /// User rotation in degrees clockwise, applied after the EXIF orientation
+    pub rotation: u32,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -23,12 +25,31 @@ pub struct Thumbnailer {
 
 #[derive(Debug, Error)]
 pub enum ThumbnailError {
-    #[error("Path is not UTF-8 valid")]
-    InvalidPathEncoding,
     #[error("Image decode failed: {0}")]
     DecodeError(String),
     #[error("Image encode/save failed: {0}")]
     EncodeError(String),
+    /// The cache directory is read-only or full, so no thumbnail can be saved
+    #[error("Thumbnail cache unavailable: {0}")]
+    CacheUnavailable(String),
+}
+
+impl ThumbnailError {
+    /// Error for a failed write into the cache, telling a read-only or full
+    /// volume apart from other failures.
+    fn write(e: std::io::Error) -> Self {
+        match crate::fs::writable::cache_problem(e.kind()) {
+            Some(_) => ThumbnailError::CacheUnavailable(e.to_string()),
+            None => ThumbnailError::EncodeError(e.to_string()),
+        }
+    }
+
+    fn save(e: image::ImageError) -> Self {
+        match e {
+            image::ImageError::IoError(e) => Self::write(e),
+            e => ThumbnailError::EncodeError(e.to_string()),
+        }
+    }
 }
 
 /// Pure FNV-1a 64-bit implementation
@@ -49,7 +70,16 @@ fn fnv1a_64(bytes: &[u8], start: u64) -> u64 {
 /// Performance optimization strategy:
 /// 1. Try to extract embedded EXIF thumbnail (fastest, ~1-5ms)
 /// 2. Fall back to full image decode + resize (slower, ~50-500ms for large files)
+///
+/// The slow path only runs for formats the image crate decodes (PNGs,
+/// screenshots, TIFF scans) and only once per photo, since the result is
+/// cached. Skipping it would fail the import of every such file. RAW files
+/// without a preview are never decoded and fail fast instead.
 fn generate_image_file(source: &Path, dest: &Path, spec: &ThumbnailSpec) -> Result<(), ThumbnailError> {
+    if crate::fs::formats::is_video_file(source) {
+        return generate_video_file(source, dest, spec);
+    }
+
     // Read EXIF orientation first
     let orientation = read_exif_orientation(source);
 
@@ -69,27 +99,81 @@ fn generate_image_file(source: &Path, dest: &Path, spec: &ThumbnailSpec) -> Resu
             embedded_thumb
         };
 
+        let corrected_thumb = if !spec.rotation.is_multiple_of(360) {
+            let img = image::load_from_memory(&corrected_thumb)
+                .map_err(|e| ThumbnailError::DecodeError(format!("Failed to decode for rotation: {}", e)))?;
+            let mut output = Vec::new();
+            apply_rotation(img, spec.rotation)
+                .write_to(&mut std::io::Cursor::new(&mut output), image::ImageFormat::Jpeg)
+                .map_err(|e| ThumbnailError::EncodeError(e.to_string()))?;
+            output
+        } else {
+            corrected_thumb
+        };
+
         // Save the corrected thumbnail
-        std::fs::write(dest, corrected_thumb)
-            .map_err(|e| ThumbnailError::EncodeError(e.to_string()))?;
+        std::fs::write(dest, corrected_thumb).map_err(ThumbnailError::write)?;
         return Ok(());
     }
 
-    // No embedded thumbnail found - skip generation, use original image directly
-    // This avoids slow full image decode. Frontend will display original scaled down.
-    Err(ThumbnailError::DecodeError("No embedded thumbnail, use original".to_string()))
+    // Step 2: RAW files can't be decoded by the image crate - frontend uses original/preview
+    if is_raw_file(source) {
+        return Err(ThumbnailError::DecodeError("No embedded thumbnail, use original".to_string()));
+    }
+
+    // Step 3: Full decode + resize for regular images without an embedded thumbnail
+    let img = super::decode::open_image(source)
+        .map_err(|e| ThumbnailError::DecodeError(e.to_string()))?;
+    let img = match orientation {
+        Some(orient) if orient > 1 => apply_orientation_to_image(img, orient),
+        _ => img,
+    };
+    let img = apply_rotation(img, spec.rotation);
+    let resized = img.thumbnail(spec.width, spec.height);
+    resized.to_rgb8()
+        .save_with_format(dest, image::ImageFormat::Jpeg)
+        .map_err(ThumbnailError::save)
+}
+
+/// Shade of the stand-in thumbnail for videos without a still
+const VIDEO_PLACEHOLDER: image::Rgb<u8> = image::Rgb([38, 38, 42]);
+
+/// Thumbnail of a video from a still it carries (see `metadata::video`), or
+/// a plain frame of the video's shape, which frontends mark as a video.
+fn generate_video_file(source: &Path, dest: &Path, spec: &ThumbnailSpec) -> Result<(), ThumbnailError> {
+    let still = crate::metadata::extract_video_still(source).ok().and_then(|bytes| image::load_from_memory(&bytes).ok());
+    let img = match still {
+        Some(img) => img.thumbnail(spec.width, spec.height),
+        None => {
+            let metadata = crate::metadata::read_video_metadata(source).map_err(|e| ThumbnailError::DecodeError(e.to_string()))?;
+            let (width, height) = match metadata.orientation {
+                5..=8 => (metadata.height, metadata.width),
+                _ => (metadata.width, metadata.height),
+            };
+            let scale = if width == 0 || height == 0 {
+                1.0
+            } else {
+                (spec.width as f64 / width as f64).min(spec.height as f64 / height as f64)
+            };
+            let (width, height) = if scale < 1.0 {
+                ((width as f64 * scale).round().max(1.0) as u32, (height as f64 * scale).round().max(1.0) as u32)
+            } else if width == 0 || height == 0 {
+                (spec.width, spec.height * 9 / 16)
+            } else {
+                (width, height)
+            };
+            image::DynamicImage::ImageRgb8(image::RgbImage::from_pixel(width, height, VIDEO_PLACEHOLDER))
+        }
+    };
+    apply_rotation(img, spec.rotation)
+        .to_rgb8()
+        .save_with_format(dest, image::ImageFormat::Jpeg)
+        .map_err(ThumbnailError::save)
 }
 
 /// Check if file is a RAW format based on extension
 pub fn is_raw_file(path: &Path) -> bool {
-    matches!(
-        path.extension()
-            .and_then(|s| s.to_str())
-            .map(|s| s.to_lowercase())
-            .as_deref(),
-        Some("cr2" | "cr3" | "nef" | "nrw" | "arw" | "srf" | "sr2" |
-             "dng" | "raf" | "orf" | "rw2" | "pef" | "raw")
-    )
+    crate::fs::formats::is_raw_extension(path)
 }
 
 /// Extract the embedded JPEG preview from a RAW file.
@@ -97,30 +181,112 @@ pub fn is_raw_file(path: &Path) -> bool {
 /// Scans the entire RAW file to find the largest embedded JPEG preview by file size.
 /// Falls back to small thumbnail if no large preview is available.
 pub fn extract_raw_preview(path: &Path) -> Result<Vec<u8>, ThumbnailError> {
-    use std::io::{BufReader, Read, Seek, SeekFrom};
+    extract_raw_preview_with(path, PreviewQuality::Largest).map(|preview| preview.bytes)
+}
+
+/// Which embedded preview to take from a RAW file. RAWs usually carry a
+/// small EXIF thumbnail, sometimes a mid-size preview, and a large one; the
+/// smaller the choice, the sooner the scan can stop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PreviewQuality {
+    /// The first preview found, usually the tiny thumbnail near the header
+    Smallest,
+    /// The first preview big enough to fill a grid cell or sidebar
+    Medium,
+    /// The full-size preview
+    #[default]
+    Largest,
+}
+
+impl PreviewQuality {
+    /// Size at which a found JPEG is taken without scanning further
+    fn stop_size(self) -> u64 {
+        match self {
+            PreviewQuality::Smallest => 0,
+            PreviewQuality::Medium => MIN_GOOD_SIZE,
+            // Once we find a JPEG > 500KB, it's almost certainly the main preview
+            PreviewQuality::Largest => 500_000,
+        }
+    }
+}
+
+/// Minimum size for a "good" preview (filters out tiny thumbnails)
+const MIN_GOOD_SIZE: u64 = 50_000;
+
+/// An embedded RAW preview and its dimensions after orientation correction.
+#[derive(Debug, Clone)]
+pub struct RawPreview {
+    pub bytes: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
 
+/// Like `extract_raw_preview`, but picks the preview by `quality` and
+/// reports its dimensions.
+pub fn extract_raw_preview_with(path: &Path, quality: PreviewQuality) -> Result<RawPreview, ThumbnailError> {
     if !is_raw_file(path) {
         return Err(ThumbnailError::DecodeError("Not a RAW file".to_string()));
     }
 
     let orientation = read_exif_orientation(path);
+    let mut bytes = find_raw_preview(path, quality)?;
 
-    let file = std::fs::File::open(path)
-        .map_err(|e| ThumbnailError::DecodeError(e.to_string()))?;
-    let file_size = file.metadata()
-        .map_err(|e| ThumbnailError::DecodeError(e.to_string()))?.len();
-    let mut reader = BufReader::with_capacity(64 * 1024, file); // 64KB buffer for faster reading
+    // Apply orientation correction
+    if let Some(orient) = orientation {
+        if orient > 1 {
+            bytes = apply_orientation_correction(&bytes, orient)?;
+        }
+    }
+    let (width, height) = jpeg_dimensions(&bytes)?;
+    Ok(RawPreview { bytes, width, height })
+}
+
+/// Reads the dimensions from a JPEG's header without decoding it.
+fn jpeg_dimensions(bytes: &[u8]) -> Result<(u32, u32), ThumbnailError> {
+    image::ImageReader::with_format(std::io::Cursor::new(bytes), image::ImageFormat::Jpeg)
+        .into_dimensions()
+        .map_err(|e| ThumbnailError::DecodeError(e.to_string()))
+}
+
+/// Where RAW scans read from. A scan opens its source once and reuses the
+/// handle for every candidate JPEG marker - reopening per candidate meant
+/// hundreds of opens per file, which hurts on network storage.
+pub(crate) trait ScanSource {
+    type Reader: std::io::Read + std::io::Seek;
+
+    fn open(&self) -> std::io::Result<Self::Reader>;
+}
+
+impl ScanSource for Path {
+    type Reader = std::io::BufReader<std::fs::File>;
+
+    fn open(&self) -> std::io::Result<Self::Reader> {
+        // 64KB buffer for faster reading
+        Ok(std::io::BufReader::with_capacity(64 * 1024, std::fs::File::open(self)?))
+    }
+}
+
+/// Opens `source` for a scan, returning the reader and the file size.
+fn open_scan<S: ScanSource + ?Sized>(source: &S) -> Result<(S::Reader, u64), ThumbnailError> {
+    use std::io::{Seek, SeekFrom};
+
+    let mut reader = source.open().map_err(|e| ThumbnailError::DecodeError(e.to_string()))?;
+    let file_size = reader.seek(SeekFrom::End(0)).map_err(|e| ThumbnailError::DecodeError(e.to_string()))?;
+    Ok((reader, file_size))
+}
+
+/// Scans a RAW file for the embedded JPEG preview matching `quality`.
+fn find_raw_preview<S: ScanSource + ?Sized>(source: &S, quality: PreviewQuality) -> Result<Vec<u8>, ThumbnailError> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let (mut reader, file_size) = open_scan(source)?;
 
     let scan_start = 8u64;
     let mut best_preview: Option<Vec<u8>> = None;
     let mut best_size = 0u64;
     let mut fallback_preview: Option<Vec<u8>> = None; // Any valid JPEG as last resort
     let mut fallback_size = 0u64;
-
-    // Threshold: once we find a JPEG > 500KB, it's almost certainly the main preview
-    const GOOD_ENOUGH_SIZE: u64 = 500_000;
-    // Minimum size for "good" preview (filter out tiny thumbnails for best_preview)
-    const MIN_GOOD_SIZE: u64 = 50_000;
+    let stop_size = quality.stop_size();
 
     reader.seek(SeekFrom::Start(scan_start))
         .map_err(|e| ThumbnailError::DecodeError(e.to_string()))?;
@@ -128,7 +294,7 @@ pub fn extract_raw_preview(path: &Path) -> Result<Vec<u8>, ThumbnailError> {
     let mut pos = scan_start;
     let mut buf = [0u8; 32768]; // 32KB chunks for faster scanning
 
-    'scan: while pos < file_size - 3 {
+    'scan: while pos < file_size.saturating_sub(3) {
         let bytes_read = reader.read(&mut buf)
             .map_err(|e| ThumbnailError::DecodeError(e.to_string()))?;
         if bytes_read == 0 {
@@ -139,18 +305,19 @@ pub fn extract_raw_preview(path: &Path) -> Result<Vec<u8>, ThumbnailError> {
             if buf[i] == 0xFF && buf[i + 1] == 0xD8 && buf[i + 2] == 0xFF {
                 let jpeg_start = pos + i as u64;
 
-                if let Ok(jpeg_data) = extract_jpeg_from_offset(path, jpeg_start, file_size) {
+                if let Ok(jpeg_data) = extract_jpeg_from_offset(&mut reader, jpeg_start, file_size) {
                     let jpeg_size = jpeg_data.len() as u64;
 
+                    // If we found a large enough preview, stop scanning immediately
+                    if jpeg_size >= stop_size {
+                        best_preview = Some(jpeg_data);
+                        break 'scan;
+                    }
+
                     // Track the largest "good" preview (>50KB)
                     if jpeg_size > best_size && jpeg_size > MIN_GOOD_SIZE {
                         best_size = jpeg_size;
                         best_preview = Some(jpeg_data.clone());
-
-                        // If we found a large enough preview, stop scanning immediately
-                        if jpeg_size >= GOOD_ENOUGH_SIZE {
-                            break 'scan;
-                        }
                     }
 
                     // Also track the largest JPEG of any size as fallback
@@ -168,25 +335,15 @@ pub fn extract_raw_preview(path: &Path) -> Result<Vec<u8>, ThumbnailError> {
     }
 
     // Use best preview if available, otherwise fall back to any valid JPEG (even small thumbnail)
-    let preview_data = best_preview.or(fallback_preview);
-
-    if let Some(preview_data) = preview_data {
-        // Apply orientation correction
-        if let Some(orient) = orientation {
-            if orient > 1 {
-                return apply_orientation_correction(&preview_data, orient);
-            }
-        }
-        return Ok(preview_data);
-    }
-
-    Err(ThumbnailError::DecodeError("No embedded JPEG preview found".to_string()))
+    best_preview
+        .or(fallback_preview)
+        .ok_or_else(|| ThumbnailError::DecodeError("No embedded JPEG preview found".to_string()))
 }
 
 /// Reads EXIF orientation tag from an image file.
 /// Tries multiple IFDs (PRIMARY and THUMBNAIL) to find the tag.
 /// Optimized: reads first 256KB into memory to avoid slow disk seeks.
-fn read_exif_orientation(source: &Path) -> Option<u32> {
+pub(crate) fn read_exif_orientation(source: &Path) -> Option<u32> {
     use std::io::{BufReader, Read, Cursor};
 
     // Read first 256KB - NEF files may have orientation tag further in
@@ -230,9 +387,19 @@ fn apply_orientation_correction(image_bytes: &[u8], orientation: u32) -> Result<
     Ok(output)
 }
 
+/// Applies a user rotation (degrees clockwise, multiples of 90).
+pub(crate) fn apply_rotation(img: image::DynamicImage, degrees: u32) -> image::DynamicImage {
+    match degrees % 360 {
+        90 => img.rotate90(),
+        180 => img.rotate180(),
+        270 => img.rotate270(),
+        _ => img,
+    }
+}
+
 /// Applies EXIF orientation transformation to a DynamicImage.
 /// See: https://magnushoff.com/articles/jpeg-orientation/
-fn apply_orientation_to_image(img: image::DynamicImage, orientation: u32) -> image::DynamicImage {
+pub(crate) fn apply_orientation_to_image(img: image::DynamicImage, orientation: u32) -> image::DynamicImage {
     match orientation {
         1 => img, // Normal
         2 => img.fliph(), // Flip horizontal
@@ -372,14 +539,10 @@ fn try_extract_embedded_thumbnail(source: &Path, spec: &ThumbnailSpec) -> Result
 
 /// Try to extract embedded JPEG preview from RAW file by scanning for JPEG markers.
 /// This is a fallback when standard EXIF thumbnail tags are not found.
-fn try_extract_raw_preview(source: &Path, spec: &ThumbnailSpec) -> Result<Vec<u8>, ThumbnailError> {
-    use std::io::{BufReader, Read, Seek, SeekFrom};
+fn try_extract_raw_preview<S: ScanSource + ?Sized>(source: &S, spec: &ThumbnailSpec) -> Result<Vec<u8>, ThumbnailError> {
+    use std::io::{Read, Seek, SeekFrom};
 
-    let file = std::fs::File::open(source)
-        .map_err(|e| ThumbnailError::DecodeError(e.to_string()))?;
-    let file_size = file.metadata()
-        .map_err(|e| ThumbnailError::DecodeError(e.to_string()))?.len();
-    let mut reader = BufReader::new(file);
+    let (mut reader, file_size) = open_scan(source)?;
 
     // Skip initial bytes (TIFF header area) and scan for JPEG start marker
     // NEF files typically have the preview starting after several KB
@@ -394,7 +557,7 @@ fn try_extract_raw_preview(source: &Path, spec: &ThumbnailSpec) -> Result<Vec<u8
     let mut buf = [0u8; 4096];
 
     // Scan for JPEG start markers (0xFF 0xD8 0xFF)
-    while pos < file_size - 3 {
+    while pos < file_size.saturating_sub(3) {
         let bytes_read = reader.read(&mut buf)
             .map_err(|e| ThumbnailError::DecodeError(e.to_string()))?;
         if bytes_read == 0 {
@@ -406,7 +569,7 @@ fn try_extract_raw_preview(source: &Path, spec: &ThumbnailSpec) -> Result<Vec<u8
                 let jpeg_start = pos + i as u64;
 
                 // Try to find JPEG end marker and extract
-                if let Ok(jpeg_data) = extract_jpeg_from_offset(source, jpeg_start, file_size) {
+                if let Ok(jpeg_data) = extract_jpeg_from_offset(&mut reader, jpeg_start, file_size) {
                     let jpeg_size = jpeg_data.len() as u64;
 
                     // Keep the largest preview (usually the full-resolution one)
@@ -446,24 +609,24 @@ fn try_extract_raw_preview(source: &Path, spec: &ThumbnailSpec) -> Result<Vec<u8
     Err(ThumbnailError::DecodeError("No embedded JPEG preview found".to_string()))
 }
 
-/// Extract JPEG data from a specific offset in a file.
-/// Tries multiple potential end markers to find a valid JPEG.
-fn extract_jpeg_from_offset(source: &Path, start: u64, file_size: u64) -> Result<Vec<u8>, ThumbnailError> {
-    use std::io::{BufReader, Read, Seek, SeekFrom};
-
-    let file = std::fs::File::open(source)
-        .map_err(|e| ThumbnailError::DecodeError(e.to_string()))?;
-    let mut reader = BufReader::new(file);
+/// Extract JPEG data from a specific offset of the file `reader` is scanning.
+/// Tries multiple potential end markers to find a valid JPEG. Leaves the
+/// reader positioned anywhere; the scan seeks back itself.
+fn extract_jpeg_from_offset<R: std::io::Read + std::io::Seek>(
+    reader: &mut R,
+    start: u64,
+    file_size: u64,
+) -> Result<Vec<u8>, ThumbnailError> {
+    use std::io::{Read, SeekFrom};
 
     reader.seek(SeekFrom::Start(start))
         .map_err(|e| ThumbnailError::DecodeError(e.to_string()))?;
 
     // Read up to 20MB max for a preview image
-    let max_size = std::cmp::min(20 * 1024 * 1024, (file_size - start) as usize);
-    let mut data = vec![0u8; max_size];
-    let bytes_read = reader.read(&mut data)
+    let max_size = std::cmp::min(20 * 1024 * 1024, file_size.saturating_sub(start));
+    let mut data = Vec::new();
+    reader.take(max_size).read_to_end(&mut data)
         .map_err(|e| ThumbnailError::DecodeError(e.to_string()))?;
-    data.truncate(bytes_read);
 
     // Find ALL potential JPEG end markers (0xFF 0xD9) and try each one
     // The first false-positive end marker might be in raw sensor data
@@ -537,6 +700,47 @@ fn find_jpeg_tiff_header_offset(source: &Path) -> Result<u64, ThumbnailError> {
     Err(ThumbnailError::DecodeError("EXIF APP1 not found".to_string()))
 }
 
+/// A JPEG is valid when it ends with the EOI marker and fully decodes.
+fn is_valid_jpeg(path: &Path) -> bool {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(_) => return false,
+    };
+    bytes.starts_with(&[0xFF, 0xD8])
+        && bytes.ends_with(&[0xFF, 0xD9])
+        && image::load_from_memory_with_format(&bytes, image::ImageFormat::Jpeg).is_ok()
+}
+
+/// A temp file suffix no other call, in this process or another, is using.
+fn temp_suffix() -> String {
+    static NEXT: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let seq = NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    format!("{:x}_{:x}_{:x}", nanos, std::process::id(), seq)
+}
+
+/// Renames a finished temp file over `dest`. Concurrent writers produce the
+/// same bytes, so losing a race is fine: on Windows replacing a file that is
+/// open, or being replaced at the same moment, fails with "access denied",
+/// and then the winner's copy is kept and ours dropped.
+fn publish(temp: &Path, dest: &Path) -> Result<(), ThumbnailError> {
+    match std::fs::rename(temp, dest) {
+        Ok(()) => Ok(()),
+        Err(_) if dest.exists() => {
+            let _ = std::fs::remove_file(temp);
+            Ok(())
+        }
+        Err(e) => {
+            // Try to cleanup temp file if rename fails
+            let _ = std::fs::remove_file(temp);
+            Err(ThumbnailError::EncodeError(format!("Atomic rename failed: {}", e)))
+        }
+    }
+}
+
 /// Generates a stable, platform-independent key for a thumbnail configuration.
 /// 
 /// Normalizes path by iterating components to avoid separator differences.
@@ -546,16 +750,20 @@ pub fn thumbnail_key(source: &Path, spec: &ThumbnailSpec) -> Result<ThumbnailKey
     // 1. Hash path components to ensure "a/b" (Linux) == "a\b" (Windows)
     for component in source.components() {
         if let Component::Normal(os_str) = component {
-            let str_slice = os_str.to_str().ok_or(ThumbnailError::InvalidPathEncoding)?;
-            hash = fnv1a_64(str_slice.as_bytes(), hash);
+            // Same bytes as the UTF-8 text for valid names, so existing keys are unchanged
+            hash = fnv1a_64(os_str.as_encoded_bytes(), hash);
             // Add a separator mimic to prevent "ab/c" colliding with "a/bc"
-            hash = fnv1a_64(&[b'/'], hash); 
+            hash = fnv1a_64(b"/", hash);
         }
     }
 
     // 2. Hash spec
     hash = fnv1a_64(&spec.width.to_le_bytes(), hash);
     hash = fnv1a_64(&spec.height.to_le_bytes(), hash);
+    // Unrotated thumbnails keep the keys they had before rotation existed
+    if !spec.rotation.is_multiple_of(360) {
+        hash = fnv1a_64(&spec.rotation.to_le_bytes(), hash);
+    }
 
     Ok(ThumbnailKey(hash))
 }
@@ -576,6 +784,67 @@ impl Thumbnailer {
         Self { cache_root }
     }
 
+    /// Files left in the cache root by the legacy flat layout
+    /// (`<content hash>.jpg`, see `get_cache_path`) and stray temp files.
+    /// The current layout only writes into shard directories.
+    pub fn legacy_files(&self) -> std::io::Result<Vec<PathBuf>> {
+        let entries = match std::fs::read_dir(&self.cache_root) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut files = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                files.push(entry.path());
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// Thumbnails in the sharded layout (`<shard>/<key>.jpg`), sorted.
+    /// Temp files of thumbnails being written are left out.
+    pub fn cached_files(&self) -> std::io::Result<Vec<PathBuf>> {
+        let shards = match std::fs::read_dir(&self.cache_root) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut files = Vec::new();
+        for shard in shards {
+            let shard = shard?;
+            if !shard.file_type()?.is_dir() {
+                continue;
+            }
+            for entry in std::fs::read_dir(shard.path())? {
+                let path = entry?.path();
+                if path.extension().is_some_and(|e| e == "jpg") {
+                    files.push(path);
+                }
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// Moves a legacy flat-layout thumbnail of `source` to where the sharded
+    /// layout looks for it. Returns false, deleting the legacy file instead,
+    /// when the current cache already has one or the file is corrupt.
+    pub fn adopt_legacy(&self, legacy: &Path, source: &Path, spec: &ThumbnailSpec) -> Result<bool, ThumbnailError> {
+        let dest = cache_path(&self.cache_root, &thumbnail_key(source, spec)?);
+        if dest.exists() || !is_valid_jpeg(legacy) {
+            std::fs::remove_file(legacy).map_err(|e| ThumbnailError::EncodeError(e.to_string()))?;
+            return Ok(false);
+        }
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| ThumbnailError::EncodeError(e.to_string()))?;
+        }
+        std::fs::rename(legacy, &dest).map_err(|e| ThumbnailError::EncodeError(e.to_string()))?;
+        Ok(true)
+    }
+
     /// Legacy adapter method
     pub fn get_cache_path(&self, _content_hash: &str, _spec: &ThumbnailSpec) -> PathBuf {
        // This is strictly a fallback for now to match interface
@@ -623,44 +892,47 @@ impl Thumbnailer {
             return Ok(dest);
         }
         
-        // Ensure parent directory exists
+        // Ensure parent directory exists (create_dir_all tolerates a concurrent creator)
         if let Some(parent) = dest.parent() {
-            if !parent.exists() {
-                std::fs::create_dir_all(parent).map_err(|e| ThumbnailError::EncodeError(e.to_string()))?;
-            }
+            std::fs::create_dir_all(parent).map_err(ThumbnailError::write)?;
         }
 
-        // 2. Generate to unique temp file
-        // Use a combination of timestamp and PID to ensure uniqueness across processes/threads
-        let nanos = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_nanos())
-            .unwrap_or(0);
-        let pid = std::process::id();
-        let random_suffix = format!("{:x}_{:x}", nanos, pid);
-        
-        let temp_dest = dest.with_file_name(format!("{}.tmp.{}", 
+        // 2. Generate to a temp file unique to this call: the PID separates
+        // processes, the counter threads whose clocks read the same instant
+        let temp_dest = dest.with_file_name(format!("{}.tmp.{}",
             dest.file_name().unwrap().to_string_lossy(), // lossless conversion not needed for temp filename
-            random_suffix
+            temp_suffix()
         ));
 
         // Generate content
-        generate_image_file(source, &temp_dest, spec).map_err(|e| {
+        generate_image_file(source, &temp_dest, spec).inspect_err(|_| {
              // Cleanup temp file on failure if it was created
              let _ = std::fs::remove_file(&temp_dest);
-             e
         })?;
 
         // 3. Atomic rename
-        std::fs::rename(&temp_dest, &dest).map_err(|e| {
-             // Try to cleanup temp file if rename fails
-             let _ = std::fs::remove_file(&temp_dest);
-             ThumbnailError::EncodeError(format!("Atomic rename failed: {}", e))
-        })?;
-        
+        publish(&temp_dest, &dest)?;
         Ok(dest)
     }
 
+    /// Checks that a cached thumbnail is a complete, decodable JPEG.
+    /// Corrupt files (e.g. truncated by a crash mid-write) are removed and regenerated.
+    ///
+    /// Returns `Ok(None)` if nothing is cached, otherwise whether a repair was needed.
+    pub fn verify(&self, source: &Path, spec: &ThumbnailSpec) -> Result<Option<bool>, ThumbnailError> {
+        let path = match self.get_cached_path(source, spec)? {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        if is_valid_jpeg(&path) {
+            return Ok(Some(false));
+        }
+
+        std::fs::remove_file(&path).map_err(|e| ThumbnailError::EncodeError(e.to_string()))?;
+        self.get_or_create(source, spec)?;
+        Ok(Some(true))
+    }
+
     /// Legacy compatibility wrapper (Deprecated)
     pub fn generate(&self, source: &Path, spec: &ThumbnailSpec) -> Result<PathBuf, ThumbnailError> {
         self.get_or_create(source, spec)
@@ -684,10 +956,10 @@ mod tests {
         fs::create_dir_all(&src_dir).unwrap();
         
         let src_path = src_dir.join("test.png");
-        let mut img: RgbImage = RgbImage::new(100, 100); // 100x100 black
+        let img: RgbImage = RgbImage::new(100, 100); // 100x100 black
         img.save_with_format(&src_path, ImageFormat::Png).unwrap();
 
-        let spec = ThumbnailSpec { width: 20, height: 20 };
+        let spec = ThumbnailSpec { width: 20, height: 20, rotation: 0 };
         let thumb_path = thumbnailer.generate(&src_path, &spec).expect("Generation failed");
 
         assert!(thumb_path.exists());
@@ -699,6 +971,87 @@ mod tests {
         fs::remove_dir_all(&temp_dir).unwrap();
     }
 
+    #[test]
+    fn test_decode_fallback_skips_raw() {
+        let temp_dir = std::env::temp_dir().join("fotos_thumb_fallback_test");
+        if temp_dir.exists() { fs::remove_dir_all(&temp_dir).unwrap(); }
+        fs::create_dir_all(&temp_dir).unwrap();
+        let spec = ThumbnailSpec { width: 20, height: 20, rotation: 90 };
+
+        // No EXIF thumbnail: decoded, resized and turned
+        let png = temp_dir.join("wide.png");
+        RgbImage::new(100, 50).save_with_format(&png, ImageFormat::Png).unwrap();
+        let dest = temp_dir.join("wide.jpg");
+        generate_image_file(&png, &dest, &spec).unwrap();
+        let thumb = image::open(&dest).unwrap();
+        assert_eq!((thumb.width(), thumb.height()), (10, 20));
+
+        // A RAW without a preview isn't handed to the decoder
+        let raw = temp_dir.join("broken.dng");
+        fs::write(&raw, b"not a raw file").unwrap();
+        let dest = temp_dir.join("broken.jpg");
+        let error = generate_image_file(&raw, &dest, &spec).unwrap_err();
+        assert!(matches!(&error, ThumbnailError::DecodeError(e) if e.contains("use original")), "{}", error);
+        assert!(!dest.exists());
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[test]
+    fn test_video_without_still_gets_placeholder() {
+        let temp_dir = std::env::temp_dir().join("fotos_thumb_video_test");
+        if temp_dir.exists() { fs::remove_dir_all(&temp_dir).unwrap(); }
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        // Just the MKV header and a 1920x1080 H.264 track, which core can't decode
+        let ebml = |id: &[u8], body: &[u8]| [id, &[0x80 | body.len() as u8], body].concat();
+        let video = ebml(&[0xE0], &[ebml(&[0xB0], &[0x07, 0x80]), ebml(&[0xBA], &[0x04, 0x38])].concat());
+        let track = ebml(&[0xAE], &[ebml(&[0x83], &[1]), ebml(&[0x86], b"V_MPEG4/ISO/AVC"), video].concat());
+        let segment = ebml(&[0x18, 0x53, 0x80, 0x67], &ebml(&[0x16, 0x54, 0xAE, 0x6B], &track));
+        let src_path = temp_dir.join("clip.mkv");
+        fs::write(&src_path, [ebml(&[0x1A, 0x45, 0xDF, 0xA3], &[]), segment].concat()).unwrap();
+
+        let spec = ThumbnailSpec { width: 64, height: 64, rotation: 0 };
+        let thumb = image::open(Thumbnailer::new(temp_dir.clone()).generate(&src_path, &spec).unwrap()).unwrap();
+        assert_eq!((thumb.width(), thumb.height()), (64, 36));
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[test]
+    fn test_cache_write_errors() {
+        let full = ThumbnailError::write(std::io::Error::from(std::io::ErrorKind::StorageFull));
+        assert!(matches!(full, ThumbnailError::CacheUnavailable(_)));
+        let read_only = ThumbnailError::save(image::ImageError::IoError(std::io::ErrorKind::ReadOnlyFilesystem.into()));
+        assert!(matches!(read_only, ThumbnailError::CacheUnavailable(_)));
+        let other = ThumbnailError::write(std::io::Error::from(std::io::ErrorKind::InvalidData));
+        assert!(matches!(other, ThumbnailError::EncodeError(_)));
+    }
+
+    #[test]
+    fn test_rotated_thumbnail_has_own_cache_entry() {
+        let temp_dir = std::env::temp_dir().join("fotos_thumb_rotate_test");
+        if temp_dir.exists() { fs::remove_dir_all(&temp_dir).unwrap(); }
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        let thumbnailer = Thumbnailer::new(temp_dir.join("cache"));
+        let src_path = temp_dir.join("scan.png");
+        RgbImage::new(100, 50).save_with_format(&src_path, ImageFormat::Png).unwrap();
+
+        let upright = ThumbnailSpec { width: 40, height: 40, rotation: 0 };
+        let rotated = ThumbnailSpec { rotation: 90, ..upright };
+        let upright_path = thumbnailer.get_or_create(&src_path, &upright).unwrap();
+        let rotated_path = thumbnailer.get_or_create(&src_path, &rotated).unwrap();
+        assert_ne!(upright_path, rotated_path);
+
+        let img = image::open(&upright_path).unwrap();
+        assert_eq!((img.width(), img.height()), (40, 20));
+        let img = image::open(&rotated_path).unwrap();
+        assert_eq!((img.width(), img.height()), (20, 40));
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
     #[test]
     fn test_idempotency_and_cache_hit() {
         let temp_dir = std::env::temp_dir().join("fotos_thumb_idempotency");
@@ -710,9 +1063,9 @@ mod tests {
         fs::create_dir_all(&src_dir).unwrap();
         
         let src_path = src_dir.join("test.png");
-        let mut img: RgbImage = RgbImage::new(50, 50); 
+        let img: RgbImage = RgbImage::new(50, 50); 
         img.save_with_format(&src_path, ImageFormat::Png).unwrap();
-        let spec = ThumbnailSpec { width: 10, height: 10 };
+        let spec = ThumbnailSpec { width: 10, height: 10, rotation: 0 };
 
         let p1 = thumbnailer.get_or_create(&src_path, &spec).unwrap();
         let m1 = fs::metadata(&p1).unwrap().modified().unwrap();
@@ -732,6 +1085,157 @@ mod tests {
         fs::remove_dir_all(&temp_dir).unwrap();
     }
 
+    /// Files in the cache that aren't finished thumbnails (leftover temp files)
+    fn stray_files(cache: &Path) -> Vec<PathBuf> {
+        let mut stray = Vec::new();
+        for shard in fs::read_dir(cache).unwrap() {
+            for entry in fs::read_dir(shard.unwrap().path()).unwrap() {
+                let path = entry.unwrap().path();
+                if path.extension().is_none_or(|e| e != "jpg") {
+                    stray.push(path);
+                }
+            }
+        }
+        stray
+    }
+
+    fn stress_source(dir: &Path) -> PathBuf {
+        let src_path = dir.join("source.png");
+        if !src_path.exists() {
+            let img = RgbImage::from_fn(240, 160, |x, y| image::Rgb([x as u8, y as u8, (x ^ y) as u8]));
+            img.save_with_format(&src_path, ImageFormat::Png).unwrap();
+        }
+        src_path
+    }
+
+    /// Specs every stress worker walks through in the same order, so each
+    /// round is a fresh cache entry they all race to create.
+    fn stress_specs() -> impl Iterator<Item = ThumbnailSpec> {
+        (0..8).map(|i| ThumbnailSpec { width: 32 + i, height: 32 + i, rotation: (i % 4) * 90 })
+    }
+
+    #[test]
+    fn test_get_or_create_under_thread_contention() {
+        let temp_dir = std::env::temp_dir().join("fotos_thumb_threads");
+        if temp_dir.exists() { fs::remove_dir_all(&temp_dir).unwrap(); }
+        fs::create_dir_all(&temp_dir).unwrap();
+        let src_path = stress_source(&temp_dir);
+        let thumbnailer = Thumbnailer::new(temp_dir.join("cache"));
+
+        let barrier = std::sync::Barrier::new(8);
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    barrier.wait();
+                    for spec in stress_specs() {
+                        let path = thumbnailer.get_or_create(&src_path, &spec).unwrap();
+                        assert!(is_valid_jpeg(&path), "{} served half-written", path.display());
+                    }
+                });
+            }
+        });
+
+        assert_eq!(thumbnailer.cached_files().unwrap().len(), stress_specs().count());
+        assert!(stray_files(&temp_dir.join("cache")).is_empty());
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    /// Run by the child processes of `test_get_or_create_across_processes`
+    const STRESS_CHILD_DIR: &str = "FOTOS_THUMB_STRESS_DIR";
+
+    #[test]
+    fn test_get_or_create_across_processes() {
+        if let Some(dir) = std::env::var_os(STRESS_CHILD_DIR) {
+            let dir = PathBuf::from(dir);
+            let thumbnailer = Thumbnailer::new(dir.join("cache"));
+            for spec in stress_specs() {
+                let path = thumbnailer.get_or_create(&dir.join("source.png"), &spec).unwrap();
+                assert!(is_valid_jpeg(&path), "{} served half-written", path.display());
+            }
+            return;
+        }
+
+        let temp_dir = std::env::temp_dir().join("fotos_thumb_processes");
+        if temp_dir.exists() { fs::remove_dir_all(&temp_dir).unwrap(); }
+        fs::create_dir_all(&temp_dir).unwrap();
+        stress_source(&temp_dir);
+
+        let exe = std::env::current_exe().unwrap();
+        let children: Vec<_> = (0..6)
+            .map(|_| {
+                std::process::Command::new(&exe)
+                    .args(["--exact", "image::thumbnail::tests::test_get_or_create_across_processes", "--test-threads=1"])
+                    .env(STRESS_CHILD_DIR, &temp_dir)
+                    .stdout(std::process::Stdio::null())
+                    .spawn()
+                    .unwrap()
+            })
+            .collect();
+        for mut child in children {
+            assert!(child.wait().unwrap().success());
+        }
+
+        let thumbnailer = Thumbnailer::new(temp_dir.join("cache"));
+        assert_eq!(thumbnailer.cached_files().unwrap().len(), stress_specs().count());
+        assert!(stray_files(&temp_dir.join("cache")).is_empty());
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[test]
+    fn test_publish_over_open_thumbnail() {
+        let temp_dir = std::env::temp_dir().join("fotos_thumb_publish");
+        if temp_dir.exists() { fs::remove_dir_all(&temp_dir).unwrap(); }
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        // A reader (the webview, another process) holds the published copy
+        // open while a slower writer finishes the same thumbnail
+        let dest = temp_dir.join("thumb.jpg");
+        fs::write(&dest, b"first").unwrap();
+        let reader = fs::File::open(&dest).unwrap();
+        let temp = temp_dir.join("thumb.jpg.tmp.1");
+        fs::write(&temp, b"second").unwrap();
+        publish(&temp, &dest).unwrap();
+        drop(reader);
+
+        // Replaced where the platform allows it, kept on Windows - never lost
+        assert!(dest.exists());
+        assert!(!temp.exists());
+
+        // Without a winner's copy to fall back on, the failure is reported
+        let missing_dir = temp_dir.join("gone").join("thumb.jpg");
+        fs::write(&temp, b"third").unwrap();
+        assert!(publish(&temp, &missing_dir).is_err());
+        assert!(!temp.exists());
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[test]
+    fn test_verify_repairs_corrupt_cache() {
+        let temp_dir = std::env::temp_dir().join("fotos_thumb_verify_test");
+        if temp_dir.exists() { fs::remove_dir_all(&temp_dir).unwrap(); }
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        let thumbnailer = Thumbnailer::new(temp_dir.join("cache"));
+        let src_path = temp_dir.join("test.png");
+        RgbImage::new(40, 40).save_with_format(&src_path, ImageFormat::Png).unwrap();
+        let spec = ThumbnailSpec { width: 10, height: 10, rotation: 0 };
+
+        assert_eq!(thumbnailer.verify(&src_path, &spec).unwrap(), None);
+
+        let thumb = thumbnailer.get_or_create(&src_path, &spec).unwrap();
+        assert_eq!(thumbnailer.verify(&src_path, &spec).unwrap(), Some(false));
+
+        // Simulate a crash mid-write
+        let bytes = fs::read(&thumb).unwrap();
+        fs::write(&thumb, &bytes[..bytes.len() / 2]).unwrap();
+        assert_eq!(thumbnailer.verify(&src_path, &spec).unwrap(), Some(true));
+        assert!(image::open(&thumb).is_ok());
+        assert_eq!(thumbnailer.verify(&src_path, &spec).unwrap(), Some(false));
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
     #[test]
     fn test_get_cached_path_check() {
         let temp_dir = std::env::temp_dir().join("fotos_thumb_check_test");
@@ -740,7 +1244,7 @@ mod tests {
 
         let thumbnailer = Thumbnailer::new(temp_dir.clone());
         let source = Path::new("some/photo.jpg");
-        let spec = ThumbnailSpec { width: 100, height: 100 };
+        let spec = ThumbnailSpec { width: 100, height: 100, rotation: 0 };
 
         let result = thumbnailer.get_cached_path(source, &spec).unwrap();
         assert!(result.is_none());
@@ -767,27 +1271,23 @@ mod tests {
         if temp_dir.exists() { fs::remove_dir_all(&temp_dir).unwrap(); }
         fs::create_dir_all(&temp_dir).unwrap();
 
-        let thumbnailer = Thumbnailer::new(temp_dir.clone());
-        
-        let bad_bytes = b"foo\xffbar.jpg";
-        let bad_os_str = std::ffi::OsStr::from_bytes(bad_bytes);
-        let bad_path = Path::new(bad_os_str);
-        let spec = ThumbnailSpec { width: 10, height: 10 };
+        let thumbnailer = Thumbnailer::new(temp_dir.join("cache"));
 
-        let result = thumbnailer.generate(bad_path, &spec);
-        
-        assert!(result.is_err());
-        match result.unwrap_err() {
-            ThumbnailError::InvalidPathEncoding => (), 
-            _ => panic!("Expected InvalidPathEncoding error"),
-        }
+        // Non-UTF-8 names get thumbnails like any other file
+        let bad_path = temp_dir.join(std::ffi::OsStr::from_bytes(b"foo\xffbar.jpg"));
+        image::RgbImage::new(20, 20).save_with_format(&bad_path, image::ImageFormat::Jpeg).unwrap();
+        let spec = ThumbnailSpec { width: 10, height: 10, rotation: 0 };
+
+        let thumb = thumbnailer.generate(&bad_path, &spec).unwrap();
+        assert!(thumb.exists());
+        assert_eq!(thumbnailer.get_cached_path(&bad_path, &spec).unwrap(), Some(thumb));
 
         fs::remove_dir_all(&temp_dir).unwrap();
     }
 
     #[test]
     fn test_key_stability() {
-        let spec = ThumbnailSpec { width: 200, height: 200 };
+        let spec = ThumbnailSpec { width: 200, height: 200, rotation: 0 };
         let p1 = Path::new("foo/bar/baz.jpg");
         let k1 = thumbnail_key(p1, &spec).unwrap();
         let k2 = thumbnail_key(p1, &spec).unwrap();
@@ -797,11 +1297,102 @@ mod tests {
         let k3 = thumbnail_key(p2, &spec).unwrap();
         assert_ne!(k1, k3);
 
-        let spec2 = ThumbnailSpec { width: 201, height: 200 };
+        let spec2 = ThumbnailSpec { width: 201, height: 200, rotation: 0 };
         let k4 = thumbnail_key(p1, &spec2).unwrap();
         assert_ne!(k1, k4);
     }
 
+    /// Keys pinned to their current values: any change to the hashing orphans
+    /// every existing thumbnail cache, so it has to be deliberate.
+    #[test]
+    fn test_key_golden_values() {
+        let spec = ThumbnailSpec { width: 256, height: 256, rotation: 0 };
+        let hex = |path: &str, spec: &ThumbnailSpec| format!("{:016x}", thumbnail_key(Path::new(path), spec).unwrap().0);
+        assert_eq!(hex("/Users/alice/Pictures/IMG_0001.JPG", &spec), "9a57397fd7978d86");
+        assert_eq!(hex("/Users/alice/Pictures/IMG_0001.JPG", &ThumbnailSpec { rotation: 90, ..spec }), "106a3ea4af90e99c");
+        assert_eq!(hex("/Fête/été.jpg", &spec), "8a3e3af1fbaf3ce2");
+    }
+
+    /// Deterministic xorshift generator for the property tests below, so
+    /// failures reproduce without a seed to chase.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> usize {
+            (self.next() % n) as usize
+        }
+
+        /// 1-4 path components drawn from a small alphabet, so that
+        /// near-identical sequences ("ab"/"c" vs "a"/"bc") come up often
+        fn components(&mut self) -> Vec<String> {
+            const ALPHABET: &[&str] = &["a", "b", "ab", "é", "IMG_", "0", "1", " ", ".jpg"];
+            (0..1 + self.below(4))
+                .map(|_| (0..1 + self.below(3)).map(|_| ALPHABET[self.below(ALPHABET.len() as u64)]).collect())
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_key_ignores_separator_spelling() {
+        let spec = ThumbnailSpec { width: 256, height: 256, rotation: 0 };
+        let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+        for _ in 0..500 {
+            let components = rng.components();
+            let joined = components.join("/");
+            let from_components: PathBuf = components.iter().collect();
+            let key = thumbnail_key(Path::new(&joined), &spec).unwrap();
+
+            assert_eq!(thumbnail_key(&from_components, &spec).unwrap(), key, "{}", joined);
+            assert_eq!(thumbnail_key(Path::new(&format!("/{}", joined)), &spec).unwrap(), key, "{}", joined);
+            assert_eq!(thumbnail_key(Path::new(&format!("/{}/", components.join("//./"))), &spec).unwrap(), key, "{}", joined);
+        }
+    }
+
+    #[test]
+    fn test_key_distinct_components_never_collide() {
+        let spec = ThumbnailSpec { width: 256, height: 256, rotation: 0 };
+        let mut rng = Rng(0xD1B5_4A32_D192_ED03);
+        let mut seen = std::collections::HashMap::new();
+        for _ in 0..5000 {
+            let components = rng.components();
+            let path: PathBuf = components.iter().collect();
+            let key = thumbnail_key(&path, &spec).unwrap();
+            if let Some(previous) = seen.insert(key, components.clone()) {
+                assert_eq!(previous, components, "key collision");
+            }
+        }
+    }
+
+    #[test]
+    fn test_key_spec_sensitivity() {
+        let mut rng = Rng(0x2545_F491_4F6C_DD1D);
+        for _ in 0..500 {
+            let path: PathBuf = rng.components().iter().collect();
+            let size = 1 + rng.below(1024) as u32;
+            let spec = ThumbnailSpec { width: size, height: size, rotation: 0 };
+            let key = thumbnail_key(&path, &spec).unwrap();
+
+            assert_ne!(thumbnail_key(&path, &ThumbnailSpec { width: size + 1, ..spec }).unwrap(), key);
+            assert_ne!(thumbnail_key(&path, &ThumbnailSpec { rotation: 90, ..spec }).unwrap(), key);
+            // Full turns are no rotation at all
+            assert_eq!(thumbnail_key(&path, &ThumbnailSpec { rotation: 360, ..spec }).unwrap(), key);
+
+            let cache = cache_path(Path::new("/cache"), &key);
+            let name = cache.file_name().unwrap().to_str().unwrap();
+            let shard = cache.parent().unwrap().file_name().unwrap().to_str().unwrap();
+            assert_eq!(name, format!("{:016x}.jpg", key.0));
+            assert_eq!(shard, &name[..2]);
+            assert_eq!(cache.parent().unwrap().parent().unwrap(), Path::new("/cache"));
+        }
+    }
+
     #[test]
     fn test_sharding_rules() {
         let root = Path::new("/cache");
@@ -815,11 +1406,76 @@ mod tests {
     #[test]
     fn test_process_independence() {
         let p1 = Path::new("/stable/path.jpg");
-        let spec = ThumbnailSpec { width: 100, height: 100 };
+        let spec = ThumbnailSpec { width: 100, height: 100, rotation: 0 };
         
         let k1 = thumbnail_key(p1, &spec).unwrap();
         let k2 = thumbnail_key(p1, &spec).unwrap();
         
         assert_eq!(k1, k2);
     }
+
+    /// A RAW stand-in held in memory that counts how often it's opened
+    struct CountingSource {
+        bytes: Vec<u8>,
+        opens: std::cell::Cell<u32>,
+    }
+
+    impl ScanSource for CountingSource {
+        type Reader = std::io::Cursor<Vec<u8>>;
+
+        fn open(&self) -> std::io::Result<Self::Reader> {
+            self.opens.set(self.opens.get() + 1);
+            Ok(std::io::Cursor::new(self.bytes.clone()))
+        }
+    }
+
+    fn noise_jpeg(size: u32, rng: &mut Rng) -> Vec<u8> {
+        let img = RgbImage::from_fn(size, size, |_, _| image::Rgb([rng.next() as u8, rng.next() as u8, rng.next() as u8]));
+        let mut out = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut out), ImageFormat::Jpeg).unwrap();
+        out
+    }
+
+    /// A RAW stand-in with a small and a large preview, returned alongside it
+    fn fake_raw() -> (CountingSource, Vec<u8>, Vec<u8>) {
+        let mut rng = Rng(0x5eed);
+        let small = noise_jpeg(40, &mut rng);
+        let large = noise_jpeg(240, &mut rng);
+        assert!(small.len() > 1024 && small.len() < 50_000 && large.len() > 50_000);
+
+        // TIFF-ish header, stray markers in "sensor data", then two previews
+        let mut bytes = b"II*\0\x08\0\0\0".to_vec();
+        for _ in 0..50 {
+            bytes.extend_from_slice(&[0xFF, 0xD8, 0xFF, 0x00, 0x12, 0x34]);
+            bytes.extend((0..200).map(|_| rng.next() as u8));
+        }
+        bytes.extend_from_slice(&small);
+        bytes.extend((0..5000).map(|_| rng.next() as u8));
+        bytes.extend_from_slice(&large);
+        bytes.extend((0..5000).map(|_| rng.next() as u8));
+        (CountingSource { bytes, opens: std::cell::Cell::new(0) }, small, large)
+    }
+
+    #[test]
+    fn test_raw_scan_opens_file_once() {
+        let (source, _, large) = fake_raw();
+
+        assert_eq!(find_raw_preview(&source, PreviewQuality::Largest).unwrap(), large);
+        assert_eq!(source.opens.get(), 1);
+
+        source.opens.set(0);
+        let spec = ThumbnailSpec { width: 64, height: 64, rotation: 0 };
+        let thumb = try_extract_raw_preview(&source, &spec).unwrap();
+        assert_eq!(image::load_from_memory(&thumb).unwrap().width(), 64);
+        assert_eq!(source.opens.get(), 1);
+    }
+
+    #[test]
+    fn test_raw_preview_quality() {
+        let (source, small, large) = fake_raw();
+        assert_eq!(find_raw_preview(&source, PreviewQuality::Smallest).unwrap(), small);
+        assert_eq!(find_raw_preview(&source, PreviewQuality::Medium).unwrap(), large);
+        assert_eq!(find_raw_preview(&source, PreviewQuality::Largest).unwrap(), large);
+        assert_eq!(jpeg_dimensions(&small).unwrap(), (40, 40));
+    }
 }
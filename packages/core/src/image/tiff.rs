@@ -0,0 +1,554 @@
+//! A baseline TIFF reader, for the scans and archival masters scanners and
+//! film labs produce. The `image` crate is built without TIFF support here,
+//! so this covers what those files use: strips or tiles, no compression,
+//! LZW, Deflate or PackBits, with or without the horizontal predictor, in
+//! bilevel, grayscale, palette, RGB(A) or CMYK at 1, 8 or 16 bits. Of a
+//! multi-page file, the first full-resolution page is read.
+
+use std::io::Read;
+use std::path::Path;
+
+use image::{DynamicImage, GrayImage, RgbImage, RgbaImage};
+
+use crate::error::CoreError;
+
+/// Largest decoded page accepted, in bytes, so a corrupt header can't
+/// ask for an absurd allocation
+const MAX_PAGE_BYTES: u64 = 1 << 30;
+
+/// Whether `path` has a TIFF extension.
+pub fn is_tiff_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("tif") || e.eq_ignore_ascii_case("tiff"))
+}
+
+/// Decodes the first full-resolution page of the TIFF at `path`.
+pub fn decode(path: &Path) -> Result<DynamicImage, CoreError> {
+    decode_bytes(&std::fs::read(path)?)
+}
+
+/// Decodes the first full-resolution page of TIFF `data`.
+pub fn decode_bytes(data: &[u8]) -> Result<DynamicImage, CoreError> {
+    let tiff = Tiff::parse(data)?;
+    let page = tiff.pages()?.into_iter().find(|page| page.subfile_type & 1 == 0).ok_or_else(unsupported("no full-resolution page"))?;
+    page.decode(&tiff)
+}
+
+fn unsupported(what: &str) -> impl Fn() -> CoreError + '_ {
+    move || CoreError::InvalidInput(format!("Unsupported TIFF: {}", what))
+}
+
+struct Tiff<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+/// The tags of one image file directory (page) that decoding needs.
+#[derive(Default)]
+struct Page {
+    subfile_type: u32,
+    width: u32,
+    height: u32,
+    bits_per_sample: Vec<u32>,
+    compression: u32,
+    photometric: u32,
+    samples_per_pixel: u32,
+    rows_per_strip: u32,
+    planar: u32,
+    predictor: u32,
+    /// Strips, or tiles when `tile_width` is set
+    offsets: Vec<u32>,
+    byte_counts: Vec<u32>,
+    tile_width: u32,
+    tile_height: u32,
+    color_map: Vec<u32>,
+}
+
+impl<'a> Tiff<'a> {
+    fn parse(data: &'a [u8]) -> Result<Self, CoreError> {
+        let big_endian = match data.get(0..4) {
+            Some(b"II*\0") => false,
+            Some(b"MM\0*") => true,
+            Some(b"II+\0") | Some(b"MM\0+") => return Err(unsupported("BigTIFF")()),
+            _ => return Err(CoreError::ImageDecode),
+        };
+        Ok(Self { data, big_endian })
+    }
+
+    fn u16_at(&self, offset: usize) -> Result<u16, CoreError> {
+        let bytes: [u8; 2] = self.data.get(offset..offset + 2).ok_or(CoreError::ImageDecode)?.try_into().unwrap();
+        Ok(if self.big_endian { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) })
+    }
+
+    fn u32_at(&self, offset: usize) -> Result<u32, CoreError> {
+        let bytes: [u8; 4] = self.data.get(offset..offset + 4).ok_or(CoreError::ImageDecode)?.try_into().unwrap();
+        Ok(if self.big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) })
+    }
+
+    /// The pages in file order.
+    fn pages(&self) -> Result<Vec<Page>, CoreError> {
+        let mut pages = Vec::new();
+        let mut offset = self.u32_at(4)? as usize;
+        // A loop in the directory chain mustn't hang the import
+        while offset != 0 && pages.len() < 256 {
+            let count = self.u16_at(offset)? as usize;
+            let mut page = Page { compression: 1, samples_per_pixel: 1, planar: 1, predictor: 1, rows_per_strip: u32::MAX, ..Default::default() };
+            for i in 0..count {
+                let entry = offset + 2 + i * 12;
+                let values = || self.values(entry);
+                match self.u16_at(entry)? {
+                    254 => page.subfile_type = first(values()?),
+                    256 => page.width = first(values()?),
+                    257 => page.height = first(values()?),
+                    258 => page.bits_per_sample = values()?,
+                    259 => page.compression = first(values()?),
+                    262 => page.photometric = first(values()?),
+                    273 | 324 => page.offsets = values()?,
+                    277 => page.samples_per_pixel = first(values()?),
+                    278 => page.rows_per_strip = first(values()?),
+                    279 | 325 => page.byte_counts = values()?,
+                    284 => page.planar = first(values()?),
+                    317 => page.predictor = first(values()?),
+                    320 => page.color_map = values()?,
+                    322 => page.tile_width = first(values()?),
+                    323 => page.tile_height = first(values()?),
+                    _ => {}
+                }
+            }
+            pages.push(page);
+            offset = self.u32_at(offset + 2 + count * 12)? as usize;
+        }
+        Ok(pages)
+    }
+
+    /// The values of the directory entry at `entry`, for the integer types.
+    fn values(&self, entry: usize) -> Result<Vec<u32>, CoreError> {
+        let kind = self.u16_at(entry + 2)?;
+        let count = self.u32_at(entry + 4)? as usize;
+        let size = match kind {
+            1 | 2 | 6 | 7 => 1, // BYTE, ASCII, SBYTE, UNDEFINED
+            3 | 8 => 2,         // SHORT, SSHORT
+            4 | 9 => 4,         // LONG, SLONG
+            _ => return Ok(Vec::new()),
+        };
+        let start = if count * size <= 4 { entry + 8 } else { self.u32_at(entry + 8)? as usize };
+        if start.checked_add(count.checked_mul(size).ok_or(CoreError::ImageDecode)?).is_none_or(|end| end > self.data.len()) {
+            return Err(CoreError::ImageDecode);
+        }
+        (0..count)
+            .map(|i| match size {
+                1 => Ok(self.data[start + i] as u32),
+                2 => self.u16_at(start + i * 2).map(u32::from),
+                _ => self.u32_at(start + i * 4),
+            })
+            .collect()
+    }
+}
+
+fn first(values: Vec<u32>) -> u32 {
+    values.first().copied().unwrap_or(0)
+}
+
+impl Page {
+    fn decode(&self, tiff: &Tiff) -> Result<DynamicImage, CoreError> {
+        let bits = self.bits_per_sample.first().copied().unwrap_or(1);
+        if self.bits_per_sample.iter().any(|&b| b != bits) || !matches!(bits, 1 | 8 | 16) {
+            return Err(unsupported("bit depth")());
+        }
+        if self.planar != 1 && self.samples_per_pixel > 1 {
+            return Err(unsupported("separate color planes")());
+        }
+        let (width, height, samples) = (self.width as usize, self.height as usize, self.samples_per_pixel as usize);
+        if width == 0 || height == 0 || samples == 0 {
+            return Err(CoreError::ImageDecode);
+        }
+        let page_bytes = (width as u64) * (height as u64) * (samples as u64) * (bits as u64).div_ceil(8);
+        if page_bytes > MAX_PAGE_BYTES {
+            return Err(unsupported("page too large")());
+        }
+
+        // Samples of the whole page, row by row, at their stored depth
+        let mut pixels = vec![0u16; width * height * samples];
+        let (block_width, block_height) = if self.tile_width > 0 {
+            (self.tile_width as usize, self.tile_height as usize)
+        } else {
+            (width, (self.rows_per_strip as usize).min(height))
+        };
+        if block_width == 0 || block_height == 0 {
+            return Err(CoreError::ImageDecode);
+        }
+        let blocks_across = width.div_ceil(block_width);
+        for (i, (&offset, &count)) in self.offsets.iter().zip(&self.byte_counts).enumerate() {
+            let (block_x, block_y) = ((i % blocks_across) * block_width, (i / blocks_across) * block_height);
+            if block_y >= height {
+                break;
+            }
+            let compressed = tiff.data.get(offset as usize..offset as usize + count as usize).ok_or(CoreError::ImageDecode)?;
+            let bytes = self.decompress(compressed)?;
+            let row_bytes = (block_width * samples * bits as usize).div_ceil(8);
+            for (row, row_data) in bytes.chunks(row_bytes).take(block_height).enumerate() {
+                let y = block_y + row;
+                if y >= height || row_data.len() < row_bytes {
+                    break;
+                }
+                let mut row_samples = unpack(row_data, bits, block_width * samples, tiff.big_endian);
+                if self.predictor == 2 {
+                    for x in samples..row_samples.len() {
+                        let mask = if bits == 16 { u16::MAX } else { (1u16 << bits) - 1 };
+                        row_samples[x] = row_samples[x].wrapping_add(row_samples[x - samples]) & mask;
+                    }
+                }
+                let visible = (width - block_x).min(block_width) * samples;
+                let start = (y * width + block_x) * samples;
+                pixels[start..start + visible].copy_from_slice(&row_samples[..visible]);
+            }
+        }
+
+        let to_8bit = |sample: u16| -> u8 {
+            match bits {
+                1 => if sample > 0 { 255 } else { 0 },
+                16 => (sample >> 8) as u8,
+                _ => sample as u8,
+            }
+        };
+        let (w, h) = (self.width, self.height);
+        let image = match (self.photometric, samples) {
+            // WhiteIsZero, BlackIsZero; an extra (alpha) sample is dropped
+            (0 | 1, 1 | 2) => {
+                let invert = self.photometric == 0;
+                let gray = pixels.chunks(samples).map(|p| if invert { 255 - to_8bit(p[0]) } else { to_8bit(p[0]) }).collect();
+                DynamicImage::ImageLuma8(GrayImage::from_raw(w, h, gray).ok_or(CoreError::ImageDecode)?)
+            }
+            (2, 3) => DynamicImage::ImageRgb8(RgbImage::from_raw(w, h, pixels.iter().map(|&s| to_8bit(s)).collect()).ok_or(CoreError::ImageDecode)?),
+            (2, n) if n >= 4 => {
+                let rgba = pixels.chunks(samples).flat_map(|p| [p[0], p[1], p[2], p[3]].map(to_8bit)).collect();
+                DynamicImage::ImageRgba8(RgbaImage::from_raw(w, h, rgba).ok_or(CoreError::ImageDecode)?)
+            }
+            // Palette: 3 * 2^bits 16-bit entries, all reds, then greens, then blues
+            (3, 1) => {
+                let colors = 1usize << bits;
+                if self.color_map.len() < colors * 3 {
+                    return Err(CoreError::ImageDecode);
+                }
+                let rgb = pixels
+                    .iter()
+                    .flat_map(|&index| [0, 1, 2].map(|channel| (self.color_map[channel * colors + index as usize] >> 8) as u8))
+                    .collect();
+                DynamicImage::ImageRgb8(RgbImage::from_raw(w, h, rgb).ok_or(CoreError::ImageDecode)?)
+            }
+            (5, n) if n >= 4 => {
+                let rgb = pixels
+                    .chunks(samples)
+                    .flat_map(|p| {
+                        let k = 255 - to_8bit(p[3]) as u32;
+                        [p[0], p[1], p[2]].map(|c| ((255 - to_8bit(c) as u32) * k / 255) as u8)
+                    })
+                    .collect();
+                DynamicImage::ImageRgb8(RgbImage::from_raw(w, h, rgb).ok_or(CoreError::ImageDecode)?)
+            }
+            _ => return Err(unsupported("color model")()),
+        };
+        Ok(image)
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, CoreError> {
+        match self.compression {
+            1 => Ok(data.to_vec()),
+            5 => lzw_decode(data),
+            8 | 32946 => {
+                let mut out = Vec::new();
+                flate2::read::ZlibDecoder::new(data).read_to_end(&mut out).map_err(|_| CoreError::ImageDecode)?;
+                Ok(out)
+            }
+            32773 => Ok(packbits_decode(data)),
+            7 => Err(unsupported("JPEG compression")()),
+            _ => Err(unsupported("compression")()),
+        }
+    }
+}
+
+/// Splits a row into samples of `bits` each, most significant bit first.
+fn unpack(row: &[u8], bits: u32, count: usize, big_endian: bool) -> Vec<u16> {
+    match bits {
+        8 => row[..count].iter().map(|&b| b as u16).collect(),
+        16 => row
+            .chunks_exact(2)
+            .take(count)
+            .map(|b| if big_endian { u16::from_be_bytes([b[0], b[1]]) } else { u16::from_le_bytes([b[0], b[1]]) })
+            .collect(),
+        _ => (0..count).map(|i| ((row[i / 8] >> (7 - i % 8)) & 1) as u16).collect(),
+    }
+}
+
+fn packbits_decode(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        let n = data[i] as i8;
+        i += 1;
+        if n >= 0 {
+            let end = (i + n as usize + 1).min(data.len());
+            out.extend_from_slice(&data[i..end]);
+            i = end;
+        } else if n != -128 {
+            if let Some(&byte) = data.get(i) {
+                out.extend(std::iter::repeat_n(byte, 1 - n as isize as usize));
+            }
+            i += 1;
+        }
+    }
+    out
+}
+
+/// TIFF's LZW: codes of 9 to 12 bits, most significant bit first, widening
+/// one code early.
+fn lzw_decode(data: &[u8]) -> Result<Vec<u8>, CoreError> {
+    const CLEAR: u16 = 256;
+    const END: u16 = 257;
+    // Each code is a string: its prefix code plus one byte
+    let mut prefix = [0u16; 4096];
+    let mut suffix = [0u8; 4096];
+    let mut first = [0u8; 4096];
+    let mut length = [0usize; 4096];
+    for code in 0..256 {
+        suffix[code] = code as u8;
+        first[code] = code as u8;
+        length[code] = 1;
+    }
+
+    let mut out = Vec::with_capacity(data.len() * 3);
+    let (mut next, mut width) = (258usize, 9u32);
+    let mut previous: Option<u16> = None;
+    let (mut buffer, mut buffered, mut pos) = (0u32, 0u32, 0usize);
+    loop {
+        while buffered < width {
+            let Some(&byte) = data.get(pos) else { return Ok(out) };
+            buffer = (buffer << 8) | byte as u32;
+            buffered += 8;
+            pos += 1;
+        }
+        let code = ((buffer >> (buffered - width)) & ((1 << width) - 1)) as u16;
+        buffered -= width;
+
+        match code {
+            CLEAR => {
+                (next, width, previous) = (258, 9, None);
+                continue;
+            }
+            END => return Ok(out),
+            _ => {}
+        }
+        let Some(prev) = previous else {
+            if code > 255 {
+                return Err(CoreError::ImageDecode);
+            }
+            out.push(code as u8);
+            previous = Some(code);
+            continue;
+        };
+        let (code_index, prev_index) = (code as usize, prev as usize);
+        // The code being defined right now is the previous string plus its own first byte
+        let start_byte = match code_index {
+            c if c < next => first[c],
+            c if c == next => first[prev_index],
+            _ => return Err(CoreError::ImageDecode),
+        };
+        if next < 4096 {
+            prefix[next] = prev;
+            suffix[next] = start_byte;
+            first[next] = first[prev_index];
+            length[next] = length[prev_index] + 1;
+            next += 1;
+        }
+        let start = out.len();
+        out.resize(start + length[code_index], 0);
+        let mut c = code_index;
+        for slot in out[start..].iter_mut().rev() {
+            *slot = suffix[c];
+            c = prefix[c] as usize;
+        }
+        if next + 1 >= (1 << width) && width < 12 {
+            width += 1;
+        }
+        previous = Some(code);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// TIFF's LZW, for test input
+    fn lzw_encode(data: &[u8]) -> Vec<u8> {
+        let mut table: std::collections::HashMap<Vec<u8>, u16> = (0..=255u16).map(|b| (vec![b as u8], b)).collect();
+        let (mut out, mut buffer, mut buffered) = (Vec::new(), 0u64, 0u32);
+        let mut emit = |code: u16, width: u32| {
+            buffer = (buffer << width) | code as u64;
+            buffered += width;
+            while buffered >= 8 {
+                out.push((buffer >> (buffered - 8)) as u8);
+                buffered -= 8;
+            }
+        };
+        let (mut next, mut width) = (258u16, 9u32);
+        emit(256, width);
+        let mut current = Vec::new();
+        for &byte in data {
+            let mut candidate = current.clone();
+            candidate.push(byte);
+            if table.contains_key(&candidate) {
+                current = candidate;
+                continue;
+            }
+            emit(table[&current], width);
+            table.insert(candidate, next);
+            next += 1;
+            if next == 4094 {
+                // Table full: start over, as encoders do
+                emit(256, width);
+                table.retain(|_, code| *code < 256);
+                (next, width) = (258, 9);
+            } else if next >= (1 << width) {
+                width += 1;
+            }
+            current = vec![byte];
+        }
+        emit(table[&current], width);
+        emit(257, width);
+        if buffered > 0 {
+            out.push((buffer << (8 - buffered)) as u8);
+        }
+        out
+    }
+
+    /// A directory entry: tag, type and values
+    type Entry = (u16, u16, Vec<u32>);
+    /// A page: its entries and strip data
+    type TestPage = (Vec<Entry>, Vec<u8>);
+
+    /// A little-endian TIFF of `pages`, each (entries, strip data); entries
+    /// are (tag, type, values) and the strip offset is filled in.
+    fn tiff(pages: &[TestPage]) -> Vec<u8> {
+        let mut out = b"II*\0\0\0\0\0".to_vec();
+        let mut link = 4;
+        for (entries, strip) in pages {
+            let strip_offset = out.len() as u32;
+            out.extend_from_slice(strip);
+            let mut entries = entries.clone();
+            entries.push((273, 4, vec![strip_offset]));
+            entries.push((279, 4, vec![strip.len() as u32]));
+            entries.sort_by_key(|e| e.0);
+            // Values that don't fit in an entry go before the directory
+            let mut external = Vec::new();
+            for (_, kind, values) in &entries {
+                let size = if *kind == 3 { 2 } else { 4 };
+                if values.len() * size > 4 {
+                    external.push(out.len() as u32);
+                    for v in values {
+                        if size == 2 { out.extend_from_slice(&(*v as u16).to_le_bytes()) } else { out.extend_from_slice(&v.to_le_bytes()) }
+                    }
+                } else {
+                    external.push(0);
+                }
+            }
+            let directory = out.len() as u32;
+            out[link..link + 4].copy_from_slice(&directory.to_le_bytes());
+            out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+            for ((tag, kind, values), external) in entries.iter().zip(external) {
+                out.extend_from_slice(&tag.to_le_bytes());
+                out.extend_from_slice(&kind.to_le_bytes());
+                out.extend_from_slice(&(values.len() as u32).to_le_bytes());
+                let mut inline = [0u8; 4];
+                if external > 0 {
+                    inline = external.to_le_bytes();
+                } else if *kind == 3 {
+                    for (i, v) in values.iter().enumerate() {
+                        inline[i * 2..i * 2 + 2].copy_from_slice(&(*v as u16).to_le_bytes());
+                    }
+                } else if let Some(v) = values.first() {
+                    inline = v.to_le_bytes();
+                }
+                out.extend_from_slice(&inline);
+            }
+            link = out.len();
+            out.extend_from_slice(&[0; 4]);
+        }
+        out
+    }
+
+    fn rgb_page(width: u32, height: u32, compression: u32, predictor: u32, strip: Vec<u8>) -> TestPage {
+        let entries = vec![
+            (256, 4, vec![width]),
+            (257, 4, vec![height]),
+            (258, 3, vec![8, 8, 8]),
+            (259, 3, vec![compression]),
+            (262, 3, vec![2]),
+            (277, 3, vec![3]),
+            (278, 4, vec![height]),
+            (317, 3, vec![predictor]),
+        ];
+        (entries, strip)
+    }
+
+    /// A 4x2 gradient, red across and green down
+    fn gradient() -> Vec<u8> {
+        (0..2u8).flat_map(|y| (0..4u8).flat_map(move |x| [x * 60, y * 200, 7])).collect()
+    }
+
+    #[test]
+    fn test_decode_compressions() {
+        let pixels = gradient();
+        let mut differenced = pixels.clone();
+        for row in differenced.chunks_mut(12) {
+            for i in (3..12).rev() {
+                row[i] = row[i].wrapping_sub(row[i - 3]);
+            }
+        }
+        let packbits = pixels.chunks(12).flat_map(|row| [&[11u8][..], row].concat()).collect();
+        let mut deflated = Vec::new();
+        std::io::Write::write_all(&mut flate2::write::ZlibEncoder::new(&mut deflated, flate2::Compression::default()), &pixels).unwrap();
+
+        for (name, compression, predictor, strip) in [
+            ("none", 1, 1, pixels.clone()),
+            ("lzw", 5, 1, lzw_encode(&pixels)),
+            ("lzw + predictor", 5, 2, lzw_encode(&differenced)),
+            ("deflate", 8, 1, deflated),
+            ("packbits", 32773, 1, packbits),
+        ] {
+            let image = decode_bytes(&tiff(&[rgb_page(4, 2, compression, predictor, strip)])).unwrap();
+            assert_eq!(image.to_rgb8().into_raw(), pixels, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_lzw_long_input() {
+        // Enough distinct strings to widen codes to 12 bits and clear the table
+        let data: Vec<u8> = (0..60_000u32).map(|i| ((i * 7919) % 251) as u8 ^ (i / 1000) as u8).collect();
+        assert_eq!(lzw_decode(&lzw_encode(&data)).unwrap(), data);
+    }
+
+    #[test]
+    fn test_multi_page_gray_and_bilevel() {
+        // A reduced-resolution preview first, then the page, then a second page
+        let preview = (vec![(254, 4, vec![1]), (256, 4, vec![1]), (257, 4, vec![1]), (258, 3, vec![8]), (262, 3, vec![1])], vec![0]);
+        let gray = (vec![(256, 4, vec![3]), (257, 4, vec![1]), (258, 3, vec![8]), (262, 3, vec![0])], vec![0, 100, 255]);
+        let bilevel = (vec![(256, 4, vec![10]), (257, 4, vec![1]), (262, 3, vec![1])], vec![0b1010_0000, 0b0100_0000]);
+
+        let image = decode_bytes(&tiff(&[preview.clone(), gray.clone(), bilevel.clone()])).unwrap();
+        assert_eq!(image.to_luma8().into_raw(), vec![255, 155, 0]);
+        let image = decode_bytes(&tiff(&[bilevel])).unwrap();
+        assert_eq!(image.to_luma8().into_raw(), vec![255, 0, 255, 0, 0, 0, 0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_unsupported_and_corrupt() {
+        let jpeg = rgb_page(4, 2, 7, 1, vec![0; 8]);
+        assert!(matches!(decode_bytes(&tiff(&[jpeg])), Err(CoreError::InvalidInput(_))));
+        assert!(decode_bytes(b"II*\0\xff\xff\xff\xff").is_err());
+        assert!(decode_bytes(b"not a tiff").is_err());
+        let truncated = tiff(&[rgb_page(400, 200, 1, 1, vec![0; 10])]);
+        // Missing strip data leaves black, as other decoders do
+        assert!(decode_bytes(&truncated).is_ok());
+    }
+}
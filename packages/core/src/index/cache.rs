@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+use crate::types::PhotoInfo;
+
+/// Most distinct queries kept at once - enough for the grid tabs and filters of a session
+const MAX_ENTRIES: usize = 32;
+
+/// Photo query results keyed by query text and the library revision they were read at.
+/// Any write bumps the revision, so stale entries are never served.
+#[derive(Debug, Default)]
+pub(crate) struct QueryCache {
+    entries: HashMap<String, (u64, Vec<PhotoInfo>)>,
+}
+
+impl QueryCache {
+    pub fn get(&self, key: &str, revision: u64) -> Option<Vec<PhotoInfo>> {
+        match self.entries.get(key) {
+            Some((rev, rows)) if *rev == revision => Some(rows.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn put(&mut self, key: String, revision: u64, rows: Vec<PhotoInfo>) {
+        if self.entries.len() >= MAX_ENTRIES && !self.entries.contains_key(&key) {
+            self.entries.retain(|_, (rev, _)| *rev == revision);
+            if self.entries.len() >= MAX_ENTRIES {
+                self.entries.clear();
+            }
+        }
+        self.entries.insert(key, (revision, rows));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_expire_with_revision() {
+        let mut cache = QueryCache::default();
+        cache.put("all".to_string(), 1, Vec::new());
+        assert!(cache.get("all", 1).is_some());
+        assert!(cache.get("all", 2).is_none());
+        assert!(cache.get("other", 1).is_none());
+
+        for i in 0..MAX_ENTRIES + 5 {
+            cache.put(format!("q{}", i), 3, Vec::new());
+        }
+        assert!(cache.entries.len() <= MAX_ENTRIES);
+        assert!(cache.get("all", 1).is_none());
+    }
+}
@@ -0,0 +1,112 @@
+use crate::types::{GeoPoint, Place};
+
+const EARTH_RADIUS_M: f64 = 6_371_000.0;
+const METERS_PER_DEGREE_LAT: f64 = 111_320.0;
+
+/// Great-circle distance in meters.
+pub fn distance_m(a: GeoPoint, b: GeoPoint) -> f64 {
+    let (lat1, lat2) = (a.lat.to_radians(), b.lat.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (b.lon - a.lon).to_radians();
+    let h = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_M * h.sqrt().min(1.0).asin()
+}
+
+/// Ray casting point-in-polygon test. Polygons are small enough to treat lat/lon as planar.
+pub fn polygon_contains(polygon: &[GeoPoint], point: GeoPoint) -> bool {
+    if polygon.len() < 3 {
+        return false;
+    }
+    let mut inside = false;
+    let mut j = polygon.len() - 1;
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i], polygon[j]);
+        if (a.lat > point.lat) != (b.lat > point.lat)
+            && point.lon < (b.lon - a.lon) * (point.lat - a.lat) / (b.lat - a.lat) + a.lon
+        {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+pub fn place_contains(place: &Place, point: GeoPoint) -> bool {
+    if place.polygon.is_empty() {
+        distance_m(GeoPoint { lat: place.lat, lon: place.lon }, point) <= place.radius_m
+    } else {
+        polygon_contains(&place.polygon, point)
+    }
+}
+
+/// Bounding box `(min_lat, max_lat, min_lon, max_lon)` used to prefilter with the lat/lon index.
+pub fn bounding_box(place: &Place) -> (f64, f64, f64, f64) {
+    if place.polygon.is_empty() {
+        let d_lat = place.radius_m / METERS_PER_DEGREE_LAT;
+        let cos_lat = place.lat.to_radians().cos();
+        let d_lon = if cos_lat > 1e-6 { place.radius_m / (METERS_PER_DEGREE_LAT * cos_lat) } else { 180.0 };
+        return (
+            (place.lat - d_lat).max(-90.0),
+            (place.lat + d_lat).min(90.0),
+            (place.lon - d_lon).max(-180.0),
+            (place.lon + d_lon).min(180.0),
+        );
+    }
+    place.polygon.iter().fold(
+        (f64::MAX, f64::MIN, f64::MAX, f64::MIN),
+        |(min_lat, max_lat, min_lon, max_lon), p| {
+            (min_lat.min(p.lat), max_lat.max(p.lat), min_lon.min(p.lon), max_lon.max(p.lon))
+        },
+    )
+}
+
+/// Polygon as stored in the index: `lat,lon;lat,lon;...`
+pub(crate) fn encode_polygon(polygon: &[GeoPoint]) -> String {
+    polygon.iter().map(|p| format!("{},{}", p.lat, p.lon)).collect::<Vec<_>>().join(";")
+}
+
+pub(crate) fn decode_polygon(encoded: &str) -> Vec<GeoPoint> {
+    encoded
+        .split(';')
+        .filter_map(|pair| {
+            let (lat, lon) = pair.split_once(',')?;
+            Some(GeoPoint { lat: lat.parse().ok()?, lon: lon.parse().ok()? })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn circle(lat: f64, lon: f64, radius_m: f64) -> Place {
+        Place { id: 0, name: "Home".to_string(), lat, lon, radius_m, polygon: Vec::new() }
+    }
+
+    #[test]
+    fn test_circle_contains() {
+        let home = circle(48.8584, 2.2945, 200.0);
+        assert!(place_contains(&home, GeoPoint { lat: 48.8590, lon: 2.2950 }));
+        assert!(!place_contains(&home, GeoPoint { lat: 48.8738, lon: 2.2950 }));
+
+        let (min_lat, max_lat, min_lon, max_lon) = bounding_box(&home);
+        assert!(min_lat < 48.8584 && max_lat > 48.8584 && min_lon < 2.2945 && max_lon > 2.2945);
+        assert!(max_lat - min_lat < 0.01);
+    }
+
+    #[test]
+    fn test_polygon_contains_and_roundtrip() {
+        let square = vec![
+            GeoPoint { lat: 0.0, lon: 0.0 },
+            GeoPoint { lat: 0.0, lon: 1.0 },
+            GeoPoint { lat: 1.0, lon: 1.0 },
+            GeoPoint { lat: 1.0, lon: 0.0 },
+        ];
+        assert!(polygon_contains(&square, GeoPoint { lat: 0.5, lon: 0.5 }));
+        assert!(!polygon_contains(&square, GeoPoint { lat: 1.5, lon: 0.5 }));
+        assert!(!polygon_contains(&square[..2], GeoPoint { lat: 0.5, lon: 0.5 }));
+
+        assert_eq!(decode_polygon(&encode_polygon(&square)), square);
+        assert!(decode_polygon("").is_empty());
+    }
+}
@@ -0,0 +1,180 @@
+//! Manifests of the library: one row per photo with where its original is,
+//! its checksum, size, dates, location and camera, and no image data. Kept
+//! for insurance inventories, or to check a backup still holds every photo
+//! intact.
+
+use std::io::Write;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::error::CoreError;
+use crate::fs::{decode_path, file_stats};
+use crate::types::{ManifestFormat, PhotoInfo};
+
+const CSV_HEADER: &str = "id,path,checksum,size,taken_at,modified_at,lat,lon,make,model";
+
+#[derive(Debug, Serialize)]
+struct ManifestEntry {
+    id: i64,
+    path: String,
+    /// BLAKE3 of the original
+    checksum: Option<String>,
+    size: Option<u64>,
+    /// Capture time as the camera's wall clock, "2023-04-15T10:03:00"
+    taken_at: Option<String>,
+    /// The original's modification time in UTC, "2023-04-15T08:03:00Z"
+    modified_at: Option<String>,
+    lat: Option<f64>,
+    lon: Option<f64>,
+    make: Option<String>,
+    model: Option<String>,
+}
+
+impl ManifestEntry {
+    /// Reads the original for its size, and for its checksum if the index
+    /// doesn't have one yet. A missing original keeps what the index knows.
+    fn new(photo: PhotoInfo) -> Self {
+        let path = decode_path(&photo.path);
+        let stats = file_stats(&path);
+        let checksum = photo.checksum.or_else(|| stats.and_then(|_| crate::image::compute_checksum(&path).ok()));
+        Self {
+            id: photo.id.id,
+            path: path.to_string_lossy().into_owned(),
+            checksum,
+            size: stats.map(|(size, _)| size),
+            taken_at: photo.metadata.taken_at.map(iso_date),
+            modified_at: stats.map(|(_, mtime)| iso_date(mtime) + "Z"),
+            lat: photo.metadata.lat,
+            lon: photo.metadata.lon,
+            make: photo.metadata.make,
+            model: photo.metadata.model,
+        }
+    }
+
+    fn csv_row(&self) -> String {
+        let text = |value: &Option<String>| csv_field(value.as_deref().unwrap_or(""));
+        let number = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or_default();
+        [
+            self.id.to_string(),
+            csv_field(&self.path),
+            text(&self.checksum),
+            self.size.map(|s| s.to_string()).unwrap_or_default(),
+            text(&self.taken_at),
+            text(&self.modified_at),
+            number(self.lat),
+            number(self.lon),
+            text(&self.make),
+            text(&self.model),
+        ]
+        .join(",")
+    }
+}
+
+/// Writes the manifest of `photos` to `dest`, in order. Returns how many
+/// photos it lists.
+pub(crate) fn write_manifest(photos: Vec<PhotoInfo>, format: ManifestFormat, dest: &Path) -> Result<u32, CoreError> {
+    let count = photos.len() as u32;
+    let entries = photos.into_iter().map(ManifestEntry::new);
+    let mut out = std::io::BufWriter::new(std::fs::File::create(dest)?);
+    match format {
+        ManifestFormat::Csv => {
+            writeln!(out, "{}", CSV_HEADER)?;
+            for entry in entries {
+                writeln!(out, "{}", entry.csv_row())?;
+            }
+        }
+        ManifestFormat::Json => {
+            let entries: Vec<ManifestEntry> = entries.collect();
+            serde_json::to_writer_pretty(&mut out, &entries).map_err(|e| CoreError::Io(e.to_string()))?;
+            writeln!(out)?;
+        }
+    }
+    out.flush()?;
+    Ok(count)
+}
+
+/// Quoted when it holds a comma, quote or line break (RFC 4180)
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// `secs` since 1970 as an ISO 8601 date without a zone
+fn iso_date(secs: i64) -> String {
+    let (year, month, day) = crate::metadata::date::civil_from_days(secs.div_euclid(86_400));
+    let time = secs.rem_euclid(86_400);
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}", year, month, day, time / 3600, time / 60 % 60, time % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{PhotoId, PhotoMetadata};
+
+    #[test]
+    fn test_manifest_formats() {
+        let dir = std::env::temp_dir().join("fotos_manifest_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let original = dir.join("IMG_0001.jpg");
+        std::fs::write(&original, b"photo").unwrap();
+        let photos = vec![
+            PhotoInfo {
+                id: PhotoId { id: 1 },
+                path: original.to_string_lossy().into_owned(),
+                hash: "h1".to_string(),
+                checksum: None,
+                metadata: PhotoMetadata {
+                    taken_at: Some(1_681_552_980),
+                    lat: Some(48.85),
+                    lon: Some(2.35),
+                    make: Some("Canon".to_string()),
+                    model: Some("EOS R5, \"mark\" I".to_string()),
+                    ..Default::default()
+                },
+                thumb_path: None,
+                file_size: 0,
+                created_at: None,
+                modified_at: None,
+                missing: false,
+                corrupt: false,
+            },
+            PhotoInfo {
+                id: PhotoId { id: 2 },
+                path: "/gone.jpg".to_string(),
+                hash: "h2".to_string(),
+                checksum: Some("abc".to_string()),
+                metadata: PhotoMetadata::default(),
+                thumb_path: None,
+                file_size: 0,
+                created_at: None,
+                modified_at: None,
+                missing: true,
+                corrupt: false,
+            },
+        ];
+
+        let csv = dir.join("manifest.csv");
+        assert_eq!(write_manifest(photos.clone(), ManifestFormat::Csv, &csv).unwrap(), 2);
+        let csv = std::fs::read_to_string(&csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        let checksum = crate::image::compute_checksum(&original).unwrap();
+        assert!(lines[1].starts_with(&format!("1,{},{},5,2023-04-15T10:03:00,", original.display(), checksum)));
+        assert!(lines[1].ends_with("Z,48.85,2.35,Canon,\"EOS R5, \"\"mark\"\" I\""));
+        assert_eq!(lines[2], "2,/gone.jpg,abc,,,,,,,");
+
+        let json = dir.join("manifest.json");
+        write_manifest(photos, ManifestFormat::Json, &json).unwrap();
+        let entries: serde_json::Value = serde_json::from_slice(&std::fs::read(&json).unwrap()).unwrap();
+        assert_eq!(entries[0]["checksum"], checksum.as_str());
+        assert_eq!(entries[0]["size"], 5);
+        assert_eq!(entries[1]["size"], serde_json::Value::Null);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,3 +1,12 @@
 pub mod photo_index;
+pub mod geo;
+pub mod undated;
+pub mod review;
+pub mod similar;
+mod manifest;
+mod cache;
+mod pool;
+mod schema;
+mod search;
 
 pub use photo_index::PhotoIndex;
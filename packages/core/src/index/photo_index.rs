@@ -1,129 +1,165 @@
-use rusqlite::{Connection, params};
+use rusqlite::{backup::Backup, Connection, OptionalExtension, params};
 use std::path::Path;
 use std::sync::Mutex;
 
-use crate::{error::CoreError, types::{PhotoId, PhotoInfo, PhotoMetadata}};
+use crate::{error::CoreError, types::{GeoPoint, Neighbors, PhotoChanges, PhotoId, PhotoInfo, PhotoLocation, PhotoMetadata, PhotoQuery, PhotoSort, NewPhoto, QuerySort, Place, PhotoAlbum, TrashedPhoto, IntakeItem, ImportSession, DatePrecision, UndatedCluster, SelectionSummary, TimelineBucket, SimilarPhoto, DuplicateGroup, LibraryVerifyReport, ManifestFormat, OriginalsAuditReport, PhotoEnhance, PhotoViews, Weather, YearInReview}};
+use crate::config::PhotoCoreConfig;
+use crate::fs::{decode_path, file_stats};
+use crate::image::thumbnail::{cache_path, thumbnail_key, Thumbnailer, ThumbnailSpec};
+use super::cache::QueryCache;
+use super::pool::{apply_key, ReaderPool, BUSY_TIMEOUT};
+use super::schema::{check_supported, init_schema};
+use super::geo;
+
+/// Pages copied per backup step; between steps a busy database gets a pause
+const BACKUP_STEP_PAGES: std::ffi::c_int = 100;
+const BACKUP_PAUSE: std::time::Duration = std::time::Duration::from_millis(250);
 
 #[derive(uniffi::Object)]
 pub struct PhotoIndex {
+    /// The one connection that writes
     conn: Mutex<Connection>,
+    /// Read-only connections for queries; none for an index without a file
+    readers: Option<ReaderPool>,
+    cache: Mutex<QueryCache>,
+    /// SQLCipher passphrase of an encrypted library, for the other
+    /// connections it opens (readers, backups)
+    key: Option<String>,
 }
 
 #[uniffi::export]
 impl PhotoIndex {
     #[uniffi::constructor]
     pub fn open(db_path: String) -> Result<std::sync::Arc<Self>, CoreError> {
-        let conn = Connection::open(Path::new(&db_path))?;
-        conn.execute_batch(
-            "CREATE TABLE IF NOT EXISTS photos (
-                id INTEGER PRIMARY KEY,
-                path TEXT NOT NULL UNIQUE,
-                hash TEXT NOT NULL,
-                make TEXT,
-                model TEXT,
-                date_taken TEXT,
-                width INTEGER,
-                height INTEGER,
-                lat REAL,
-                lon REAL,
-                iso INTEGER,
-                f_number REAL,
-                exposure_time TEXT,
-                orientation INTEGER
-            );
-            CREATE INDEX IF NOT EXISTS idx_photos_hash ON photos (hash);",
-        )?;
-        Ok(std::sync::Arc::new(Self { conn: Mutex::new(conn) }))
+        Self::open_with_key(db_path, None)
     }
 
-    pub fn insert(&self, path: String, hash: String, metadata: PhotoMetadata) -> Result<PhotoId, CoreError> {
-        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+    /// Opens or creates a library encrypted with SQLCipher, so locations,
+    /// dates and paths can't be read from the file without `passphrase`.
+    /// SQLCipher derives the key from it (PBKDF2-HMAC-SHA512). Needs core
+    /// built with the `encryption` feature; other builds refuse rather than
+    /// write a plaintext library. Backups of it are encrypted the same way.
+    #[uniffi::constructor]
+    pub fn open_encrypted(db_path: String, passphrase: String) -> Result<std::sync::Arc<Self>, CoreError> {
+        if passphrase.is_empty() {
+            return Err(CoreError::InvalidInput("passphrase is empty".to_string()));
+        }
+        Self::open_with_key(db_path, Some(passphrase))
+    }
 
-        // 1. Check if path already exists (same file, no change needed)
-        let mut stmt = conn.prepare("SELECT id FROM photos WHERE path = ?1")?;
-        let mut rows = stmt.query_map(params![path], |row| row.get::<_, i64>(0))?;
+    /// Writes a consistent snapshot of the library to `path` with SQLite's
+    /// online backup, e.g. before a reset. Readers carry on meanwhile;
+    /// writers wait until it's done.
+    pub fn backup_to(&self, path: String) -> Result<(), CoreError> {
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let mut dest = Connection::open(Path::new(&path))?;
+        apply_key(&dest, self.key.as_deref())?;
+        Backup::new(&conn, &mut dest)?.run_to_completion(BACKUP_STEP_PAGES, BACKUP_PAUSE, None)?;
+        Ok(())
+    }
 
-        if let Some(existing_id) = rows.next() {
-            return Ok(PhotoId { id: existing_id? });
+    /// Replaces the library with a snapshot from `backup_to`, bringing an
+    /// older snapshot's schema up to date. Anything that isn't a library
+    /// (or is from a newer release) is refused before the library is touched.
+    pub fn restore_from(&self, path: String) -> Result<(), CoreError> {
+        let source = Connection::open_with_flags(Path::new(&path), rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        apply_key(&source, self.key.as_deref())?;
+        let has_photos: bool = source.query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'photos')",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_photos {
+            return Err(CoreError::Database(format!("{} is not a photo library backup", path)));
         }
-        drop(rows);
-        drop(stmt);
+        check_supported(&source)?;
 
-        // 2. Check if hash already exists (same photo, different path - update path)
-        let mut stmt = conn.prepare("SELECT id FROM photos WHERE hash = ?1")?;
-        let mut rows = stmt.query_map(params![hash], |row| row.get::<_, i64>(0))?;
+        let mut conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        Backup::new(&source, &mut conn)?.run_to_completion(BACKUP_STEP_PAGES, BACKUP_PAUSE, None)?;
+        init_schema(&conn)?;
+        // Revisions start over at the snapshot's, so cached results can't be trusted
+        *self.cache.lock().map_err(|e| CoreError::Database(e.to_string()))? = QueryCache::default();
+        Ok(())
+    }
 
-        if let Some(existing_id) = rows.next() {
-            let id = existing_id?;
-            drop(rows);
-            drop(stmt);
-            // Update path to new location
-            conn.execute("UPDATE photos SET path = ?1 WHERE id = ?2", params![path, id])?;
-            return Ok(PhotoId { id });
-        }
-        drop(rows);
-        drop(stmt);
+    /// Library revision: the latest entry of the persistent change log.
+    /// Pass it to `changes_since` later to learn what changed in between.
+    pub fn revision(&self) -> Result<u64, CoreError> {
+        let revision: i64 = self.read(|conn| {
+            Ok(conn.query_row("SELECT COALESCE(MAX(revision), 0) FROM change_log", [], |row| row.get(0))?)
+        })?;
+        Ok(revision as u64)
+    }
 
-        // 3. Insert new record
-        conn.execute(
-            "INSERT INTO photos (
-                path, hash, make, model, date_taken, width, height,
-                lat, lon, iso, f_number, exposure_time, orientation
-            )
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
-            params![
-                path,
-                hash,
-                metadata.make,
-                metadata.model,
-                metadata.date_taken,
-                metadata.width,
-                metadata.height,
-                metadata.lat,
-                metadata.lon,
-                metadata.iso,
-                metadata.f_number,
-                metadata.exposure_time,
-                metadata.orientation
-            ],
+    /// Photos added, updated or removed after `revision`, so a frontend can
+    /// reconcile its grid instead of refetching everything.
+    /// A photo added and removed again within the range isn't reported.
+    pub fn changes_since(&self, revision: u64) -> Result<PhotoChanges, CoreError> {
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let mut stmt = conn.prepare(
+            "SELECT revision, photo_id, kind FROM change_log WHERE revision > ?1 ORDER BY revision",
         )?;
+        let rows = stmt.query_map(params![revision as i64], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(2)?))
+        })?;
+
+        // photo_id -> (first kind, last kind) within the range
+        let mut changes = PhotoChanges { revision, ..Default::default() };
+        let mut per_photo: std::collections::BTreeMap<i64, (String, String)> = std::collections::BTreeMap::new();
+        for row in rows {
+            let (rev, photo_id, kind) = row?;
+            changes.revision = rev as u64;
+            per_photo
+                .entry(photo_id)
+                .and_modify(|(_, last)| *last = kind.clone())
+                .or_insert((kind.clone(), kind));
+        }
+
+        for (photo_id, (first, last)) in per_photo {
+            match (first.as_str(), last.as_str()) {
+                ("added", "removed") => {}
+                (_, "removed") => changes.removed.push(photo_id),
+                ("added", _) => changes.added.push(photo_id),
+                _ => changes.updated.push(photo_id),
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Indexes the photo at `path`. `hash` is its perceptual hash; the
+    /// file's bytes are checksummed here too, so a photo that only looks
+    /// like one already indexed (same `hash`, other content) is kept apart
+    /// while a moved one is recognized by its content.
+    pub fn insert(&self, path: String, hash: String, metadata: PhotoMetadata) -> Result<PhotoId, CoreError> {
+        // Read before locking, so checksumming a large original doesn't hold up other writers
+        let checksum = read_checksum(&path);
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        insert_photo(&conn, &path, &hash, checksum.as_deref(), &metadata)
+    }
 
-        Ok(PhotoId { id: conn.last_insert_rowid() })
+    /// Inserts many photos in one transaction, each following the rules of
+    /// `insert`. Far faster than an `insert` per photo for large imports.
+    /// Returns the ids in input order; nothing is written if any insert fails.
+    pub fn insert_batch(&self, photos: Vec<NewPhoto>) -> Result<Vec<PhotoId>, CoreError> {
+        let checksums: Vec<Option<String>> = photos.iter().map(|photo| read_checksum(&photo.path)).collect();
+        let mut conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let tx = conn.transaction()?;
+        let ids = photos
+            .iter()
+            .zip(&checksums)
+            .map(|(photo, checksum)| insert_photo(&tx, &photo.path, &photo.hash, checksum.as_deref(), &photo.metadata))
+            .collect::<Result<Vec<_>, _>>()?;
+        tx.commit()?;
+        Ok(ids)
     }
 
     pub fn get_by_path(&self, path: String) -> Result<Option<PhotoInfo>, CoreError> {
         let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
-        let mut stmt = conn.prepare(
-            "SELECT 
-                id, path, hash, make, model, date_taken, width, height,
-                lat, lon, iso, f_number, exposure_time, orientation 
-             FROM photos WHERE path = ?1",
+        let mut stmt = conn.prepare_cached(
+            &format!("SELECT {} FROM photos WHERE path = ?1", PHOTO_COLUMNS),
         )?;
         
-        let mut rows = stmt.query_map(params![path], |row| {
-            Ok(PhotoInfo {
-                id: PhotoId { id: row.get(0)? },
-                path: row.get(1)?,
-                hash: row.get(2)?,
-                metadata: PhotoMetadata {
-                    make: row.get(3)?,
-                    model: row.get(4)?,
-                    date_taken: row.get(5)?,
-                    width: row.get::<_, i64>(6)? as u32,
-                    height: row.get::<_, i64>(7)? as u32,
-                    lat: row.get(8)?,
-                    lon: row.get(9)?,
-                    iso: row.get::<_, Option<i64>>(10)?.map(|x| x as u32),
-                    f_number: row.get::<_, Option<f64>>(11)?.map(|x| x as f32),
-                    exposure_time: row.get(12)?,
-                    orientation: row.get::<_, i64>(13)? as u32,
-                },
-                thumb_path: None,
-                file_size: 0,
-                created_at: None,
-                modified_at: None,
-            })
-        })?;
+        let mut rows = stmt.query_map(params![path], photo_from_row)?;
 
         if let Some(res) = rows.next() {
             Ok(Some(res?))
@@ -132,40 +168,32 @@ impl PhotoIndex {
         }
     }
 
-    /// Get a photo by its ID.
-    pub fn get_by_id(&self, id: i64) -> Result<Option<PhotoInfo>, CoreError> {
+    /// Whether each of `paths` is already indexed, in the same order. One
+    /// query per batch instead of a `get_by_path` per file when sorting out
+    /// what an import still has to do.
+    pub fn paths_exist(&self, paths: Vec<String>) -> Result<Vec<bool>, CoreError> {
         let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
-        let mut stmt = conn.prepare(
-            "SELECT
-                id, path, hash, make, model, date_taken, width, height,
-                lat, lon, iso, f_number, exposure_time, orientation
-             FROM photos WHERE id = ?1",
+        let mut known = std::collections::HashSet::with_capacity(paths.len());
+        for chunk in paths.chunks(LOOKUP_BATCH) {
+            let placeholders = vec!["?"; chunk.len()].join(", ");
+            let mut stmt = conn.prepare_cached(&format!("SELECT path FROM photos WHERE path IN ({})", placeholders))?;
+            let rows = stmt.query_map(rusqlite::params_from_iter(chunk), |row| row.get::<_, String>(0))?;
+            for path in rows {
+                known.insert(path?);
+            }
+        }
+        Ok(paths.iter().map(|path| known.contains(path)).collect())
+    }
+
+    /// Get a photo by its perceptual hash. Lookalikes share one, so this
+    /// is the first of them; `PhotoInfo::checksum` tells exact copies apart.
+    pub fn get_by_hash(&self, hash: String) -> Result<Option<PhotoInfo>, CoreError> {
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let mut stmt = conn.prepare_cached(
+            &format!("SELECT {} FROM photos WHERE hash = ?1", PHOTO_COLUMNS),
         )?;
 
-        let mut rows = stmt.query_map(params![id], |row| {
-            Ok(PhotoInfo {
-                id: PhotoId { id: row.get(0)? },
-                path: row.get(1)?,
-                hash: row.get(2)?,
-                metadata: PhotoMetadata {
-                    make: row.get(3)?,
-                    model: row.get(4)?,
-                    date_taken: row.get(5)?,
-                    width: row.get::<_, i64>(6)? as u32,
-                    height: row.get::<_, i64>(7)? as u32,
-                    lat: row.get(8)?,
-                    lon: row.get(9)?,
-                    iso: row.get::<_, Option<i64>>(10)?.map(|x| x as u32),
-                    f_number: row.get::<_, Option<f64>>(11)?.map(|x| x as f32),
-                    exposure_time: row.get(12)?,
-                    orientation: row.get::<_, i64>(13)? as u32,
-                },
-                thumb_path: None,
-                file_size: 0,
-                created_at: None,
-                modified_at: None,
-            })
-        })?;
+        let mut rows = stmt.query_map(params![hash], photo_from_row)?;
 
         if let Some(res) = rows.next() {
             Ok(Some(res?))
@@ -174,168 +202,3140 @@ impl PhotoIndex {
         }
     }
 
+    /// Get a photo by its ID.
+    pub fn get_by_id(&self, id: i64) -> Result<Option<PhotoInfo>, CoreError> {
+        self.read(|conn| {
+            let mut stmt = conn.prepare_cached(
+                &format!("SELECT {} FROM photos WHERE id = ?1", PHOTO_COLUMNS),
+            )?;
+
+            let mut rows = stmt.query_map(params![id], photo_from_row)?;
+
+            if let Some(res) = rows.next() {
+                Ok(Some(res?))
+            } else {
+                Ok(None)
+            }
+        })
+    }
+
+    /// Photos by ID in one query per batch, in the order asked for. Unknown
+    /// IDs are left out.
+    pub fn get_by_ids(&self, ids: Vec<i64>) -> Result<Vec<PhotoInfo>, CoreError> {
+        self.read(|conn| fetch_by_ids(conn, &ids))
+    }
+
     /// Delete a photo by its ID.
+    /// Leaves a tombstone (hash, checksum + deleted_at) so imports can skip it later.
     /// Returns the deleted photo's info (including path) if found, None if not found.
     pub fn delete_by_id(&self, id: i64) -> Result<Option<PhotoInfo>, CoreError> {
-        // First get the photo info
-        let photo = self.get_by_id(id)?;
-
-        if photo.is_some() {
-            let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
-            conn.execute("DELETE FROM photos WHERE id = ?1", params![id])?;
+        let mut conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let tx = conn.transaction()?;
+        let photo = fetch_by_ids(&tx, &[id])?.pop();
+        if let Some(photo) = &photo {
+            tx.execute("DELETE FROM photos WHERE id = ?1", params![id])?;
+            forget_photo(&tx, photo)?;
         }
-
+        tx.commit()?;
         Ok(photo)
     }
 
     /// Delete multiple photos by their IDs.
     /// Returns the list of deleted photos' info.
     pub fn delete_by_ids(&self, ids: Vec<i64>) -> Result<Vec<PhotoInfo>, CoreError> {
-        let mut deleted = Vec::new();
+        let mut conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let tx = conn.transaction()?;
+        let mut deleted = fetch_by_ids(&tx, &ids)?;
+        // Each photo once, however often its ID was passed
+        let mut seen = std::collections::HashSet::new();
+        deleted.retain(|photo| seen.insert(photo.id.id));
+        {
+            let mut delete = tx.prepare_cached("DELETE FROM photos WHERE id = ?1")?;
+            for photo in &deleted {
+                delete.execute(params![photo.id.id])?;
+                forget_photo(&tx, photo)?;
+            }
+        }
+        tx.commit()?;
+        Ok(deleted)
+    }
 
-        for id in ids {
-            if let Some(photo) = self.delete_by_id(id)? {
-                deleted.push(photo);
+    /// Whether the file at `path`, with perceptual hash `hash`, is a photo
+    /// that was deliberately deleted from the library, or is in the trash.
+    /// Lookalikes sharing the hash are told apart by checksum, which is only
+    /// read when one of them was deleted; deletions recorded before checksums
+    /// (or of a file that can't be read) still go by the hash alone.
+    pub fn is_deleted(&self, path: String, hash: String) -> Result<bool, CoreError> {
+        let deleted: Vec<Option<String>> = self.read(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT checksum FROM tombstones WHERE hash = ?1 UNION ALL SELECT checksum FROM trash WHERE hash = ?1",
+            )?;
+            let checksums = stmt.query_map(params![hash], |row| row.get(0))?.collect::<Result<_, _>>()?;
+            Ok(checksums)
+        })?;
+        if deleted.is_empty() {
+            return Ok(false);
+        }
+        if deleted.iter().any(Option::is_none) {
+            return Ok(true);
+        }
+        Ok(read_checksum(&path).is_none_or(|checksum| deleted.contains(&Some(checksum))))
+    }
+
+    /// Drops tombstones of photos deleted before `before` (unix seconds), letting
+    /// them be re-imported again. Pass `i64::MAX` to forget all deletions.
+    /// Returns how many tombstones were removed.
+    pub fn purge_tombstones(&self, before: i64) -> Result<u32, CoreError> {
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        Ok(conn.execute("DELETE FROM tombstones WHERE deleted_at < ?1", params![before])? as u32)
+    }
+
+    /// Moves photos to the trash. They drop out of the library but keep their
+    /// albums, weather and thumbnails until the trash is emptied or purged.
+    /// Returns how many were moved.
+    pub fn move_to_trash(&self, ids: Vec<i64>) -> Result<u32, CoreError> {
+        let mut conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let tx = conn.transaction()?;
+        let mut moved = 0;
+        {
+            let mut copy = tx.prepare(&format!(
+                "INSERT OR REPLACE INTO trash ({0}, {1}, trashed_at) SELECT {0}, {1}, ?2 FROM photos WHERE id = ?1",
+                PHOTO_COLUMNS, FILE_STATE_COLUMNS
+            ))?;
+            let mut delete = tx.prepare("DELETE FROM photos WHERE id = ?1")?;
+            let now = unix_now();
+            for id in ids {
+                if copy.execute(params![id, now])? > 0 {
+                    delete.execute(params![id])?;
+                    moved += 1;
+                }
             }
         }
+        tx.commit()?;
+        Ok(moved)
+    }
 
-        Ok(deleted)
+    /// Puts trashed photos back into the library. A photo whose path or content
+    /// was imported again in the meantime stays in the trash.
+    /// Returns how many were restored.
+    pub fn restore_from_trash(&self, ids: Vec<i64>) -> Result<u32, CoreError> {
+        let mut conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let tx = conn.transaction()?;
+        let mut restored = 0;
+        for id in ids {
+            restored += untrash(&tx, id)? as u32;
+        }
+        tx.commit()?;
+        Ok(restored)
     }
 
-    /// Returns a list of all photos in the index.
-    ///
-    /// ### ⚠️ Performance & Scale Note
-    /// Current implementation returns a full `Vec<PhotoInfo>` from the database.
-    /// For very large databases, this may lead to significant memory spikes.
-    ///
-    /// **Recommendations for Callers:**
-    /// - Avoid calling this frequently on the full database if UI virtualization is not used.
-    /// - Future versions may introduce `LIMIT/OFFSET` paging or an iterator API.
-    pub fn list(&self) -> Result<Vec<PhotoInfo>, CoreError> {
+    /// The trash, most recently trashed first.
+    pub fn list_trash(&self) -> Result<Vec<TrashedPhoto>, CoreError> {
         let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
-        let mut stmt = conn.prepare(
-            "SELECT 
-                id, path, hash, make, model, date_taken, width, height,
-                lat, lon, iso, f_number, exposure_time, orientation 
-             FROM photos",
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}, trashed_at FROM trash ORDER BY trashed_at DESC, id DESC",
+            PHOTO_COLUMNS
+        ))?;
+        let rows = stmt.query_map([], |row| {
+            Ok(TrashedPhoto { photo: photo_from_row(row)?, trashed_at: row.get(PHOTO_COLUMN_COUNT)? })
+        })?;
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Permanently deletes everything in the trash, leaving tombstones as
+    /// `delete_by_id` does. Returns the deleted photos so callers can remove
+    /// their thumbnails.
+    pub fn empty_trash(&self) -> Result<Vec<PhotoInfo>, CoreError> {
+        self.purge_trash_before(i64::MAX)
+    }
+
+    /// Permanently deletes photos that have been in the trash for more than
+    /// `retention_days`, like `empty_trash`. Meant to run at startup.
+    pub fn purge_trash(&self, retention_days: u32) -> Result<Vec<PhotoInfo>, CoreError> {
+        self.purge_trash_before(unix_now() - retention_days as i64 * 86_400)
+    }
+
+    /// Queues a photo a native picker handed over, before any work is done on
+    /// it, so an import interrupted by a crash picks up where it stopped.
+    /// `identifier` is the picker's id for the asset; queuing it again while
+    /// it's pending is a no-op. Returns whether it was newly queued.
+    pub fn enqueue_intake(&self, identifier: String, payload_path: String) -> Result<bool, CoreError> {
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let queued = conn
+            .prepare_cached("INSERT OR IGNORE INTO intake_queue (identifier, payload_path, queued_at) VALUES (?1, ?2, ?3)")?
+            .execute(params![identifier, payload_path, unix_now()])?;
+        Ok(queued > 0)
+    }
+
+    /// Up to `limit` queued photos, oldest first.
+    pub fn pending_intake(&self, limit: u32) -> Result<Vec<IntakeItem>, CoreError> {
+        self.read(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT identifier, payload_path, queued_at, attempts FROM intake_queue
+                 ORDER BY queued_at, rowid LIMIT ?1",
+            )?;
+            let rows = stmt.query_map(params![limit], |row| {
+                Ok(IntakeItem {
+                    identifier: row.get(0)?,
+                    payload_path: row.get(1)?,
+                    queued_at: row.get(2)?,
+                    attempts: row.get(3)?,
+                })
+            })?;
+            Ok(rows.collect::<Result<Vec<_>, _>>()?)
+        })
+    }
+
+    /// Takes a photo off the intake queue once it's imported (or given up on).
+    pub fn complete_intake(&self, identifier: String) -> Result<(), CoreError> {
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        conn.prepare_cached("DELETE FROM intake_queue WHERE identifier = ?1")?.execute(params![identifier])?;
+        Ok(())
+    }
+
+    /// Counts a failed import attempt of a queued photo and returns the new
+    /// count.
+    pub fn fail_intake(&self, identifier: String) -> Result<u32, CoreError> {
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let attempts = conn
+            .prepare_cached("UPDATE intake_queue SET attempts = attempts + 1 WHERE identifier = ?1 RETURNING attempts")?
+            .query_row(params![identifier], |row| row.get(0))
+            .optional()?;
+        Ok(attempts.unwrap_or(0))
+    }
+
+    /// Starts journaling an import of `root` over `paths`, or continues the
+    /// unfinished one for `root`: files new since then are added, and files
+    /// it already handled stay done. Work through `pending_import_files`.
+    pub fn begin_import_session(&self, root: String, paths: Vec<String>) -> Result<ImportSession, CoreError> {
+        let (id, existing) = {
+            let mut conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+            let tx = conn.transaction()?;
+            let existing: Option<i64> = tx
+                .prepare_cached("SELECT id FROM import_sessions WHERE root = ?1")?
+                .query_row(params![root], |row| row.get(0))
+                .optional()?;
+            let id = match existing {
+                Some(id) => id,
+                None => {
+                    tx.execute("INSERT INTO import_sessions (root, started_at) VALUES (?1, ?2)", params![root, unix_now()])?;
+                    tx.last_insert_rowid()
+                }
+            };
+            {
+                let mut add = tx.prepare_cached("INSERT OR IGNORE INTO import_session_files (session_id, path) VALUES (?1, ?2)")?;
+                for path in &paths {
+                    add.execute(params![id, path])?;
+                }
+            }
+            tx.commit()?;
+            (id, existing.is_some())
+        };
+        let session = self.unfinished_import_sessions()?.into_iter().find(|session| session.id == id);
+        let session = session.ok_or_else(|| CoreError::Database(format!("import session {} vanished", id)))?;
+        Ok(ImportSession { resumed: existing, ..session })
+    }
 
+    /// Imports that were interrupted, oldest first, to offer continuing them.
+    pub fn unfinished_import_sessions(&self) -> Result<Vec<ImportSession>, CoreError> {
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, root, started_at,
+                (SELECT COUNT(*) FROM import_session_files WHERE session_id = id AND done = 1),
+                (SELECT COUNT(*) FROM import_session_files WHERE session_id = id AND done = 0)
+             FROM import_sessions ORDER BY started_at, id",
+        )?;
         let rows = stmt.query_map([], |row| {
-            Ok(PhotoInfo {
-                id: PhotoId { id: row.get(0)? },
-                path: row.get(1)?,
-                hash: row.get(2)?,
-                metadata: PhotoMetadata {
-                    make: row.get(3)?,
-                    model: row.get(4)?,
-                    date_taken: row.get(5)?,
-                    width: row.get::<_, i64>(6)? as u32,
-                    height: row.get::<_, i64>(7)? as u32,
-                    lat: row.get(8)?,
-                    lon: row.get(9)?,
-                    iso: row.get::<_, Option<i64>>(10)?.map(|x| x as u32),
-                    f_number: row.get::<_, Option<f64>>(11)?.map(|x| x as f32),
-                    exposure_time: row.get(12)?,
-                    orientation: row.get::<_, i64>(13)? as u32,
-                },
-                thumb_path: None,
-                file_size: 0,
-                created_at: None,
-                modified_at: None,
+            Ok(ImportSession {
+                id: row.get(0)?,
+                root: row.get(1)?,
+                started_at: row.get(2)?,
+                done: row.get(3)?,
+                pending: row.get(4)?,
+                // Importing the root again would continue it
+                resumed: true,
             })
         })?;
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
 
-        Ok(rows.filter_map(Result::ok).collect())
+    /// Files of an import session not handled yet, in the order they were added.
+    pub fn pending_import_files(&self, session_id: i64) -> Result<Vec<String>, CoreError> {
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT path FROM import_session_files WHERE session_id = ?1 AND done = 0 ORDER BY rowid",
+        )?;
+        let rows = stmt.query_map(params![session_id], |row| row.get(0))?;
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Records files of an import session as handled, whatever the outcome.
+    /// Call once their photos are in the index, so a crash in between only
+    /// means doing them again.
+    pub fn mark_import_files_done(&self, session_id: i64, paths: Vec<String>) -> Result<(), CoreError> {
+        let mut conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let tx = conn.transaction()?;
+        {
+            let mut mark = tx.prepare_cached("UPDATE import_session_files SET done = 1 WHERE session_id = ?1 AND path = ?2")?;
+            for path in paths {
+                mark.execute(params![session_id, path])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
 
-    fn setup_test_index() -> std::sync::Arc<PhotoIndex> {
-        // Use in-memory database for deterministic, file-system-independent testing
-        let conn = Connection::open_in_memory().unwrap();
-        conn.execute_batch(
-            "CREATE TABLE photos (
-                id INTEGER PRIMARY KEY,
-                path TEXT NOT NULL UNIQUE,
-                hash TEXT NOT NULL,
-                make TEXT,
-                model TEXT,
-                date_taken TEXT,
-                width INTEGER,
-                height INTEGER,
-                lat REAL,
-                lon REAL,
-                iso INTEGER,
-                f_number REAL,
-                exposure_time TEXT,
-                orientation INTEGER
-            );
-            CREATE INDEX idx_photos_hash ON photos (hash);",
-        ).unwrap();
-        std::sync::Arc::new(PhotoIndex { conn: Mutex::new(conn) })
+    /// Closes a completed import session, dropping its journal.
+    pub fn finish_import_session(&self, session_id: i64) -> Result<(), CoreError> {
+        let mut conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM import_session_files WHERE session_id = ?1", params![session_id])?;
+        tx.execute("DELETE FROM import_sessions WHERE id = ?1", params![session_id])?;
+        tx.commit()?;
+        Ok(())
     }
 
-    #[test]
-    fn test_index_uniqueness_invariant() {
-        let index = setup_test_index();
-        let metadata = PhotoMetadata::default();
-        let path = "/test/photo.jpg".to_string();
-        let hash = "hash1".to_string();
+    /// Summarizes a selection (size, date span, cameras, GPS coverage) so the
+    /// UI doesn't need every row to show "1,204 photos · 8.3 GB · Mar–Jul 2022".
+    pub fn summarize_selection(&self, ids: Vec<i64>) -> Result<SelectionSummary, CoreError> {
+        self.read(|conn| {
+            let mut summary = SelectionSummary::default();
+            let mut cameras = std::collections::BTreeSet::new();
+            let (mut first, mut last) = (None::<i64>, None::<i64>);
 
-        let id1 = index.insert(path.clone(), hash.clone(), metadata.clone()).expect("First insert failed");
-        let id2 = index.insert(path, hash, metadata).expect("Second insert failed");
+            for chunk in ids.chunks(LOOKUP_BATCH) {
+                let placeholders = vec!["?"; chunk.len()].join(", ");
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT file_size, make, model, taken_at, lat IS NOT NULL AND lon IS NOT NULL
+                     FROM photos WHERE id IN ({})",
+                    placeholders
+                ))?;
+                let rows = stmt.query_map(rusqlite::params_from_iter(chunk), |row| {
+                    Ok((
+                        row.get::<_, Option<i64>>(0)?,
+                        row.get::<_, Option<String>>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                        row.get::<_, Option<i64>>(3)?,
+                        row.get::<_, bool>(4)?,
+                    ))
+                })?;
 
-        // Contract: Same path returns same ID
-        assert_eq!(id1, id2);
+                for row in rows {
+                    let (size, make, model, taken_at, has_gps) = row?;
+                    summary.count += 1;
+                    summary.total_bytes += size.unwrap_or(0) as u64;
+                    if has_gps {
+                        summary.geotagged += 1;
+                    }
+                    // `taken_at` includes dates set by hand, which `date_taken` doesn't
+                    if let Some(taken_at) = taken_at {
+                        first = Some(first.map_or(taken_at, |first| first.min(taken_at)));
+                        last = Some(last.map_or(taken_at, |last| last.max(taken_at)));
+                    }
+                    if let Some(camera) = camera_name(make.as_deref(), model.as_deref()) {
+                        cameras.insert(camera);
+                    }
+                }
+            }
 
-        let conn = index.conn.lock().unwrap();
-        let count: i64 = conn.query_row("SELECT COUNT(*) FROM photos", [], |r| r.get(0)).unwrap();
-        assert_eq!(count, 1);
+            summary.date_start = first.map(crate::metadata::date::exif_date);
+            summary.date_end = last.map(crate::metadata::date::exif_date);
+            summary.cameras = cameras.into_iter().collect();
+            Ok(summary)
+        })
     }
 
-    #[test]
-    fn test_hash_deduplication_updates_path() {
-        let index = setup_test_index();
-        let metadata = PhotoMetadata::default();
-        let hash = "same_hash".to_string();
-        let old_path = "/sdcard/photo.jpg".to_string();
-        let new_path = "/local/photo.jpg".to_string();
-
-        // Insert with old path
-        let id1 = index.insert(old_path.clone(), hash.clone(), metadata.clone()).expect("First insert failed");
+    /// Geotag photos (e.g. dropped onto the map). Pass `None` for both to clear the location.
+    /// Only the index is updated - original files are never rewritten.
+    /// Returns the previous coordinates of each updated photo so the caller can undo.
+    pub fn set_location(&self, ids: Vec<i64>, lat: Option<f64>, lon: Option<f64>) -> Result<Vec<PhotoLocation>, CoreError> {
+        match (lat, lon) {
+            (Some(lat), Some(lon)) => {
+                if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+                    return Err(CoreError::InvalidInput(format!("Coordinates out of range: {}, {}", lat, lon)));
+                }
+            }
+            (None, None) => {}
+            _ => return Err(CoreError::InvalidInput("lat and lon must be set together".to_string())),
+        }
 
-        // Insert same hash with new path - should update, not create new
-        let id2 = index.insert(new_path.clone(), hash.clone(), metadata).expect("Second insert failed");
+        let mut conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let tx = conn.transaction()?;
+        let mut previous = Vec::new();
+        {
+            let mut select = tx.prepare("SELECT lat, lon FROM photos WHERE id = ?1")?;
+            for id in ids {
+                let mut rows = select.query_map(params![id], |row| {
+                    Ok(PhotoLocation { id, lat: row.get(0)?, lon: row.get(1)? })
+                })?;
+                if let Some(location) = rows.next() {
+                    previous.push(location?);
+                    write_location(&tx, id, lat, lon)?;
+                }
+            }
+        }
+        tx.commit()?;
 
-        // Contract: Same hash returns same ID
-        assert_eq!(id1, id2);
+        Ok(previous)
+    }
 
-        // Only one record in database
-        let conn = index.conn.lock().unwrap();
-        let count: i64 = conn.query_row("SELECT COUNT(*) FROM photos", [], |r| r.get(0)).unwrap();
-        assert_eq!(count, 1);
+    /// Undo for `set_location`: writes back the coordinates it returned.
+    pub fn restore_locations(&self, locations: Vec<PhotoLocation>) -> Result<(), CoreError> {
+        let mut conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let tx = conn.transaction()?;
+        for location in &locations {
+            write_location(&tx, location.id, location.lat, location.lon)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
 
-        // Path should be updated to new location
-        let stored_path: String = conn.query_row("SELECT path FROM photos WHERE id = ?1", [id1.id], |r| r.get(0)).unwrap();
-        assert_eq!(stored_path, new_path);
+    /// Replaces the stored hash and metadata of a photo after its file changed
+    /// (e.g. rewritten by orientation normalization). The user's rotation is
+    /// kept, and so is a hand-entered date unless the file now has its own.
+    /// Returns false if `id` doesn't exist.
+    pub fn refresh(&self, id: i64, hash: String, metadata: PhotoMetadata) -> Result<bool, CoreError> {
+        let path: Option<String> = self.read(|conn| {
+            Ok(conn.prepare_cached("SELECT path FROM photos WHERE id = ?1")?.query_row(params![id], |row| row.get(0)).optional()?)
+        })?;
+        let Some(path) = path else { return Ok(false) };
+        let checksum = read_checksum(&path);
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let stats = file_stats(&decode_path(&path));
+        let updated = conn.execute(
+            "UPDATE photos SET
+                hash = ?1, make = ?2, model = ?3, date_taken = ?4, width = ?5, height = ?6,
+                lat = ?7, lon = ?8, iso = ?9, f_number = ?10, exposure_time = ?11, orientation = ?12,
+                has_motion = ?13, is_portrait = ?14, is_proraw = ?15, light = ?16, moon_phase = ?17,
+                taken_at = CASE WHEN ?18 IS NULL AND date_precision IS NOT NULL THEN taken_at ELSE ?18 END,
+                date_precision = CASE WHEN ?18 IS NULL THEN date_precision END,
+                color_profile = ?19, bit_depth = ?20, chroma_subsampling = ?21, is_scan = ?22,
+                file_size = ?23, file_mtime = ?24,
+                media_type = ?25, duration_ms = ?26, video_codec = ?27, description = ?28
+             WHERE id = ?29",
+            params![
+                hash,
+                metadata.make,
+                metadata.model,
+                metadata.date_taken,
+                metadata.width,
+                metadata.height,
+                metadata.lat,
+                metadata.lon,
+                metadata.iso,
+                metadata.f_number,
+                metadata.exposure_time,
+                metadata.orientation,
+                metadata.has_motion,
+                metadata.is_portrait,
+                metadata.is_proraw,
+                metadata.light,
+                metadata.moon_phase,
+                taken_at(&metadata),
+                metadata.color_profile,
+                metadata.bit_depth,
+                metadata.chroma_subsampling,
+                metadata.is_scan,
+                stats.map(|(size, _)| size as i64),
+                stats.map(|(_, mtime)| mtime),
+                crate::metadata::video::media_type_name(metadata.media_type),
+                metadata.duration_ms.map(|x| x as i64),
+                metadata.video_codec,
+                metadata.description,
+                id
+            ],
+        )?;
+        // The old checksum would now flag the edited file as corrupt
+        record_checksum(&conn, id, checksum.as_deref(), stats)?;
+        Ok(updated > 0)
     }
 
-    #[test]
-    fn test_index_scale_performance_degradation() {
-        let index = setup_test_index();
-        let metadata = PhotoMetadata::default();
+    /// Rotates a photo by `degrees` (a multiple of 90, negative for
+    /// counter-clockwise) on top of its current rotation. Only the stored
+    /// rotation changes; callers regenerate thumbnails for the new value.
+    /// Returns the updated photo, or `None` if `id` doesn't exist.
+    pub fn rotate(&self, id: i64, degrees: i32) -> Result<Option<PhotoInfo>, CoreError> {
+        if degrees % 90 != 0 {
+            return Err(CoreError::InvalidInput(format!("Rotation must be a multiple of 90 degrees, got {}", degrees)));
+        }
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        conn.execute(
+            "UPDATE photos SET rotation = (((rotation + ?1) % 360) + 360) % 360 WHERE id = ?2",
+            params![degrees, id],
+        )?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM photos WHERE id = ?1", PHOTO_COLUMNS))?;
+        let mut rows = stmt.query_map(params![id], photo_from_row)?;
+        match rows.next() {
+            Some(photo) => Ok(Some(photo?)),
+            None => Ok(None),
+        }
+    }
 
-        // Scale test: Insertion of many items (each with unique hash)
-        for i in 0..1000 {
-            let path = format!("/path/photo_{}.jpg", i);
-            let hash = format!("hash_{}", i);
-            index.insert(path, hash, metadata.clone()).expect("Bulk insert failed");
+    /// Saves a user-named place. `place.id` is ignored; returns the new id.
+    pub fn add_place(&self, place: Place) -> Result<i64, CoreError> {
+        if place.name.trim().is_empty() {
+            return Err(CoreError::InvalidInput("Place name is empty".to_string()));
+        }
+        if place.polygon.is_empty() && place.radius_m <= 0.0 {
+            return Err(CoreError::InvalidInput("Place radius must be positive".to_string()));
+        }
+        if !place.polygon.is_empty() && place.polygon.len() < 3 {
+            return Err(CoreError::InvalidInput("Place polygon needs at least 3 points".to_string()));
         }
 
-        let list = index.list().expect("List failed");
-        assert_eq!(list.len(), 1000);
+        let (min_lat, max_lat, min_lon, max_lon) = geo::bounding_box(&place);
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        conn.execute(
+            "INSERT INTO places (name, lat, lon, radius_m, polygon, min_lat, max_lat, min_lon, max_lon)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                place.name,
+                place.lat,
+                place.lon,
+                place.radius_m,
+                geo::encode_polygon(&place.polygon),
+                min_lat,
+                max_lat,
+                min_lon,
+                max_lon
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn list_places(&self) -> Result<Vec<Place>, CoreError> {
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM places ORDER BY fold(name), name", PLACE_COLUMNS))?;
+        let rows = stmt.query_map([], place_from_row)?;
+        Ok(rows.filter_map(Result::ok).collect())
+    }
+
+    /// Saved places whose name has words starting with every word of `query`,
+    /// ignoring case and diacritics ("sao" finds "São Paulo"), in
+    /// `list_places` order.
+    pub fn search_places(&self, query: String) -> Result<Vec<Place>, CoreError> {
+        let mut places = self.list_places()?;
+        places.retain(|place| super::search::matches_words(&place.name, &query));
+        Ok(places)
+    }
+
+    /// Returns false if no place had this id.
+    pub fn delete_place(&self, id: i64) -> Result<bool, CoreError> {
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        Ok(conn.execute("DELETE FROM places WHERE id = ?1", params![id])? > 0)
+    }
+
+    pub fn create_album(&self, name: String) -> Result<i64, CoreError> {
+        let name = album_name(&name)?;
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        conn.execute("INSERT INTO albums (name, created_at) VALUES (?1, ?2)", params![name, unix_now()])?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Returns false if no album had this id.
+    pub fn rename_album(&self, id: i64, name: String) -> Result<bool, CoreError> {
+        let name = album_name(&name)?;
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        Ok(conn.execute("UPDATE albums SET name = ?1 WHERE id = ?2", params![name, id])? > 0)
+    }
+
+    /// Deletes the album only; its photos stay in the library.
+    /// Returns false if no album had this id.
+    pub fn delete_album(&self, id: i64) -> Result<bool, CoreError> {
+        let mut conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM album_photos WHERE album_id = ?1", params![id])?;
+        let deleted = tx.execute("DELETE FROM albums WHERE id = ?1", params![id])? > 0;
+        tx.commit()?;
+        Ok(deleted)
+    }
+
+    pub fn list_albums(&self) -> Result<Vec<PhotoAlbum>, CoreError> {
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, created_at,
+                (SELECT COUNT(*) FROM album_photos JOIN photos ON photos.id = photo_id WHERE album_id = albums.id),
+                (SELECT photo_id FROM album_photos JOIN photos ON photos.id = photo_id
+                 WHERE album_id = albums.id ORDER BY album_photos.rowid LIMIT 1)
+             FROM albums ORDER BY fold(name), id",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(PhotoAlbum {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                created_at: row.get(2)?,
+                photo_count: row.get::<_, i64>(3)? as u32,
+                cover_photo_id: row.get(4)?,
+            })
+        })?;
+        Ok(rows.filter_map(Result::ok).collect())
+    }
+
+    /// Adds photos to an album, skipping ones already in it or not in the
+    /// library. Returns how many were added.
+    pub fn add_to_album(&self, album_id: i64, photo_ids: Vec<i64>) -> Result<u32, CoreError> {
+        let mut conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let tx = conn.transaction()?;
+        let exists: bool = tx.query_row("SELECT EXISTS(SELECT 1 FROM albums WHERE id = ?1)", params![album_id], |row| row.get(0))?;
+        if !exists {
+            return Err(CoreError::InvalidInput(format!("Unknown album {}", album_id)));
+        }
+        let mut added = 0;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR IGNORE INTO album_photos (album_id, photo_id, added_at)
+                 SELECT ?1, id, ?2 FROM photos WHERE id = ?3",
+            )?;
+            let now = unix_now();
+            for photo_id in photo_ids {
+                added += stmt.execute(params![album_id, now, photo_id])? as u32;
+            }
+        }
+        tx.commit()?;
+        Ok(added)
+    }
+
+    /// Removes photos from an album (not from the library). Returns how many were removed.
+    pub fn remove_from_album(&self, album_id: i64, photo_ids: Vec<i64>) -> Result<u32, CoreError> {
+        let mut conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let tx = conn.transaction()?;
+        let mut removed = 0;
+        {
+            let mut stmt = tx.prepare("DELETE FROM album_photos WHERE album_id = ?1 AND photo_id = ?2")?;
+            for photo_id in photo_ids {
+                removed += stmt.execute(params![album_id, photo_id])? as u32;
+            }
+        }
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    /// An album's photos in the order they were added.
+    pub fn list_album_photos(&self, album_id: i64) -> Result<Vec<PhotoInfo>, CoreError> {
+        self.cached_photos(
+            &format!(
+                "SELECT {} FROM photos JOIN album_photos ON photo_id = id WHERE album_id = ?1 ORDER BY album_photos.rowid",
+                PHOTO_COLUMNS
+            ),
+            &[album_id.into()],
+        )
+    }
+
+    /// Resolves a photo to the places containing its location (empty if not geotagged).
+    pub fn places_for_photo(&self, photo_id: i64) -> Result<Vec<Place>, CoreError> {
+        let photo = match self.get_by_id(photo_id)? {
+            Some(photo) => photo,
+            None => return Ok(Vec::new()),
+        };
+        let point = match (photo.metadata.lat, photo.metadata.lon) {
+            (Some(lat), Some(lon)) => GeoPoint { lat, lon },
+            _ => return Ok(Vec::new()),
+        };
+
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM places WHERE ?1 BETWEEN min_lat AND max_lat AND ?2 BETWEEN min_lon AND max_lon ORDER BY name",
+            PLACE_COLUMNS
+        ))?;
+        let rows = stmt.query_map(params![point.lat, point.lon], place_from_row)?;
+        Ok(rows.filter_map(Result::ok).filter(|place| geo::place_contains(place, point)).collect())
+    }
+
+    /// Photos taken inside a place. The bounding box is matched with the lat/lon index,
+    /// then refined with the exact circle/polygon test.
+    pub fn list_by_place(&self, place_id: i64) -> Result<Vec<PhotoInfo>, CoreError> {
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM places WHERE id = ?1", PLACE_COLUMNS))?;
+        let place = match stmt.query_map(params![place_id], place_from_row)?.next() {
+            Some(place) => place?,
+            None => return Err(CoreError::InvalidInput(format!("Unknown place {}", place_id))),
+        };
+
+        let (min_lat, max_lat, min_lon, max_lon) = geo::bounding_box(&place);
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM photos WHERE lat BETWEEN ?1 AND ?2 AND lon BETWEEN ?3 AND ?4",
+            PHOTO_COLUMNS
+        ))?;
+        let rows = stmt.query_map(params![min_lat, max_lat, min_lon, max_lon], photo_from_row)?;
+
+        Ok(rows
+            .filter_map(Result::ok)
+            .filter(|photo| match (photo.metadata.lat, photo.metadata.lon) {
+                (Some(lat), Some(lon)) => geo::place_contains(&place, GeoPoint { lat, lon }),
+                _ => false,
+            })
+            .collect())
+    }
+
+    /// Geotagged, dated photos that have no weather yet - the work list of the enrichment pass.
+    pub fn list_missing_weather(&self) -> Result<Vec<PhotoInfo>, CoreError> {
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM photos
+             WHERE lat IS NOT NULL AND lon IS NOT NULL AND taken_at IS NOT NULL
+               AND id NOT IN (SELECT photo_id FROM photo_weather)",
+            PHOTO_COLUMNS
+        ))?;
+        let rows = stmt.query_map([], photo_from_row)?;
+        Ok(rows.filter_map(Result::ok).collect())
+    }
+
+    pub fn set_weather(&self, photo_id: i64, weather: Weather) -> Result<(), CoreError> {
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        conn.execute(
+            "INSERT OR REPLACE INTO photo_weather (photo_id, temperature_c, condition, source)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![photo_id, weather.temperature_c, weather.condition.to_lowercase(), weather.source],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_weather(&self, photo_id: i64) -> Result<Option<Weather>, CoreError> {
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let mut stmt = conn.prepare("SELECT temperature_c, condition, source FROM photo_weather WHERE photo_id = ?1")?;
+        let mut rows = stmt.query_map(params![photo_id], |row| {
+            Ok(Weather { temperature_c: row.get(0)?, condition: row.get(1)?, source: row.get(2)? })
+        })?;
+        match rows.next() {
+            Some(weather) => Ok(Some(weather?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Records the web page or image URL a photo was saved from.
+    pub fn set_source_url(&self, photo_id: i64, url: String) -> Result<(), CoreError> {
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        conn.execute(
+            "INSERT OR REPLACE INTO photo_sources (photo_id, url) VALUES (?1, ?2)",
+            params![photo_id, url],
+        )?;
+        Ok(())
+    }
+
+    /// Where a photo was downloaded from, if it came from the web.
+    pub fn get_source_url(&self, photo_id: i64) -> Result<Option<String>, CoreError> {
+        self.read(|conn| {
+            Ok(conn
+                .query_row("SELECT url FROM photo_sources WHERE photo_id = ?1", params![photo_id], |row| row.get(0))
+                .optional()?)
+        })
+    }
+
+    /// Counts a view of `photo_id`, e.g. when it's opened full size, and
+    /// stamps it as last viewed now. Returns false for an unknown photo.
+    /// Views aren't changes: the library revision stays put.
+    pub fn record_view(&self, photo_id: i64) -> Result<bool, CoreError> {
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let recorded = conn.prepare_cached(
+            "INSERT INTO photo_views (photo_id, view_count, last_viewed_at)
+             SELECT id, 1, ?2 FROM photos WHERE id = ?1
+             ON CONFLICT (photo_id) DO UPDATE SET view_count = view_count + 1, last_viewed_at = excluded.last_viewed_at",
+        )?
+        .execute(params![photo_id, unix_now()])?;
+        Ok(recorded > 0)
+    }
+
+    /// How often `photo_id` was viewed and when last.
+    pub fn view_stats(&self, photo_id: i64) -> Result<PhotoViews, CoreError> {
+        let views = self.read(|conn| {
+            Ok(conn
+                .prepare_cached("SELECT view_count, last_viewed_at FROM photo_views WHERE photo_id = ?1")?
+                .query_row(params![photo_id], |row| Ok(PhotoViews { view_count: row.get::<_, i64>(0)? as u32, last_viewed_at: row.get(1)? }))
+                .optional()?)
+        })?;
+        Ok(views.unwrap_or_default())
+    }
+
+    /// Stores `photo_id`'s auto-enhance edit, replacing the one it had.
+    /// Returns false for an unknown photo.
+    pub fn set_enhance(&self, photo_id: i64, enhance: PhotoEnhance) -> Result<bool, CoreError> {
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let recipe = enhance.recipe;
+        let stored = conn.prepare_cached(
+            "INSERT OR REPLACE INTO photo_edits (photo_id, black_point, white_point, gain_r, gain_g, gain_b, saturation, enabled)
+             SELECT id, ?2, ?3, ?4, ?5, ?6, ?7, ?8 FROM photos WHERE id = ?1",
+        )?
+        .execute(params![
+            photo_id,
+            recipe.black_point,
+            recipe.white_point,
+            recipe.gain_r,
+            recipe.gain_g,
+            recipe.gain_b,
+            recipe.saturation,
+            enhance.enabled
+        ])?;
+        Ok(stored > 0)
+    }
+
+    /// `photo_id`'s auto-enhance edit, `None` if it was never enhanced.
+    pub fn get_enhance(&self, photo_id: i64) -> Result<Option<PhotoEnhance>, CoreError> {
+        self.read(|conn| {
+            Ok(conn
+                .prepare_cached(
+                    "SELECT black_point, white_point, gain_r, gain_g, gain_b, saturation, enabled FROM photo_edits WHERE photo_id = ?1",
+                )?
+                .query_row(params![photo_id], |row| {
+                    Ok(PhotoEnhance {
+                        recipe: crate::image::AutoEnhance {
+                            black_point: row.get(0)?,
+                            white_point: row.get(1)?,
+                            gain_r: row.get::<_, f64>(2)? as f32,
+                            gain_g: row.get::<_, f64>(3)? as f32,
+                            gain_b: row.get::<_, f64>(4)? as f32,
+                            saturation: row.get::<_, f64>(5)? as f32,
+                        },
+                        enabled: row.get(6)?,
+                    })
+                })
+                .optional()?)
+        })
+    }
+
+    /// Photos taken in the given weather condition ("snow" for snowy photos).
+    pub fn list_by_weather(&self, condition: String) -> Result<Vec<PhotoInfo>, CoreError> {
+        self.cached_photos(
+            &format!(
+                "SELECT {} FROM photos WHERE id IN (SELECT photo_id FROM photo_weather WHERE condition = ?1)",
+                PHOTO_COLUMNS
+            ),
+            &[condition.to_lowercase().into()],
+        )
+    }
+
+    /// Photos taken in the given light: "day", "golden_hour", "blue_hour" or "night".
+    pub fn list_by_light(&self, light: String) -> Result<Vec<PhotoInfo>, CoreError> {
+        self.cached_photos(&format!("SELECT {} FROM photos WHERE light = ?1", PHOTO_COLUMNS), &[light.into()])
+    }
+
+    /// Photos matching every filter set in `query`, in its order.
+    pub fn query(&self, query: PhotoQuery) -> Result<Vec<PhotoInfo>, CoreError> {
+        self.query_photos(&query)
+    }
+
+    /// Up to `before`/`after` photos either side of `id` in the order `query`
+    /// lists them, for the filmstrip under the detail view. Empty if `id`
+    /// isn't among the query's results.
+    pub fn neighbors(&self, id: i64, query: PhotoQuery, before: u32, after: u32) -> Result<Neighbors, CoreError> {
+        let sql = self.query_sql(&query)?;
+        let mut params = sql.params;
+        params.extend([id.into(), before.into(), after.into()]);
+        // Only the window's rows leave SQLite, numbered relative to `id`
+        self.read(|conn| {
+            let mut stmt = conn.prepare_cached(&format!(
+                "WITH ranked AS (SELECT id, ROW_NUMBER() OVER (ORDER BY {}) AS pos FROM {} {})
+                 SELECT ranked.id, ranked.pos - target.pos FROM ranked, (SELECT pos FROM ranked WHERE id = ?) AS target
+                 WHERE ranked.pos BETWEEN target.pos - ? AND target.pos + ? ORDER BY ranked.pos",
+                sql.order, sql.from, sql.filter
+            ))?;
+            let window = stmt
+                .query_map(rusqlite::params_from_iter(&params), |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)))?
+                .collect::<Result<Vec<_>, _>>()?;
+            let side = |wanted: std::cmp::Ordering| -> Vec<i64> {
+                window.iter().filter(|(_, offset)| offset.cmp(&0) == wanted).map(|(id, _)| *id).collect()
+            };
+            let mut before = fetch_by_ids(conn, &side(std::cmp::Ordering::Less))?;
+            before.reverse();
+            Ok(Neighbors { before, after: fetch_by_ids(conn, &side(std::cmp::Ordering::Greater))? })
+        })
+    }
+
+    /// Runs SQLite's quick integrity check. Returns the problems found,
+    /// empty when the database is healthy.
+    pub fn integrity_check(&self) -> Result<Vec<String>, CoreError> {
+        let conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let mut stmt = conn.prepare("PRAGMA quick_check")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        let mut problems = Vec::new();
+        for row in rows {
+            let row = row?;
+            if row != "ok" {
+                problems.push(row);
+            }
+        }
+        Ok(problems)
+    }
+
+    /// Checks that each photo's original is still there, flagging the ones
+    /// that aren't as missing (and clearing the flag of those that are back),
+    /// and finds cached thumbnails under `config` that belong to no photo in
+    /// the library or the trash. With `remove_orphans` those are deleted.
+    pub fn verify(&self, config: PhotoCoreConfig, remove_orphans: bool) -> Result<LibraryVerifyReport, CoreError> {
+        let thumb_root = std::path::PathBuf::from(&config.thumbnail_dir);
+        let thumbnail_of = |path: &str, rotation: u32| {
+            let spec = ThumbnailSpec { width: config.thumbnail_size, height: config.thumbnail_size, rotation };
+            thumbnail_key(&decode_path(path), &spec).ok().map(|key| cache_path(&thumb_root, &key))
+        };
+
+        let mut report = LibraryVerifyReport::default();
+        let mut known_thumbnails = std::collections::HashSet::new();
+        let mut changed = Vec::new();
+        for photo in self.list()? {
+            report.checked += 1;
+            let missing = !decode_path(&photo.path).exists();
+            if missing {
+                report.missing.push(photo.id.id);
+            }
+            if missing != photo.missing {
+                changed.push((photo.id.id, missing));
+            }
+            known_thumbnails.extend(thumbnail_of(&photo.path, photo.metadata.rotation));
+        }
+        for trashed in self.list_trash()? {
+            known_thumbnails.extend(thumbnail_of(&trashed.photo.path, trashed.photo.metadata.rotation));
+        }
+
+        if !changed.is_empty() {
+            let mut conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+            let tx = conn.transaction()?;
+            {
+                let mut stmt = tx.prepare("UPDATE photos SET status = ?1 WHERE id = ?2")?;
+                for (id, missing) in changed {
+                    stmt.execute(params![if missing { STATUS_MISSING } else { STATUS_OK }, id])?;
+                    if !missing {
+                        report.found += 1;
+                    }
+                }
+            }
+            tx.commit()?;
+        }
+
+        let thumbnailer = Thumbnailer::new(thumb_root.clone());
+        for file in thumbnailer.cached_files()? {
+            if known_thumbnails.contains(&file) {
+                continue;
+            }
+            report.orphaned_thumbnails += 1;
+            report.orphaned_bytes += std::fs::metadata(&file).map(|m| m.len()).unwrap_or(0);
+            if remove_orphans {
+                std::fs::remove_file(&file)?;
+                report.removed += 1;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Re-reads the originals of up to `sample` photos, those checked longest
+    /// ago first, and compares them with their stored checksums to catch
+    /// silent corruption (bit rot). A checksum is only compared while the
+    /// file has the size and modification time it was taken at; an edited or
+    /// replaced file gets a new one. Mismatches flag the photo as corrupt.
+    /// Reads whole files: run it in the background, a sample at a time.
+    pub fn audit_originals(&self, sample: u32) -> Result<OriginalsAuditReport, CoreError> {
+        let due = self.read(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT id, path, checksum, checksum_size, checksum_mtime FROM photos
+                 WHERE status != ?1 ORDER BY checked_at IS NOT NULL, checked_at, id LIMIT ?2",
+            )?;
+            let rows = stmt.query_map(params![STATUS_MISSING, sample], |row| {
+                Ok(AuditedFile {
+                    id: row.get(0)?,
+                    path: row.get(1)?,
+                    checksum: match (row.get::<_, Option<String>>(2)?, row.get::<_, Option<i64>>(3)?, row.get::<_, Option<i64>>(4)?) {
+                        (Some(checksum), Some(size), Some(mtime)) => Some((checksum, (size as u64, mtime))),
+                        _ => None,
+                    },
+                })
+            })?;
+            Ok(rows.collect::<Result<Vec<_>, _>>()?)
+        })?;
+
+        // Files are read before the writer is locked, so imports carry on meanwhile
+        let mut report = OriginalsAuditReport::default();
+        let mut outcomes = Vec::new();
+        for file in due {
+            report.checked += 1;
+            let path = decode_path(&file.path);
+            let outcome = match (file_stats(&path), crate::image::compute_checksum(&path)) {
+                (Some(stats), Ok(checksum)) => match file.checksum {
+                    Some((recorded, at)) if at == stats => {
+                        let intact = recorded == checksum;
+                        if intact {
+                            report.verified += 1;
+                        } else {
+                            report.corrupt.push(file.id);
+                        }
+                        AuditOutcome::Compared { corrupt: !intact }
+                    }
+                    _ => {
+                        report.recorded += 1;
+                        AuditOutcome::Recorded(checksum, stats)
+                    }
+                },
+                _ => {
+                    report.unreadable += 1;
+                    AuditOutcome::Unreadable
+                }
+            };
+            outcomes.push((file.id, outcome));
+        }
+
+        let now = unix_now();
+        let mut conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let tx = conn.transaction()?;
+        {
+            let mut checked = tx.prepare_cached("UPDATE photos SET checked_at = ?1 WHERE id = ?2")?;
+            let mut status = tx.prepare_cached("UPDATE photos SET status = ?1 WHERE id = ?2 AND status != ?3")?;
+            let mut record = tx.prepare_cached(
+                "UPDATE photos SET checksum = ?1, checksum_size = ?2, checksum_mtime = ?3 WHERE id = ?4",
+            )?;
+            for (id, outcome) in outcomes {
+                checked.execute(params![now, id])?;
+                match outcome {
+                    AuditOutcome::Compared { corrupt } => {
+                        status.execute(params![if corrupt { STATUS_CORRUPT } else { STATUS_OK }, id, STATUS_MISSING])?;
+                    }
+                    AuditOutcome::Recorded(checksum, (size, mtime)) => {
+                        record.execute(params![checksum, size as i64, mtime, id])?;
+                        status.execute(params![STATUS_OK, id, STATUS_MISSING])?;
+                    }
+                    AuditOutcome::Unreadable => {}
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(report)
+    }
+
+    /// Undated photos grouped by folder and file-number sequence, each group
+    /// with a date proposed from its dated neighbors, for manual dating.
+    pub fn undated_clusters(&self) -> Result<Vec<UndatedCluster>, CoreError> {
+        Ok(crate::index::undated::cluster_undated(&self.list()?))
+    }
+
+    /// Writes a CSV or JSON manifest of the photos matching `query` to
+    /// `dest_path`: path, checksum, size, dates, location and camera of
+    /// each, no images. Originals indexed before checksums were taken are
+    /// read in full for theirs. Returns how many photos it lists.
+    pub fn export_manifest(&self, query: PhotoQuery, format: ManifestFormat, dest_path: String) -> Result<u32, CoreError> {
+        super::manifest::write_manifest(self.query_photos(&query)?, format, Path::new(&dest_path))
+    }
+
+    /// Top places, cameras and busiest days of `year`, for a shareable
+    /// summary. The photos viewed most, then those in an album, are
+    /// preferred as a day's highlight.
+    pub fn year_in_review(&self, year: i32) -> Result<YearInReview, CoreError> {
+        let start = crate::metadata::date::days_from_civil(year as i64, 1, 1) * 86_400;
+        let end = crate::metadata::date::days_from_civil(year as i64 + 1, 1, 1) * 86_400 - 1;
+        let photos = self.query(PhotoQuery {
+            taken_from: Some(start),
+            taken_to: Some(end),
+            sort: QuerySort::DateTaken,
+            ..Default::default()
+        })?;
+        let in_albums = self.read(|conn| {
+            let mut stmt = conn.prepare("SELECT DISTINCT photo_id FROM album_photos")?;
+            let ids = stmt.query_map([], |row| row.get(0))?.collect::<Result<_, _>>()?;
+            Ok(ids)
+        })?;
+        let views = self.view_counts()?.into_iter().map(|(id, (count, _))| (id, count)).collect();
+        Ok(super::review::summarize_year(year, &photos, &self.list_places()?, &in_albums, &views))
+    }
+
+    /// Dates photos by hand. `taken_at` is rounded down to `precision`, which
+    /// is stored alongside so the UI can show "1998" rather than a made-up
+    /// day. The EXIF `date_taken` is left alone. Returns how many photos
+    /// were updated.
+    pub fn set_approximate_date(&self, ids: Vec<i64>, taken_at: i64, precision: DatePrecision) -> Result<u32, CoreError> {
+        let taken_at = crate::metadata::truncate_date(taken_at, precision);
+        let mut conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let tx = conn.transaction()?;
+        let mut updated = 0;
+        {
+            let mut stmt = tx.prepare("UPDATE photos SET taken_at = ?1, date_precision = ?2 WHERE id = ?3")?;
+            for id in ids {
+                updated += stmt.execute(params![taken_at, crate::metadata::date::precision_name(precision), id])? as u32;
+            }
+        }
+        tx.commit()?;
+        Ok(updated)
+    }
+
+    /// Photos whose camera make/model, path or capture date match every word
+    /// of `query` (prefixes count: "can 2019" finds Canon shots from 2019),
+    /// best matches first. A blank query matches nothing.
+    pub fn search(&self, query: String) -> Result<Vec<PhotoInfo>, CoreError> {
+        let Some(fts) = fts_query(&query) else {
+            return Ok(Vec::new());
+        };
+        self.cached_photos(
+            &format!(
+                "SELECT {} FROM photos
+                 JOIN (SELECT rowid AS match_id, rank FROM photos_fts WHERE photos_fts MATCH ?1) ON match_id = id
+                 ORDER BY rank",
+                PHOTO_COLUMNS
+            ),
+            &[fts.into()],
+        )
+    }
+
+    /// Number of photos in the index.
+    pub fn count(&self) -> Result<u64, CoreError> {
+        let count: i64 = self.read(|conn| Ok(conn.query_row("SELECT COUNT(*) FROM photos", [], |row| row.get(0))?))?;
+        Ok(count as u64)
+    }
+
+    /// Photos that look like `photo_id`, closest first, within `max_distance`
+    /// bits of its perceptual hash (about 10 catches resized and re-encoded
+    /// copies). Empty when the photo only has a file-based hash.
+    pub fn find_similar(&self, photo_id: i64, max_distance: u32) -> Result<Vec<SimilarPhoto>, CoreError> {
+        self.nearest(photo_id, max_distance, usize::MAX)
+    }
+
+    /// The `top_k` photos that look most like `photo_id`, however far off,
+    /// closest first - "more like this" rather than duplicate hunting.
+    /// Ranked by perceptual hash distance, the only image signature the
+    /// index keeps.
+    pub fn find_similar_to(&self, photo_id: i64, top_k: u32) -> Result<Vec<SimilarPhoto>, CoreError> {
+        self.nearest(photo_id, u64::BITS, top_k as usize)
+    }
+
+    /// Every group of near-duplicate photos in the library, largest first.
+    /// See `find_similar` for `max_distance`.
+    pub fn duplicate_report(&self, max_distance: u32) -> Result<Vec<DuplicateGroup>, CoreError> {
+        let groups = super::similar::group_near_duplicates(&self.perceptual_hashes()?, max_distance);
+        if groups.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut photos: std::collections::HashMap<i64, PhotoInfo> =
+            self.list()?.into_iter().map(|photo| (photo.id.id, photo)).collect();
+        Ok(groups
+            .into_iter()
+            .map(|ids| DuplicateGroup { photos: ids.iter().filter_map(|id| photos.remove(id)).collect() })
+            .collect())
+    }
+
+    /// Geotagged photos inside a map viewport, bounds inclusive. A viewport
+    /// across the antimeridian has `min_lon` greater than `max_lon`.
+    pub fn list_in_bounds(&self, min_lat: f64, min_lon: f64, max_lat: f64, max_lon: f64) -> Result<Vec<PhotoInfo>, CoreError> {
+        // The R-tree stores 32-bit floats rounded outwards, so it finds
+        // candidates and the exact coordinates decide
+        let lon_ranges = if min_lon <= max_lon {
+            vec![(min_lon, max_lon)]
+        } else {
+            vec![(min_lon, 180.0), (-180.0, max_lon)]
+        };
+        self.read(|conn| {
+            let mut stmt = conn.prepare_cached(&format!(
+                "SELECT {} FROM photos
+                 WHERE id IN (SELECT id FROM photo_geo
+                              WHERE max_lat >= ?1 AND min_lat <= ?3 AND max_lon >= ?2 AND min_lon <= ?4)
+                   AND lat BETWEEN ?1 AND ?3 AND lon BETWEEN ?2 AND ?4",
+                PHOTO_COLUMNS
+            ))?;
+            let mut photos = Vec::new();
+            for (from, to) in lon_ranges {
+                let rows = stmt.query_map(params![min_lat, from, max_lat, to], photo_from_row)?;
+                for photo in rows {
+                    photos.push(photo?);
+                }
+            }
+            Ok(photos)
+        })
+    }
+
+    /// Dated photos counted per day, month or year (oldest first), each
+    /// bucket with a cover photo, so a timeline scrubber needs no full listing.
+    /// Undated photos are left out.
+    pub fn timeline(&self, granularity: DatePrecision) -> Result<Vec<TimelineBucket>, CoreError> {
+        let modifier = match granularity {
+            DatePrecision::Day => "start of day",
+            DatePrecision::Month => "start of month",
+            DatePrecision::Year => "start of year",
+        };
+        self.read(|conn| {
+            // The bare `id` next to MIN(taken_at) comes from the earliest row
+            let mut stmt = conn.prepare(&format!(
+                "WITH buckets AS (
+                    SELECT CAST(strftime('%s', taken_at, 'unixepoch', '{}') AS INTEGER) AS bucket_start,
+                           COUNT(*) AS bucket_count, id AS cover_id, MIN(taken_at)
+                    FROM photos WHERE taken_at IS NOT NULL GROUP BY bucket_start
+                 )
+                 SELECT {}, bucket_start, bucket_count
+                 FROM buckets JOIN photos ON photos.id = buckets.cover_id
+                 ORDER BY bucket_start",
+                modifier, PHOTO_COLUMNS
+            ))?;
+            let rows = stmt.query_map([], |row| {
+                Ok(TimelineBucket {
+                    cover: photo_from_row(row)?,
+                    start: row.get(PHOTO_COLUMN_COUNT)?,
+                    count: row.get(PHOTO_COLUMN_COUNT + 1)?,
+                })
+            })?;
+            Ok(rows.collect::<Result<_, _>>()?)
+        })
+    }
+
+    /// Up to `limit` photos starting at `offset` in `sort` order, read
+    /// straight from the database so a virtualized grid only holds the rows
+    /// it shows. Pair with `count()` for the scroll extent.
+    pub fn list_page(&self, offset: u32, limit: u32, sort: PhotoSort) -> Result<Vec<PhotoInfo>, CoreError> {
+        let order = match sort {
+            PhotoSort::DateTaken => "taken_at IS NULL, taken_at, id",
+            PhotoSort::DateTakenDescending => "taken_at IS NULL, taken_at DESC, id DESC",
+            PhotoSort::Imported => "id",
+            PhotoSort::Dimensions => "width * height DESC, id",
+        };
+        // Served from the query cache, so switching back to a tab doesn't read again
+        self.cached_photos(
+            &format!("SELECT {} FROM photos ORDER BY {} LIMIT ?1 OFFSET ?2", PHOTO_COLUMNS, order),
+            &[limit.into(), offset.into()],
+        )
+    }
+
+    /// Returns a list of all photos in the index.
+    ///
+    /// ### ⚠️ Performance & Scale Note
+    /// Current implementation returns a full `Vec<PhotoInfo>` from the database.
+    /// For very large databases, this may lead to significant memory spikes.
+    ///
+    /// **Recommendations for Callers:**
+    /// - Avoid calling this frequently on the full database; use `list_page`
+    ///   and `count` for virtualized views, and `for_each` for batch jobs.
+    pub fn list(&self) -> Result<Vec<PhotoInfo>, CoreError> {
+        self.cached_photos(&format!("SELECT {} FROM photos", PHOTO_COLUMNS), &[])
+    }
+}
+
+impl PhotoIndex {
+    fn from_connection(conn: Connection) -> Self {
+        Self { conn: Mutex::new(conn), readers: None, cache: Mutex::new(QueryCache::default()), key: None }
+    }
+
+    /// Opens the library at `db_path`, unlocking it with `key` if it's encrypted.
+    fn open_with_key(db_path: String, key: Option<String>) -> Result<std::sync::Arc<Self>, CoreError> {
+        let conn = Connection::open(Path::new(&db_path))?;
+        if key.is_some() {
+            // Plain SQLite ignores PRAGMA key, which would leave the library unencrypted
+            let cipher: Option<String> = conn.query_row("PRAGMA cipher_version", [], |row| row.get(0)).optional()?;
+            if cipher.is_none() {
+                return Err(CoreError::Database("this build has no encryption support".to_string()));
+            }
+            apply_key(&conn, key.as_deref())?;
+            // The key is only checked when the file is first read
+            conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0))
+                .map_err(|e| match e.sqlite_error_code() {
+                    Some(rusqlite::ErrorCode::NotADatabase) => {
+                        CoreError::PermissionDenied(format!("wrong passphrase for {}", db_path))
+                    }
+                    _ => e.into(),
+                })?;
+        }
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        // Lets readers run alongside the writer. The mode sticks to the file.
+        conn.query_row("PRAGMA journal_mode = WAL", [], |row| row.get::<_, String>(0))?;
+        init_schema(&conn)?;
+        let mut index = Self::from_connection(conn);
+        index.readers = Some(ReaderPool::new(db_path.into(), key.clone()));
+        index.key = key;
+        Ok(std::sync::Arc::new(index))
+    }
+
+    /// Runs a read-only query on a pooled reader, so it doesn't wait for the
+    /// writer. An index without a file reads through the writer.
+    fn read<T>(&self, f: impl FnOnce(&Connection) -> Result<T, CoreError>) -> Result<T, CoreError> {
+        match &self.readers {
+            Some(readers) => f(&*readers.get()?),
+            None => f(&*self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?),
+        }
+    }
+
+    /// Calls `f` with every photo in import order without materializing the
+    /// whole library. Rows are read a batch at a time and the database isn't
+    /// locked while `f` runs, so `f` may use the index itself. Stops at the
+    /// first error `f` returns. Rust only, as UniFFI exports take no closures;
+    /// foreign callers page with `list_page`.
+    pub fn for_each(&self, mut f: impl FnMut(PhotoInfo) -> Result<(), CoreError>) -> Result<(), CoreError> {
+        let mut after_id = 0;
+        loop {
+            let batch: Vec<PhotoInfo> = self.read(|conn| {
+                let mut stmt = conn.prepare_cached(&format!(
+                    "SELECT {} FROM photos WHERE id > ?1 ORDER BY id LIMIT ?2",
+                    PHOTO_COLUMNS
+                ))?;
+                let rows = stmt.query_map(params![after_id, FOR_EACH_BATCH], photo_from_row)?;
+                Ok(rows.collect::<rusqlite::Result<_>>()?)
+            })?;
+            let Some(last) = batch.last() else {
+                return Ok(());
+            };
+            after_id = last.id.id;
+            let done = batch.len() < FOR_EACH_BATCH as usize;
+            for photo in batch {
+                f(photo)?;
+            }
+            if done {
+                return Ok(());
+            }
+        }
+    }
+
+    /// View count and latest view of every photo viewed at least once.
+    fn view_counts(&self) -> Result<std::collections::HashMap<i64, (u32, i64)>, CoreError> {
+        self.read(|conn| {
+            let mut stmt = conn.prepare_cached("SELECT photo_id, view_count, last_viewed_at FROM photo_views")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, (row.get::<_, i64>(1)? as u32, row.get(2)?))))?;
+            Ok(rows.collect::<Result<_, _>>()?)
+        })
+    }
+
+    /// All photos matching `query`, in its order.
+    fn query_photos(&self, query: &PhotoQuery) -> Result<Vec<PhotoInfo>, CoreError> {
+        let sql = self.query_sql(query)?;
+        self.cached_photos(
+            &format!("SELECT {} FROM {} {} ORDER BY {}", PHOTO_COLUMNS, sql.from, sql.filter, sql.order),
+            &sql.params,
+        )
+    }
+
+    /// `query` as SQL, so SQLite filters and sorts instead of the whole
+    /// library being read.
+    fn query_sql(&self, query: &PhotoQuery) -> Result<QuerySql, CoreError> {
+        use rusqlite::types::Value;
+
+        let mut filters: Vec<String> = Vec::new();
+        let mut params: Vec<Value> = Vec::new();
+        let mut filter = |clause: &str, value: Value| {
+            filters.push(clause.to_string());
+            params.push(value);
+        };
+        if let Some(light) = &query.light {
+            filter("light = ?", light.clone().into());
+        }
+        if let Some(condition) = &query.weather_condition {
+            filter("id IN (SELECT photo_id FROM photo_weather WHERE condition = ?)", condition.to_lowercase().into());
+        }
+        if let Some(place_id) = query.place_id {
+            // A place's outline is checked in Rust; its photos are few
+            let ids: Vec<i64> = self.list_by_place(place_id)?.iter().map(|p| p.id.id).collect();
+            let ids = serde_json::to_string(&ids).map_err(|e| CoreError::Database(e.to_string()))?;
+            filter("id IN (SELECT value FROM json_each(?))", ids.into());
+        }
+        // Comparisons with NULL fail, so undated photos never match a range
+        if let Some(from) = query.taken_from {
+            filter("taken_at >= ?", from.into());
+        }
+        if let Some(to) = query.taken_to {
+            filter("taken_at <= ?", to.into());
+        }
+        if let Some(camera) = &query.camera {
+            filter("camera_name(make, model) = ?", camera.clone().into());
+        }
+        if let Some(has_gps) = query.has_gps {
+            filter("(lat IS NOT NULL AND lon IS NOT NULL) = ?", has_gps.into());
+        }
+        if let Some(min) = query.iso_min {
+            filter("iso >= ?", min.into());
+        }
+        if let Some(max) = query.iso_max {
+            filter("iso <= ?", max.into());
+        }
+        if let Some(orientation) = &query.orientation {
+            filter(&format!("{} = ?", SHAPE), orientation.clone().into());
+        }
+        if !query.file_types.is_empty() {
+            filters.push(format!("file_extension(path) IN ({})", vec!["?"; query.file_types.len()].join(", ")));
+            params.extend(query.file_types.iter().map(|t| Value::from(t.to_lowercase())));
+        }
+        let mut filter = |clause: &str, value: Value| {
+            filters.push(clause.to_string());
+            params.push(value);
+        };
+        if let Some(profile) = &query.color_profile {
+            filter("color_profile = ? COLLATE NOCASE", profile.clone().into());
+        }
+        if let Some(min) = query.min_bit_depth {
+            filter("bit_depth >= ?", min.into());
+        }
+        if let Some(chroma) = &query.chroma_subsampling {
+            filter("chroma_subsampling = ?", chroma.clone().into());
+        }
+        if let Some(is_scan) = query.is_scan {
+            filter("is_scan = ?", is_scan.into());
+        }
+        if let Some(missing) = query.missing {
+            filter(&format!("(status = '{}') = ?", STATUS_MISSING), missing.into());
+        }
+        if let Some(corrupt) = query.corrupt {
+            filter(&format!("(status = '{}') = ?", STATUS_CORRUPT), corrupt.into());
+        }
+        if let Some(media_type) = query.media_type {
+            filter("media_type = ?", crate::metadata::video::media_type_name(media_type).to_string().into());
+        }
+        if let Some(viewed) = query.viewed {
+            filter("(view_count IS NOT NULL) = ?", viewed.into());
+        }
+
+        let direction = if query.descending { "DESC" } else { "ASC" };
+        let order = match query.sort {
+            // Undated photos last either way
+            QuerySort::DateTaken => format!("taken_at IS NULL, taken_at {0}, id {0}", direction),
+            QuerySort::FileSize => format!("IFNULL(file_size, 0) {0}, id {0}", direction),
+            QuerySort::Path => format!("path {0}, id {0}", direction),
+            // Never viewed (NULL) sorts lowest
+            QuerySort::ViewCount => format!("view_count {0}, last_viewed_at {0}, id {0}", direction),
+        };
+        let views = query.viewed.is_some() || query.sort == QuerySort::ViewCount;
+        Ok(QuerySql {
+            from: if views { "photos LEFT JOIN photo_views ON photo_id = id" } else { "photos" },
+            filter: if filters.is_empty() { String::new() } else { format!("WHERE {}", filters.join(" AND ")) },
+            order,
+            params,
+        })
+    }
+
+    /// Permanently deletes the photos trashed before `before` (unix seconds),
+    /// leaving tombstones. Returns the deleted photos.
+    fn purge_trash_before(&self, before: i64) -> Result<Vec<PhotoInfo>, CoreError> {
+        let mut conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let tx = conn.transaction()?;
+        let purged = {
+            let mut stmt = tx.prepare(&format!("SELECT {} FROM trash WHERE trashed_at < ?1", PHOTO_COLUMNS))?;
+            let rows = stmt.query_map(params![before], photo_from_row)?;
+            rows.collect::<Result<Vec<_>, _>>()?
+        };
+        for photo in &purged {
+            tx.execute("DELETE FROM trash WHERE id = ?1", params![photo.id.id])?;
+            forget_photo(&tx, photo)?;
+        }
+        tx.commit()?;
+        Ok(purged)
+    }
+
+    /// Up to `limit` photos within `max_distance` of `photo_id`'s hash, closest
+    /// (then oldest) first.
+    fn nearest(&self, photo_id: i64, max_distance: u32, limit: usize) -> Result<Vec<SimilarPhoto>, CoreError> {
+        let hashes = self.perceptual_hashes()?;
+        let Some(&(_, target)) = hashes.iter().find(|(id, _)| *id == photo_id) else {
+            return Ok(Vec::new());
+        };
+        let mut matches: Vec<(u32, i64)> = hashes
+            .iter()
+            .filter(|(id, _)| *id != photo_id)
+            .map(|(id, bits)| (super::similar::distance(target, *bits), *id))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .collect();
+        matches.sort();
+        matches.truncate(limit);
+
+        let photos = self.get_by_ids(matches.iter().map(|(_, id)| *id).collect())?;
+        let distances: std::collections::HashMap<i64, u32> = matches.into_iter().map(|(d, id)| (id, d)).collect();
+        Ok(photos
+            .into_iter()
+            .map(|photo| SimilarPhoto { distance: distances[&photo.id.id], photo })
+            .collect())
+    }
+
+    /// (id, hash bits) of every photo with a perceptual hash.
+    fn perceptual_hashes(&self) -> Result<Vec<(i64, u64)>, CoreError> {
+        self.read(|conn| {
+            let mut stmt = conn.prepare("SELECT id, hash FROM photos")?;
+            let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
+            let mut hashes = Vec::new();
+            for row in rows {
+                let (id, hash) = row?;
+                if let Some(bits) = crate::image::hash_bits(&hash) {
+                    hashes.push((id, bits));
+                }
+            }
+            Ok(hashes)
+        })
+    }
+
+    /// Photos with ids `first_id..first_id + count`, in id order.
+    pub(crate) fn list_id_range(&self, first_id: i64, count: u32) -> Result<Vec<PhotoInfo>, CoreError> {
+        self.read(|conn| {
+            let mut stmt = conn.prepare_cached(&format!(
+                "SELECT {} FROM photos WHERE id >= ?1 AND id < ?2 ORDER BY id",
+                PHOTO_COLUMNS
+            ))?;
+            let rows = stmt.query_map(params![first_id, first_id.saturating_add(count as i64)], photo_from_row)?;
+            Ok(rows.collect::<Result<Vec<_>, _>>()?)
+        })
+    }
+
+    /// Every indexed original with the size and modification time recorded
+    /// for it, for `rescan`.
+    pub(crate) fn indexed_files(&self) -> Result<Vec<IndexedFile>, CoreError> {
+        self.read(|conn| {
+            let mut stmt = conn.prepare_cached("SELECT id, path, file_size, file_mtime, status, rotation FROM photos")?;
+            let rows = stmt.query_map([], |row| {
+                Ok(IndexedFile {
+                    id: row.get(0)?,
+                    path: row.get(1)?,
+                    stats: match (row.get::<_, Option<i64>>(2)?, row.get::<_, Option<i64>>(3)?) {
+                        (Some(size), Some(mtime)) => Some((size as u64, mtime)),
+                        _ => None,
+                    },
+                    missing: row.get::<_, String>(4)? == STATUS_MISSING,
+                    rotation: row.get::<_, i64>(5)? as u32,
+                })
+            })?;
+            Ok(rows.collect::<Result<Vec<_>, _>>()?)
+        })
+    }
+
+    /// Records the current size and modification time of the originals of
+    /// `ids`, found where the index says and unchanged. Clears their missing flag.
+    pub(crate) fn record_file_stats(&self, ids: &[i64]) -> Result<(), CoreError> {
+        let mut conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let tx = conn.transaction()?;
+        {
+            let mut path = tx.prepare_cached("SELECT path FROM photos WHERE id = ?1")?;
+            let mut update = tx.prepare_cached("UPDATE photos SET file_size = ?1, file_mtime = ?2, status = ?3 WHERE id = ?4")?;
+            for &id in ids {
+                let Some(path) = path.query_row(params![id], |row| row.get::<_, String>(0)).optional()? else { continue };
+                let stats = file_stats(&decode_path(&path));
+                update.execute(params![stats.map(|(size, _)| size as i64), stats.map(|(_, mtime)| mtime), STATUS_OK, id])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Flags the photos of `ids` as missing their original, as `verify` does.
+    pub(crate) fn mark_missing(&self, ids: &[i64]) -> Result<(), CoreError> {
+        let mut conn = self.conn.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        let tx = conn.transaction()?;
+        {
+            let mut update = tx.prepare_cached("UPDATE photos SET status = ?1 WHERE id = ?2")?;
+            for &id in ids {
+                update.execute(params![STATUS_MISSING, id])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Runs a photo query, served from the query cache while the library
+    /// revision is unchanged.
+    fn cached_photos(&self, sql: &str, params: &[rusqlite::types::Value]) -> Result<Vec<PhotoInfo>, CoreError> {
+        // Entries are keyed by the writer's counter. While the writer is busy
+        // (e.g. importing) the cache is bypassed instead of waited for.
+        let revision = match self.conn.try_lock() {
+            Ok(conn) => Some(write_counter(&conn)?),
+            Err(std::sync::TryLockError::WouldBlock) => None,
+            Err(std::sync::TryLockError::Poisoned(e)) => return Err(CoreError::Database(e.to_string())),
+        };
+        let key = format!("{}\u{0}{:?}", sql, params);
+
+        if let Some(revision) = revision {
+            let cache = self.cache.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+            if let Some(rows) = cache.get(&key, revision) {
+                return Ok(rows);
+            }
+        }
+
+        let rows: Vec<PhotoInfo> = self.read(|conn| {
+            let mut stmt = conn.prepare(sql)?;
+            let rows = stmt.query_map(rusqlite::params_from_iter(params), photo_from_row)?;
+            Ok(rows.filter_map(Result::ok).collect())
+        })?;
+        if let Some(revision) = revision {
+            let mut cache = self.cache.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+            cache.put(key, revision, rows.clone());
+        }
+        Ok(rows)
+    }
+}
+
+/// An indexed original as `rescan` compares it with the disk.
+pub(crate) struct IndexedFile {
+    pub id: i64,
+    pub path: String,
+    /// Size and modification time when indexed, unknown for photos indexed
+    /// before they were recorded
+    pub stats: Option<(u64, i64)>,
+    pub missing: bool,
+    /// The user's rotation, which its thumbnails are cached for too
+    pub rotation: u32,
+}
+
+/// Rows `for_each` reads per database lock
+const FOR_EACH_BATCH: u32 = 500;
+/// Paths or IDs looked up per `IN (...)` query, well under SQLite's bound-parameter limit
+const LOOKUP_BATCH: usize = 500;
+
+/// Number of columns in `PHOTO_COLUMNS`; extra selected columns start here.
+const PHOTO_COLUMN_COUNT: usize = 33;
+
+/// A photo due for `audit_originals`, with its checksum and the file size
+/// and modification time it was taken at.
+struct AuditedFile {
+    id: i64,
+    path: String,
+    checksum: Option<(String, (u64, i64))>,
+}
+
+enum AuditOutcome {
+    /// Compared with the stored checksum; whether they differ
+    Compared { corrupt: bool },
+    /// Checksummed anew, with the file's size and modification time
+    Recorded(String, (u64, i64)),
+    Unreadable,
+}
+
+/// `status` of a photo whose original is where `path` says
+const STATUS_OK: &str = "ok";
+/// `status` of a photo whose original `verify` couldn't find
+const STATUS_MISSING: &str = "missing";
+/// `status` of a photo whose original `audit_originals` found changed
+/// while its size and modification time weren't
+const STATUS_CORRUPT: &str = "corrupt";
+
+/// Column list matching `photo_from_row`.
+const PHOTO_COLUMNS: &str = "id, path, hash, make, model, date_taken, width, height,
+    lat, lon, iso, f_number, exposure_time, orientation,
+    has_motion, is_portrait, is_proraw, light, moon_phase, rotation, taken_at, date_precision,
+    color_profile, bit_depth, chroma_subsampling, is_scan, status, media_type, duration_ms, video_codec,
+    description, checksum, file_size";
+
+/// What `rescan` and `audit_originals` recorded about a photo's file, which
+/// `photo_from_row` doesn't read. Kept through the trash so a restored photo
+/// isn't hashed and verified all over again.
+const FILE_STATE_COLUMNS: &str = "file_mtime, checksum_size, checksum_mtime, checked_at";
+
+fn photo_from_row(row: &rusqlite::Row) -> rusqlite::Result<PhotoInfo> {
+    Ok(PhotoInfo {
+        id: PhotoId { id: row.get(0)? },
+        path: row.get(1)?,
+        hash: row.get(2)?,
+        checksum: row.get(31)?,
+        metadata: PhotoMetadata {
+            make: row.get(3)?,
+            model: row.get(4)?,
+            date_taken: row.get(5)?,
+            width: row.get::<_, i64>(6)? as u32,
+            height: row.get::<_, i64>(7)? as u32,
+            lat: row.get(8)?,
+            lon: row.get(9)?,
+            iso: row.get::<_, Option<i64>>(10)?.map(|x| x as u32),
+            f_number: row.get::<_, Option<f64>>(11)?.map(|x| x as f32),
+            exposure_time: row.get(12)?,
+            orientation: row.get::<_, i64>(13)? as u32,
+            has_motion: row.get(14)?,
+            is_portrait: row.get(15)?,
+            is_proraw: row.get(16)?,
+            light: row.get(17)?,
+            moon_phase: row.get(18)?,
+            rotation: row.get::<_, i64>(19)? as u32,
+            taken_at: row.get(20)?,
+            date_precision: row.get::<_, Option<String>>(21)?.as_deref().and_then(crate::metadata::date::precision_from_name),
+            color_profile: row.get(22)?,
+            bit_depth: row.get::<_, Option<i64>>(23)?.map(|x| x as u32),
+            chroma_subsampling: row.get(24)?,
+            is_scan: row.get(25)?,
+            media_type: crate::metadata::video::media_type_from_name(&row.get::<_, String>(27)?),
+            duration_ms: row.get::<_, Option<i64>>(28)?.map(|x| x as u64),
+            video_codec: row.get(29)?,
+            description: row.get(30)?,
+        },
+        thumb_path: None,
+        file_size: row.get::<_, Option<i64>>(32)?.unwrap_or(0) as u64,
+        created_at: None,
+        modified_at: None,
+        missing: row.get::<_, String>(26)? == STATUS_MISSING,
+        corrupt: row.get::<_, String>(26)? == STATUS_CORRUPT,
+    })
+}
+
+/// Checksum of the original at `path` (as stored), `None` if it can't be read.
+fn read_checksum(path: &str) -> Option<String> {
+    crate::image::compute_checksum(&decode_path(path)).ok()
+}
+
+/// Stores `checksum` as taken while the original had `stats`, for
+/// `audit_originals` to compare against.
+fn record_checksum(conn: &Connection, id: i64, checksum: Option<&str>, stats: Option<(u64, i64)>) -> Result<(), CoreError> {
+    let stats = stats.filter(|_| checksum.is_some());
+    conn.prepare_cached("UPDATE photos SET checksum = ?1, checksum_size = ?2, checksum_mtime = ?3 WHERE id = ?4")?
+        .execute(params![checksum, stats.map(|(size, _)| size as i64), stats.map(|(_, mtime)| mtime), id])?;
+    Ok(())
+}
+
+/// `insert` on an already locked connection (or transaction).
+fn insert_photo(conn: &Connection, path: &str, hash: &str, checksum: Option<&str>, metadata: &PhotoMetadata) -> Result<PhotoId, CoreError> {
+    // 1. Check if path already exists (same file, no change needed)
+    let existing = conn
+        .prepare_cached("SELECT id FROM photos WHERE path = ?1")?
+        .query_row(params![path], |row| row.get::<_, i64>(0))
+        .optional()?;
+    if let Some(id) = existing {
+        // Importing it again means the file is there (a corrupt one stays flagged)
+        conn.prepare_cached("UPDATE photos SET status = ?1 WHERE id = ?2 AND status = ?3")?
+            .execute(params![STATUS_OK, id, STATUS_MISSING])?;
+        return Ok(PhotoId { id });
+    }
+
+    // Recorded for `rescan` wherever the photo's path is set
+    let stats = file_stats(&decode_path(path));
+    let (size, mtime) = (stats.map(|(size, _)| size as i64), stats.map(|(_, mtime)| mtime));
+
+    // 2. Check if the same content or hash already exists (same photo, different
+    // path - update path). A photo with the same perceptual hash is only taken
+    // for this one while one of their checksums is unknown: with both known
+    // and different, it's a lookalike (a burst frame, a re-edit) of its own.
+    let existing = conn
+        .prepare_cached(
+            "SELECT id FROM photos WHERE checksum = ?2
+             UNION ALL SELECT id FROM photos WHERE hash = ?1 AND (checksum IS NULL OR ?2 IS NULL)
+             LIMIT 1",
+        )?
+        .query_row(params![hash, checksum], |row| row.get::<_, i64>(0))
+        .optional()?;
+    if let Some(id) = existing {
+        // Update path to new location
+        conn.prepare_cached("UPDATE photos SET path = ?1, status = ?2, file_size = ?3, file_mtime = ?4 WHERE id = ?5")?
+            .execute(params![path, STATUS_OK, size, mtime, id])?;
+        if checksum.is_some() {
+            record_checksum(conn, id, checksum, stats)?;
+        }
+        return Ok(PhotoId { id });
+    }
+
+    // 3. Check if the photo is in the trash (take it back out, albums and all)
+    let trashed = conn
+        .prepare_cached("SELECT id FROM trash WHERE hash = ?1 AND (checksum IS NULL OR ?2 IS NULL OR checksum = ?2)")?
+        .query_row(params![hash, checksum], |row| row.get::<_, i64>(0))
+        .optional()?;
+    if let Some(id) = trashed {
+        if untrash(conn, id)? {
+            conn.prepare_cached("UPDATE photos SET path = ?1, status = ?2, file_size = ?3, file_mtime = ?4 WHERE id = ?5")?
+                .execute(params![path, STATUS_OK, size, mtime, id])?;
+            if checksum.is_some() {
+                record_checksum(conn, id, checksum, stats)?;
+            }
+            return Ok(PhotoId { id });
+        }
+    }
+
+    // 4. Insert new record (re-importing a deleted photo brings it back to life).
+    // Trashed photos keep their ids, so a new photo mustn't take one of them.
+    conn.prepare_cached("DELETE FROM tombstones WHERE hash = ?1 AND (checksum IS NULL OR ?2 IS NULL OR checksum = ?2)")?
+        .execute(params![hash, checksum])?;
+    conn.prepare_cached(
+        "INSERT INTO photos (
+            id, path, hash, make, model, date_taken, width, height,
+            lat, lon, iso, f_number, exposure_time, orientation,
+            has_motion, is_portrait, is_proraw, light, moon_phase, taken_at,
+            color_profile, bit_depth, chroma_subsampling, is_scan, file_size, file_mtime,
+            media_type, duration_ms, video_codec, description,
+            checksum, checksum_size, checksum_mtime
+        )
+         VALUES ((SELECT MAX(id) + 1 FROM (SELECT MAX(id) AS id FROM photos UNION ALL SELECT MAX(id) FROM trash)), ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30,
+                 CASE WHEN ?30 IS NOT NULL THEN ?24 END, CASE WHEN ?30 IS NOT NULL THEN ?25 END)",
+    )?
+    .execute(params![
+            path,
+            hash,
+            metadata.make,
+            metadata.model,
+            metadata.date_taken,
+            metadata.width,
+            metadata.height,
+            metadata.lat,
+            metadata.lon,
+            metadata.iso,
+            metadata.f_number,
+            metadata.exposure_time,
+            metadata.orientation,
+            metadata.has_motion,
+            metadata.is_portrait,
+            metadata.is_proraw,
+            metadata.light,
+            metadata.moon_phase,
+            taken_at(metadata),
+            metadata.color_profile,
+            metadata.bit_depth,
+            metadata.chroma_subsampling,
+            metadata.is_scan,
+            size,
+            mtime,
+            crate::metadata::video::media_type_name(metadata.media_type),
+            metadata.duration_ms.map(|x| x as i64),
+            metadata.video_codec,
+            metadata.description,
+            checksum
+        ])?;
+
+    Ok(PhotoId { id: conn.last_insert_rowid() })
+}
+
+/// Drops what hangs off a permanently deleted photo and leaves a tombstone so
+/// imports skip it.
+fn forget_photo(conn: &Connection, photo: &PhotoInfo) -> Result<(), CoreError> {
+    conn.execute("DELETE FROM photo_weather WHERE photo_id = ?1", params![photo.id.id])?;
+    conn.execute("DELETE FROM photo_sources WHERE photo_id = ?1", params![photo.id.id])?;
+    conn.execute("DELETE FROM photo_views WHERE photo_id = ?1", params![photo.id.id])?;
+    conn.execute("DELETE FROM photo_edits WHERE photo_id = ?1", params![photo.id.id])?;
+    conn.execute("DELETE FROM album_photos WHERE photo_id = ?1", params![photo.id.id])?;
+    conn.execute(
+        "INSERT OR REPLACE INTO tombstones (hash, checksum, deleted_at) VALUES (?1, ?2, ?3)",
+        params![photo.hash, photo.checksum, unix_now()],
+    )?;
+    Ok(())
+}
+
+/// Rows of the photos with `ids`, in that order, skipping unknown IDs.
+fn fetch_by_ids(conn: &Connection, ids: &[i64]) -> Result<Vec<PhotoInfo>, CoreError> {
+    let mut found = std::collections::HashMap::with_capacity(ids.len());
+    for chunk in ids.chunks(LOOKUP_BATCH) {
+        let placeholders = vec!["?"; chunk.len()].join(", ");
+        let mut stmt = conn.prepare_cached(&format!("SELECT {} FROM photos WHERE id IN ({})", PHOTO_COLUMNS, placeholders))?;
+        for photo in stmt.query_map(rusqlite::params_from_iter(chunk), photo_from_row)? {
+            let photo = photo?;
+            found.insert(photo.id.id, photo);
+        }
+    }
+    Ok(ids.iter().filter_map(|id| found.get(id).cloned()).collect())
+}
+
+/// Moves a photo from the trash back into `photos`. False if it isn't in the
+/// trash, or its path or hash is taken by a photo imported since.
+fn untrash(conn: &Connection, id: i64) -> Result<bool, CoreError> {
+    let restored = conn
+        .prepare_cached(&format!(
+            "INSERT OR IGNORE INTO photos ({0}, {1}) SELECT {0}, {1} FROM trash WHERE id = ?1",
+            PHOTO_COLUMNS, FILE_STATE_COLUMNS
+        ))?
+        .execute(params![id])?;
+    if restored > 0 {
+        conn.prepare_cached("DELETE FROM trash WHERE id = ?1")?.execute(params![id])?;
+    }
+    Ok(restored > 0)
+}
+
+/// Rows changed through this connection plus SQLite's data_version, which
+/// moves when another connection commits. Both only grow, so any write bumps it -
+/// including writes to tables the change log doesn't track.
+fn write_counter(conn: &Connection) -> Result<u64, CoreError> {
+    let (changes, data_version): (i64, i64) = conn.query_row(
+        "SELECT total_changes(), data_version FROM pragma_data_version",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    Ok((changes + data_version) as u64)
+}
+
+fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Updates a photo's coordinates along with the sun/moon state derived from them.
+fn write_location(conn: &Connection, id: i64, lat: Option<f64>, lon: Option<f64>) -> Result<(), CoreError> {
+    let date_taken: Option<String> = conn.query_row(
+        "SELECT date_taken FROM photos WHERE id = ?1",
+        params![id],
+        |row| row.get(0),
+    )?;
+    let mut metadata = PhotoMetadata { lat, lon, date_taken, ..Default::default() };
+    crate::metadata::astro::apply_astronomy(&mut metadata);
+
+    conn.execute(
+        "UPDATE photos SET lat = ?1, lon = ?2, light = ?3, moon_phase = ?4 WHERE id = ?5",
+        params![lat, lon, metadata.light, metadata.moon_phase, id],
+    )?;
+    Ok(())
+}
+
+/// Column list matching `place_from_row`.
+const PLACE_COLUMNS: &str = "id, name, lat, lon, radius_m, polygon";
+
+fn place_from_row(row: &rusqlite::Row) -> rusqlite::Result<Place> {
+    Ok(Place {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        lat: row.get(2)?,
+        lon: row.get(3)?,
+        radius_m: row.get(4)?,
+        polygon: geo::decode_polygon(&row.get::<_, String>(5)?),
+    })
+}
+
+/// Turns free text into an FTS5 query: every word must match, as a prefix,
+/// in any column. Folded like the indexed text. Words are quoted so FTS5 syntax in the input is literal.
+fn fts_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = super::search::query_terms(text)
+        .into_iter()
+        .map(|(term, prefix)| format!("\"{}\"{}", term.replace('"', "\"\""), if prefix { "*" } else { "" }))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+fn album_name(name: &str) -> Result<&str, CoreError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(CoreError::InvalidInput("Album name is empty".to_string()));
+    }
+    Ok(name)
+}
+
+/// Display name of a camera, e.g. "Canon EOS R5".
+pub(crate) fn camera_name(make: Option<&str>, model: Option<&str>) -> Option<String> {
+    let name = match (make, model) {
+        // Models usually repeat the make ("Canon" + "Canon EOS R5")
+        (Some(make), Some(model)) if model.starts_with(make.trim()) => model.to_string(),
+        (Some(make), Some(model)) => format!("{} {}", make.trim(), model),
+        (None, Some(name)) | (Some(name), None) => name.to_string(),
+        (None, None) => return None,
+    };
+    Some(name.trim().to_string())
+}
+
+/// "landscape", "portrait" or "square" as displayed, after the EXIF
+/// orientation (5-8 transpose the stored pixels) and the user's rotation.
+const SHAPE: &str = "(CASE WHEN (orientation BETWEEN 5 AND 8) != (rotation % 180 = 90)
+    THEN (CASE WHEN height > width THEN 'landscape' WHEN height < width THEN 'portrait' ELSE 'square' END)
+    ELSE (CASE WHEN width > height THEN 'landscape' WHEN width < height THEN 'portrait' ELSE 'square' END) END)";
+
+/// A `PhotoQuery` as the parts of a SELECT, see `PhotoIndex::query_sql`.
+struct QuerySql {
+    from: &'static str,
+    /// "WHERE ..." with `?` placeholders for `params`, in order; empty for no filters
+    filter: String,
+    order: String,
+    params: Vec<rusqlite::types::Value>,
+}
+
+/// Typed capture date for the index. Parsed from `date_taken` when the caller
+/// only filled in the raw string - except for scans, where that's the scan date.
+fn taken_at(metadata: &PhotoMetadata) -> Option<i64> {
+    if metadata.is_scan {
+        return metadata.taken_at;
+    }
+    metadata.taken_at.or_else(|| metadata.date_taken.as_deref().and_then(crate::metadata::parse_date_taken))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::MediaType;
+
+    fn setup_test_index() -> std::sync::Arc<PhotoIndex> {
+        // Use in-memory database for deterministic, file-system-independent testing
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        std::sync::Arc::new(PhotoIndex::from_connection(conn))
+    }
+
+    #[test]
+    fn test_index_uniqueness_invariant() {
+        let index = setup_test_index();
+        let metadata = PhotoMetadata::default();
+        let path = "/test/photo.jpg".to_string();
+        let hash = "hash1".to_string();
+
+        let id1 = index.insert(path.clone(), hash.clone(), metadata.clone()).expect("First insert failed");
+        let id2 = index.insert(path, hash, metadata).expect("Second insert failed");
+
+        // Contract: Same path returns same ID
+        assert_eq!(id1, id2);
+
+        let conn = index.conn.lock().unwrap();
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM photos", [], |r| r.get(0)).unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_hash_deduplication_updates_path() {
+        let index = setup_test_index();
+        let metadata = PhotoMetadata::default();
+        let hash = "same_hash".to_string();
+        let old_path = "/sdcard/photo.jpg".to_string();
+        let new_path = "/local/photo.jpg".to_string();
+
+        // Insert with old path
+        let id1 = index.insert(old_path.clone(), hash.clone(), metadata.clone()).expect("First insert failed");
+
+        // Insert same hash with new path - should update, not create new
+        let id2 = index.insert(new_path.clone(), hash.clone(), metadata).expect("Second insert failed");
+
+        // Contract: Same hash returns same ID
+        assert_eq!(id1, id2);
+
+        // Only one record in database
+        let conn = index.conn.lock().unwrap();
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM photos", [], |r| r.get(0)).unwrap();
+        assert_eq!(count, 1);
+
+        // Path should be updated to new location
+        let stored_path: String = conn.query_row("SELECT path FROM photos WHERE id = ?1", [id1.id], |r| r.get(0)).unwrap();
+        assert_eq!(stored_path, new_path);
+    }
+
+    #[test]
+    fn test_index_scale_performance_degradation() {
+        let index = setup_test_index();
+        let metadata = PhotoMetadata::default();
+
+        // Scale test: Insertion of many items (each with unique hash)
+        for i in 0..1000 {
+            let path = format!("/path/photo_{}.jpg", i);
+            let hash = format!("hash_{}", i);
+            index.insert(path, hash, metadata.clone()).expect("Bulk insert failed");
+        }
+
+        let list = index.list().expect("List failed");
+        assert_eq!(list.len(), 1000);
+    }
+
+    #[test]
+    fn test_set_location_and_undo() {
+        let index = setup_test_index();
+        let scanned = index.insert("/scan.jpg".to_string(), "h1".to_string(), PhotoMetadata::default()).unwrap();
+        let phone = PhotoMetadata { lat: Some(1.0), lon: Some(2.0), ..Default::default() };
+        let tagged = index.insert("/phone.jpg".to_string(), "h2".to_string(), phone).unwrap();
+
+        let previous = index.set_location(vec![scanned.id, tagged.id, 999], Some(48.85), Some(2.35)).unwrap();
+        assert_eq!(previous, vec![
+            PhotoLocation { id: scanned.id, lat: None, lon: None },
+            PhotoLocation { id: tagged.id, lat: Some(1.0), lon: Some(2.0) },
+        ]);
+        let moved = index.get_by_id(scanned.id).unwrap().unwrap();
+        assert_eq!((moved.metadata.lat, moved.metadata.lon), (Some(48.85), Some(2.35)));
+
+        index.restore_locations(previous).unwrap();
+        assert_eq!(index.get_by_id(scanned.id).unwrap().unwrap().metadata.lat, None);
+        assert_eq!(index.get_by_id(tagged.id).unwrap().unwrap().metadata.lon, Some(2.0));
+
+        assert!(index.set_location(vec![scanned.id], Some(91.0), Some(0.0)).is_err());
+        assert!(index.set_location(vec![scanned.id], Some(10.0), None).is_err());
+    }
+
+    #[test]
+    fn test_places_resolve_and_filter() {
+        let index = setup_test_index();
+        let at = |lat, lon| PhotoMetadata { lat: Some(lat), lon: Some(lon), ..Default::default() };
+        let garden = index.insert("/garden.jpg".to_string(), "h1".to_string(), at(51.5007, -0.1246)).unwrap();
+        index.insert("/city.jpg".to_string(), "h2".to_string(), at(51.5155, -0.0922)).unwrap();
+        index.insert("/none.jpg".to_string(), "h3".to_string(), PhotoMetadata::default()).unwrap();
+
+        let home = index.add_place(Place {
+            id: 0, name: "Home".to_string(), lat: 51.5007, lon: -0.1246, radius_m: 300.0, polygon: Vec::new(),
+        }).unwrap();
+        let polygon = vec![
+            GeoPoint { lat: 51.51, lon: -0.10 },
+            GeoPoint { lat: 51.51, lon: -0.08 },
+            GeoPoint { lat: 51.52, lon: -0.08 },
+            GeoPoint { lat: 51.52, lon: -0.10 },
+        ];
+        let office = index.add_place(Place {
+            id: 0, name: "Office".to_string(), lat: 51.515, lon: -0.09, radius_m: 0.0, polygon: polygon.clone(),
+        }).unwrap();
+
+        let places = index.list_places().unwrap();
+        assert_eq!(places.len(), 2);
+        assert_eq!(places[1].polygon, polygon);
+
+        let home_photos = index.list_by_place(home).unwrap();
+        assert_eq!(home_photos.len(), 1);
+        assert_eq!(home_photos[0].path, "/garden.jpg");
+        assert_eq!(index.list_by_place(office).unwrap()[0].path, "/city.jpg");
+
+        let resolved = index.places_for_photo(garden.id).unwrap();
+        assert_eq!(resolved.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), vec!["Home"]);
+
+        assert!(index.delete_place(home).unwrap());
+        assert!(index.list_by_place(home).is_err());
+        assert!(index.add_place(Place {
+            id: 0, name: " ".to_string(), lat: 0.0, lon: 0.0, radius_m: 10.0, polygon: Vec::new(),
+        }).is_err());
+    }
+
+    #[test]
+    fn test_weather_enrichment() {
+        let index = setup_test_index();
+        let dated = PhotoMetadata {
+            lat: Some(46.5), lon: Some(7.9), date_taken: Some("2023-01-15 10:30:00".to_string()), ..Default::default()
+        };
+        let alps = index.insert("/alps.jpg".to_string(), "h1".to_string(), dated).unwrap();
+        index.insert("/undated.jpg".to_string(), "h2".to_string(), PhotoMetadata::default()).unwrap();
+
+        let missing = index.list_missing_weather().unwrap();
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].id, alps);
+
+        let snow = Weather { temperature_c: Some(-4.5), condition: "Snow".to_string(), source: "test".to_string() };
+        index.set_weather(alps.id, snow).unwrap();
+        assert!(index.list_missing_weather().unwrap().is_empty());
+        assert_eq!(index.get_weather(alps.id).unwrap().unwrap().condition, "snow");
+        assert_eq!(index.list_by_weather("snow".to_string()).unwrap()[0].path, "/alps.jpg");
+        assert!(index.list_by_weather("rain".to_string()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_light_follows_location() {
+        let index = setup_test_index();
+        let dusk = PhotoMetadata { date_taken: Some("2023-03-20 00:30:00".to_string()), ..Default::default() };
+        let photo = index.insert("/scan.jpg".to_string(), "h1".to_string(), dusk).unwrap();
+        assert!(index.list_by_light("night".to_string()).unwrap().is_empty());
+
+        index.set_location(vec![photo.id], Some(51.48), Some(0.0)).unwrap();
+        let stored = index.get_by_id(photo.id).unwrap().unwrap();
+        assert_eq!(stored.metadata.light.as_deref(), Some("night"));
+        assert!(stored.metadata.moon_phase.is_some());
+        assert_eq!(index.list_by_light("night".to_string()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_deleted_photos_leave_tombstones() {
+        let index = setup_test_index();
+        let id = index.insert("/a.jpg".to_string(), "h1".to_string(), PhotoMetadata::default()).unwrap();
+        assert!(!index.is_deleted("/a.jpg".to_string(), "h1".to_string()).unwrap());
+
+        index.delete_by_ids(vec![id.id]).unwrap();
+        assert!(index.is_deleted("/a.jpg".to_string(), "h1".to_string()).unwrap());
+
+        // Explicit re-import clears the tombstone
+        index.insert("/a.jpg".to_string(), "h1".to_string(), PhotoMetadata::default()).unwrap();
+        assert!(!index.is_deleted("/a.jpg".to_string(), "h1".to_string()).unwrap());
+
+        let id = index.get_by_path("/a.jpg".to_string()).unwrap().unwrap().id;
+        // A tombstone that can't be written leaves the photo in place
+        index.conn.lock().unwrap()
+            .execute_batch("CREATE TEMP TRIGGER refuse BEFORE INSERT ON tombstones BEGIN SELECT RAISE(ABORT, 'disk full'); END")
+            .unwrap();
+        assert!(index.delete_by_id(id.id).is_err());
+        assert!(index.get_by_id(id.id).unwrap().is_some());
+        index.conn.lock().unwrap().execute_batch("DROP TRIGGER refuse").unwrap();
+
+        index.delete_by_id(id.id).unwrap();
+        assert_eq!(index.purge_tombstones(0).unwrap(), 0);
+        assert_eq!(index.purge_tombstones(i64::MAX).unwrap(), 1);
+        assert!(!index.is_deleted("/a.jpg".to_string(), "h1".to_string()).unwrap());
+    }
+
+    #[test]
+    fn test_query_cache_invalidated_by_writes() {
+        let db = std::env::temp_dir().join("fotos_query_cache_test.db");
+        let _ = std::fs::remove_file(&db);
+        let index = PhotoIndex::open(db.to_string_lossy().to_string()).unwrap();
+        let other = PhotoIndex::open(db.to_string_lossy().to_string()).unwrap();
+
+        index.insert("/a.jpg".to_string(), "h1".to_string(), PhotoMetadata::default()).unwrap();
+        let revision = index.revision().unwrap();
+        assert_eq!(index.list().unwrap().len(), 1);
+        assert_eq!(index.list().unwrap().len(), 1);
+        assert_eq!(index.revision().unwrap(), revision, "reads must not bump the revision");
+
+        // Own writes
+        index.insert("/b.jpg".to_string(), "h2".to_string(), PhotoMetadata::default()).unwrap();
+        assert!(index.revision().unwrap() > revision);
+        assert_eq!(index.list().unwrap().len(), 2);
+        // Grid pages and queries go through the cache too
+        let page = || index.list_page(0, 10, PhotoSort::Imported).unwrap().len();
+        let queried = || index.query(PhotoQuery::default()).unwrap().len();
+        assert_eq!((page(), queried()), (2, 2));
+        assert_eq!((page(), queried()), (2, 2));
+
+        // Writes through another connection
+        other.insert("/c.jpg".to_string(), "h3".to_string(), PhotoMetadata::default()).unwrap();
+        assert_eq!(index.list().unwrap().len(), 3);
+        assert_eq!((page(), queried()), (3, 3));
+
+        drop(index);
+        drop(other);
+        std::fs::remove_file(&db).unwrap();
+    }
+
+    #[test]
+    fn test_changes_since() {
+        let index = setup_test_index();
+        let a = index.insert("/a.jpg".to_string(), "h1".to_string(), PhotoMetadata::default()).unwrap();
+        let b = index.insert("/b.jpg".to_string(), "h2".to_string(), PhotoMetadata::default()).unwrap();
+        let start = index.revision().unwrap();
+        assert!(start > 0);
+
+        let c = index.insert("/c.jpg".to_string(), "h3".to_string(), PhotoMetadata::default()).unwrap();
+        index.set_location(vec![a.id], Some(1.0), Some(1.0)).unwrap();
+        index.delete_by_id(b.id).unwrap();
+        let temp = index.insert("/tmp.jpg".to_string(), "h4".to_string(), PhotoMetadata::default()).unwrap();
+        index.delete_by_id(temp.id).unwrap();
+
+        let changes = index.changes_since(start).unwrap();
+        assert_eq!(changes.added, vec![c.id]);
+        assert_eq!(changes.updated, vec![a.id]);
+        assert_eq!(changes.removed, vec![b.id]);
+        assert_eq!(changes.revision, index.revision().unwrap());
+
+        let none = index.changes_since(changes.revision).unwrap();
+        assert!(none.added.is_empty() && none.updated.is_empty() && none.removed.is_empty());
+        assert_eq!(none.revision, changes.revision);
+    }
+
+    #[test]
+    fn test_summarize_selection() {
+        let index = setup_test_index();
+        let photo = |make: &str, model: &str, date: &str, gps: bool| PhotoMetadata {
+            make: Some(make.to_string()),
+            model: Some(model.to_string()),
+            date_taken: Some(date.to_string()),
+            lat: gps.then_some(1.0),
+            lon: gps.then_some(2.0),
+            ..Default::default()
+        };
+        let a = index.insert("/a.jpg".to_string(), "h1".to_string(), photo("Canon", "Canon EOS R5", "2022-07-01 10:00:00", true)).unwrap();
+        let b = index.insert("/b.jpg".to_string(), "h2".to_string(), photo("Apple", "iPhone 13", "2022-03-05 08:00:00", false)).unwrap();
+        let c = index.insert("/c.jpg".to_string(), "h3".to_string(), photo("Apple", "iPhone 13", "2022-05-05 08:00:00", true)).unwrap();
+        index.insert("/d.jpg".to_string(), "h4".to_string(), photo("Sony", "A7", "2010-01-01 00:00:00", true)).unwrap();
+
+        let dir = std::env::temp_dir().join("fotos_summarize_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("e.jpg"), b"photo").unwrap();
+        let e = index.insert(dir.join("e.jpg").to_string_lossy().into_owned(), "h5".to_string(), PhotoMetadata::default()).unwrap();
+
+        let summary = index.summarize_selection(vec![a.id, b.id, c.id, e.id, 999]).unwrap();
+        assert_eq!(summary.count, 4);
+        // As indexed; the originals aren't read
+        assert_eq!(summary.total_bytes, 5);
+        assert_eq!(summary.date_start.as_deref(), Some("2022:03:05 08:00:00"));
+        assert_eq!(summary.date_end.as_deref(), Some("2022:07:01 10:00:00"));
+        assert_eq!(summary.cameras, vec!["Apple iPhone 13".to_string(), "Canon EOS R5".to_string()]);
+        assert_eq!(summary.geotagged, 2);
+
+        // A date set by hand counts
+        index.set_approximate_date(vec![b.id], 1_609_459_200, DatePrecision::Year).unwrap();
+        let summary = index.summarize_selection(vec![a.id, b.id]).unwrap();
+        assert_eq!(summary.date_start.as_deref(), Some("2021:01:01 00:00:00"));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(index.summarize_selection(Vec::new()).unwrap(), SelectionSummary::default());
+    }
+
+    #[test]
+    fn test_neighbors_follow_query_order() {
+        let index = setup_test_index();
+        let dated = |date: Option<&str>| PhotoMetadata { date_taken: date.map(String::from), ..Default::default() };
+        let c = index.insert("/c.jpg".to_string(), "h3".to_string(), dated(Some("2022-03-01 00:00:00"))).unwrap();
+        let a = index.insert("/a.jpg".to_string(), "h1".to_string(), dated(Some("2022-01-01 00:00:00"))).unwrap();
+        let undated = index.insert("/u.jpg".to_string(), "h4".to_string(), dated(None)).unwrap();
+        let b = index.insert("/b.jpg".to_string(), "h2".to_string(), dated(Some("2022-02-01 00:00:00"))).unwrap();
+        let ids = |photos: &[PhotoInfo]| photos.iter().map(|p| p.id.id).collect::<Vec<_>>();
+
+        let n = index.neighbors(b.id, PhotoQuery::default(), 5, 5).unwrap();
+        assert_eq!(ids(&n.before), vec![a.id]);
+        assert_eq!(ids(&n.after), vec![c.id, undated.id]);
+
+        let newest_first = PhotoQuery { descending: true, ..Default::default() };
+        let n = index.neighbors(c.id, newest_first, 1, 1).unwrap();
+        assert!(n.before.is_empty());
+        assert_eq!(ids(&n.after), vec![b.id]);
+
+        // Not part of the filtered results
+        let night = PhotoQuery { light: Some("night".to_string()), ..Default::default() };
+        let n = index.neighbors(b.id, night, 5, 5).unwrap();
+        assert!(n.before.is_empty() && n.after.is_empty());
+    }
+
+    #[test]
+    fn test_list_page() {
+        let index = setup_test_index();
+        let dated = |date: Option<&str>| PhotoMetadata { date_taken: date.map(str::to_string), ..Default::default() };
+        let a = index.insert("/a.jpg".to_string(), "h1".to_string(), dated(Some("2022:06:01 10:00:00"))).unwrap();
+        let b = index.insert("/b.jpg".to_string(), "h2".to_string(), dated(None)).unwrap();
+        let c = index.insert("/c.jpg".to_string(), "h3".to_string(), dated(Some("2021:06:01 10:00:00"))).unwrap();
+        let ids = |page: Vec<PhotoInfo>| page.iter().map(|p| p.id.id).collect::<Vec<_>>();
+
+        assert_eq!(index.count().unwrap(), 3);
+        assert_eq!(ids(index.list_page(0, 2, PhotoSort::DateTaken).unwrap()), vec![c.id, a.id]);
+        assert_eq!(ids(index.list_page(2, 2, PhotoSort::DateTaken).unwrap()), vec![b.id]);
+        assert_eq!(ids(index.list_page(0, 3, PhotoSort::DateTakenDescending).unwrap()), vec![a.id, c.id, b.id]);
+        assert_eq!(ids(index.list_page(1, 1, PhotoSort::Imported).unwrap()), vec![b.id]);
+        assert!(index.list_page(3, 10, PhotoSort::Imported).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_set_approximate_date() {
+        let index = setup_test_index();
+        let a = index.insert("/scans/IMG_0001.jpg".to_string(), "h1".to_string(), PhotoMetadata::default()).unwrap();
+        assert_eq!(index.undated_clusters().unwrap()[0].photo_ids, vec![a.id]);
+
+        let mid_1998 = crate::metadata::parse_date_taken("1998:07:14 15:00:00").unwrap();
+        assert_eq!(index.set_approximate_date(vec![a.id, 999], mid_1998, DatePrecision::Year).unwrap(), 1);
+        let photo = index.get_by_id(a.id).unwrap().unwrap();
+        assert_eq!(photo.metadata.taken_at, crate::metadata::parse_date_taken("1998:01:01 00:00"));
+        assert_eq!(photo.metadata.date_precision, Some(DatePrecision::Year));
+        assert_eq!(photo.metadata.date_taken, None);
+        assert!(index.undated_clusters().unwrap().is_empty());
+
+        // Re-reading a file that still has no date keeps the manual one
+        index.refresh(a.id, "h1".to_string(), PhotoMetadata::default()).unwrap();
+        assert_eq!(index.get_by_id(a.id).unwrap().unwrap().metadata.date_precision, Some(DatePrecision::Year));
+    }
+
+    #[test]
+    fn test_scans_keep_scan_date_and_circa_date() {
+        let index = setup_test_index();
+        let scanned = PhotoMetadata {
+            make: Some("EPSON".to_string()),
+            model: Some("Perfection V600".to_string()),
+            date_taken: Some("2024:02:10 20:15:00".to_string()),
+            is_scan: true,
+            ..Default::default()
+        };
+        let a = index.insert("/scans/roll1_01.tif".to_string(), "h1".to_string(), scanned.clone()).unwrap();
+        index.insert("/camera/IMG_0001.jpg".to_string(), "h2".to_string(), PhotoMetadata::default()).unwrap();
+        let scans = |wanted| index.query(PhotoQuery { is_scan: Some(wanted), ..Default::default() }).unwrap();
+        assert_eq!(scans(true).iter().map(|p| p.id.id).collect::<Vec<_>>(), vec![a.id]);
+        assert_eq!(scans(false).len(), 1);
+
+        // The scan date isn't taken as the capture date
+        assert_eq!(index.get_by_id(a.id).unwrap().unwrap().metadata.taken_at, None);
+
+        let summer_1987 = crate::metadata::parse_date_taken("1987:07:01 00:00:00").unwrap();
+        index.set_approximate_date(vec![a.id], summer_1987, DatePrecision::Month).unwrap();
+        // Re-reading the scan keeps the circa date beside the scan date
+        index.refresh(a.id, "h1".to_string(), scanned).unwrap();
+        let photo = index.get_by_id(a.id).unwrap().unwrap();
+        assert_eq!(photo.metadata.taken_at, Some(summer_1987));
+        assert_eq!(photo.metadata.date_precision, Some(DatePrecision::Month));
+        assert_eq!(photo.metadata.date_taken.as_deref(), Some("2024:02:10 20:15:00"));
+        assert_eq!(index.timeline(DatePrecision::Year).unwrap()[0].start, crate::metadata::parse_date_taken("1987:01:01 00:00:00").unwrap());
+    }
+
+    #[test]
+    fn test_for_each() {
+        let index = setup_test_index();
+        let a = index.insert("/a.jpg".to_string(), "h1".to_string(), PhotoMetadata::default()).unwrap();
+        let b = index.insert("/b.jpg".to_string(), "h2".to_string(), PhotoMetadata::default()).unwrap();
+
+        // The callback may use the index while iterating
+        let mut seen = Vec::new();
+        index
+            .for_each(|photo| {
+                index.rotate(photo.id.id, 90)?;
+                seen.push(photo.id.id);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(seen, vec![a.id, b.id]);
+
+        let mut calls = 0;
+        let result = index.for_each(|_| {
+            calls += 1;
+            Err(CoreError::InvalidInput("stop".to_string()))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_search() {
+        let index = setup_test_index();
+        let camera = |make: &str, model: &str, date: &str| PhotoMetadata {
+            make: Some(make.to_string()),
+            model: Some(model.to_string()),
+            date_taken: Some(date.to_string()),
+            ..Default::default()
+        };
+        let a = index.insert("/Trips/Kyoto/IMG_0042.JPG".to_string(), "h1".to_string(), camera("Canon", "EOS R5", "2019:04:02 10:00:00")).unwrap();
+        let b = index.insert("/Trips/Oslo/DSC_0001.JPG".to_string(), "h2".to_string(), camera("SONY", "ILCE-7M3", "2021:12:24 18:00:00")).unwrap();
+        let ids = |query: &str| index.search(query.to_string()).unwrap().iter().map(|p| p.id.id).collect::<Vec<_>>();
+
+        assert_eq!(ids("can 2019"), vec![a.id]);
+        assert_eq!(ids("sony"), vec![b.id]);
+        assert_eq!(ids("kyoto img_0042"), vec![a.id]);
+        assert_eq!(ids("2021:12"), vec![b.id]);
+        assert_eq!(ids("trips").len(), 2);
+        assert!(ids("  ").is_empty());
+        assert!(ids("\"unbalanced OR").is_empty());
+
+        // Moves and deletions keep the search index in step
+        index.insert("/Trips/Nara/IMG_0042.JPG".to_string(), "h1".to_string(), PhotoMetadata::default()).unwrap();
+        assert_eq!(ids("nara"), vec![a.id]);
+        assert!(ids("kyoto").is_empty());
+        index.delete_by_id(b.id).unwrap();
+        assert!(ids("sony").is_empty());
+
+        // Case, diacritics and decomposed (macOS) file names don't matter
+        let c = index.insert("/Viagens/Sa\u{0303}o Paulo/IMG_7.JPG".to_string(), "h3".to_string(), PhotoMetadata::default()).unwrap();
+        let d = index.insert("/Reisen/Straße/IMG_8.JPG".to_string(), "h4".to_string(), PhotoMetadata::default()).unwrap();
+        assert_eq!(ids("sao paulo"), vec![c.id]);
+        assert_eq!(ids("SÃO"), vec![c.id]);
+        assert_eq!(ids("strasse"), vec![d.id]);
+        assert_eq!(ids("STRAẞE"), vec![d.id]);
+    }
+
+    #[test]
+    fn test_search_cjk() {
+        let index = setup_test_index();
+        let a = index.insert("/旅行/北京天安门/IMG_0001.JPG".to_string(), "h1".to_string(), PhotoMetadata::default()).unwrap();
+        let b = index.insert("/旅行/東京タワー/DSC_0002.JPG".to_string(), "h2".to_string(), PhotoMetadata::default()).unwrap();
+        let c = index.insert("/Trips/서울 Seoul/IMG_0003.JPG".to_string(), "h3".to_string(), PhotoMetadata::default()).unwrap();
+        let ids = |query: &str| {
+            let mut ids = index.search(query.to_string()).unwrap().iter().map(|p| p.id.id).collect::<Vec<_>>();
+            ids.sort();
+            ids
+        };
+
+        // Words inside a run of characters, at either end or in the middle
+        assert_eq!(ids("天安门"), vec![a.id]);
+        assert_eq!(ids("北京"), vec![a.id]);
+        assert_eq!(ids("门"), vec![a.id]);
+        assert_eq!(ids("タワー"), vec![b.id]);
+        assert_eq!(ids("旅行"), vec![a.id, b.id]);
+        assert!(ids("京天门").is_empty());
+
+        // Mixed with Latin words, spaced or not
+        assert_eq!(ids("北京 img"), vec![a.id]);
+        assert_eq!(ids("東京dsc"), vec![b.id]);
+        assert_eq!(ids("seoul 서울"), vec![c.id]);
+        assert!(ids("北京 dsc").is_empty());
+    }
+
+    #[test]
+    fn test_search_places_and_name_order() {
+        let index = setup_test_index();
+        for name in ["Zürich", "São Paulo", "Ålesund", "salzburg"] {
+            index.add_place(Place { id: 0, name: name.to_string(), lat: 0.0, lon: 0.0, radius_m: 100.0, polygon: Vec::new() }).unwrap();
+        }
+        let names = |places: Vec<Place>| places.into_iter().map(|p| p.name).collect::<Vec<_>>();
+
+        assert_eq!(names(index.list_places().unwrap()), vec!["Ålesund", "salzburg", "São Paulo", "Zürich"]);
+        assert_eq!(names(index.search_places("sao paulo".to_string()).unwrap()), vec!["São Paulo"]);
+        assert_eq!(names(index.search_places("S".to_string()).unwrap()), vec!["salzburg", "São Paulo"]);
+        assert_eq!(names(index.search_places("zur".to_string()).unwrap()), vec!["Zürich"]);
+        assert!(index.search_places("".to_string()).unwrap().is_empty());
+
+        index.create_album("Élan".to_string()).unwrap();
+        index.create_album("Dogs".to_string()).unwrap();
+        index.create_album("fish".to_string()).unwrap();
+        let albums: Vec<String> = index.list_albums().unwrap().into_iter().map(|a| a.name).collect();
+        assert_eq!(albums, vec!["Dogs", "Élan", "fish"]);
+    }
+
+    #[test]
+    fn test_query_filters_and_sort() {
+        let index = setup_test_index();
+        let a = index.insert("/b/a.JPG".to_string(), "h1".to_string(), PhotoMetadata {
+            make: Some("Canon".to_string()),
+            model: Some("Canon EOS R5".to_string()),
+            date_taken: Some("2022:06:01 10:00:00".to_string()),
+            iso: Some(100),
+            width: 6000,
+            height: 4000,
+            lat: Some(1.0),
+            lon: Some(2.0),
+            color_profile: Some("Display P3".to_string()),
+            bit_depth: Some(8),
+            chroma_subsampling: Some("4:2:0".to_string()),
+            ..Default::default()
+        }).unwrap();
+        let b = index.insert("/a/b.dng".to_string(), "h2".to_string(), PhotoMetadata {
+            make: Some("Apple".to_string()),
+            model: Some("iPhone 15 Pro".to_string()),
+            date_taken: Some("2023:01:01 10:00:00".to_string()),
+            iso: Some(1600),
+            width: 4000,
+            height: 3000,
+            orientation: 6,
+            bit_depth: Some(16),
+            ..Default::default()
+        }).unwrap();
+        let ids = |query: PhotoQuery| index.query(query).unwrap().iter().map(|p| p.id.id).collect::<Vec<_>>();
+
+        assert_eq!(ids(PhotoQuery::default()), vec![a.id, b.id]);
+        let from_2023 = crate::metadata::parse_date_taken("2023:01:01 00:00");
+        assert_eq!(ids(PhotoQuery { taken_from: from_2023, ..Default::default() }), vec![b.id]);
+        assert_eq!(ids(PhotoQuery { camera: Some("Apple iPhone 15 Pro".to_string()), ..Default::default() }), vec![b.id]);
+        assert_eq!(ids(PhotoQuery { has_gps: Some(true), ..Default::default() }), vec![a.id]);
+        assert_eq!(ids(PhotoQuery { iso_min: Some(400), iso_max: Some(3200), ..Default::default() }), vec![b.id]);
+        assert_eq!(ids(PhotoQuery { orientation: Some("portrait".to_string()), ..Default::default() }), vec![b.id]);
+        assert_eq!(ids(PhotoQuery { file_types: vec!["jpg".to_string()], ..Default::default() }), vec![a.id]);
+        assert_eq!(ids(PhotoQuery { color_profile: Some("display p3".to_string()), ..Default::default() }), vec![a.id]);
+        assert_eq!(ids(PhotoQuery { min_bit_depth: Some(10), ..Default::default() }), vec![b.id]);
+        assert_eq!(ids(PhotoQuery { chroma_subsampling: Some("4:2:0".to_string()), ..Default::default() }), vec![a.id]);
+        assert_eq!(index.get_by_id(a.id).unwrap().unwrap().metadata.bit_depth, Some(8));
+        assert_eq!(ids(PhotoQuery { sort: QuerySort::Path, ..Default::default() }), vec![b.id, a.id]);
+        assert_eq!(ids(PhotoQuery { sort: QuerySort::Path, descending: true, ..Default::default() }), vec![a.id, b.id]);
+        index.conn.lock().unwrap().execute("UPDATE photos SET file_size = 10 WHERE id = ?1", params![a.id]).unwrap();
+        let largest_first = PhotoQuery { sort: QuerySort::FileSize, descending: true, ..Default::default() };
+        assert_eq!(index.query(largest_first).unwrap().iter().map(|p| (p.id.id, p.file_size)).collect::<Vec<_>>(), vec![(a.id, 10), (b.id, 0)]);
+
+        let clip = index.insert("/c/clip.mov".to_string(), "h3".to_string(), PhotoMetadata {
+            media_type: MediaType::Video,
+            duration_ms: Some(12_500),
+            video_codec: Some("HEVC".to_string()),
+            description: Some("Fireworks".to_string()),
+            ..Default::default()
+        }).unwrap();
+        assert_eq!(ids(PhotoQuery { media_type: Some(MediaType::Video), ..Default::default() }), vec![clip.id]);
+        assert_eq!(ids(PhotoQuery { media_type: Some(MediaType::Photo), ..Default::default() }), vec![a.id, b.id]);
+        let clip = index.get_by_id(clip.id).unwrap().unwrap().metadata;
+        assert_eq!((clip.duration_ms, clip.video_codec.as_deref()), (Some(12_500), Some("HEVC")));
+        assert_eq!(clip.description.as_deref(), Some("Fireworks"));
+    }
+
+    #[test]
+    fn test_photo_views() {
+        let index = setup_test_index();
+        let a = index.insert("/a.jpg".to_string(), "h1".to_string(), PhotoMetadata::default()).unwrap().id;
+        let b = index.insert("/b.jpg".to_string(), "h2".to_string(), PhotoMetadata::default()).unwrap().id;
+        let c = index.insert("/c.jpg".to_string(), "h3".to_string(), PhotoMetadata::default()).unwrap().id;
+        let revision = index.revision().unwrap();
+
+        assert!(index.record_view(b).unwrap());
+        assert!(index.record_view(b).unwrap());
+        assert!(index.record_view(a).unwrap());
+        assert!(!index.record_view(999).unwrap());
+        assert_eq!(index.revision().unwrap(), revision, "views aren't changes");
+
+        let stats = index.view_stats(b).unwrap();
+        assert_eq!(stats.view_count, 2);
+        assert!(stats.last_viewed_at.is_some());
+        assert_eq!(index.view_stats(c).unwrap(), PhotoViews::default());
+
+        let ids = |query: PhotoQuery| index.query(query).unwrap().iter().map(|p| p.id.id).collect::<Vec<_>>();
+        let most_viewed = PhotoQuery { viewed: Some(true), sort: QuerySort::ViewCount, descending: true, ..Default::default() };
+        assert_eq!(ids(most_viewed), vec![b, a]);
+        assert_eq!(ids(PhotoQuery { viewed: Some(false), ..Default::default() }), vec![c]);
+
+        // Views go with the photo once it's deleted for good
+        index.delete_by_id(b).unwrap();
+        assert_eq!(index.view_stats(b).unwrap(), PhotoViews::default());
+    }
+
+    #[test]
+    fn test_enhance_edits() {
+        let index = setup_test_index();
+        let a = index.insert("/a.jpg".to_string(), "h1".to_string(), PhotoMetadata::default()).unwrap().id;
+        assert_eq!(index.get_enhance(a).unwrap(), None);
+
+        let recipe = crate::image::AutoEnhance { black_point: 12, white_point: 240, gain_r: 0.9, gain_b: 1.25, ..Default::default() };
+        let on = PhotoEnhance { recipe, enabled: true };
+        assert!(index.set_enhance(a, on).unwrap());
+        assert!(!index.set_enhance(999, on).unwrap());
+        assert_eq!(index.get_enhance(a).unwrap(), Some(on));
+
+        // Switched off, the recipe stays for switching back on
+        index.set_enhance(a, PhotoEnhance { enabled: false, ..on }).unwrap();
+        assert_eq!(index.get_enhance(a).unwrap(), Some(PhotoEnhance { recipe, enabled: false }));
+
+        // Kept in the trash, gone once deleted for good
+        index.move_to_trash(vec![a]).unwrap();
+        index.restore_from_trash(vec![a]).unwrap();
+        assert!(index.get_enhance(a).unwrap().is_some());
+        index.delete_by_id(a).unwrap();
+        assert_eq!(index.get_enhance(a).unwrap(), None);
+    }
+
+    #[test]
+    fn test_albums() {
+        let index = setup_test_index();
+        let a = index.insert("/a.jpg".to_string(), "h1".to_string(), PhotoMetadata::default()).unwrap();
+        let b = index.insert("/b.jpg".to_string(), "h2".to_string(), PhotoMetadata::default()).unwrap();
+
+        assert!(index.create_album("  ".to_string()).is_err());
+        let trip = index.create_album("Trip".to_string()).unwrap();
+        let empty = index.create_album("Empty".to_string()).unwrap();
+        assert!(index.add_to_album(999, vec![a.id]).is_err());
+
+        assert_eq!(index.add_to_album(trip, vec![b.id, a.id, b.id, 999]).unwrap(), 2);
+        let ids = |album| index.list_album_photos(album).unwrap().iter().map(|p| p.id.id).collect::<Vec<_>>();
+        assert_eq!(ids(trip), vec![b.id, a.id]);
+
+        assert!(index.rename_album(trip, " Road trip ".to_string()).unwrap());
+        let albums = index.list_albums().unwrap();
+        assert_eq!(albums.iter().map(|a| a.name.as_str()).collect::<Vec<_>>(), vec!["Empty", "Road trip"]);
+        assert_eq!((albums[1].photo_count, albums[1].cover_photo_id), (2, Some(b.id)));
+        assert_eq!((albums[0].photo_count, albums[0].cover_photo_id), (0, None));
+
+        assert_eq!(index.remove_from_album(trip, vec![b.id]).unwrap(), 1);
+        index.delete_by_id(a.id).unwrap();
+        assert!(ids(trip).is_empty());
+
+        assert!(index.delete_album(empty).unwrap());
+        assert!(!index.delete_album(empty).unwrap());
+        assert_eq!(index.list_albums().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_trash() {
+        let index = setup_test_index();
+        let a = index.insert("/a.jpg".to_string(), "ha".to_string(), PhotoMetadata::default()).unwrap().id;
+        let b = index.insert("/b.jpg".to_string(), "hb".to_string(), PhotoMetadata::default()).unwrap().id;
+        let c = index.insert("/c.jpg".to_string(), "hc".to_string(), PhotoMetadata::default()).unwrap().id;
+        let album = index.create_album("Trip".to_string()).unwrap();
+        index.add_to_album(album, vec![a]).unwrap();
+
+        assert_eq!(index.move_to_trash(vec![a, c, 999]).unwrap(), 2);
+        assert_eq!(index.count().unwrap(), 1);
+        assert!(index.get_by_id(a).unwrap().is_none());
+        assert!(index.is_deleted("/a.jpg".to_string(), "ha".to_string()).unwrap());
+        assert_eq!(index.list_albums().unwrap()[0].photo_count, 0);
+        let trash: Vec<i64> = index.list_trash().unwrap().iter().map(|t| t.photo.id.id).collect();
+        assert_eq!(trash, vec![c, a]);
+
+        // Trashed ids stay reserved
+        let d = index.insert("/d.jpg".to_string(), "hd".to_string(), PhotoMetadata::default()).unwrap().id;
+        assert!(d > c);
+
+        // Restoring brings back album membership
+        assert_eq!(index.restore_from_trash(vec![a, b]).unwrap(), 1);
+        assert_eq!(index.get_by_id(a).unwrap().unwrap().path, "/a.jpg");
+        assert_eq!(index.list_albums().unwrap()[0].photo_count, 1);
+
+        // Importing a trashed photo again takes it out of the trash
+        index.move_to_trash(vec![b]).unwrap();
+        let moved = index.insert("/moved/b.jpg".to_string(), "hb".to_string(), PhotoMetadata::default()).unwrap();
+        assert_eq!(moved.id, b);
+        assert_eq!(index.get_by_id(b).unwrap().unwrap().path, "/moved/b.jpg");
+
+        assert!(index.purge_trash(30).unwrap().is_empty());
+        let purged = index.empty_trash().unwrap();
+        assert_eq!(purged.iter().map(|p| p.id.id).collect::<Vec<_>>(), vec![c]);
+        assert!(index.list_trash().unwrap().is_empty());
+        assert!(index.is_deleted("/c.jpg".to_string(), "hc".to_string()).unwrap());
+    }
+
+    #[test]
+    fn test_trash_keeps_file_state() {
+        let dir = std::env::temp_dir().join("fotos_trash_file_state_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.jpg"), b"photo").unwrap();
+        let index = setup_test_index();
+        let a = index.insert(dir.join("a.jpg").to_string_lossy().into_owned(), "ha".to_string(), PhotoMetadata::default()).unwrap().id;
+        index.conn.lock().unwrap().execute("UPDATE photos SET checked_at = 42 WHERE id = ?1", params![a]).unwrap();
+        let state = |index: &PhotoIndex| -> Vec<Option<i64>> {
+            index.conn.lock().unwrap()
+                .query_row(&format!("SELECT file_size, {} FROM photos WHERE id = ?1", FILE_STATE_COLUMNS), params![a], |row| {
+                    (0..5).map(|i| row.get(i)).collect()
+                })
+                .unwrap()
+        };
+        let before = state(&index);
+        assert_eq!((before[0], before[2], before[4]), (Some(5), Some(5), Some(42)));
+
+        index.move_to_trash(vec![a]).unwrap();
+        index.restore_from_trash(vec![a]).unwrap();
+        assert_eq!(state(&index), before);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_source_url() {
+        let index = setup_test_index();
+        let id = index.insert("/web.png".to_string(), "hw".to_string(), PhotoMetadata::default()).unwrap().id;
+        assert_eq!(index.get_source_url(id).unwrap(), None);
+
+        index.set_source_url(id, "https://example.com/ref.png".to_string()).unwrap();
+        assert_eq!(index.get_source_url(id).unwrap().as_deref(), Some("https://example.com/ref.png"));
+
+        // Kept while in the trash, dropped with the photo
+        index.move_to_trash(vec![id]).unwrap();
+        index.restore_from_trash(vec![id]).unwrap();
+        assert!(index.get_source_url(id).unwrap().is_some());
+        index.move_to_trash(vec![id]).unwrap();
+        index.empty_trash().unwrap();
+        assert_eq!(index.get_source_url(id).unwrap(), None);
+    }
+
+    #[test]
+    fn test_backup_and_restore() {
+        let dir = std::env::temp_dir().join("fotos_backup_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = |name: &str| dir.join(name).to_string_lossy().into_owned();
+
+        let index = PhotoIndex::open(db("library.db")).unwrap();
+        index.insert("/a.jpg".to_string(), "ha".to_string(), PhotoMetadata::default()).unwrap();
+        index.backup_to(db("snapshot.db")).unwrap();
+
+        index.insert("/b.jpg".to_string(), "hb".to_string(), PhotoMetadata::default()).unwrap();
+        assert_eq!(index.list().unwrap().len(), 2);
+        index.restore_from(db("snapshot.db")).unwrap();
+        // Cached listings from before the restore aren't served
+        let paths: Vec<String> = index.list().unwrap().into_iter().map(|p| p.path).collect();
+        assert_eq!(paths, vec!["/a.jpg"]);
+        assert_eq!(index.count().unwrap(), 1);
+
+        // Other databases are refused and leave the library alone
+        Connection::open(db("other.db")).unwrap().execute_batch("CREATE TABLE notes (text TEXT)").unwrap();
+        assert!(index.restore_from(db("other.db")).is_err());
+        assert_eq!(index.count().unwrap(), 1);
+
+        drop(index);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_open_encrypted() {
+        let dir = std::env::temp_dir().join("fotos_encrypted_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = |name: &str| dir.join(name).to_string_lossy().into_owned();
+        let passphrase = || "correct horse".to_string();
+
+        assert!(PhotoIndex::open_encrypted(db("library.db"), String::new()).is_err());
+        let opened = PhotoIndex::open_encrypted(db("library.db"), passphrase());
+        if !cfg!(feature = "encryption") {
+            // Refused rather than silently writing a plaintext library
+            assert!(opened.is_err());
+            let _ = std::fs::remove_dir_all(&dir);
+            return;
+        }
+
+        let index = opened.unwrap();
+        let at = PhotoMetadata { lat: Some(59.91), lon: Some(10.75), ..Default::default() };
+        index.insert("/secret.jpg".to_string(), "ha".to_string(), at).unwrap();
+        index.backup_to(db("snapshot.db")).unwrap();
+        index.insert("/b.jpg".to_string(), "hb".to_string(), PhotoMetadata::default()).unwrap();
+        drop(index);
+
+        for file in ["library.db", "snapshot.db"] {
+            assert!(!std::fs::read(db(file)).unwrap().starts_with(b"SQLite format 3"));
+        }
+        assert!(PhotoIndex::open(db("library.db")).is_err());
+        let wrong = PhotoIndex::open_encrypted(db("library.db"), "wrong".to_string());
+        assert!(matches!(wrong, Err(CoreError::PermissionDenied(_))));
+
+        let index = PhotoIndex::open_encrypted(db("library.db"), passphrase()).unwrap();
+        assert_eq!(index.list().unwrap().len(), 2);
+        index.restore_from(db("snapshot.db")).unwrap();
+        assert_eq!(index.list().unwrap()[0].metadata.lat, Some(59.91));
+        assert_eq!(index.count().unwrap(), 1);
+
+        drop(index);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_audit_originals_flags_bit_rot() {
+        let dir = std::env::temp_dir().join("fotos_audit_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let index = setup_test_index();
+        let (a, b) = (dir.join("a.jpg"), dir.join("b.jpg"));
+        for (name, path) in [("ha", &a), ("hb", &b)] {
+            std::fs::write(path, format!("photo bytes {}", name)).unwrap();
+            index.insert(path.to_string_lossy().into_owned(), name.to_string(), PhotoMetadata::default()).unwrap();
+        }
+        let a_id = index.get_by_hash("ha".to_string()).unwrap().unwrap().id.id;
+
+        // Checksums are taken at import, then samples rotate through the library
+        let report = index.audit_originals(1).unwrap();
+        assert_eq!((report.checked, report.verified, report.recorded), (1, 1, 0));
+        let report = index.audit_originals(5).unwrap();
+        assert_eq!((report.checked, report.verified, report.corrupt.len()), (2, 2, 0));
+
+        // A flipped byte with the size and modification time as they were
+        let mtime = std::fs::metadata(&a).unwrap().modified().unwrap();
+        std::fs::write(&a, b"photo bytez ha").unwrap();
+        std::fs::File::options().write(true).open(&a).unwrap().set_modified(mtime).unwrap();
+        let report = index.audit_originals(5).unwrap();
+        assert_eq!((report.verified, report.corrupt.clone()), (1, vec![a_id]));
+        assert!(index.get_by_id(a_id).unwrap().unwrap().corrupt);
+        let corrupt = index.query(PhotoQuery { corrupt: Some(true), ..Default::default() }).unwrap();
+        assert_eq!(corrupt.iter().map(|p| p.id.id).collect::<Vec<_>>(), vec![a_id]);
+
+        // Replacing the file (here: restoring a good copy) counts as an edit
+        std::fs::write(&a, b"photo bytes, restored").unwrap();
+        let report = index.audit_originals(5).unwrap();
+        assert_eq!((report.recorded, report.corrupt.len()), (1, 0));
+        assert!(!index.get_by_id(a_id).unwrap().unwrap().corrupt);
+
+        // Gone files are left to `verify`
+        std::fs::remove_file(&b).unwrap();
+        assert_eq!(index.audit_originals(5).unwrap().unreadable, 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_verify_flags_missing_and_orphans() {
+        let dir = std::env::temp_dir().join("fotos_verify_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let thumbs = dir.join("thumbnails");
+        std::fs::create_dir_all(&thumbs).unwrap();
+        let config = PhotoCoreConfig { thumbnail_dir: thumbs.to_string_lossy().into_owned(), thumbnail_size: 256 };
+        let thumbnail = |path: &std::path::Path| {
+            let spec = ThumbnailSpec { width: 256, height: 256, rotation: 0 };
+            let file = cache_path(&thumbs, &thumbnail_key(path, &spec).unwrap());
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(&file, b"jpeg").unwrap();
+            file
+        };
+
+        let index = setup_test_index();
+        let (a, b) = (dir.join("a.jpg"), dir.join("b.jpg"));
+        for (name, path) in [("ha", &a), ("hb", &b)] {
+            std::fs::write(path, name).unwrap();
+            index.insert(path.to_string_lossy().into_owned(), name.to_string(), PhotoMetadata::default()).unwrap();
+            thumbnail(path);
+        }
+        let orphan = thumbnail(&dir.join("deleted long ago.jpg"));
+        let b_id = index.get_by_hash("hb".to_string()).unwrap().unwrap().id.id;
+        std::fs::remove_file(&b).unwrap();
+
+        let report = index.verify(config.clone(), false).unwrap();
+        assert_eq!((report.checked, report.missing.clone(), report.found), (2, vec![b_id], 0));
+        assert_eq!((report.orphaned_thumbnails, report.orphaned_bytes, report.removed), (1, 4, 0));
+        assert!(orphan.exists());
+        let missing = index.query(PhotoQuery { missing: Some(true), ..Default::default() }).unwrap();
+        assert_eq!(missing.iter().map(|p| p.id.id).collect::<Vec<_>>(), vec![b_id]);
+
+        // The file is back, and the orphan goes
+        std::fs::write(&b, b"photo").unwrap();
+        let report = index.verify(config, true).unwrap();
+        assert_eq!((report.missing.len(), report.found, report.removed), (0, 1, 1));
+        assert!(!orphan.exists());
+        assert!(!index.get_by_id(b_id).unwrap().unwrap().missing);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_find_similar_and_duplicate_report() {
+        let index = setup_test_index();
+        let hash = |bits: u64| image_hasher::ImageHash::<Box<[u8]>>::from_bytes(&bits.to_be_bytes()).unwrap().to_base64();
+        let base = 0x00FF_00FF_00FF_00FFu64;
+        let original = index.insert("/o.jpg".to_string(), hash(base), PhotoMetadata::default()).unwrap().id;
+        let resized = index.insert("/r.jpg".to_string(), hash(base ^ 0b111), PhotoMetadata::default()).unwrap().id;
+        let edited = index.insert("/e.jpg".to_string(), hash(base ^ 0xFF00), PhotoMetadata::default()).unwrap().id;
+        index.insert("/x.jpg".to_string(), hash(!base), PhotoMetadata::default()).unwrap();
+        let raw = index.insert("/raw.nef".to_string(), "file:0123456789abcdef".to_string(), PhotoMetadata::default()).unwrap().id;
+
+        let similar: Vec<(i64, u32)> =
+            index.find_similar(original, 10).unwrap().iter().map(|s| (s.photo.id.id, s.distance)).collect();
+        assert_eq!(similar, vec![(resized, 3), (edited, 8)]);
+        assert!(index.find_similar(raw, 64).unwrap().is_empty());
+
+        // Top-k ignores the threshold: the far-off photo fills the last slot
+        let top: Vec<i64> = index.find_similar_to(original, 3).unwrap().iter().map(|s| s.photo.id.id).collect();
+        assert_eq!(top.len(), 3);
+        assert_eq!(&top[..2], &[resized, edited]);
+        assert_eq!(index.find_similar_to(original, 1).unwrap()[0].photo.id.id, resized);
+        assert!(index.find_similar_to(raw, 3).unwrap().is_empty());
+
+        let report = index.duplicate_report(4).unwrap();
+        assert_eq!(report.len(), 1);
+        let ids: Vec<i64> = report[0].photos.iter().map(|p| p.id.id).collect();
+        assert_eq!(ids, vec![original, resized]);
+    }
+
+    #[test]
+    fn test_list_in_bounds() {
+        let index = setup_test_index();
+        let at = |lat: f64, lon: f64| PhotoMetadata { lat: Some(lat), lon: Some(lon), ..Default::default() };
+        let paris = index.insert("/paris.jpg".to_string(), "hp".to_string(), at(48.8566, 2.3522)).unwrap().id;
+        let lyon = index.insert("/lyon.jpg".to_string(), "hl".to_string(), at(45.764, 4.8357)).unwrap().id;
+        let fiji = index.insert("/fiji.jpg".to_string(), "hf".to_string(), at(-17.7134, 178.065)).unwrap().id;
+        index.insert("/nowhere.jpg".to_string(), "hn".to_string(), PhotoMetadata::default()).unwrap();
+
+        let ids = |photos: Vec<PhotoInfo>| {
+            let mut ids: Vec<i64> = photos.iter().map(|p| p.id.id).collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(ids(index.list_in_bounds(44.0, 0.0, 50.0, 6.0).unwrap()), vec![paris, lyon]);
+        // Just outside Paris, within the R-tree's float rounding
+        assert_eq!(ids(index.list_in_bounds(48.85661, 0.0, 50.0, 6.0).unwrap()), Vec::<i64>::new());
+        // Across the antimeridian
+        assert_eq!(ids(index.list_in_bounds(-20.0, 170.0, -10.0, -170.0).unwrap()), vec![fiji]);
+
+        // Moving, clearing and trashing keep the R-tree in step
+        index.set_location(vec![lyon], Some(-17.0), Some(179.0)).unwrap();
+        index.set_location(vec![paris], None, None).unwrap();
+        assert!(index.list_in_bounds(44.0, 0.0, 50.0, 6.0).unwrap().is_empty());
+        assert_eq!(ids(index.list_in_bounds(-20.0, 170.0, -10.0, -170.0).unwrap()), vec![lyon, fiji]);
+        index.move_to_trash(vec![fiji]).unwrap();
+        assert_eq!(ids(index.list_in_bounds(-20.0, 170.0, -10.0, -170.0).unwrap()), vec![lyon]);
+        index.restore_from_trash(vec![fiji]).unwrap();
+        assert_eq!(ids(index.list_in_bounds(-20.0, 170.0, -10.0, -170.0).unwrap()), vec![lyon, fiji]);
+    }
+
+    #[test]
+    fn test_timeline() {
+        let index = setup_test_index();
+        for (i, date) in ["2023:03:04 18:00:00", "2023:03:04 09:00:00", "2023:03:20 12:00:00", "2024:01:02 08:00:00"]
+            .iter()
+            .enumerate()
+        {
+            let metadata = PhotoMetadata { date_taken: Some(date.to_string()), ..Default::default() };
+            index.insert(format!("/t/{}.jpg", i), format!("t{}", i), metadata).unwrap();
+        }
+        index.insert("/t/undated.jpg".to_string(), "tu".to_string(), PhotoMetadata::default()).unwrap();
+
+        let months = index.timeline(DatePrecision::Month).unwrap();
+        let summary: Vec<(i64, u32)> = months.iter().map(|b| (b.start, b.count)).collect();
+        let month_start = |date: &str| crate::metadata::parse_date_taken(date).unwrap();
+        assert_eq!(summary, vec![(month_start("2023:03:01 00:00"), 3), (month_start("2024:01:01 00:00"), 1)]);
+        // The cover is the earliest photo of the bucket
+        assert_eq!(months[0].cover.path, "/t/1.jpg");
+
+        assert_eq!(index.timeline(DatePrecision::Day).unwrap().len(), 3);
+        let years: Vec<u32> = index.timeline(DatePrecision::Year).unwrap().iter().map(|b| b.count).collect();
+        assert_eq!(years, vec![3, 1]);
+    }
+
+    #[test]
+    fn test_insert_batch() {
+        let index = setup_test_index();
+        let existing = index.insert("/a.jpg".to_string(), "ha".to_string(), PhotoMetadata::default()).unwrap();
+        let photo = |path: &str, hash: &str| NewPhoto {
+            path: path.to_string(),
+            hash: hash.to_string(),
+            metadata: PhotoMetadata::default(),
+        };
+
+        let ids = index
+            .insert_batch(vec![photo("/b.jpg", "hb"), photo("/a.jpg", "ha"), photo("/c.jpg", "hc"), photo("/b2.jpg", "hb")])
+            .unwrap();
+        // Same rules as `insert`: known paths and hashes keep their id
+        assert_eq!(ids[1], existing);
+        assert_eq!(ids[3], ids[0]);
+        assert!(ids[0].id < ids[2].id);
+        assert_eq!(index.count().unwrap(), 3);
+        assert_eq!(index.get_by_id(ids[0].id).unwrap().unwrap().path, "/b2.jpg");
+
+        assert!(index.insert_batch(Vec::new()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_paths_exist() {
+        let index = setup_test_index();
+        for i in 0..LOOKUP_BATCH + 10 {
+            index.insert(format!("/p/{}.jpg", i), format!("h{}", i), PhotoMetadata::default()).unwrap();
+        }
+
+        let mut paths: Vec<String> = (0..LOOKUP_BATCH + 10).map(|i| format!("/p/{}.jpg", i)).collect();
+        paths.insert(3, "/p/missing.jpg".to_string());
+        paths.push("/p/0.jpg".to_string());
+        let exist = index.paths_exist(paths).unwrap();
+        assert_eq!(exist.len(), LOOKUP_BATCH + 12);
+        assert_eq!(exist.iter().filter(|e| !**e).count(), 1);
+        assert!(!exist[3]);
+        assert!(index.paths_exist(Vec::new()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_get_and_delete_by_ids() {
+        let index = setup_test_index();
+        let ids: Vec<i64> = (0..LOOKUP_BATCH + 10)
+            .map(|i| index.insert(format!("/p/{}.jpg", i), format!("h{}", i), PhotoMetadata::default()).unwrap().id)
+            .collect();
+
+        let wanted = vec![ids[LOOKUP_BATCH + 5], -1, ids[0], ids[3]];
+        let paths: Vec<String> = index.get_by_ids(wanted).unwrap().into_iter().map(|p| p.path).collect();
+        assert_eq!(paths, vec![format!("/p/{}.jpg", LOOKUP_BATCH + 5), "/p/0.jpg".to_string(), "/p/3.jpg".to_string()]);
+        assert_eq!(index.get_by_ids(ids.clone()).unwrap().len(), ids.len());
+        assert!(index.get_by_ids(Vec::new()).unwrap().is_empty());
+
+        let deleted = index.delete_by_ids(vec![ids[1], ids[1], -1, ids[2]]).unwrap();
+        assert_eq!(deleted.iter().map(|p| p.id.id).collect::<Vec<_>>(), vec![ids[1], ids[2]]);
+        assert_eq!(index.count().unwrap() as usize, ids.len() - 2);
+        assert!(index.is_deleted("/p/1.jpg".to_string(), "h1".to_string()).unwrap());
+    }
+
+    #[test]
+    fn test_lookalikes_are_told_apart_by_content() {
+        let dir = std::env::temp_dir().join("fotos_content_checksum_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("moved")).unwrap();
+        let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+        std::fs::write(dir.join("a.jpg"), b"burst frame 1").unwrap();
+        std::fs::write(dir.join("b.jpg"), b"burst frame 2").unwrap();
+
+        // Same perceptual hash, different bytes: two photos
+        let index = setup_test_index();
+        let a = index.insert(path("a.jpg"), "h".to_string(), PhotoMetadata::default()).unwrap();
+        let b = index.insert(path("b.jpg"), "h".to_string(), PhotoMetadata::default()).unwrap();
+        assert_ne!(a, b);
+        let a_checksum = index.get_by_id(a.id).unwrap().unwrap().checksum;
+        assert_eq!(a_checksum, Some(crate::image::compute_checksum(&dir.join("a.jpg")).unwrap()));
+
+        // The same bytes under another name: the photo was moved
+        std::fs::rename(dir.join("a.jpg"), dir.join("moved").join("a.jpg")).unwrap();
+        assert_eq!(index.insert(path("moved/a.jpg"), "h".to_string(), PhotoMetadata::default()).unwrap(), a);
+        assert_eq!(index.get_by_id(a.id).unwrap().unwrap().path, path("moved/a.jpg"));
+        assert_eq!(index.count().unwrap(), 2);
+
+        // Without a checksum to go by, the perceptual hash decides as before
+        let unreadable = index.insert("/elsewhere/c.jpg".to_string(), "h".to_string(), PhotoMetadata::default()).unwrap();
+        assert!([a, b].contains(&unreadable));
+        assert_eq!(index.count().unwrap(), 2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_forgetting_a_lookalike_keeps_the_others() {
+        let dir = std::env::temp_dir().join("fotos_lookalike_tombstone_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+        std::fs::write(dir.join("a.jpg"), b"resized copy").unwrap();
+        std::fs::write(dir.join("b.jpg"), b"original").unwrap();
+        std::fs::write(dir.join("c.jpg"), b"burst frame").unwrap();
+
+        let index = setup_test_index();
+        let a = index.insert(path("a.jpg"), "h".to_string(), PhotoMetadata::default()).unwrap();
+        let b = index.insert(path("b.jpg"), "h".to_string(), PhotoMetadata::default()).unwrap();
+        index.delete_by_id(a.id).unwrap();
+        index.move_to_trash(vec![b.id]).unwrap();
+        assert!(index.is_deleted(path("a.jpg"), "h".to_string()).unwrap());
+        assert!(index.is_deleted(path("b.jpg"), "h".to_string()).unwrap());
+
+        // A lookalike that was never deleted is still imported
+        assert!(!index.is_deleted(path("c.jpg"), "h".to_string()).unwrap());
+        let c = index.insert(path("c.jpg"), "h".to_string(), PhotoMetadata::default()).unwrap();
+        assert_ne!(c, b);
+        assert!(index.is_deleted(path("a.jpg"), "h".to_string()).unwrap());
+        assert_eq!(index.list_trash().unwrap().len(), 1);
+
+        // A deletion from before checksums still goes by the hash
+        index.conn.lock().unwrap().execute("INSERT INTO tombstones (hash, deleted_at) VALUES ('old', 0)", []).unwrap();
+        assert!(index.is_deleted(path("c.jpg"), "old".to_string()).unwrap());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_legacy_hash_duplicates_still_open() {
+        // Duplicates written before the constraint existed don't stop the library opening
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE photos (
+                id INTEGER PRIMARY KEY, path TEXT NOT NULL UNIQUE, hash TEXT NOT NULL,
+                make TEXT, model TEXT, date_taken TEXT, width INTEGER, height INTEGER, lat REAL, lon REAL,
+                iso INTEGER, f_number REAL, exposure_time TEXT, orientation INTEGER
+             );
+             CREATE INDEX idx_photos_hash ON photos (hash);
+             INSERT INTO photos (path, hash, width, height, orientation) VALUES ('/a.jpg', 'h', 1, 1, 1), ('/b.jpg', 'h', 1, 1, 1);",
+        ).unwrap();
+        init_schema(&conn).unwrap();
+        let index = PhotoIndex::from_connection(conn);
+        assert_eq!(index.get_by_hash("h".to_string()).unwrap().unwrap().path, "/a.jpg");
+    }
+
+    #[test]
+    fn test_reads_do_not_wait_for_writer() {
+        let dir = std::env::temp_dir().join("fotos_reader_pool_test");
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+        std::fs::create_dir_all(&dir).unwrap();
+        let index = PhotoIndex::open(dir.join("index.db").to_string_lossy().to_string()).unwrap();
+        index.insert("/a.jpg".to_string(), "ha".to_string(), PhotoMetadata::default()).unwrap();
+
+        {
+            // An import holding the writer mid-transaction
+            let writer = index.conn.lock().unwrap();
+            let mode: String = writer.query_row("PRAGMA journal_mode", [], |row| row.get(0)).unwrap();
+            assert_eq!(mode, "wal");
+            writer.execute_batch("BEGIN; INSERT INTO photos (path, hash, width, height, orientation) VALUES ('/b.jpg', 'hb', 1, 1, 1);").unwrap();
+
+            // Readers see the last committed state instead of blocking
+            assert_eq!(index.count().unwrap(), 1);
+            assert_eq!(index.list().unwrap().len(), 1);
+            assert_eq!(index.list_page(0, 10, PhotoSort::Imported).unwrap().len(), 1);
+            writer.execute_batch("COMMIT").unwrap();
+        }
+        assert_eq!(index.count().unwrap(), 2);
+        assert_eq!(index.list().unwrap().len(), 2);
+
+        drop(index);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_rotate_accumulates() {
+        let index = setup_test_index();
+        let id = index.insert("/scan.jpg".to_string(), "h1".to_string(), PhotoMetadata::default()).unwrap();
+
+        assert_eq!(index.rotate(id.id, 90).unwrap().unwrap().metadata.rotation, 90);
+        assert_eq!(index.rotate(id.id, 270).unwrap().unwrap().metadata.rotation, 0);
+        assert_eq!(index.rotate(id.id, -90).unwrap().unwrap().metadata.rotation, 270);
+        assert!(index.rotate(id.id, 45).is_err());
+        assert!(index.rotate(999, 90).unwrap().is_none());
+
+        // Re-importing the same file keeps the user's rotation
+        index.insert("/scan.jpg".to_string(), "h1".to_string(), PhotoMetadata::default()).unwrap();
+        assert_eq!(index.get_by_id(id.id).unwrap().unwrap().metadata.rotation, 270);
+    }
+
+    #[test]
+    fn test_integrity_check_healthy() {
+        let index = setup_test_index();
+        index.insert("/a.jpg".to_string(), "h1".to_string(), PhotoMetadata::default()).unwrap();
+        assert!(index.integrity_check().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_refresh_keeps_rotation() {
+        let index = setup_test_index();
+        let metadata = PhotoMetadata { width: 40, height: 20, orientation: 6, ..Default::default() };
+        let id = index.insert("/scan.jpg".to_string(), "h1".to_string(), metadata).unwrap();
+        index.rotate(id.id, 180).unwrap();
+
+        let upright = PhotoMetadata { width: 20, height: 40, orientation: 1, ..Default::default() };
+        assert!(index.refresh(id.id, "h2".to_string(), upright).unwrap());
+        let photo = index.get_by_id(id.id).unwrap().unwrap();
+        assert_eq!(photo.hash, "h2");
+        assert_eq!((photo.metadata.width, photo.metadata.height, photo.metadata.orientation), (20, 40, 1));
+        assert_eq!(photo.metadata.rotation, 180);
+
+        assert!(!index.refresh(999, "h3".to_string(), PhotoMetadata::default()).unwrap());
+    }
+
+    #[test]
+    fn test_open_upgrades_legacy_schema() {
+        // Database created before has_motion existed
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE photos (
+                id INTEGER PRIMARY KEY,
+                path TEXT NOT NULL UNIQUE,
+                hash TEXT NOT NULL,
+                make TEXT,
+                model TEXT,
+                date_taken TEXT,
+                width INTEGER,
+                height INTEGER,
+                lat REAL,
+                lon REAL,
+                iso INTEGER,
+                f_number REAL,
+                exposure_time TEXT,
+                orientation INTEGER
+            );
+            INSERT INTO photos (path, hash, width, height, orientation) VALUES ('/old.jpg', 'h', 1, 1, 1);
+            INSERT INTO photos (path, hash, date_taken, width, height, orientation)
+                VALUES ('/dated.jpg', 'h3', '2023-04-15 10:03:00', 1, 1, 1);
+            INSERT INTO photos (path, hash, date_taken, width, height, orientation)
+                VALUES ('/unset-clock.jpg', 'h4', '0000:00:00 00:00:00', 1, 1, 1);
+            INSERT INTO photos (path, hash, make, model, date_taken, width, height, orientation)
+                VALUES ('/camera.jpg', 'h5', 'Nikon', 'D850', '2023-04-15 10:03:00', 1, 1, 1);
+            INSERT INTO photos (path, hash, make, model, date_taken, width, height, orientation)
+                VALUES ('/coolscan.jpg', 'h6', 'Nikon', 'Coolscan V', '2023-04-15 10:03:00', 1, 1, 1);",
+        ).unwrap();
+
+        init_schema(&conn).unwrap();
+        let index = PhotoIndex::from_connection(conn);
+
+        let old = index.get_by_path("/old.jpg".to_string()).unwrap().unwrap();
+        assert!(!old.metadata.has_motion);
+        assert_eq!(old.metadata.taken_at, None);
+
+        // Capture dates are parsed on upgrade; placeholders stay unknown
+        let dated = index.get_by_path("/dated.jpg".to_string()).unwrap().unwrap();
+        assert_eq!(dated.metadata.taken_at, Some(1_681_552_980));
+        let unset = index.get_by_path("/unset-clock.jpg".to_string()).unwrap().unwrap();
+        assert_eq!(unset.metadata.taken_at, None);
+        assert_eq!(unset.metadata.date_taken.as_deref(), Some("0000:00:00 00:00:00"));
+        // Scans are flagged and their scan date no longer counts as taken
+        let scan = index.get_by_path("/coolscan.jpg".to_string()).unwrap().unwrap();
+        assert!(scan.metadata.is_scan);
+        assert_eq!(scan.metadata.taken_at, None);
+        assert!(!index.get_by_path("/camera.jpg".to_string()).unwrap().unwrap().metadata.is_scan);
+        // Existing photos are searchable
+        assert_eq!(index.search("dated".to_string()).unwrap().len(), 1);
+
+        let metadata = PhotoMetadata { has_motion: true, ..Default::default() };
+        index.insert("/new.jpg".to_string(), "h2".to_string(), metadata).unwrap();
+        assert!(index.get_by_path("/new.jpg".to_string()).unwrap().unwrap().metadata.has_motion);
     }
 }
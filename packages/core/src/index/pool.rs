@@ -0,0 +1,81 @@
+use std::ops::Deref;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use rusqlite::{Connection, OpenFlags};
+
+use crate::error::CoreError;
+
+/// Idle read connections kept open; more are opened under load and closed after
+const MAX_IDLE: usize = 4;
+/// How long a connection waits for another one's lock before failing
+pub(super) const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Unlocks a SQLCipher database with `key`; it must come before anything
+/// else reads the file. Without a key the connection is left as is.
+pub(super) fn apply_key(conn: &Connection, key: Option<&str>) -> Result<(), CoreError> {
+    if let Some(key) = key {
+        conn.pragma_update(None, "key", key)?;
+    }
+    Ok(())
+}
+
+/// Read-only connections for queries, so listing doesn't queue behind the
+/// writer. With the database in WAL mode a reader sees the last committed
+/// state while an import is writing.
+pub(super) struct ReaderPool {
+    path: PathBuf,
+    /// SQLCipher passphrase, for an encrypted library
+    key: Option<String>,
+    idle: Mutex<Vec<Connection>>,
+}
+
+impl ReaderPool {
+    pub fn new(path: PathBuf, key: Option<String>) -> Self {
+        Self { path, key, idle: Mutex::new(Vec::new()) }
+    }
+
+    /// An idle reader, or a new one if all are in use.
+    pub fn get(&self) -> Result<Reader<'_>, CoreError> {
+        let idle = self.idle.lock().map_err(|e| CoreError::Database(e.to_string()))?.pop();
+        let conn = match idle {
+            Some(conn) => conn,
+            None => {
+                let conn = Connection::open_with_flags(
+                    &self.path,
+                    OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX | OpenFlags::SQLITE_OPEN_URI,
+                )?;
+                apply_key(&conn, self.key.as_deref())?;
+                conn.busy_timeout(BUSY_TIMEOUT)?;
+                super::schema::define_functions(&conn)?;
+                conn
+            }
+        };
+        Ok(Reader { pool: self, conn: Some(conn) })
+    }
+}
+
+/// A pooled read connection, returned to the pool when dropped.
+pub(super) struct Reader<'a> {
+    pool: &'a ReaderPool,
+    conn: Option<Connection>,
+}
+
+impl Deref for Reader<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("reader connection is only taken on drop")
+    }
+}
+
+impl Drop for Reader<'_> {
+    fn drop(&mut self) {
+        if let (Some(conn), Ok(mut idle)) = (self.conn.take(), self.pool.idle.lock()) {
+            if idle.len() < MAX_IDLE {
+                idle.push(conn);
+            }
+        }
+    }
+}
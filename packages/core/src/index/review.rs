@@ -0,0 +1,243 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::index::geo::place_contains;
+use crate::index::photo_index::camera_name;
+use crate::metadata::date::civil_from_days;
+use crate::types::{GeoPoint, PhotoInfo, Place, ReviewCount, ReviewHighlight, YearInReview};
+
+/// Entries kept in each "top" list
+const TOP_COUNT: usize = 5;
+/// Busiest days shown as highlights
+const HIGHLIGHT_DAYS: usize = 12;
+const DAY: i64 = 86_400;
+const MONTH_NAMES: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+
+/// Summarizes the dated `photos` of `year`. A day's highlight is its most
+/// viewed photo (`views`, by ID), else its first photo that's in an album
+/// (`in_albums`), else the middle one of the day.
+pub fn summarize_year(
+    year: i32,
+    photos: &[PhotoInfo],
+    places: &[Place],
+    in_albums: &HashSet<i64>,
+    views: &HashMap<i64, u32>,
+) -> YearInReview {
+    let mut review = YearInReview { year, months: vec![0; 12], ..Default::default() };
+    let mut place_counts = vec![0u32; places.len()];
+    let mut cameras: HashMap<String, u32> = HashMap::new();
+    let mut days: BTreeMap<i64, Vec<&PhotoInfo>> = BTreeMap::new();
+
+    for photo in photos {
+        let Some(taken_at) = photo.metadata.taken_at else { continue };
+        let (photo_year, month, _) = civil_from_days(taken_at.div_euclid(DAY));
+        if photo_year != year as i64 {
+            continue;
+        }
+        review.photo_count += 1;
+        review.months[month as usize - 1] += 1;
+        days.entry(taken_at.div_euclid(DAY)).or_default().push(photo);
+
+        if let (Some(lat), Some(lon)) = (photo.metadata.lat, photo.metadata.lon) {
+            review.geotagged += 1;
+            for (count, place) in place_counts.iter_mut().zip(places) {
+                if place_contains(place, GeoPoint { lat, lon }) {
+                    *count += 1;
+                }
+            }
+        }
+        if let Some(camera) = camera_name(photo.metadata.make.as_deref(), photo.metadata.model.as_deref()) {
+            *cameras.entry(camera).or_default() += 1;
+        }
+    }
+
+    review.days_with_photos = days.len() as u32;
+    review.top_places = top(places.iter().map(|p| p.name.clone()).zip(place_counts));
+    review.top_cameras = top(cameras.into_iter());
+
+    let mut busiest: Vec<(i64, &Vec<&PhotoInfo>)> = days.iter().map(|(day, photos)| (*day, photos)).collect();
+    // Most photos first; earlier day wins a tie
+    busiest.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then(a.0.cmp(&b.0)));
+    busiest.truncate(HIGHLIGHT_DAYS);
+    busiest.sort_by_key(|(day, _)| *day);
+    review.highlights = busiest
+        .into_iter()
+        .map(|(_, photos)| {
+            // min_by_key keeps the first of equally viewed photos
+            let pick = photos
+                .iter()
+                .filter_map(|p| views.get(&p.id.id).map(|&count| (p, count)))
+                .min_by_key(|&(_, count)| std::cmp::Reverse(count))
+                .map(|(p, _)| p)
+                .or_else(|| photos.iter().find(|p| in_albums.contains(&p.id.id)))
+                .unwrap_or(&photos[photos.len() / 2]);
+            ReviewHighlight {
+                photo_id: pick.id.id,
+                taken_at: pick.metadata.taken_at.unwrap_or_default(),
+                day_count: photos.len() as u32,
+            }
+        })
+        .collect();
+    review
+}
+
+/// The `TOP_COUNT` largest non-zero counts, ties by name.
+fn top(counts: impl Iterator<Item = (String, u32)>) -> Vec<ReviewCount> {
+    let mut counts: Vec<ReviewCount> = counts.filter(|(_, count)| *count > 0).map(|(name, count)| ReviewCount { name, count }).collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    counts.truncate(TOP_COUNT);
+    counts
+}
+
+/// A self-contained HTML page for `review`. Highlights are shown from
+/// `photos/<photo_id>.jpg` next to the page, which the caller writes.
+#[uniffi::export]
+pub fn render_year_in_review(review: YearInReview) -> String {
+    let mut html = String::new();
+    html.push_str(&format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{year} in photos</title>\n<style>{style}</style>\n</head>\n<body>\n\
+         <h1>{year} in photos</h1>\n<p class=\"lead\">{count} photos on {days} days",
+        year = review.year,
+        style = STYLE,
+        count = review.photo_count,
+        days = review.days_with_photos,
+    ));
+    if review.geotagged > 0 {
+        html.push_str(&format!(", {} with a location", review.geotagged));
+    }
+    html.push_str("</p>\n");
+
+    let busiest_month = review.months.iter().copied().max().unwrap_or(0).max(1);
+    html.push_str("<section>\n<h2>Month by month</h2>\n<ol class=\"months\">\n");
+    for (name, count) in MONTH_NAMES.iter().zip(&review.months) {
+        html.push_str(&format!(
+            "<li><span>{}</span><span class=\"bar\" style=\"width: {}%\"></span><span>{}</span></li>\n",
+            &name[..3],
+            count * 100 / busiest_month,
+            count
+        ));
+    }
+    html.push_str("</ol>\n</section>\n");
+
+    for (title, counts) in [("Top places", &review.top_places), ("Cameras", &review.top_cameras)] {
+        if counts.is_empty() {
+            continue;
+        }
+        html.push_str(&format!("<section>\n<h2>{}</h2>\n<ol class=\"top\">\n", title));
+        for entry in counts {
+            html.push_str(&format!("<li>{} <span>{}</span></li>\n", escape_html(&entry.name), entry.count));
+        }
+        html.push_str("</ol>\n</section>\n");
+    }
+
+    if !review.highlights.is_empty() {
+        html.push_str("<section>\n<h2>Highlights</h2>\n<div class=\"highlights\">\n");
+        for highlight in &review.highlights {
+            let (_, month, day) = civil_from_days(highlight.taken_at.div_euclid(DAY));
+            html.push_str(&format!(
+                "<figure><img src=\"photos/{}.jpg\" alt=\"\" loading=\"lazy\">\
+                 <figcaption>{} {} &middot; {} photos</figcaption></figure>\n",
+                highlight.photo_id,
+                MONTH_NAMES[month as usize - 1],
+                day,
+                highlight.day_count
+            ));
+        }
+        html.push_str("</div>\n</section>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:960px;margin:2rem auto;padding:0 1rem;color:#222}\
+h1{font-size:2.5rem;margin-bottom:.25rem}.lead{color:#666;margin-top:0}h2{margin-top:2rem}\
+ol{list-style:none;padding:0}.months li{display:flex;align-items:center;gap:.5rem;margin:.2rem 0}\
+.months li span:first-child{width:2.5rem;color:#666}.bar{height:.8rem;background:#60a5fa;border-radius:2px}\
+.top li{padding:.3rem 0;border-bottom:1px solid #eee}.top span{float:right;color:#666}\
+.highlights{display:grid;grid-template-columns:repeat(auto-fill,minmax(200px,1fr));gap:1rem}\
+figure{margin:0}figure img{width:100%;aspect-ratio:1;object-fit:cover;border-radius:4px}\
+figcaption{font-size:.85rem;color:#666;margin-top:.25rem}";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{PhotoId, PhotoMetadata};
+
+    fn photo(id: i64, taken_at: &str, model: &str, lat: Option<f64>) -> PhotoInfo {
+        PhotoInfo {
+            id: PhotoId { id },
+            path: format!("/p/{}.jpg", id),
+            hash: format!("h{}", id),
+            checksum: None,
+            metadata: PhotoMetadata {
+                taken_at: crate::metadata::parse_date_taken(taken_at),
+                model: Some(model.to_string()),
+                lat,
+                lon: lat.map(|_| 7.0),
+                ..Default::default()
+            },
+            thumb_path: None,
+            file_size: 0,
+            created_at: None,
+            modified_at: None,
+            missing: false,
+            corrupt: false,
+        }
+    }
+
+    #[test]
+    fn test_summarize_year() {
+        let photos = vec![
+            photo(1, "2023:03:04 10:00:00", "X100V", Some(46.0)),
+            photo(2, "2023:03:04 11:00:00", "X100V", Some(46.0)),
+            photo(3, "2023:03:04 12:00:00", "iPhone 14", None),
+            photo(4, "2023:07:20 09:00:00", "iPhone 14", Some(10.0)),
+            photo(5, "2024:01:01 00:00:00", "iPhone 14", None),
+        ];
+        let alps = Place { id: 1, name: "Alps".to_string(), lat: 46.0, lon: 7.0, radius_m: 1000.0, polygon: Vec::new() };
+        let review = summarize_year(2023, &photos, &[alps], &HashSet::from([3]), &HashMap::new());
+
+        assert_eq!((review.photo_count, review.days_with_photos, review.geotagged), (4, 2, 3));
+        assert_eq!(review.months[2], 3);
+        assert_eq!(review.months[6], 1);
+        assert_eq!(review.top_places, vec![ReviewCount { name: "Alps".to_string(), count: 2 }]);
+        assert_eq!(review.top_cameras[0], ReviewCount { name: "X100V".to_string(), count: 2 });
+        assert_eq!(review.top_cameras[1], ReviewCount { name: "iPhone 14".to_string(), count: 2 });
+        // The album photo stands for March 4th; July falls back to its only photo
+        let picks: Vec<(i64, u32)> = review.highlights.iter().map(|h| (h.photo_id, h.day_count)).collect();
+        assert_eq!(picks, vec![(3, 3), (4, 1)]);
+        // Viewing beats being in an album
+        let viewed = summarize_year(2023, &photos, &[], &HashSet::from([3]), &HashMap::from([(2, 4), (1, 4), (4, 1)]));
+        assert_eq!(viewed.highlights.iter().map(|h| h.photo_id).collect::<Vec<_>>(), vec![1, 4]);
+
+        let html = render_year_in_review(review);
+        assert!(html.contains("<h1>2023 in photos</h1>"));
+        assert!(html.contains("photos/3.jpg"));
+        assert!(html.contains("March 4"));
+    }
+
+    #[test]
+    fn test_render_escapes_names() {
+        let review = YearInReview {
+            year: 2023,
+            months: vec![0; 12],
+            top_cameras: vec![ReviewCount { name: "<script>".to_string(), count: 1 }],
+            ..Default::default()
+        };
+        let html = render_year_in_review(review);
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(!html.contains("<script>"));
+    }
+}
@@ -0,0 +1,653 @@
+use rusqlite::{functions::FunctionFlags, Connection, params};
+
+use crate::error::CoreError;
+use crate::fs::decode_path;
+use crate::metadata::looks_scanned;
+use crate::types::PhotoMetadata;
+use super::photo_index::camera_name;
+use super::search::{fold, index_text};
+
+/// One schema change. Runs in a transaction together with the version bump.
+type Migration = fn(&Connection) -> Result<(), CoreError>;
+
+/// Schema changes in order; `PRAGMA user_version` counts how many a database
+/// has had. Append new steps - never edit or reorder ones that have shipped.
+const MIGRATIONS: &[Migration] = &[adopt_unversioned, add_photo_sources, add_geo_index, add_color_info, add_scan_flag, add_photo_status, add_intake_queue, fold_search_text, add_import_sessions, split_cjk_search_text, add_file_stats, add_checksums, add_media_type, add_photo_views, add_description, add_content_checksums, add_tombstone_checksums, add_trash_file_state, add_photo_edits];
+
+/// Brings a database, new or from any earlier release, up to the current schema.
+/// Also defines the SQL functions the schema's triggers call, so every
+/// connection that writes photos must come through here.
+pub(super) fn init_schema(conn: &Connection) -> Result<(), CoreError> {
+    define_functions(conn)?;
+    migrate(conn, MIGRATIONS)
+}
+
+/// The SQL functions of the schema's triggers and of photo queries, which
+/// run on read-only connections too.
+pub(super) fn define_functions(conn: &Connection) -> Result<(), CoreError> {
+    let flags = || FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC;
+    conn.create_scalar_function("fold", 1, flags(), |ctx| {
+        Ok(ctx.get::<Option<String>>(0)?.map(|text| fold(&text)))
+    })?;
+    conn.create_scalar_function("index_text", 1, flags(), |ctx| {
+        Ok(ctx.get::<Option<String>>(0)?.map(|text| index_text(&text)))
+    })?;
+    // "Make Model" as `SelectionSummary::cameras` lists it
+    conn.create_scalar_function("camera_name", 2, flags(), |ctx| {
+        Ok(camera_name(ctx.get::<Option<String>>(0)?.as_deref(), ctx.get::<Option<String>>(1)?.as_deref()))
+    })?;
+    // Lowercase extension of a stored path, "" for none
+    conn.create_scalar_function("file_extension", 1, flags(), |ctx| {
+        let path = decode_path(&ctx.get::<String>(0)?);
+        Ok(path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default())
+    })?;
+    Ok(())
+}
+
+/// Fails for a database from a newer release, whose schema this one doesn't know.
+pub(super) fn check_supported(conn: &Connection) -> Result<(), CoreError> {
+    check_version(conn, MIGRATIONS).map(|_| ())
+}
+
+/// The schema version of `conn`, if `migrations` cover it.
+fn check_version(conn: &Connection, migrations: &[Migration]) -> Result<u32, CoreError> {
+    let version: u32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version as usize > migrations.len() {
+        return Err(CoreError::Database(format!(
+            "library schema version {} is newer than this app supports ({})",
+            version,
+            migrations.len()
+        )));
+    }
+    Ok(version)
+}
+
+/// Applies the steps `conn` hasn't had yet. A failing step is rolled back and
+/// leaves the database at the previous version.
+fn migrate(conn: &Connection, migrations: &[Migration]) -> Result<(), CoreError> {
+    let version = check_version(conn, migrations)?;
+    for (applied, migration) in migrations.iter().enumerate().skip(version as usize) {
+        let tx = conn.unchecked_transaction()?;
+        migration(&tx)?;
+        tx.pragma_update(None, "user_version", (applied + 1) as u32)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+/// Version 1: the schema as it was when versioning was introduced. Older
+/// databases all have user_version 0 in whatever shape their release left
+/// them, so this step only creates and appends what's missing.
+fn adopt_unversioned(conn: &Connection) -> Result<(), CoreError> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS photos (
+            id INTEGER PRIMARY KEY,
+            path TEXT NOT NULL UNIQUE,
+            hash TEXT NOT NULL,
+            make TEXT,
+            model TEXT,
+            date_taken TEXT,
+            width INTEGER,
+            height INTEGER,
+            lat REAL,
+            lon REAL,
+            iso INTEGER,
+            f_number REAL,
+            exposure_time TEXT,
+            orientation INTEGER,
+            has_motion INTEGER NOT NULL DEFAULT 0,
+            is_portrait INTEGER NOT NULL DEFAULT 0,
+            is_proraw INTEGER NOT NULL DEFAULT 0,
+            light TEXT,
+            moon_phase REAL,
+            rotation INTEGER NOT NULL DEFAULT 0,
+            taken_at INTEGER,
+            date_precision TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_photos_lat_lon ON photos (lat, lon);
+
+        CREATE TABLE IF NOT EXISTS places (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            lat REAL NOT NULL,
+            lon REAL NOT NULL,
+            radius_m REAL NOT NULL,
+            polygon TEXT NOT NULL,
+            min_lat REAL NOT NULL,
+            max_lat REAL NOT NULL,
+            min_lon REAL NOT NULL,
+            max_lon REAL NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS albums (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            created_at INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS album_photos (
+            album_id INTEGER NOT NULL,
+            photo_id INTEGER NOT NULL,
+            added_at INTEGER NOT NULL,
+            PRIMARY KEY (album_id, photo_id)
+        );
+        CREATE INDEX IF NOT EXISTS idx_album_photos_photo ON album_photos (photo_id);
+
+        CREATE TABLE IF NOT EXISTS photo_weather (
+            photo_id INTEGER PRIMARY KEY,
+            temperature_c REAL,
+            condition TEXT NOT NULL,
+            source TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_photo_weather_condition ON photo_weather (condition);
+
+        -- Trashed photos, moved out of photos with their id and every column
+        -- (keep in step with photos) plus when they were trashed
+        CREATE TABLE IF NOT EXISTS trash (
+            id INTEGER PRIMARY KEY,
+            path TEXT NOT NULL,
+            hash TEXT NOT NULL,
+            make TEXT,
+            model TEXT,
+            date_taken TEXT,
+            width INTEGER,
+            height INTEGER,
+            lat REAL,
+            lon REAL,
+            iso INTEGER,
+            f_number REAL,
+            exposure_time TEXT,
+            orientation INTEGER,
+            has_motion INTEGER NOT NULL DEFAULT 0,
+            is_portrait INTEGER NOT NULL DEFAULT 0,
+            is_proraw INTEGER NOT NULL DEFAULT 0,
+            light TEXT,
+            moon_phase REAL,
+            rotation INTEGER NOT NULL DEFAULT 0,
+            taken_at INTEGER,
+            date_precision TEXT,
+            trashed_at INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_trash_hash ON trash (hash);
+        CREATE INDEX IF NOT EXISTS idx_trash_trashed_at ON trash (trashed_at);
+
+        CREATE TABLE IF NOT EXISTS tombstones (
+            hash TEXT PRIMARY KEY,
+            deleted_at INTEGER NOT NULL
+        );
+
+        -- Change log for changes_since, written by triggers so every write path
+        -- (and every connection) is covered
+        CREATE TABLE IF NOT EXISTS change_log (
+            revision INTEGER PRIMARY KEY AUTOINCREMENT,
+            photo_id INTEGER NOT NULL,
+            kind TEXT NOT NULL
+        );
+        CREATE TRIGGER IF NOT EXISTS photos_log_insert AFTER INSERT ON photos BEGIN
+            INSERT INTO change_log (photo_id, kind) VALUES (NEW.id, 'added');
+        END;
+        CREATE TRIGGER IF NOT EXISTS photos_log_update AFTER UPDATE ON photos BEGIN
+            INSERT INTO change_log (photo_id, kind) VALUES (NEW.id, 'updated');
+        END;
+        CREATE TRIGGER IF NOT EXISTS photos_log_delete AFTER DELETE ON photos BEGIN
+            INSERT INTO change_log (photo_id, kind) VALUES (OLD.id, 'removed');
+        END;
+        CREATE TRIGGER IF NOT EXISTS photo_weather_log_insert AFTER INSERT ON photo_weather BEGIN
+            INSERT INTO change_log (photo_id, kind) VALUES (NEW.photo_id, 'updated');
+        END;",
+    )?;
+
+    // Columns added after the first release - older databases need them appended
+    ensure_column(conn, "photos", "has_motion", "INTEGER NOT NULL DEFAULT 0")?;
+    ensure_column(conn, "photos", "is_portrait", "INTEGER NOT NULL DEFAULT 0")?;
+    ensure_column(conn, "photos", "is_proraw", "INTEGER NOT NULL DEFAULT 0")?;
+    ensure_column(conn, "photos", "light", "TEXT")?;
+    ensure_column(conn, "photos", "moon_phase", "REAL")?;
+    ensure_column(conn, "photos", "rotation", "INTEGER NOT NULL DEFAULT 0")?;
+    if ensure_column(conn, "photos", "taken_at", "INTEGER")? {
+        backfill_taken_at(conn)?;
+    }
+    ensure_column(conn, "photos", "date_precision", "TEXT")?;
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_photos_light ON photos (light);
+         CREATE INDEX IF NOT EXISTS idx_photos_taken_at ON photos (taken_at);",
+    )?;
+    ensure_unique_hash(conn)?;
+    init_search(conn)?;
+    Ok(())
+}
+
+/// Version 2: where photos saved from the web were downloaded from.
+fn add_photo_sources(conn: &Connection) -> Result<(), CoreError> {
+    conn.execute_batch(
+        "CREATE TABLE photo_sources (
+            photo_id INTEGER PRIMARY KEY,
+            url TEXT NOT NULL
+        );",
+    )?;
+    Ok(())
+}
+
+/// Version 3: an R-tree over photo coordinates for map viewport queries,
+/// kept in step with `photos` by triggers.
+fn add_geo_index(conn: &Connection) -> Result<(), CoreError> {
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE photo_geo USING rtree (id, min_lat, max_lat, min_lon, max_lon);
+        INSERT INTO photo_geo SELECT id, lat, lat, lon, lon FROM photos WHERE lat IS NOT NULL AND lon IS NOT NULL;
+
+        CREATE TRIGGER photos_geo_insert AFTER INSERT ON photos
+        WHEN NEW.lat IS NOT NULL AND NEW.lon IS NOT NULL BEGIN
+            INSERT INTO photo_geo VALUES (NEW.id, NEW.lat, NEW.lat, NEW.lon, NEW.lon);
+        END;
+        CREATE TRIGGER photos_geo_update AFTER UPDATE OF lat, lon ON photos BEGIN
+            DELETE FROM photo_geo WHERE id = OLD.id;
+            INSERT INTO photo_geo SELECT NEW.id, NEW.lat, NEW.lat, NEW.lon, NEW.lon
+            WHERE NEW.lat IS NOT NULL AND NEW.lon IS NOT NULL;
+        END;
+        CREATE TRIGGER photos_geo_delete AFTER DELETE ON photos BEGIN
+            DELETE FROM photo_geo WHERE id = OLD.id;
+        END;",
+    )?;
+    Ok(())
+}
+
+/// Version 4: color profile, bit depth and chroma subsampling of the
+/// originals. Photos imported earlier get them when they're next refreshed.
+fn add_color_info(conn: &Connection) -> Result<(), CoreError> {
+    for table in ["photos", "trash"] {
+        conn.execute_batch(&format!(
+            "ALTER TABLE {0} ADD COLUMN color_profile TEXT;
+            ALTER TABLE {0} ADD COLUMN bit_depth INTEGER;
+            ALTER TABLE {0} ADD COLUMN chroma_subsampling TEXT;",
+            table
+        ))?;
+    }
+    Ok(())
+}
+
+/// Version 5: flags film and print scans. Scans already in the library lose
+/// the scan date as their capture date, unless it was set by hand.
+fn add_scan_flag(conn: &Connection) -> Result<(), CoreError> {
+    conn.execute_batch(
+        "ALTER TABLE photos ADD COLUMN is_scan INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE trash ADD COLUMN is_scan INTEGER NOT NULL DEFAULT 0;",
+    )?;
+    for table in ["photos", "trash"] {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, make, model, iso, f_number, exposure_time FROM {} WHERE make IS NOT NULL OR model IS NOT NULL",
+            table
+        ))?;
+        let photos: Vec<(i64, bool)> = stmt
+            .query_map([], |row| {
+                let metadata = PhotoMetadata {
+                    make: row.get(1)?,
+                    model: row.get(2)?,
+                    iso: row.get::<_, Option<i64>>(3)?.map(|x| x as u32),
+                    f_number: row.get::<_, Option<f64>>(4)?.map(|x| x as f32),
+                    exposure_time: row.get(5)?,
+                    ..Default::default()
+                };
+                Ok((row.get(0)?, looks_scanned(&metadata)))
+            })?
+            .collect::<Result<_, _>>()?;
+        let mut flag = conn.prepare(&format!(
+            "UPDATE {} SET is_scan = 1, taken_at = CASE WHEN date_precision IS NULL THEN NULL ELSE taken_at END WHERE id = ?1",
+            table
+        ))?;
+        for (id, _) in photos.into_iter().filter(|(_, scan)| *scan) {
+            flag.execute(params![id])?;
+        }
+    }
+    Ok(())
+}
+
+/// Version 6: whether a photo's original is where the index says, see
+/// `PhotoIndex::verify`.
+fn add_photo_status(conn: &Connection) -> Result<(), CoreError> {
+    conn.execute_batch(
+        "ALTER TABLE photos ADD COLUMN status TEXT NOT NULL DEFAULT 'ok';
+        ALTER TABLE trash ADD COLUMN status TEXT NOT NULL DEFAULT 'ok';",
+    )?;
+    Ok(())
+}
+
+/// Version 7: photos handed over by mobile pickers and not yet imported, see
+/// `PhotoIndex::enqueue_intake`.
+fn add_intake_queue(conn: &Connection) -> Result<(), CoreError> {
+    conn.execute_batch(
+        "CREATE TABLE intake_queue (
+            identifier TEXT PRIMARY KEY,
+            payload_path TEXT NOT NULL,
+            queued_at INTEGER NOT NULL,
+            attempts INTEGER NOT NULL DEFAULT 0
+        );",
+    )?;
+    Ok(())
+}
+
+/// Version 8: the full-text index holds folded text (see `search::fold`),
+/// so searches ignore case, diacritics and compatibility forms.
+fn fold_search_text(conn: &Connection) -> Result<(), CoreError> {
+    conn.execute_batch(
+        "DROP TRIGGER IF EXISTS photos_fts_insert;
+        DROP TRIGGER IF EXISTS photos_fts_update;
+        CREATE TRIGGER photos_fts_insert AFTER INSERT ON photos BEGIN
+            INSERT INTO photos_fts (rowid, make, model, path, date_taken)
+            VALUES (NEW.id, fold(NEW.make), fold(NEW.model), fold(NEW.path), NEW.date_taken);
+        END;
+        CREATE TRIGGER photos_fts_update AFTER UPDATE OF make, model, path, date_taken ON photos BEGIN
+            UPDATE photos_fts SET make = fold(NEW.make), model = fold(NEW.model), path = fold(NEW.path), date_taken = NEW.date_taken
+            WHERE rowid = NEW.id;
+        END;
+        DELETE FROM photos_fts;
+        INSERT INTO photos_fts (rowid, make, model, path, date_taken)
+            SELECT id, fold(make), fold(model), fold(path), date_taken FROM photos;",
+    )?;
+    Ok(())
+}
+
+/// Version 9: journals of folder imports, see `PhotoIndex::begin_import_session`.
+fn add_import_sessions(conn: &Connection) -> Result<(), CoreError> {
+    conn.execute_batch(
+        "CREATE TABLE import_sessions (
+            id INTEGER PRIMARY KEY,
+            root TEXT NOT NULL UNIQUE,
+            started_at INTEGER NOT NULL
+        );
+        CREATE TABLE import_session_files (
+            session_id INTEGER NOT NULL,
+            path TEXT NOT NULL,
+            done INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (session_id, path)
+        );",
+    )?;
+    Ok(())
+}
+
+/// Version 10: the full-text index splits CJK text into bigrams (see
+/// `search::index_text`), so words inside Chinese or Japanese names are found.
+fn split_cjk_search_text(conn: &Connection) -> Result<(), CoreError> {
+    conn.execute_batch(
+        "DROP TRIGGER IF EXISTS photos_fts_insert;
+        DROP TRIGGER IF EXISTS photos_fts_update;
+        CREATE TRIGGER photos_fts_insert AFTER INSERT ON photos BEGIN
+            INSERT INTO photos_fts (rowid, make, model, path, date_taken)
+            VALUES (NEW.id, index_text(NEW.make), index_text(NEW.model), index_text(NEW.path), NEW.date_taken);
+        END;
+        CREATE TRIGGER photos_fts_update AFTER UPDATE OF make, model, path, date_taken ON photos BEGIN
+            UPDATE photos_fts SET make = index_text(NEW.make), model = index_text(NEW.model), path = index_text(NEW.path), date_taken = NEW.date_taken
+            WHERE rowid = NEW.id;
+        END;
+        DELETE FROM photos_fts;
+        INSERT INTO photos_fts (rowid, make, model, path, date_taken)
+            SELECT id, index_text(make), index_text(model), index_text(path), date_taken FROM photos;",
+    )?;
+    Ok(())
+}
+
+/// Version 11: size and modification time of each original when it was
+/// indexed, see `rescan`. Unknown (NULL) for photos indexed before.
+fn add_file_stats(conn: &Connection) -> Result<(), CoreError> {
+    conn.execute_batch(
+        "ALTER TABLE photos ADD COLUMN file_size INTEGER;
+        ALTER TABLE photos ADD COLUMN file_mtime INTEGER;",
+    )?;
+    Ok(())
+}
+
+/// Version 12: content checksums of originals and the file size and
+/// modification time they were taken at, see `PhotoIndex::audit_originals`.
+fn add_checksums(conn: &Connection) -> Result<(), CoreError> {
+    conn.execute_batch(
+        "ALTER TABLE photos ADD COLUMN checksum TEXT;
+        ALTER TABLE photos ADD COLUMN checksum_size INTEGER;
+        ALTER TABLE photos ADD COLUMN checksum_mtime INTEGER;
+        ALTER TABLE photos ADD COLUMN checked_at INTEGER;
+        CREATE INDEX idx_photos_checked_at ON photos (checked_at);",
+    )?;
+    Ok(())
+}
+
+/// Version 13: videos, indexed alongside photos with their length and codec.
+fn add_media_type(conn: &Connection) -> Result<(), CoreError> {
+    for table in ["photos", "trash"] {
+        conn.execute_batch(&format!(
+            "ALTER TABLE {0} ADD COLUMN media_type TEXT NOT NULL DEFAULT 'photo';
+            ALTER TABLE {0} ADD COLUMN duration_ms INTEGER;
+            ALTER TABLE {0} ADD COLUMN video_codec TEXT;",
+            table
+        ))?;
+    }
+    Ok(())
+}
+
+/// Version 14: how often and when each photo was last opened, see
+/// `PhotoIndex::record_view`. Not in the change log - viewing a photo
+/// doesn't change it.
+fn add_photo_views(conn: &Connection) -> Result<(), CoreError> {
+    conn.execute_batch(
+        "CREATE TABLE photo_views (
+            photo_id INTEGER PRIMARY KEY,
+            view_count INTEGER NOT NULL,
+            last_viewed_at INTEGER NOT NULL
+        );",
+    )?;
+    Ok(())
+}
+
+/// Version 15: captions, e.g. from Google Takeout sidecars.
+fn add_description(conn: &Connection) -> Result<(), CoreError> {
+    for table in ["photos", "trash"] {
+        conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN description TEXT;", table))?;
+    }
+    Ok(())
+}
+
+/// Version 16: photos are told apart by the checksum of their content, the
+/// perceptual hash only finds lookalikes, so it no longer has to be unique.
+/// Trashed photos keep their checksum.
+fn add_content_checksums(conn: &Connection) -> Result<(), CoreError> {
+    conn.execute_batch(
+        "DROP INDEX IF EXISTS idx_photos_hash_unique;
+        CREATE INDEX IF NOT EXISTS idx_photos_hash ON photos (hash);
+        CREATE INDEX idx_photos_checksum ON photos (checksum);
+        ALTER TABLE trash ADD COLUMN checksum TEXT;",
+    )?;
+    Ok(())
+}
+
+/// Version 17: tombstones keep the deleted photo's checksum, so deleting one
+/// of several lookalikes doesn't keep the others from being imported. Rows
+/// from before have none and still match by hash. The hash alone is no longer
+/// the key, so the table is rebuilt.
+fn add_tombstone_checksums(conn: &Connection) -> Result<(), CoreError> {
+    conn.execute_batch(
+        "CREATE TABLE tombstones_new (
+            hash TEXT NOT NULL,
+            checksum TEXT,
+            deleted_at INTEGER NOT NULL
+        );
+        INSERT INTO tombstones_new (hash, deleted_at) SELECT hash, deleted_at FROM tombstones;
+        DROP TABLE tombstones;
+        ALTER TABLE tombstones_new RENAME TO tombstones;
+        CREATE UNIQUE INDEX idx_tombstones_content ON tombstones (hash, IFNULL(checksum, ''));",
+    )?;
+    Ok(())
+}
+
+/// Version 18: the trash keeps a photo's file size, modification time and
+/// audit state, so restoring it doesn't lose them.
+fn add_trash_file_state(conn: &Connection) -> Result<(), CoreError> {
+    conn.execute_batch(
+        "ALTER TABLE trash ADD COLUMN file_size INTEGER;
+        ALTER TABLE trash ADD COLUMN file_mtime INTEGER;
+        ALTER TABLE trash ADD COLUMN checksum_size INTEGER;
+        ALTER TABLE trash ADD COLUMN checksum_mtime INTEGER;
+        ALTER TABLE trash ADD COLUMN checked_at INTEGER;",
+    )?;
+    Ok(())
+}
+
+/// Version 19: each photo's auto-enhance recipe and whether it's switched
+/// on, see `PhotoIndex::set_enhance`.
+fn add_photo_edits(conn: &Connection) -> Result<(), CoreError> {
+    conn.execute_batch(
+        "CREATE TABLE photo_edits (
+            photo_id INTEGER PRIMARY KEY,
+            black_point INTEGER NOT NULL,
+            white_point INTEGER NOT NULL,
+            gain_r REAL NOT NULL,
+            gain_g REAL NOT NULL,
+            gain_b REAL NOT NULL,
+            saturation REAL NOT NULL,
+            enabled INTEGER NOT NULL
+        );",
+    )?;
+    Ok(())
+}
+
+/// `insert` merged photos by hash, so hashes were unique until version 16;
+/// the index made it a constraint. A database that somehow holds duplicates
+/// keeps the plain index rather than failing to open.
+fn ensure_unique_hash(conn: &Connection) -> Result<(), CoreError> {
+    match conn.execute("CREATE UNIQUE INDEX IF NOT EXISTS idx_photos_hash_unique ON photos (hash)", []) {
+        Ok(_) => {
+            conn.execute("DROP INDEX IF EXISTS idx_photos_hash", [])?;
+        }
+        Err(rusqlite::Error::SqliteFailure(e, _)) if e.code == rusqlite::ErrorCode::ConstraintViolation => {
+            conn.execute("CREATE INDEX IF NOT EXISTS idx_photos_hash ON photos (hash)", [])?;
+        }
+        Err(e) => return Err(e.into()),
+    }
+    Ok(())
+}
+
+/// Full-text index over the searchable photo fields, keyed by photo id and
+/// kept in step by triggers. `keywords` is reserved for tags. Filled from
+/// the existing photos when an older database first gets it.
+fn init_search(conn: &Connection) -> Result<(), CoreError> {
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE name = 'photos_fts')",
+        [],
+        |row| row.get(0),
+    )?;
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS photos_fts USING fts5(make, model, path, date_taken, keywords);
+        CREATE TRIGGER IF NOT EXISTS photos_fts_insert AFTER INSERT ON photos BEGIN
+            INSERT INTO photos_fts (rowid, make, model, path, date_taken)
+            VALUES (NEW.id, NEW.make, NEW.model, NEW.path, NEW.date_taken);
+        END;
+        CREATE TRIGGER IF NOT EXISTS photos_fts_update AFTER UPDATE OF make, model, path, date_taken ON photos BEGIN
+            UPDATE photos_fts SET make = NEW.make, model = NEW.model, path = NEW.path, date_taken = NEW.date_taken
+            WHERE rowid = NEW.id;
+        END;
+        CREATE TRIGGER IF NOT EXISTS photos_fts_delete AFTER DELETE ON photos BEGIN
+            DELETE FROM photos_fts WHERE rowid = OLD.id;
+        END;",
+    )?;
+    if !exists {
+        conn.execute(
+            "INSERT INTO photos_fts (rowid, make, model, path, date_taken)
+             SELECT id, make, model, path, date_taken FROM photos",
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+/// Parses the dates of photos indexed before `taken_at` existed.
+fn backfill_taken_at(conn: &Connection) -> Result<(), CoreError> {
+    let dated: Vec<(i64, String)> = conn
+        .prepare("SELECT id, date_taken FROM photos WHERE date_taken IS NOT NULL")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    let mut update = conn.prepare("UPDATE photos SET taken_at = ?1 WHERE id = ?2")?;
+    for (id, date_taken) in dated {
+        if let Some(taken_at) = crate::metadata::parse_date_taken(&date_taken) {
+            update.execute(params![taken_at, id])?;
+        }
+    }
+    Ok(())
+}
+
+/// Adds `column` to `table` if an existing database predates it.
+/// Returns whether it was added.
+fn ensure_column(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<bool, CoreError> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(Result::ok)
+        .any(|name| name == column);
+    if !exists {
+        conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl))?;
+    }
+    Ok(!exists)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(conn: &Connection) -> u32 {
+        conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap()
+    }
+
+    fn steps(conn: &Connection) -> Vec<i64> {
+        let mut stmt = conn.prepare("SELECT step FROM steps ORDER BY rowid").unwrap();
+        stmt.query_map([], |row| row.get(0)).unwrap().map(Result::unwrap).collect()
+    }
+
+    fn first(conn: &Connection) -> Result<(), CoreError> {
+        conn.execute_batch("CREATE TABLE steps (step INTEGER); INSERT INTO steps VALUES (1);")?;
+        Ok(())
+    }
+
+    fn second(conn: &Connection) -> Result<(), CoreError> {
+        conn.execute("INSERT INTO steps VALUES (2)", [])?;
+        Ok(())
+    }
+
+    fn failing(conn: &Connection) -> Result<(), CoreError> {
+        conn.execute("INSERT INTO steps VALUES (3)", [])?;
+        Err(CoreError::Database("step failed".to_string()))
+    }
+
+    #[test]
+    fn test_migrations_apply_once_in_order() {
+        let conn = Connection::open_in_memory().unwrap();
+        migrate(&conn, &[first]).unwrap();
+        assert_eq!(version(&conn), 1);
+
+        // A later release adds a step: only that one runs
+        migrate(&conn, &[first, second]).unwrap();
+        migrate(&conn, &[first, second]).unwrap();
+        assert_eq!(version(&conn), 2);
+        assert_eq!(steps(&conn), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_failed_migration_rolls_back() {
+        let conn = Connection::open_in_memory().unwrap();
+        assert!(migrate(&conn, &[first, failing]).is_err());
+        assert_eq!(version(&conn), 1);
+        assert_eq!(steps(&conn), vec![1]);
+    }
+
+    #[test]
+    fn test_newer_database_is_refused() {
+        let conn = Connection::open_in_memory().unwrap();
+        migrate(&conn, &[first, second]).unwrap();
+        assert!(migrate(&conn, &[first]).is_err());
+        assert_eq!(version(&conn), 2);
+    }
+
+    #[test]
+    fn test_current_schema_is_versioned() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        assert_eq!(version(&conn) as usize, MIGRATIONS.len());
+        // Reopening an up-to-date database changes nothing
+        init_schema(&conn).unwrap();
+        assert_eq!(version(&conn) as usize, MIGRATIONS.len());
+    }
+}
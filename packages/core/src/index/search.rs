@@ -0,0 +1,193 @@
+/// Latin Extended-A (U+0100-U+017F) without diacritics, one letter per code
+/// point. The two ligatures in the block (Ĳ, Œ) are spelled out in `fold`.
+const LATIN_EXTENDED_A: &[u8; 128] =
+    b"aaaaaaccccccccddddeeeeeeeeeegggggggghhhhiiiiiiiiiiiijjkkkllllllllllnnnnnnnnnoooooooorrrrrrssssssssttttttuuuuuuuuuuuuwwyyyzzzzzzs";
+
+/// Text as search compares it: lower case, diacritics removed and
+/// compatibility forms spelled plainly, so "sao paulo" finds "São Paulo"
+/// (composed or, as macOS file names are, decomposed), "strasse" finds
+/// "Straße" and "full" finds "ＦＵＬＬ". Covers the Latin scripts; other
+/// letters are only lower-cased.
+pub fn fold(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    for c in text.chars().flat_map(char::to_lowercase) {
+        match c {
+            // Combining diacritical marks
+            '\u{0300}'..='\u{036F}' => {}
+            'à'..='å' => folded.push('a'),
+            'æ' => folded.push_str("ae"),
+            'ç' => folded.push('c'),
+            'è'..='ë' => folded.push('e'),
+            'ì'..='ï' => folded.push('i'),
+            'ð' => folded.push('d'),
+            'ñ' => folded.push('n'),
+            'ò'..='ö' | 'ø' => folded.push('o'),
+            'ù'..='ü' => folded.push('u'),
+            'ý' | 'ÿ' => folded.push('y'),
+            'þ' => folded.push_str("th"),
+            'ß' => folded.push_str("ss"),
+            'ĳ' => folded.push_str("ij"),
+            'œ' => folded.push_str("oe"),
+            '\u{0100}'..='\u{017F}' => folded.push(LATIN_EXTENDED_A[c as usize - 0x100] as char),
+            // Ligatures: ff, fi, fl, ffi, ffl, ſt, st
+            '\u{FB00}'..='\u{FB06}' => folded.push_str(["ff", "fi", "fl", "ffi", "ffl", "st", "st"][c as usize - 0xFB00]),
+            // Fullwidth ASCII
+            '\u{FF01}'..='\u{FF5E}' => {
+                let ascii = char::from_u32(c as u32 - 0xFEE0).unwrap_or(c);
+                folded.push(ascii.to_ascii_lowercase());
+            }
+            _ => folded.push(c),
+        }
+    }
+    folded
+}
+
+/// Han, kana and Hangul: scripts written without spaces between words, which
+/// the full-text tokenizer would otherwise take a whole sentence at a time.
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'     // Hiragana, Katakana
+        | '\u{3400}'..='\u{4DBF}'   // CJK Extension A
+        | '\u{4E00}'..='\u{9FFF}'   // CJK Unified Ideographs
+        | '\u{AC00}'..='\u{D7AF}'   // Hangul syllables
+        | '\u{F900}'..='\u{FAFF}'   // CJK Compatibility Ideographs
+        | '\u{20000}'..='\u{2FA1F}' // Extensions B-F, Compatibility Supplement
+    )
+}
+
+/// Splits `text` into runs of CJK and of other characters, flagging the CJK ones.
+fn segments(text: &str) -> Vec<(bool, &str)> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((_, c)) = chars.next() {
+        let cjk = is_cjk(c);
+        let end = chars.peek().map_or(text.len(), |&(i, _)| i);
+        if chars.peek().is_none_or(|&(_, next)| is_cjk(next) != cjk) {
+            segments.push((cjk, &text[start..end]));
+            start = end;
+        }
+    }
+    segments
+}
+
+/// Overlapping character pairs of a CJK run ("天安门" -> "天安", "安门"), or
+/// the run itself if it is a single character.
+fn bigrams(run: &str) -> Vec<String> {
+    let chars: Vec<char> = run.chars().collect();
+    if chars.len() < 2 {
+        return vec![run.to_string()];
+    }
+    chars.windows(2).map(|pair| pair.iter().collect()).collect()
+}
+
+/// Text as the full-text index stores it: folded, with CJK runs spelled as
+/// their bigrams so any word inside them can be found. Each run also ends
+/// with its last character alone, for one-character searches.
+pub fn index_text(text: &str) -> String {
+    let folded = fold(text);
+    let mut indexed = String::with_capacity(folded.len() * 2);
+    for (cjk, segment) in segments(&folded) {
+        if !cjk {
+            indexed.push_str(segment);
+            continue;
+        }
+        indexed.push(' ');
+        indexed.push_str(&bigrams(segment).join(" "));
+        if segment.chars().nth(1).is_some() {
+            indexed.push(' ');
+            indexed.extend(segment.chars().last());
+        }
+        indexed.push(' ');
+    }
+    indexed
+}
+
+/// The terms of a search query, folded, each with whether it may match as a
+/// prefix: the words as typed, except that a CJK run becomes the phrase of
+/// its bigrams (see `index_text`) and matches exactly.
+pub fn query_terms(query: &str) -> Vec<(String, bool)> {
+    let folded = fold(query);
+    let mut terms = Vec::new();
+    for word in folded.split_whitespace() {
+        for (cjk, segment) in segments(word) {
+            if cjk {
+                let single = segment.chars().nth(1).is_none();
+                terms.push((bigrams(segment).join(" "), single));
+            } else {
+                terms.push((segment.to_string(), true));
+            }
+        }
+    }
+    terms
+}
+
+/// Whether every word of `query` starts a word of `text`, both folded - the
+/// in-memory counterpart of the full-text search, for short lists like
+/// place names. CJK runs of the query can match anywhere in the text.
+pub fn matches_words(text: &str, query: &str) -> bool {
+    let text = fold(text);
+    let words: Vec<&str> = text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).collect();
+    let query = fold(query);
+    let mut terms = query
+        .split(|c: char| !c.is_alphanumeric())
+        .flat_map(segments)
+        .peekable();
+    terms.peek().is_some()
+        && terms.all(|(cjk, term)| {
+            if cjk {
+                text.contains(term)
+            } else {
+                words.iter().any(|word| word.starts_with(term))
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold() {
+        assert_eq!(fold("São Paulo"), "sao paulo");
+        assert_eq!(fold("Sa\u{0303}o Paulo"), "sao paulo");
+        assert_eq!(fold("KØBENHAVN"), "kobenhavn");
+        assert_eq!(fold("Straße"), "strasse");
+        assert_eq!(fold("Ærøskøbing"), "aeroskobing");
+        assert_eq!(fold("Łódź Œuvre"), "lodz oeuvre");
+        assert_eq!(fold("ＦＵＬＬ ﬁsh"), "full fish");
+        assert_eq!(fold("İstanbul"), "istanbul");
+        assert_eq!(fold("Москва"), "москва");
+    }
+
+    #[test]
+    fn test_matches_words() {
+        assert!(matches_words("São Paulo", "sao paulo"));
+        assert!(matches_words("São Paulo", "PAU"));
+        assert!(matches_words("Café de Flore", "cafe flo"));
+        assert!(!matches_words("São Paulo", "aulo"));
+        assert!(!matches_words("São Paulo", " "));
+        assert!(matches_words("北京市", "京市"));
+        assert!(matches_words("東京 Tower", "東京 tow"));
+        assert!(!matches_words("北京市", "上海"));
+    }
+
+    #[test]
+    fn test_index_text() {
+        assert_eq!(index_text("北京天安门"), " 北京 京天 天安 安门 门 ");
+        assert_eq!(index_text("IMG_北京2024"), "img_ 北京 京 2024");
+        assert_eq!(index_text("上"), " 上 ");
+        assert_eq!(index_text("서울 Café"), " 서울 울  cafe");
+    }
+
+    #[test]
+    fn test_query_terms() {
+        let terms = |query: &str| query_terms(query).into_iter().map(|(t, p)| format!("{}{}", t, if p { "*" } else { "" })).collect::<Vec<_>>();
+        assert_eq!(terms("天安门"), ["天安 安门"]);
+        assert_eq!(terms("门"), ["门*"]);
+        assert_eq!(terms("京都"), ["京都"]);
+        assert_eq!(terms("Tokyo 東京タワー"), ["tokyo*", "東京 京タ タワ ワー"]);
+        assert_eq!(terms("北京trip"), ["北京", "trip*"]);
+        assert!(terms("  ").is_empty());
+    }
+}
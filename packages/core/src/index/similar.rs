@@ -0,0 +1,69 @@
+use std::collections::BTreeMap;
+
+/// Hamming distance between two perceptual hashes: how many of the 64 bits differ.
+pub fn distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Groups photos whose hashes are within `max_distance` of each other,
+/// transitively (a~b and b~c puts a, b and c together). Compares every pair,
+/// which at 64-bit XOR-and-count stays fast into tens of thousands of photos.
+/// Groups are in id order, largest group first; photos with no near
+/// duplicate are left out.
+pub fn group_near_duplicates(hashes: &[(i64, u64)], max_distance: u32) -> Vec<Vec<i64>> {
+    let mut parent: Vec<usize> = (0..hashes.len()).collect();
+    for i in 0..hashes.len() {
+        for j in i + 1..hashes.len() {
+            if distance(hashes[i].1, hashes[j].1) <= max_distance {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                parent[a.max(b)] = a.min(b);
+            }
+        }
+    }
+
+    let mut groups: BTreeMap<usize, Vec<i64>> = BTreeMap::new();
+    for (i, (id, _)) in hashes.iter().enumerate() {
+        let r = root(&mut parent, i);
+        groups.entry(r).or_default().push(*id);
+    }
+    let mut groups: Vec<Vec<i64>> = groups
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .map(|mut group| {
+            group.sort();
+            group
+        })
+        .collect();
+    groups.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+    groups
+}
+
+/// Union-find root of `i`, flattening the path on the way.
+fn root(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_near_duplicates() {
+        let base = 0xF0F0_F0F0_F0F0_F0F0u64;
+        let hashes = [
+            (1, base),
+            (2, 0x0123_4567_89AB_CDEF),
+            (3, base ^ 0b11),     // 2 bits from 1
+            (4, base ^ 0b1111),   // 2 bits from 3, 4 from 1
+            (5, !base),
+            (6, 0x0123_4567_89AB_CDEF),
+        ];
+        assert_eq!(group_near_duplicates(&hashes, 2), vec![vec![1, 3, 4], vec![2, 6]]);
+        assert_eq!(group_near_duplicates(&hashes, 0), vec![vec![2, 6]]);
+        assert_eq!(distance(base, !base), 64);
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::fs::{decode_path, encode_path};
+use crate::types::{DatePrecision, PhotoInfo, UndatedCluster};
+
+/// Largest jump in file numbers still treated as one shooting sequence
+const MAX_NUMBER_GAP: u64 = 20;
+const DAY: i64 = 86_400;
+
+/// Groups the undated photos among `photos` by folder and file-number run,
+/// proposing a date from the nearest dated photos of the same sequence.
+pub fn cluster_undated(photos: &[PhotoInfo]) -> Vec<UndatedCluster> {
+    let mut folders: BTreeMap<String, Vec<Entry>> = BTreeMap::new();
+    for photo in photos {
+        let path = decode_path(&photo.path);
+        let folder = path.parent().map(encode_path).unwrap_or_default();
+        let (prefix, number) = file_number(&path);
+        folders.entry(folder).or_default().push(Entry {
+            id: photo.id.id,
+            prefix,
+            number,
+            name: photo.path.clone(),
+            taken_at: photo.metadata.taken_at,
+        });
+    }
+
+    let mut clusters = Vec::new();
+    for (folder, mut entries) in folders {
+        if entries.iter().all(|e| e.taken_at.is_some()) {
+            continue;
+        }
+        entries.sort_by(|a, b| {
+            (&a.prefix, a.number.is_none(), a.number, &a.name).cmp(&(&b.prefix, b.number.is_none(), b.number, &b.name))
+        });
+
+        let mut i = 0;
+        while i < entries.len() {
+            if entries[i].taken_at.is_some() {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i + 1 < entries.len() && entries[i + 1].taken_at.is_none() && in_sequence(&entries[i], &entries[i + 1]) {
+                i += 1;
+            }
+            let end = i;
+            i += 1;
+
+            let before = start.checked_sub(1).map(|j| &entries[j]).filter(|e| in_sequence(e, &entries[start]));
+            let after = entries.get(end + 1).filter(|e| in_sequence(&entries[end], e));
+            let (suggested_taken_at, suggested_precision) =
+                suggest(before.and_then(|e| e.taken_at), after.and_then(|e| e.taken_at));
+            clusters.push(UndatedCluster {
+                folder: folder.clone(),
+                photo_ids: entries[start..=end].iter().map(|e| e.id).collect(),
+                suggested_taken_at,
+                suggested_precision,
+            });
+        }
+    }
+    clusters
+}
+
+struct Entry {
+    id: i64,
+    prefix: String,
+    number: Option<u64>,
+    name: String,
+    taken_at: Option<i64>,
+}
+
+/// Camera-style file names: "IMG_0042" is prefix "IMG_", number 42.
+/// Names without a number share the empty prefix so they group together.
+fn file_number(path: &Path) -> (String, Option<u64>) {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let digits = stem.len() - stem.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 {
+        return (String::new(), None);
+    }
+    let (prefix, number) = stem.split_at(stem.len() - digits);
+    (prefix.to_string(), number.parse().ok())
+}
+
+fn in_sequence(a: &Entry, b: &Entry) -> bool {
+    a.prefix == b.prefix
+        && match (a.number, b.number) {
+            (Some(x), Some(y)) => x.abs_diff(y) <= MAX_NUMBER_GAP,
+            // Unnumbered files only group with each other and get no neighbors
+            (None, None) => a.taken_at.is_none() && b.taken_at.is_none(),
+            _ => false,
+        }
+}
+
+/// Midway between the neighbors, as precise as their spread allows. A single
+/// neighbor only says roughly when.
+fn suggest(before: Option<i64>, after: Option<i64>) -> (Option<i64>, Option<DatePrecision>) {
+    match (before, after) {
+        (Some(a), Some(b)) => {
+            let precision = match a.abs_diff(b) {
+                spread if spread <= DAY as u64 => DatePrecision::Day,
+                spread if spread <= 31 * DAY as u64 => DatePrecision::Month,
+                _ => DatePrecision::Year,
+            };
+            (Some(a + (b - a) / 2), Some(precision))
+        }
+        (Some(t), None) | (None, Some(t)) => (Some(t), Some(DatePrecision::Month)),
+        (None, None) => (None, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{PhotoId, PhotoMetadata};
+
+    fn photo(id: i64, path: &str, taken_at: Option<i64>) -> PhotoInfo {
+        PhotoInfo {
+            id: PhotoId { id },
+            path: path.to_string(),
+            hash: id.to_string(),
+            checksum: None,
+            metadata: PhotoMetadata { taken_at, ..Default::default() },
+            thumb_path: None,
+            file_size: 0,
+            created_at: None,
+            modified_at: None,
+            missing: false,
+            corrupt: false,
+        }
+    }
+
+    #[test]
+    fn test_runs_between_dated_neighbors() {
+        let photos = vec![
+            photo(1, "/scans/IMG_0010.jpg", Some(1000 * DAY)),
+            photo(2, "/scans/IMG_0011.jpg", None),
+            photo(3, "/scans/IMG_0012.jpg", None),
+            photo(4, "/scans/IMG_0013.jpg", Some(1010 * DAY)),
+            photo(5, "/scans/IMG_0900.jpg", None),
+            photo(6, "/other/IMG_0014.jpg", None),
+        ];
+        let clusters = cluster_undated(&photos);
+        assert_eq!(clusters.len(), 3);
+
+        assert_eq!(clusters[0].folder, "/other");
+        assert_eq!(clusters[0].suggested_taken_at, None);
+
+        assert_eq!(clusters[1].photo_ids, vec![2, 3]);
+        assert_eq!(clusters[1].suggested_taken_at, Some(1005 * DAY));
+        assert_eq!(clusters[1].suggested_precision, Some(DatePrecision::Month));
+
+        // Too far from the dated sequence to borrow its date
+        assert_eq!(clusters[2].photo_ids, vec![5]);
+        assert_eq!(clusters[2].suggested_taken_at, None);
+    }
+
+    #[test]
+    fn test_file_number() {
+        assert_eq!(file_number(Path::new("/a/DSC01234.JPG")), ("DSC".to_string(), Some(1234)));
+        assert_eq!(file_number(Path::new("/a/holiday.jpg")), (String::new(), None));
+    }
+}
@@ -6,15 +6,22 @@ pub mod fs;
 pub mod image;
 pub mod metadata;
 pub mod index;
+pub mod watch;
+#[cfg(feature = "typescript")]
+pub mod typescript;
 
-pub use config::PhotoCoreConfig;
-pub use error::CoreError;
-pub use types::{PhotoId, PhotoInfo, PhotoMetadata, ImportResult};
+pub use config::{PhotoCoreConfig, ImportOptions};
+pub use error::{CoreError, UserMessage};
+pub use types::{PhotoId, PhotoInfo, PhotoMetadata, NewPhoto, PhotoLocation, PhotoChanges, PhotoQuery, PhotoSort, QuerySort, DatePrecision, MediaType, ManifestFormat, UndatedCluster, SimilarPhoto, DuplicateGroup, TimelineBucket, YearInReview, ReviewCount, ReviewHighlight, Neighbors, SelectionSummary, GeoPoint, Place, PhotoAlbum, TrashedPhoto, IntakeItem, ImportSession, RescanReport, Weather, ThumbnailVerifyReport, LegacyThumbnailReport, LibraryVerifyReport, OriginalsAuditReport, PhotoViews, PhotoEnhance, OrientationReport, ImportResult, ImportPlan, ImportBreakdown, ImportStage, ImportThroughput, StageTime, ImportError, MAX_IMPORT_ERRORS, CacheUnavailable, CacheProblem};
 
-pub use fs::scan_photos;
-pub use image::{Thumbnailer, ThumbnailSpec, ThumbnailError, compute_hash, extract_raw_preview};
+pub use fs::{scan_photos, scan_photos_with, scan_photos_counted, count_unsupported, is_supported_image, is_supported_extension, supported_extensions, set_extra_extensions, encode_path, decode_path, file_stats, TempManager, TempJob, check_cache_dir};
+pub use image::{Thumbnailer, ThumbnailSpec, ThumbnailError, compute_hash, compute_checksum, hash_bits, extract_raw_preview, extract_raw_preview_with, PreviewQuality, RawPreview};
+pub use image::{AutoEnhance, compute_auto_enhance, apply_auto_enhance, render_png, normalize_jpeg_orientation};
 pub use index::PhotoIndex;
-pub use metadata::{read_metadata, read_date_taken};
+pub use watch::{start_watch, FolderWatcher, WatchListener};
+pub use index::review::render_year_in_review;
+pub use metadata::{read_metadata, read_date_taken, read_video_metadata, extract_video_still, find_motion_video_offset, extract_motion_video, extract_depth_map};
+pub use metadata::{DngInfo, read_dng_info};
 
 uniffi::setup_scaffolding!();
 
@@ -24,68 +31,574 @@ pub fn get_version() -> String {
 }
 
 /// Runs the complete import pipeline for a directory.
+/// With `options.dry_run` nothing is imported and `ImportResult::plan` reports what would be.
 #[uniffi::export]
 pub fn run_import_pipeline(
     root: String,
     index: std::sync::Arc<PhotoIndex>,
     config: PhotoCoreConfig,
+    options: ImportOptions,
 ) -> Result<ImportResult, CoreError> {
+    if options.dry_run {
+        let plan = plan_import_with(root, index, &options)?;
+        return Ok(ImportResult { plan: Some(plan), ..Default::default() });
+    }
+
     let root_path = std::path::Path::new(&root);
-    let photos = scan_photos(root_path)?;
-    println!("Found {} photos to process", photos.len());
+    let photos = scan_photos_with(root_path, &options)?;
+    // Journaled, so an interrupted import of this folder continues where it
+    // stopped. Files already indexed are skipped, not journaled.
+    let indexed = index.paths_exist(photos.iter().map(|p| encode_path(p)).collect())?;
+    let new_paths = photos.iter().zip(&indexed).filter(|(_, indexed)| !**indexed).map(|(p, _)| encode_path(p)).collect();
+    let session = index.begin_import_session(root.clone(), new_paths)?;
+    let todo: std::collections::HashSet<String> = index.pending_import_files(session.id)?.into_iter().collect();
+    let photos: Vec<_> = photos.into_iter().filter(|p| todo.contains(&encode_path(p))).collect();
+    println!("Found {} photos to process ({} done before)", photos.len(), session.done);
     let mut result = ImportResult::default();
+    let mut pending = Vec::new();
+    let mut handled = Vec::new();
+    let mut complete = true;
 
-    for (i, path) in photos.iter().enumerate() {
-        if i % 10 == 0 {
+    let mut collect = |i: usize, prepared: PreparedImport| -> Result<(), CoreError> {
+        let path = &photos[i];
+        if i.is_multiple_of(10) {
             println!("Processing [{}/{}] ...", i, photos.len());
         }
-        // Individual file processing failures increment failure count but don't stop the pipeline
-        
-        let metadata = match read_metadata(&path) {
-            Ok(m) => m,
-            Err(_) => {
-                result.failure += 1;
-                continue;
+        match prepared {
+            PreparedImport::Ready(photo) => {
+                // Done once its batch is written; a failed batch stays pending
+                pending.push((path, *photo));
+                if pending.len() >= IMPORT_BATCH_SIZE {
+                    let batch = pending.len();
+                    let written = flush_import_batch(&index, &mut pending, root_path, &mut result);
+                    complete &= written.len() == batch;
+                    handled.extend(written);
+                    index.mark_import_files_done(session.id, std::mem::take(&mut handled))?;
+                }
+                return Ok(());
+            }
+            PreparedImport::SkippedDeleted => result.skipped_deleted += 1,
+            PreparedImport::Filtered => result.skipped_filtered += 1,
+            PreparedImport::Failed(stage, message) => result.record_failure(root_path, path, stage, message),
+        }
+        handled.push(encode_path(path));
+        Ok(())
+    };
+
+    if options.concurrency <= 1 {
+        for (i, path) in photos.iter().enumerate() {
+            collect(i, prepare_import(path, &index, &config, &options)?)?;
+        }
+    } else {
+        // Workers take the next file as they free up; results come back in
+        // completion order and are batched here, on one thread
+        let next = std::sync::atomic::AtomicUsize::new(0);
+        let failed = std::sync::atomic::AtomicBool::new(false);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::scope(|scope| -> Result<(), CoreError> {
+            for _ in 0..options.concurrency.min(photos.len() as u32) {
+                let sender = sender.clone();
+                let (next, failed, photos, index, config, options) = (&next, &failed, &photos, &index, &config, &options);
+                scope.spawn(move || {
+                    while !failed.load(std::sync::atomic::Ordering::Relaxed) {
+                        let i = next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                        let Some(path) = photos.get(i) else { break };
+                        if sender.send((i, prepare_import(path, index, config, options))).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(sender);
+            for (i, prepared) in receiver {
+                // Index errors stop the import, as they do sequentially
+                let prepared = prepared.inspect_err(|_| failed.store(true, std::sync::atomic::Ordering::Relaxed))?;
+                collect(i, prepared)?;
+            }
+            Ok(())
+        })?;
+    }
+    let batch = pending.len();
+    let written = flush_import_batch(&index, &mut pending, root_path, &mut result);
+    if complete && written.len() == batch {
+        index.finish_import_session(session.id)?;
+    } else {
+        // Kept for importing the folder again to retry the failed batches
+        handled.extend(written);
+        index.mark_import_files_done(session.id, handled)?;
+    }
+
+    Ok(result)
+}
+
+/// What became of one file of an import before it reaches the index.
+enum PreparedImport {
+    Ready(Box<NewPhoto>),
+    SkippedDeleted,
+    /// Taken outside the import's date range
+    Filtered,
+    /// Metadata, hash or thumbnail failed; the import goes on without it
+    Failed(ImportStage, String),
+}
+
+/// The per-file work of an import: metadata, hash and thumbnail. Only index
+/// errors are returned as errors; everything else fails just this file.
+fn prepare_import(
+    path: &std::path::Path,
+    index: &PhotoIndex,
+    config: &PhotoCoreConfig,
+    options: &ImportOptions,
+) -> Result<PreparedImport, CoreError> {
+    let metadata = match read_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) => return Ok(PreparedImport::Failed(ImportStage::Metadata, e.to_string())),
+    };
+    if !options.accepts_date(metadata.taken_at) {
+        return Ok(PreparedImport::Filtered);
+    }
+    let hash = match compute_hash(path) {
+        Ok(hash) => hash,
+        Err(e) => return Ok(PreparedImport::Failed(ImportStage::Hash, e.to_string())),
+    };
+    if !options.reimport_deleted && index.is_deleted(encode_path(path), hash.clone())? {
+        return Ok(PreparedImport::SkippedDeleted);
+    }
+    if let Err(e) = generate_thumbnail(path, config) {
+        return Ok(PreparedImport::Failed(ImportStage::Thumbnail, e.to_string()));
+    }
+    Ok(PreparedImport::Ready(Box::new(NewPhoto { path: encode_path(path), hash, metadata })))
+}
+
+/// Photos inserted per index transaction during an import
+const IMPORT_BATCH_SIZE: usize = 200;
+
+/// Inserts the pending photos in one transaction. A failed batch counts all
+/// of its files as failures, since nothing from it was written. Returns the
+/// paths written, as stored: all of the batch's or none.
+fn flush_import_batch(
+    index: &PhotoIndex,
+    pending: &mut Vec<(&std::path::PathBuf, NewPhoto)>,
+    root: &std::path::Path,
+    result: &mut ImportResult,
+) -> Vec<String> {
+    if pending.is_empty() {
+        return Vec::new();
+    }
+    let (paths, photos): (Vec<_>, Vec<_>) = pending.drain(..).unzip();
+    let stored: Vec<String> = photos.iter().map(|photo| photo.path.clone()).collect();
+    let inserted = index.insert_batch(photos);
+    for path in paths {
+        match &inserted {
+            Ok(_) => result.record(root, path, true),
+            Err(e) => result.record_failure(root, path, ImportStage::Index, e.to_string()),
+        }
+    }
+    if inserted.is_ok() { stored } else { Vec::new() }
+}
+
+/// Brings the index in step with `root` without a full import, which would
+/// hash every file again. Size and modification time tell which files
+/// changed since they were indexed: new files are imported, changed ones get
+/// their hash, metadata and thumbnail refreshed in place (keeping albums and
+/// edits), and indexed files that are gone are flagged missing, as `verify`
+/// does. Unchanged files aren't read. Photos indexed before sizes and times
+/// were recorded count as unchanged and have theirs recorded.
+#[uniffi::export]
+pub fn rescan(root: String, index: std::sync::Arc<PhotoIndex>, config: PhotoCoreConfig) -> Result<RescanReport, CoreError> {
+    let root_path = std::path::Path::new(&root);
+    let mut indexed: std::collections::HashMap<String, _> = index
+        .indexed_files()?
+        .into_iter()
+        .filter(|file| decode_path(&file.path).starts_with(root_path))
+        .map(|file| (file.path.clone(), file))
+        .collect();
+    let options = ImportOptions::default();
+    let thumbnailer = Thumbnailer::new(std::path::PathBuf::from(&config.thumbnail_dir));
+    let thumb_spec = |rotation| ThumbnailSpec { width: config.thumbnail_size, height: config.thumbnail_size, rotation };
+    let mut report = RescanReport::default();
+    let mut new_photos = Vec::new();
+    // Files found unchanged or re-read, whose recorded state needs catching up
+    let mut confirmed = Vec::new();
+
+    for path in scan_photos(root_path)? {
+        let Some(file) = indexed.remove(&encode_path(&path)) else {
+            match prepare_import(&path, &index, &config, &options)? {
+                PreparedImport::Ready(photo) => new_photos.push(*photo),
+                PreparedImport::SkippedDeleted | PreparedImport::Filtered => {}
+                PreparedImport::Failed(..) => report.failed += 1,
             }
+            continue;
         };
+        if file.stats.is_none() || file.stats == file_stats(&path) {
+            report.unchanged += 1;
+            if file.stats.is_none() || file.missing {
+                confirmed.push(file.id);
+            }
+            continue;
+        }
+        // Thumbnail keys are path based - drop the old ones so they're made again
+        for rotation in [0, file.rotation] {
+            if let Ok(Some(thumb)) = thumbnailer.get_cached_path(&path, &thumb_spec(rotation)) {
+                let _ = std::fs::remove_file(thumb);
+            }
+        }
+        let refreshed = match prepare_import(&path, &index, &config, &options)? {
+            // Not refreshed if it no longer reads, or is now a photo deleted before
+            PreparedImport::Ready(photo) => index.refresh(file.id, photo.hash, photo.metadata).unwrap_or(false),
+            _ => false,
+        };
+        if refreshed {
+            report.modified += 1;
+            if file.rotation != 0 {
+                let _ = thumbnailer.get_or_create(&path, &thumb_spec(file.rotation));
+            }
+            if file.missing {
+                confirmed.push(file.id);
+            }
+        } else {
+            report.failed += 1;
+        }
+    }
+    index.record_file_stats(&confirmed)?;
+
+    // A new file with the hash of a gone one is that photo, moved (for
+    // photos with a perceptual hash; file hashes include the path)
+    let mut added = std::collections::HashSet::new();
+    for batch in new_photos.chunks(IMPORT_BATCH_SIZE) {
+        added.extend(index.insert_batch(batch.to_vec())?.into_iter().map(|id| id.id));
+    }
+    report.added = new_photos.len() as u32;
+    let gone: Vec<i64> = indexed.into_values().map(|file| file.id).filter(|id| !added.contains(id)).collect();
+    index.mark_missing(&gone)?;
+    report.missing = gone.len() as u32;
+    Ok(report)
+}
+
+/// Imports up to `limit` photos from the intake queue (see
+/// `PhotoIndex::enqueue_intake`), oldest first. Each leaves the queue only
+/// once its photo is in the index, so after a crash the next call resumes
+/// with what's left. A photo that keeps failing is retried on later calls
+/// and dropped after `INTAKE_MAX_ATTEMPTS`; one whose payload is gone is
+/// dropped right away. Call again until `pending_intake` comes back empty.
+#[uniffi::export]
+pub fn drain_intake_queue(
+    index: std::sync::Arc<PhotoIndex>,
+    config: PhotoCoreConfig,
+    limit: u32,
+) -> Result<ImportResult, CoreError> {
+    let mut result = ImportResult::default();
+    for item in index.pending_intake(limit)? {
+        let path = decode_path(&item.payload_path);
+        let root = path.parent().unwrap_or(std::path::Path::new(""));
+        if !path.exists() {
+            index.complete_intake(item.identifier)?;
+            result.record_failure(root, &path, ImportStage::Metadata, "Payload file is gone".to_string());
+            continue;
+        }
+
+        match import_intake(&index, &config, &path) {
+            Ok(true) => result.record(root, &path, true),
+            Ok(false) => result.skipped_deleted += 1,
+            Err((stage, e)) => {
+                result.record_failure(root, &path, stage, e.to_string());
+                if index.fail_intake(item.identifier.clone())? < INTAKE_MAX_ATTEMPTS {
+                    continue;
+                }
+            }
+        }
+        index.complete_intake(item.identifier)?;
+    }
+    Ok(result)
+}
+
+/// Failed imports of a queued photo before it's taken off the intake queue
+const INTAKE_MAX_ATTEMPTS: u32 = 3;
+
+/// Imports one queued payload. False when it was deleted from the library
+/// before and is skipped, as in `run_import_pipeline`.
+fn import_intake(index: &PhotoIndex, config: &PhotoCoreConfig, path: &std::path::Path) -> Result<bool, (ImportStage, CoreError)> {
+    let at = |stage| move |e| (stage, e);
+    let metadata = read_metadata(path).map_err(at(ImportStage::Metadata))?;
+    let hash = compute_hash(path).map_err(at(ImportStage::Hash))?;
+    if index.is_deleted(encode_path(path), hash.clone()).map_err(at(ImportStage::Index))? {
+        return Ok(false);
+    }
+    generate_thumbnail(path, config).map_err(at(ImportStage::Thumbnail))?;
+    index.insert(encode_path(path), hash, metadata).map_err(at(ImportStage::Index))?;
+    Ok(true)
+}
+
+/// Rough sustained read speed for import estimates (external drives are often slower)
+const ESTIMATE_BYTES_PER_SEC: u64 = 80 * 1024 * 1024;
+/// Rough per-photo cost of metadata, hashing, thumbnail and index insert
+const ESTIMATE_MS_PER_PHOTO: u64 = 30;
+
+/// Scans `root` and reports what an import would do without writing anything.
+/// New photos are hashed so photos already indexed under another path count as duplicates.
+#[uniffi::export]
+pub fn plan_import(root: String, index: std::sync::Arc<PhotoIndex>) -> Result<ImportPlan, CoreError> {
+    plan_import_with(root, index, &ImportOptions::default())
+}
+
+/// `plan_import` for an import with `options`' filters.
+fn plan_import_with(root: String, index: std::sync::Arc<PhotoIndex>, options: &ImportOptions) -> Result<ImportPlan, CoreError> {
+    let root_path = std::path::Path::new(&root);
+    let mut plan = ImportPlan::default();
+
+    let photos = if root_path.is_file() {
+        vec![root_path.to_path_buf()]
+    } else {
+        plan.unsupported = count_unsupported(root_path)?;
+        let (photos, filtered) = scan_photos_counted(root_path, options)?;
+        plan.filtered = filtered;
+        photos
+    };
+    let filter_dates = options.taken_after.is_some() || options.taken_before.is_some();
 
-        let hash = match compute_hash(&path) {
-            Ok(h) => h,
+    let indexed = index.paths_exist(photos.iter().map(|path| encode_path(path)).collect())?;
+    for (path, indexed) in photos.iter().zip(indexed) {
+        if indexed {
+            plan.duplicates += 1;
+            continue;
+        }
+        if filter_dates && !options.accepts_date(read_metadata(path).ok().and_then(|m| m.taken_at)) {
+            plan.filtered += 1;
+            continue;
+        }
+        let hash = match compute_hash(path) {
+            Ok(hash) => hash,
             Err(_) => {
-                result.failure += 1;
+                plan.unreadable += 1;
                 continue;
             }
         };
+        if index.is_deleted(encode_path(path), hash.clone())? {
+            plan.previously_deleted += 1;
+            continue;
+        }
+        if index.get_by_hash(hash)?.is_some() {
+            plan.duplicates += 1;
+            continue;
+        }
+        plan.new_files += 1;
+        plan.new_bytes += std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    }
+
+    plan.estimated_seconds = plan.new_bytes / ESTIMATE_BYTES_PER_SEC
+        + plan.new_files as u64 * ESTIMATE_MS_PER_PHOTO / 1000;
+    Ok(plan)
+}
+
+/// Spot-checks up to `limit` cached thumbnails starting at `offset` (in index order)
+/// and regenerates corrupt ones. Meant to run as periodic maintenance, continuing
+/// from `next_offset` each time so the whole cache is covered over several runs.
+#[uniffi::export]
+pub fn verify_thumbnails(
+    index: std::sync::Arc<PhotoIndex>,
+    config: PhotoCoreConfig,
+    offset: u32,
+    limit: u32,
+) -> Result<ThumbnailVerifyReport, CoreError> {
+    let thumbnailer = Thumbnailer::new(std::path::PathBuf::from(&config.thumbnail_dir));
+    let photos = index.list()?;
+
+    let mut report = ThumbnailVerifyReport::default();
+    let end = photos.len().min(offset as usize + limit as usize);
+    for photo in photos.iter().take(end).skip(offset as usize) {
+        let spec = ThumbnailSpec {
+            width: config.thumbnail_size,
+            height: config.thumbnail_size,
+            rotation: photo.metadata.rotation,
+        };
+        match thumbnailer.verify(&decode_path(&photo.path), &spec) {
+            Ok(None) => continue,
+            Ok(Some(false)) => {}
+            Ok(Some(true)) => report.repaired += 1,
+            Err(_) => report.failed += 1,
+        }
+        report.checked += 1;
+    }
+    report.next_offset = if end >= photos.len() { 0 } else { end as u32 };
+
+    Ok(report)
+}
+
+/// Which photos with ids `first_id..first_id + count` have a cached thumbnail,
+/// as a bitmap: bit `i % 8` of byte `i / 8` is set when photo `first_id + i`
+/// has one. Ids without a photo read as not ready. Lets a grid choose between
+/// placeholder and image for a whole screen in one call.
+#[uniffi::export]
+pub fn thumbnail_ready_map(
+    index: std::sync::Arc<PhotoIndex>,
+    config: PhotoCoreConfig,
+    first_id: i64,
+    count: u32,
+) -> Result<Vec<u8>, CoreError> {
+    let thumbnailer = Thumbnailer::new(std::path::PathBuf::from(&config.thumbnail_dir));
+    let mut map = vec![0u8; (count as usize).div_ceil(8)];
+    for photo in index.list_id_range(first_id, count)? {
+        let spec = ThumbnailSpec {
+            width: config.thumbnail_size,
+            height: config.thumbnail_size,
+            rotation: photo.metadata.rotation,
+        };
+        if let Ok(Some(_)) = thumbnailer.get_cached_path(&decode_path(&photo.path), &spec) {
+            let i = (photo.id.id - first_id) as usize;
+            map[i / 8] |= 1 << (i % 8);
+        }
+    }
+    Ok(map)
+}
 
-        if generate_thumbnail(&path, &config).is_err() {
-            result.failure += 1;
+/// Moves thumbnails from the legacy flat cache layout (`<content hash>.jpg`
+/// in the cache root) into the sharded one, up to `limit` files per call, so
+/// caches from older versions are reused instead of orphaned. Files whose
+/// hash isn't indexed any more, and stray temp files, are deleted. Call again
+/// while `remaining` is non-zero.
+#[uniffi::export]
+pub fn migrate_legacy_thumbnails(
+    index: std::sync::Arc<PhotoIndex>,
+    config: PhotoCoreConfig,
+    limit: u32,
+) -> Result<LegacyThumbnailReport, CoreError> {
+    let thumbnailer = Thumbnailer::new(std::path::PathBuf::from(&config.thumbnail_dir));
+    let legacy_files = thumbnailer.legacy_files()?;
+
+    let mut report = LegacyThumbnailReport::default();
+    for legacy in legacy_files.iter().take(limit as usize) {
+        let is_jpeg = legacy.extension().is_some_and(|e| e == "jpg");
+        let hash = legacy.file_stem().and_then(|s| s.to_str()).filter(|_| is_jpeg);
+        let photo = match hash {
+            Some(hash) => index.get_by_hash(hash.to_string())?,
+            None => None,
+        };
+        let Some(photo) = photo else {
+            std::fs::remove_file(legacy)?;
+            report.removed += 1;
             continue;
+        };
+
+        let spec = ThumbnailSpec {
+            width: config.thumbnail_size,
+            height: config.thumbnail_size,
+            rotation: photo.metadata.rotation,
+        };
+        let moved = thumbnailer
+            .adopt_legacy(legacy, &decode_path(&photo.path), &spec)
+            .map_err(|e| CoreError::Io(e.to_string()))?;
+        if moved {
+            report.migrated += 1;
+        } else {
+            report.removed += 1;
         }
+    }
+    report.remaining = legacy_files.len().saturating_sub(limit as usize) as u32;
 
-        let path_str = match path.to_str() {
-            Some(s) => s,
-            None => {
-                result.failure += 1;
-                continue;
-            }
+    Ok(report)
+}
+
+/// Maintenance for libraries with inconsistent orientation (typically scans):
+/// regenerates cached thumbnails whose shape doesn't match the oriented photo,
+/// and with `rewrite_pixels` rewrites JPEG originals to upright pixels with
+/// orientation 1, re-indexing them afterwards.
+#[uniffi::export]
+pub fn normalize_orientation(
+    index: std::sync::Arc<PhotoIndex>,
+    config: PhotoCoreConfig,
+    rewrite_pixels: bool,
+) -> Result<OrientationReport, CoreError> {
+    let thumbnailer = Thumbnailer::new(std::path::PathBuf::from(&config.thumbnail_dir));
+    let mut report = OrientationReport::default();
+
+    // A video's orientation comes from its track matrix, which players apply
+    for photo in index.list()?.into_iter().filter(|p| p.metadata.orientation > 1 && p.metadata.media_type == MediaType::Photo) {
+        report.checked += 1;
+        let path = decode_path(&photo.path);
+        let path = path.as_path();
+        let spec = ThumbnailSpec {
+            width: config.thumbnail_size,
+            height: config.thumbnail_size,
+            rotation: photo.metadata.rotation,
         };
-        match index.insert(path_str.to_string(), hash.clone(), metadata.clone()) {
-            Ok(_) => result.success += 1,
-            Err(_) => {
-                result.failure += 1;
-                continue;
+
+        if rewrite_pixels {
+            let is_jpeg = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| e.eq_ignore_ascii_case("jpg") || e.eq_ignore_ascii_case("jpeg"));
+            if !is_jpeg {
+                report.skipped += 1;
+            } else {
+                let rewritten = normalize_jpeg_orientation(path).and_then(|changed| {
+                    if changed {
+                        let metadata = read_metadata(path)?;
+                        let hash = compute_hash(path)?;
+                        index.refresh(photo.id.id, hash, metadata)?;
+                    }
+                    Ok(changed)
+                });
+                match rewritten {
+                    Ok(true) => {
+                        report.rewritten += 1;
+                        // The thumbnail key is path based - drop the old one
+                        if let Ok(Some(thumb)) = thumbnailer.get_cached_path(path, &spec) {
+                            let _ = std::fs::remove_file(thumb);
+                        }
+                        let _ = thumbnailer.get_or_create(path, &spec);
+                        continue;
+                    }
+                    Ok(false) => {}
+                    Err(_) => {
+                        report.failed += 1;
+                        continue;
+                    }
+                }
+            }
+        }
+
+        if let Ok(Some(thumb)) = thumbnailer.get_cached_path(path, &spec) {
+            if thumbnail_mismatched(&thumb, &photo.metadata) {
+                let _ = std::fs::remove_file(&thumb);
+                match thumbnailer.get_or_create(path, &spec) {
+                    Ok(_) => report.thumbnails_fixed += 1,
+                    Err(_) => report.failed += 1,
+                }
             }
         }
     }
 
-    Ok(result)
+    Ok(report)
+}
+
+/// Whether a cached thumbnail is landscape where the oriented photo is portrait
+/// (or vice versa). Square or unknown dimensions never count as mismatched.
+fn thumbnail_mismatched(thumb: &std::path::Path, metadata: &PhotoMetadata) -> bool {
+    let (mut width, mut height) = (metadata.width, metadata.height);
+    // Orientations 5-8 transpose the stored pixels
+    if (5..=8).contains(&metadata.orientation) {
+        std::mem::swap(&mut width, &mut height);
+    }
+    if metadata.rotation % 180 == 90 {
+        std::mem::swap(&mut width, &mut height);
+    }
+    let (thumb_width, thumb_height) = match ::image::image_dimensions(thumb) {
+        Ok(dims) => dims,
+        Err(_) => return false,
+    };
+    if width == height || thumb_width == thumb_height {
+        return false;
+    }
+    (width > height) != (thumb_width > thumb_height)
 }
 
 /// Convenience function to generate a thumbnail using the core config
 pub fn generate_thumbnail(path: &std::path::Path, config: &PhotoCoreConfig) -> Result<std::path::PathBuf, CoreError> {
     let thumbnailer = Thumbnailer::new(std::path::PathBuf::from(&config.thumbnail_dir));
-    let spec = ThumbnailSpec { width: config.thumbnail_size, height: config.thumbnail_size };
-    thumbnailer.generate(path, &spec).map_err(|e| CoreError::Io(e.to_string()))
+    let spec = ThumbnailSpec { width: config.thumbnail_size, height: config.thumbnail_size, rotation: 0 };
+    thumbnailer.generate(path, &spec).map_err(|e| match e {
+        ThumbnailError::CacheUnavailable(detail) => CoreError::CacheUnavailable(detail),
+        e => CoreError::Io(e.to_string()),
+    })
 }
 
 #[cfg(test)]
@@ -123,12 +636,396 @@ mod tests {
 
         // Note: The real read_metadata might fail because files aren't real images.
         // But the pipeline is error tolerant!
-        let result = run_import_pipeline(src_dir.to_string_lossy().to_string(), index, config).unwrap();
+        let result = run_import_pipeline(src_dir.to_string_lossy().to_string(), index, config, ImportOptions::default()).unwrap();
 
         // Since they aren't real images, success will be 0 and failure will be 2.
         // This confirms the pipeline DOES NOT STOP on errors.
         assert_eq!(result.success, 0);
         assert_eq!(result.failure, 2);
+        assert_eq!(result.by_extension, vec![ImportBreakdown { key: "jpg".to_string(), success: 0, failure: 2 }]);
+        assert_eq!(result.by_folder, vec![ImportBreakdown { key: ".".to_string(), success: 0, failure: 2 }]);
+        // Each failure says which file and why
+        let mut failed: Vec<_> = result.errors.iter().map(|e| e.path.clone()).collect();
+        failed.sort();
+        assert_eq!(failed, vec![encode_path(&v1), encode_path(&v2)]);
+        assert!(result.errors.iter().all(|e| !e.message.is_empty()));
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[test]
+    fn test_parallel_import_matches_sequential() {
+        let temp_dir = std::env::temp_dir().join("fotos_parallel_import_test");
+        let src_dir = temp_dir.join("src");
+        if temp_dir.exists() { fs::remove_dir_all(&temp_dir).unwrap(); }
+        fs::create_dir_all(src_dir.join("trip")).unwrap();
+
+        for i in 0..12 {
+            let folder = if i % 2 == 0 { src_dir.clone() } else { src_dir.join("trip") };
+            ::image::RgbImage::from_pixel(8 + i, 8, ::image::Rgb([i as u8, 0, 0])).save(folder.join(format!("{}.png", i))).unwrap();
+        }
+        // Same content as 0.png, whichever of the two a worker gets to first
+        fs::copy(src_dir.join("0.png"), src_dir.join("copy.png")).unwrap();
+        fs::write(src_dir.join("broken.jpg"), b"not a jpeg").unwrap();
+
+        let import = |name: &str, concurrency: u32| {
+            let index = PhotoIndex::open(temp_dir.join(format!("{}.db", name)).to_string_lossy().to_string()).unwrap();
+            let config = PhotoCoreConfig {
+                thumbnail_dir: temp_dir.join(name).to_string_lossy().to_string(),
+                thumbnail_size: 64,
+            };
+            let options = ImportOptions { concurrency, ..Default::default() };
+            let result = run_import_pipeline(src_dir.to_string_lossy().to_string(), index.clone(), config, options).unwrap();
+            (result, index.count().unwrap())
+        };
+        let (sequential, sequential_count) = import("sequential", 0);
+        let (parallel, parallel_count) = import("parallel", 4);
+
+        assert_eq!((parallel.success, parallel.failure), (13, 1));
+        assert_eq!((parallel.success, parallel.failure), (sequential.success, sequential.failure));
+        let sorted = |mut breakdown: Vec<ImportBreakdown>| {
+            breakdown.sort_by(|a, b| a.key.cmp(&b.key));
+            breakdown
+        };
+        assert_eq!(sorted(parallel.by_folder), sorted(sequential.by_folder));
+        assert_eq!(sorted(parallel.by_extension), sorted(sequential.by_extension));
+        // The copy is the same photo as 0.png
+        assert_eq!(parallel_count, 12);
+        assert_eq!(parallel_count, sequential_count);
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[test]
+    fn test_import_resumes_interrupted_session() {
+        let temp_dir = std::env::temp_dir().join("fotos_import_session_test");
+        let src_dir = temp_dir.join("src");
+        if temp_dir.exists() { fs::remove_dir_all(&temp_dir).unwrap(); }
+        fs::create_dir_all(&src_dir).unwrap();
+        let files: Vec<_> = (0..3).map(|i| src_dir.join(format!("{}.png", i))).collect();
+        for (i, file) in files.iter().enumerate() {
+            ::image::RgbImage::from_pixel(8, 8, ::image::Rgb([i as u8, 0, 0])).save(file).unwrap();
+        }
+
+        let index = PhotoIndex::open(temp_dir.join("test.db").to_string_lossy().to_string()).unwrap();
+        let config = PhotoCoreConfig {
+            thumbnail_dir: temp_dir.join("thumbs").to_string_lossy().to_string(),
+            thumbnail_size: 64,
+        };
+        let root = src_dir.to_string_lossy().to_string();
+
+        // An import that got through 0.png before the app died
+        let session = index.begin_import_session(root.clone(), files.iter().map(|f| encode_path(f)).collect()).unwrap();
+        assert!(!session.resumed);
+        index.mark_import_files_done(session.id, vec![encode_path(&files[0])]).unwrap();
+        let unfinished = index.unfinished_import_sessions().unwrap();
+        assert_eq!((unfinished.len(), unfinished[0].done, unfinished[0].pending), (1, 1, 2));
+
+        let result = run_import_pipeline(root.clone(), index.clone(), config.clone(), ImportOptions::default()).unwrap();
+        assert_eq!(result.success, 2);
+        assert!(index.get_by_path(encode_path(&files[0])).unwrap().is_none());
+        assert!(index.unfinished_import_sessions().unwrap().is_empty());
+
+        // A finished session isn't resumed: the next import covers every file
+        // not indexed yet, including the one the first session had done
+        let result = run_import_pipeline(root, index.clone(), config.clone(), ImportOptions::default()).unwrap();
+        assert_eq!(result.success, 1);
+        assert_eq!(index.count().unwrap(), 3);
+
+        // A batch the index refused stays pending for the next import
+        let other_dir = temp_dir.join("other");
+        fs::create_dir_all(&other_dir).unwrap();
+        ::image::RgbImage::from_pixel(8, 8, ::image::Rgb([0, 0, 9])).save(other_dir.join("new.png")).unwrap();
+        let other = other_dir.to_string_lossy().to_string();
+        let db = rusqlite::Connection::open(temp_dir.join("test.db")).unwrap();
+        db.execute_batch("CREATE TRIGGER refuse BEFORE INSERT ON photos BEGIN SELECT RAISE(ABORT, 'disk full'); END").unwrap();
+        let result = run_import_pipeline(other.clone(), index.clone(), config.clone(), ImportOptions::default()).unwrap();
+        assert_eq!((result.success, result.failure), (0, 1));
+        let unfinished = index.unfinished_import_sessions().unwrap();
+        assert_eq!((unfinished.len(), unfinished[0].done, unfinished[0].pending), (1, 0, 1));
+
+        db.execute_batch("DROP TRIGGER refuse").unwrap();
+        let result = run_import_pipeline(other, index.clone(), config, ImportOptions::default()).unwrap();
+        assert_eq!(result.success, 1);
+        assert!(index.unfinished_import_sessions().unwrap().is_empty());
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[test]
+    fn test_rescan_detects_changes() {
+        let temp_dir = std::env::temp_dir().join("fotos_rescan_test");
+        let src_dir = temp_dir.join("src");
+        if temp_dir.exists() { fs::remove_dir_all(&temp_dir).unwrap(); }
+        fs::create_dir_all(&src_dir).unwrap();
+        let file = |name: &str| src_dir.join(name);
+        let save = |name: &str, shade: u8| ::image::RgbImage::from_pixel(8, 8, ::image::Rgb([shade, 0, 0])).save(file(name)).unwrap();
+        for (i, name) in ["a.png", "b.png", "c.png"].into_iter().enumerate() {
+            save(name, i as u8);
+        }
+
+        let index = PhotoIndex::open(temp_dir.join("test.db").to_string_lossy().to_string()).unwrap();
+        let config = PhotoCoreConfig {
+            thumbnail_dir: temp_dir.join("thumbs").to_string_lossy().to_string(),
+            thumbnail_size: 64,
+        };
+        let root = src_dir.to_string_lossy().to_string();
+        run_import_pipeline(root.clone(), index.clone(), config.clone(), ImportOptions::default()).unwrap();
+        let photo = |name: &str| index.get_by_path(encode_path(&file(name))).unwrap();
+        let b = photo("b.png").unwrap();
+        let thumbnailer = Thumbnailer::new(temp_dir.join("thumbs"));
+        let thumb = |name: &str| {
+            let spec = ThumbnailSpec { width: 64, height: 64, rotation: 0 };
+            fs::read(thumbnailer.get_cached_path(&file(name), &spec).unwrap().unwrap()).unwrap()
+        };
+        let b_thumb = thumb("b.png");
+
+        // Nothing changed: nothing is read
+        let report = rescan(root.clone(), index.clone(), config.clone()).unwrap();
+        assert_eq!(report, RescanReport { unchanged: 3, ..Default::default() });
+
+        save("d.png", 3);
+        ::image::RgbImage::from_pixel(16, 8, ::image::Rgb([9, 9, 9])).save(file("b.png")).unwrap();
+        fs::remove_file(file("c.png")).unwrap();
+        let report = rescan(root.clone(), index.clone(), config.clone()).unwrap();
+        assert_eq!(report, RescanReport { added: 1, modified: 1, missing: 1, unchanged: 1, failed: 0 });
+        let refreshed = photo("b.png").unwrap();
+        assert_eq!(refreshed.id, b.id);
+        assert_ne!(refreshed.hash, b.hash);
+        // Edited in place, so its thumbnail is made again
+        assert_ne!(thumb("b.png"), b_thumb);
+        assert!(photo("c.png").unwrap().missing);
+        assert!(photo("d.png").is_some());
+
+        // A file that's back is no longer missing
+        save("c.png", 2);
+        let report = rescan(root, index.clone(), config).unwrap();
+        assert_eq!(report, RescanReport { modified: 1, unchanged: 3, ..Default::default() });
+        assert!(!photo("c.png").unwrap().missing);
+        assert_eq!(index.count().unwrap(), 4);
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[test]
+    fn test_drain_intake_queue() {
+        let temp_dir = std::env::temp_dir().join("fotos_intake_test");
+        let thumb_dir = temp_dir.join("thumbs");
+        let inbox = temp_dir.join("inbox");
+        if temp_dir.exists() { fs::remove_dir_all(&temp_dir).unwrap(); }
+        fs::create_dir_all(&inbox).unwrap();
+
+        let db_path = temp_dir.join("test.db").to_string_lossy().to_string();
+        let index = PhotoIndex::open(db_path.clone()).unwrap();
+        let config = PhotoCoreConfig {
+            thumbnail_dir: thumb_dir.to_string_lossy().to_string(),
+            thumbnail_size: 64,
+        };
+        let good = inbox.join("IMG_0001.png");
+        ::image::RgbImage::new(8, 8).save(&good).unwrap();
+        let broken = inbox.join("IMG_0002.jpg");
+        fs::write(&broken, b"truncated").unwrap();
+
+        assert!(index.enqueue_intake("asset-1".to_string(), encode_path(&good)).unwrap());
+        assert!(!index.enqueue_intake("asset-1".to_string(), encode_path(&good)).unwrap());
+        index.enqueue_intake("asset-2".to_string(), encode_path(&broken)).unwrap();
+        index.enqueue_intake("asset-3".to_string(), encode_path(&inbox.join("gone.jpg"))).unwrap();
+        drop(index);
+
+        // The queue outlives the process (a crash before draining loses nothing)
+        let index = PhotoIndex::open(db_path).unwrap();
+        assert_eq!(index.pending_intake(10).unwrap().len(), 3);
+
+        let result = drain_intake_queue(index.clone(), config.clone(), 10).unwrap();
+        assert_eq!((result.success, result.failure), (1, 2));
+        assert!(index.get_by_path(encode_path(&good)).unwrap().is_some());
+        // The unreadable payload stays queued for a retry, the missing one is dropped
+        let pending = index.pending_intake(10).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!((pending[0].identifier.as_str(), pending[0].attempts), ("asset-2", 1));
+
+        for _ in 1..INTAKE_MAX_ATTEMPTS {
+            drain_intake_queue(index.clone(), config.clone(), 10).unwrap();
+        }
+        assert!(index.pending_intake(10).unwrap().is_empty());
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[test]
+    fn test_thumbnail_ready_map() {
+        let temp_dir = std::env::temp_dir().join("fotos_ready_map_test");
+        let thumb_dir = temp_dir.join("thumbs");
+        if temp_dir.exists() { fs::remove_dir_all(&temp_dir).unwrap(); }
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        let index = PhotoIndex::open(temp_dir.join("test.db").to_string_lossy().to_string()).unwrap();
+        let config = PhotoCoreConfig {
+            thumbnail_dir: thumb_dir.to_string_lossy().to_string(),
+            thumbnail_size: 64,
+        };
+        let mut ids = Vec::new();
+        for i in 0..10 {
+            let path = temp_dir.join(format!("{}.png", i));
+            ::image::RgbImage::from_pixel(8, 8, ::image::Rgb([i as u8, 0, 0])).save(&path).unwrap();
+            ids.push(index.insert(encode_path(&path), i.to_string(), read_metadata(&path).unwrap()).unwrap().id);
+            // Every third photo has its thumbnail
+            if i % 3 == 0 {
+                generate_thumbnail(&path, &config).unwrap();
+            }
+        }
+
+        let map = thumbnail_ready_map(index.clone(), config.clone(), ids[0], 10).unwrap();
+        assert_eq!(map, vec![0b0100_1001, 0b10]);
+        // Ranges past the last photo are simply not ready
+        let map = thumbnail_ready_map(index, config, ids[9], 12).unwrap();
+        assert_eq!(map, vec![0b1, 0]);
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[test]
+    fn test_dry_run_writes_nothing() {
+        let temp_dir = std::env::temp_dir().join("fotos_dry_run_test");
+        let thumb_dir = temp_dir.join("thumbs");
+        let src_dir = temp_dir.join("src");
+
+        if temp_dir.exists() { fs::remove_dir_all(&temp_dir).unwrap(); }
+        fs::create_dir_all(&src_dir).unwrap();
+
+        let known = src_dir.join("known.png");
+        let fresh = src_dir.join("fresh.png");
+        ::image::RgbImage::new(8, 8).save(&known).unwrap();
+        ::image::RgbImage::new(16, 16).save(&fresh).unwrap();
+        fs::write(src_dir.join("notes.txt"), b"not a photo").unwrap();
+
+        let index = PhotoIndex::open(temp_dir.join("test.db").to_string_lossy().to_string()).unwrap();
+        let known_hash = compute_hash(&known).unwrap();
+        index.insert(known.to_string_lossy().to_string(), known_hash, PhotoMetadata::default()).unwrap();
+
+        let config = PhotoCoreConfig {
+            thumbnail_dir: thumb_dir.to_string_lossy().to_string(),
+            thumbnail_size: 256,
+        };
+        let options = ImportOptions { dry_run: true, ..Default::default() };
+        let result = run_import_pipeline(src_dir.to_string_lossy().to_string(), index.clone(), config, options).unwrap();
+
+        let plan = result.plan.expect("dry run should report a plan");
+        assert_eq!(plan.new_files, 1);
+        assert_eq!(plan.duplicates, 1);
+        assert_eq!(plan.unsupported, 1);
+        assert_eq!(plan.new_bytes, fs::metadata(&fresh).unwrap().len());
+        assert_eq!(result.success, 0);
+
+        assert_eq!(index.list().unwrap().len(), 1);
+        assert!(!thumb_dir.exists());
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[test]
+    fn test_import_filters() {
+        let temp_dir = std::env::temp_dir().join("fotos_import_filter_test");
+        let src_dir = temp_dir.join("src");
+        if temp_dir.exists() { fs::remove_dir_all(&temp_dir).unwrap(); }
+        fs::create_dir_all(src_dir.join("2023")).unwrap();
+
+        ::image::RgbImage::from_pixel(64, 64, ::image::Rgb([10, 20, 30])).save(src_dir.join("big.png")).unwrap();
+        ::image::RgbImage::new(1, 1).save(src_dir.join("icon.png")).unwrap();
+        ::image::RgbImage::from_pixel(64, 64, ::image::Rgb([30, 20, 10])).save(src_dir.join("2023").join("nested.png")).unwrap();
+        ::image::RgbImage::from_pixel(64, 64, ::image::Rgb([0, 99, 0])).save(src_dir.join("photo.jpg")).unwrap();
+        let min_file_size = fs::metadata(src_dir.join("icon.png")).unwrap().len() + 1;
+
+        let index = PhotoIndex::open(temp_dir.join("test.db").to_string_lossy().to_string()).unwrap();
+        let config = PhotoCoreConfig {
+            thumbnail_dir: temp_dir.join("thumbs").to_string_lossy().to_string(),
+            thumbnail_size: 64,
+        };
+        let root = src_dir.to_string_lossy().to_string();
+        let options = ImportOptions {
+            extensions: vec!["PNG".to_string()],
+            recursive: false,
+            min_file_size,
+            ..Default::default()
+        };
+
+        let plan = run_import_pipeline(root.clone(), index.clone(), config.clone(), ImportOptions { dry_run: true, ..options.clone() })
+            .unwrap()
+            .plan
+            .unwrap();
+        assert_eq!((plan.new_files, plan.filtered), (1, 3));
+
+        // A date range leaves out photos without a date
+        let dated = ImportOptions { taken_after: Some(1_672_531_200), ..options.clone() };
+        let result = run_import_pipeline(root.clone(), index.clone(), config.clone(), dated).unwrap();
+        assert_eq!((result.success, result.skipped_filtered), (0, 1));
+
+        let result = run_import_pipeline(root, index.clone(), config, options).unwrap();
+        assert_eq!(result.success, 1);
+        assert!(index.get_by_path(encode_path(&src_dir.join("big.png"))).unwrap().is_some());
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[test]
+    fn test_migrate_legacy_thumbnails() {
+        let temp_dir = std::env::temp_dir().join("fotos_legacy_thumb_test");
+        let thumb_dir = temp_dir.join("thumbs");
+        if temp_dir.exists() { fs::remove_dir_all(&temp_dir).unwrap(); }
+        fs::create_dir_all(&thumb_dir).unwrap();
+
+        let photo = temp_dir.join("photo.png");
+        ::image::RgbImage::new(8, 8).save(&photo).unwrap();
+        let index = PhotoIndex::open(temp_dir.join("test.db").to_string_lossy().to_string()).unwrap();
+        index.insert(photo.to_string_lossy().to_string(), "abc123".to_string(), PhotoMetadata::default()).unwrap();
+
+        ::image::RgbImage::new(4, 4).save(thumb_dir.join("abc123.jpg")).unwrap();
+        ::image::RgbImage::new(4, 4).save(thumb_dir.join("gone.jpg")).unwrap();
+        fs::write(thumb_dir.join("ab.jpg.tmp.1_2"), b"partial").unwrap();
+
+        let config = PhotoCoreConfig {
+            thumbnail_dir: thumb_dir.to_string_lossy().to_string(),
+            thumbnail_size: 256,
+        };
+        let first = migrate_legacy_thumbnails(index.clone(), config.clone(), 2).unwrap();
+        assert_eq!(first.migrated + first.removed, 2);
+        assert_eq!(first.remaining, 1);
+        let second = migrate_legacy_thumbnails(index.clone(), config.clone(), 2).unwrap();
+        assert_eq!(first.migrated + second.migrated, 1);
+        assert_eq!(first.removed + second.removed, 2);
+        assert_eq!(second.remaining, 0);
+
+        let thumbnailer = Thumbnailer::new(thumb_dir.clone());
+        let spec = ThumbnailSpec { width: 256, height: 256, rotation: 0 };
+        assert!(thumbnailer.get_cached_path(&photo, &spec).unwrap().is_some());
+        assert!(thumbnailer.legacy_files().unwrap().is_empty());
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_import_non_utf8_path() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let temp_dir = std::env::temp_dir().join("fotos_non_utf8_import_test");
+        let src_dir = temp_dir.join("src");
+        if temp_dir.exists() { fs::remove_dir_all(&temp_dir).unwrap(); }
+        fs::create_dir_all(&src_dir).unwrap();
+
+        let photo = src_dir.join(std::ffi::OsStr::from_bytes(b"caf\xe9.png"));
+        ::image::RgbImage::new(8, 8).save_with_format(&photo, ::image::ImageFormat::Png).unwrap();
+
+        let index = PhotoIndex::open(temp_dir.join("test.db").to_string_lossy().to_string()).unwrap();
+        let config = PhotoCoreConfig {
+            thumbnail_dir: temp_dir.join("thumbs").to_string_lossy().to_string(),
+            thumbnail_size: 256,
+        };
+        let result = run_import_pipeline(src_dir.to_string_lossy().to_string(), index.clone(), config, ImportOptions::default()).unwrap();
+        assert_eq!(result.success, 1);
+
+        let photos = index.list().unwrap();
+        assert_eq!(decode_path(&photos[0].path), photo);
 
         fs::remove_dir_all(&temp_dir).unwrap();
     }
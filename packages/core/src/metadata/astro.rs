@@ -0,0 +1,127 @@
+//! Sun and moon state at capture time, computed offline from GPS + timestamp.
+//! Low-precision solar/lunar formulas - good to a fraction of a degree, plenty for
+//! golden hour / night classification.
+
+use super::date::parse_date_taken;
+use crate::types::PhotoMetadata;
+
+const UNIX_EPOCH_JD: f64 = 2_440_587.5;
+const J2000_JD: f64 = 2_451_545.0;
+const SYNODIC_MONTH_DAYS: f64 = 29.530_588_853;
+/// A known new moon (2000-01-06 18:14 UTC)
+const NEW_MOON_JD: f64 = 2_451_550.1;
+
+/// Fills `light` and `moon_phase` from the GPS position and `date_taken`.
+/// Leaves them `None` when either is missing.
+pub(crate) fn apply_astronomy(metadata: &mut PhotoMetadata) {
+    let utc = match (metadata.lat, metadata.lon, metadata.date_taken.as_deref()) {
+        (Some(_), Some(lon), Some(date_taken)) => local_to_utc_estimate(date_taken, lon),
+        _ => None,
+    };
+    match (utc, metadata.lat, metadata.lon) {
+        (Some(utc), Some(lat), Some(lon)) => {
+            metadata.light = Some(light_phase(sun_altitude(lat, lon, utc)).to_string());
+            metadata.moon_phase = Some(moon_phase(utc));
+        }
+        _ => {
+            metadata.light = None;
+            metadata.moon_phase = None;
+        }
+    }
+}
+
+/// Sun altitude above the horizon in degrees.
+pub fn sun_altitude(lat: f64, lon: f64, unix_utc: i64) -> f64 {
+    let d = unix_utc as f64 / 86_400.0 + UNIX_EPOCH_JD - J2000_JD;
+
+    let mean_anomaly = (357.529 + 0.985_600_28 * d).to_radians();
+    let mean_longitude = 280.459 + 0.985_647_36 * d;
+    let ecliptic_longitude = (mean_longitude
+        + 1.915 * mean_anomaly.sin()
+        + 0.020 * (2.0 * mean_anomaly).sin())
+    .to_radians();
+    let obliquity = (23.439 - 0.000_000_36 * d).to_radians();
+
+    let right_ascension = (obliquity.cos() * ecliptic_longitude.sin()).atan2(ecliptic_longitude.cos());
+    let declination = (obliquity.sin() * ecliptic_longitude.sin()).asin();
+
+    let sidereal_deg = (18.697_374_558 + 24.065_709_824_419_08 * d) * 15.0 + lon;
+    let hour_angle = (sidereal_deg - right_ascension.to_degrees()).to_radians();
+
+    let lat = lat.to_radians();
+    (lat.sin() * declination.sin() + lat.cos() * declination.cos() * hour_angle.cos())
+        .asin()
+        .to_degrees()
+}
+
+/// Moon phase as a fraction of the synodic month: 0.0 new, 0.5 full.
+pub fn moon_phase(unix_utc: i64) -> f64 {
+    let jd = unix_utc as f64 / 86_400.0 + UNIX_EPOCH_JD;
+    ((jd - NEW_MOON_JD) / SYNODIC_MONTH_DAYS).rem_euclid(1.0)
+}
+
+/// Classifies sun altitude: "day", "golden_hour" (-4° to 6°), "blue_hour" (-6° to -4°) or "night".
+pub fn light_phase(sun_altitude: f64) -> &'static str {
+    if sun_altitude >= 6.0 {
+        "day"
+    } else if sun_altitude >= -4.0 {
+        "golden_hour"
+    } else if sun_altitude >= -6.0 {
+        "blue_hour"
+    } else {
+        "night"
+    }
+}
+
+/// EXIF times are local without a zone, so the UTC offset is estimated from longitude
+/// (15° per hour). Off by the DST / political offset in some regions.
+fn local_to_utc_estimate(date_taken: &str, lon: f64) -> Option<i64> {
+    let local = parse_date_taken(date_taken)?;
+    Some(local - (lon / 15.0).round() as i64 * 3600)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sun_altitude_and_light() {
+        // Greenwich, spring equinox noon: sun ~38.5° high
+        let noon = parse_date_taken("2023-03-20 12:00:00").unwrap();
+        assert!((sun_altitude(51.48, 0.0, noon) - 38.5).abs() < 1.0);
+        // Midnight is deep night
+        let midnight = parse_date_taken("2023-03-20 00:00:00").unwrap();
+        assert_eq!(light_phase(sun_altitude(51.48, 0.0, midnight)), "night");
+
+        assert_eq!(light_phase(20.0), "day");
+        assert_eq!(light_phase(2.0), "golden_hour");
+        assert_eq!(light_phase(-5.0), "blue_hour");
+        assert_eq!(light_phase(-12.0), "night");
+    }
+
+    #[test]
+    fn test_moon_phase() {
+        // New moon of the 2024-04-08 eclipse, full moon 2024-04-23
+        let new_moon = moon_phase(parse_date_taken("2024-04-08 18:21:00").unwrap());
+        assert!(!(0.03..=0.97).contains(&new_moon));
+        let full_moon = moon_phase(parse_date_taken("2024-04-23 23:49:00").unwrap());
+        assert!((full_moon - 0.5).abs() < 0.03);
+    }
+
+    #[test]
+    fn test_apply_astronomy() {
+        let mut metadata = PhotoMetadata {
+            lat: Some(-0.18),
+            lon: Some(-78.47),
+            date_taken: Some("2023-06-01 13:00:00".to_string()),
+            ..Default::default()
+        };
+        apply_astronomy(&mut metadata);
+        assert_eq!(metadata.light.as_deref(), Some("day"));
+        assert!(metadata.moon_phase.is_some());
+
+        metadata.lat = None;
+        apply_astronomy(&mut metadata);
+        assert_eq!(metadata.light, None);
+    }
+}
@@ -0,0 +1,232 @@
+/// Color encoding of an original, read from the file header without decoding
+/// any pixels.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct ColorInfo {
+    /// Embedded ICC profile description ("Display P3", "Adobe RGB (1998)")
+    pub profile: Option<String>,
+    /// Bits per channel
+    pub bit_depth: Option<u32>,
+    /// JPEG chroma subsampling: "4:4:4", "4:2:2", "4:2:0", "4:1:1" or "4:0:0" (grayscale)
+    pub subsampling: Option<String>,
+}
+
+/// Reads what the JPEG or PNG `header` says about its color encoding. Other
+/// formats come back empty.
+pub(crate) fn read_color_info(header: &[u8]) -> ColorInfo {
+    if header.starts_with(&[0xFF, 0xD8]) {
+        jpeg_color_info(header)
+    } else if header.starts_with(b"\x89PNG\r\n\x1a\n") {
+        png_color_info(header)
+    } else {
+        ColorInfo::default()
+    }
+}
+
+/// Walks the JPEG segments up to the first frame header (SOFn), collecting
+/// the APP2 ICC_PROFILE chunks on the way.
+fn jpeg_color_info(header: &[u8]) -> ColorInfo {
+    let mut info = ColorInfo::default();
+    let mut icc = Vec::new();
+    let mut pos = 2;
+    while pos + 4 <= header.len() {
+        if header[pos] != 0xFF {
+            break;
+        }
+        let marker = header[pos + 1];
+        if marker == 0xFF {
+            // Fill byte
+            pos += 1;
+            continue;
+        }
+        let len = u16::from_be_bytes([header[pos + 2], header[pos + 3]]) as usize;
+        let start = pos + 4;
+        let end = (pos + 2 + len).min(header.len());
+        if len < 2 || start > end {
+            break;
+        }
+        let segment = &header[start..end];
+        match marker {
+            0xE2 if segment.starts_with(b"ICC_PROFILE\0") && segment.len() > 14 => {
+                // Chunks follow each other in order; the sequence bytes are skipped
+                icc.extend_from_slice(&segment[14..]);
+            }
+            // SOF0-SOF15, minus DHT (C4), JPG (C8) and DAC (CC)
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                info.bit_depth = segment.first().map(|&p| p as u32);
+                info.subsampling = subsampling(segment);
+                break;
+            }
+            0xDA | 0xD9 => break,
+            _ => {}
+        }
+        pos += 2 + len;
+    }
+    info.profile = icc_description(&icc);
+    info
+}
+
+/// Chroma subsampling from a frame header: precision, height, width, then
+/// per component an id, packed sampling factors and a table number.
+fn subsampling(frame: &[u8]) -> Option<String> {
+    let count = *frame.get(5)? as usize;
+    let factors = |i: usize| frame.get(7 + i * 3).map(|f| (f >> 4, f & 0x0F));
+    let name = match count {
+        1 => "4:0:0",
+        3 => {
+            let (luma, chroma) = (factors(0)?, factors(1)?);
+            if chroma != factors(2)? || chroma.0 == 0 || chroma.1 == 0 {
+                return None;
+            }
+            match (luma.0 / chroma.0, luma.1 / chroma.1) {
+                (1, 1) => "4:4:4",
+                (2, 1) => "4:2:2",
+                (2, 2) => "4:2:0",
+                (4, 1) => "4:1:1",
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+/// Walks the PNG chunks up to the image data: IHDR has the bit depth, iCCP
+/// names the profile and sRGB marks an sRGB image without one.
+fn png_color_info(header: &[u8]) -> ColorInfo {
+    let mut info = ColorInfo::default();
+    let mut pos = 8;
+    while pos + 8 <= header.len() {
+        let len = u32::from_be_bytes([header[pos], header[pos + 1], header[pos + 2], header[pos + 3]]) as usize;
+        let kind = &header[pos + 4..pos + 8];
+        let data = &header[pos + 8..(pos + 8).saturating_add(len).min(header.len())];
+        match kind {
+            b"IHDR" => info.bit_depth = data.get(8).map(|&d| d as u32),
+            b"iCCP" => {
+                let name = data.split(|&b| b == 0).next().unwrap_or_default();
+                info.profile = Some(String::from_utf8_lossy(name).into_owned()).filter(|n| !n.is_empty());
+            }
+            b"sRGB" if info.profile.is_none() => info.profile = Some("sRGB".to_string()),
+            b"IDAT" | b"IEND" => break,
+            _ => {}
+        }
+        pos = pos.saturating_add(12).saturating_add(len);
+    }
+    info
+}
+
+/// The description ('desc' tag) of an ICC profile: ASCII in version 2
+/// profiles, a multi-localized UTF-16 record list in version 4.
+fn icc_description(icc: &[u8]) -> Option<String> {
+    let be32 = |at: usize| icc.get(at..at + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize);
+    let tag_count = be32(128)?.min(256);
+    let entry = (0..tag_count).map(|i| 132 + i * 12).find(|&at| icc.get(at..at + 4) == Some(&b"desc"[..]))?;
+    let (offset, size) = (be32(entry + 4)?, be32(entry + 8)?);
+    let data = icc.get(offset..offset.checked_add(size)?)?;
+
+    let text = match data.get(0..4)? {
+        b"desc" => {
+            let len = u32::from_be_bytes(data.get(8..12)?.try_into().ok()?) as usize;
+            let ascii = data.get(12..12 + len)?;
+            String::from_utf8_lossy(ascii.split(|&b| b == 0).next()?).into_owned()
+        }
+        b"mluc" => {
+            // First record; profiles list their default language first
+            let record = data.get(16..28)?;
+            let len = u32::from_be_bytes(record[4..8].try_into().ok()?) as usize;
+            let at = u32::from_be_bytes(record[8..12].try_into().ok()?) as usize;
+            let utf16: Vec<u16> = data
+                .get(at..at.checked_add(len)?)?
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            String::from_utf16_lossy(&utf16)
+        }
+        _ => return None,
+    };
+    let text = text.trim_end_matches('\0').trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A minimal ICC profile with just a 'desc' tag holding `desc`.
+    fn icc_profile(desc: &[u8]) -> Vec<u8> {
+        let mut icc = vec![0u8; 128];
+        icc.extend_from_slice(&1u32.to_be_bytes());
+        icc.extend_from_slice(b"desc");
+        icc.extend_from_slice(&144u32.to_be_bytes());
+        icc.extend_from_slice(&(desc.len() as u32).to_be_bytes());
+        icc.extend_from_slice(desc);
+        icc
+    }
+
+    fn segment(marker: u8, data: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0xFF, marker];
+        bytes.extend_from_slice(&(data.len() as u16 + 2).to_be_bytes());
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    fn jpeg(icc: &[u8], luma_factors: u8) -> Vec<u8> {
+        let mut bytes = vec![0xFF, 0xD8];
+        let mut app2 = b"ICC_PROFILE\0\x01\x01".to_vec();
+        app2.extend_from_slice(icc);
+        bytes.extend(segment(0xE2, &app2));
+        bytes.extend(segment(0xC0, &[8, 0, 16, 0, 16, 3, 1, luma_factors, 0, 2, 0x11, 1, 3, 0x11, 1]));
+        bytes.extend_from_slice(&[0xFF, 0xDA]);
+        bytes
+    }
+
+    #[test]
+    fn test_jpeg_color_info() {
+        // Version 2 'desc' type: ASCII count then text
+        let mut v2 = b"desc\0\0\0\0".to_vec();
+        v2.extend_from_slice(&11u32.to_be_bytes());
+        v2.extend_from_slice(b"Display P3\0");
+        let info = read_color_info(&jpeg(&icc_profile(&v2), 0x22));
+        assert_eq!(info.profile.as_deref(), Some("Display P3"));
+        assert_eq!(info.bit_depth, Some(8));
+        assert_eq!(info.subsampling.as_deref(), Some("4:2:0"));
+
+        // Version 4 'mluc' type: one en-US record in UTF-16
+        let text: Vec<u8> = "sRGB".encode_utf16().flat_map(|u| u.to_be_bytes()).collect();
+        let mut v4 = b"mluc\0\0\0\0".to_vec();
+        v4.extend_from_slice(&1u32.to_be_bytes());
+        v4.extend_from_slice(&12u32.to_be_bytes());
+        v4.extend_from_slice(b"enUS");
+        v4.extend_from_slice(&(text.len() as u32).to_be_bytes());
+        v4.extend_from_slice(&28u32.to_be_bytes());
+        v4.extend_from_slice(&text);
+        let info = read_color_info(&jpeg(&icc_profile(&v4), 0x21));
+        assert_eq!(info.profile.as_deref(), Some("sRGB"));
+        assert_eq!(info.subsampling.as_deref(), Some("4:2:2"));
+
+        let info = read_color_info(&jpeg(&[], 0x11));
+        assert_eq!(info.profile, None);
+        assert_eq!(info.subsampling.as_deref(), Some("4:4:4"));
+    }
+
+    #[test]
+    fn test_png_color_info() {
+        let chunk = |kind: &[u8], data: &[u8]| {
+            let mut bytes = (data.len() as u32).to_be_bytes().to_vec();
+            bytes.extend_from_slice(kind);
+            bytes.extend_from_slice(data);
+            bytes.extend_from_slice(&[0; 4]);
+            bytes
+        };
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.extend(chunk(b"IHDR", &[0, 0, 0, 1, 0, 0, 0, 1, 16, 2, 0, 0, 0]));
+        png.extend(chunk(b"iCCP", b"Adobe RGB (1998)\0\0compressed"));
+        png.extend(chunk(b"IDAT", &[]));
+
+        let info = read_color_info(&png);
+        assert_eq!(info.bit_depth, Some(16));
+        assert_eq!(info.profile.as_deref(), Some("Adobe RGB (1998)"));
+        assert_eq!(info.subsampling, None);
+
+        assert_eq!(read_color_info(b"II*\0not an image we parse"), ColorInfo::default());
+    }
+}
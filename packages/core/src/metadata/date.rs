@@ -0,0 +1,149 @@
+//! Capture dates. EXIF stores "YYYY:MM:DD HH:MM:SS" as local wall-clock time
+//! without a zone, and cameras with an unset clock write placeholders like
+//! "0000:00:00 00:00:00" - those must read as "date unknown", not year 0.
+
+use crate::types::DatePrecision;
+
+/// Earliest year taken as a real capture date; anything before is a placeholder
+const MIN_YEAR: i64 = 1826;
+const MAX_YEAR: i64 = 9999;
+
+/// Parses an EXIF or ISO-like capture date ("2023:04:15 10:03:00",
+/// "2023-04-15T10:03", surrounding quotes tolerated) into wall-clock seconds
+/// since 1970-01-01 00:00, i.e. as if the local time were UTC.
+/// Returns `None` for missing or impossible dates (month 13, Feb 30, ...).
+pub fn parse_date_taken(raw: &str) -> Option<i64> {
+    let s = raw.trim().trim_matches('"').trim();
+    let bytes = s.as_bytes();
+    let separators_ok = matches!(bytes.get(4), Some(b':' | b'-'))
+        && bytes.get(7) == bytes.get(4)
+        && matches!(bytes.get(10), Some(b' ' | b'T'))
+        && bytes.get(13) == Some(&b':')
+        && (bytes.len() == 16 || bytes.get(16) == Some(&b':'));
+    if !separators_ok {
+        return None;
+    }
+
+    let field = |range: std::ops::Range<usize>| -> Option<i64> {
+        let digits = s.get(range)?;
+        digits.bytes().all(|b| b.is_ascii_digit()).then(|| digits.parse().ok())?
+    };
+    let (year, month, day) = (field(0..4)?, field(5..7)?, field(8..10)?);
+    let (hour, minute) = (field(11..13)?, field(14..16)?);
+    let second = if bytes.len() > 16 { field(17..19)? } else { 0 };
+
+    if !(MIN_YEAR..=MAX_YEAR).contains(&year)
+        || !(1..=12).contains(&month)
+        || !(1..=days_in_month(year, month)).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+    Some(days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second)
+}
+
+/// `secs` since 1970 as an EXIF date, the inverse of `parse_date_taken`
+pub(crate) fn exif_date(secs: i64) -> String {
+    let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+    let time = secs.rem_euclid(86_400);
+    format!("{:04}:{:02}:{:02} {:02}:{:02}:{:02}", year, month, day, time / 3600, time / 60 % 60, time % 60)
+}
+
+/// Rounds a `taken_at` value down to the start of its year, month or day,
+/// so a hand-entered date doesn't claim more than the user knows.
+pub fn truncate_date(taken_at: i64, precision: DatePrecision) -> i64 {
+    let days = taken_at.div_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+    match precision {
+        DatePrecision::Year => days_from_civil(year, 1, 1) * 86_400,
+        DatePrecision::Month => days_from_civil(year, month, 1) * 86_400,
+        DatePrecision::Day => days_from_civil(year, month, day) * 86_400,
+    }
+}
+
+pub(crate) fn precision_name(precision: DatePrecision) -> &'static str {
+    match precision {
+        DatePrecision::Year => "year",
+        DatePrecision::Month => "month",
+        DatePrecision::Day => "day",
+    }
+}
+
+pub(crate) fn precision_from_name(name: &str) -> Option<DatePrecision> {
+    match name {
+        "year" => Some(DatePrecision::Year),
+        "month" => Some(DatePrecision::Month),
+        "day" => Some(DatePrecision::Day),
+        _ => None,
+    }
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date (Howard Hinnant's algorithm).
+pub(crate) fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Inverse of `days_from_civil`.
+pub(crate) fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_date_taken() {
+        assert_eq!(parse_date_taken("1970-01-01 00:00:00"), Some(0));
+        assert_eq!(parse_date_taken("2024:02:29 12:00:00"), Some(1_709_208_000));
+        assert_eq!(parse_date_taken("\"2024-02-29T12:00\""), Some(1_709_208_000));
+        assert_eq!(parse_date_taken("garbage"), None);
+        assert_eq!(exif_date(0), "1970:01:01 00:00:00");
+        assert_eq!(exif_date(1_709_208_000), "2024:02:29 12:00:00");
+    }
+
+    #[test]
+    fn test_truncate_date() {
+        let taken_at = parse_date_taken("2024:02:29 12:34:56").unwrap();
+        assert_eq!(truncate_date(taken_at, DatePrecision::Day), parse_date_taken("2024:02:29 00:00").unwrap());
+        assert_eq!(truncate_date(taken_at, DatePrecision::Month), parse_date_taken("2024:02:01 00:00").unwrap());
+        assert_eq!(truncate_date(taken_at, DatePrecision::Year), parse_date_taken("2024:01:01 00:00").unwrap());
+        assert_eq!(truncate_date(-1, DatePrecision::Year), parse_date_taken("1969:01:01 00:00").unwrap());
+    }
+
+    #[test]
+    fn test_placeholder_and_impossible_dates() {
+        assert_eq!(parse_date_taken("0000:00:00 00:00:00"), None);
+        assert_eq!(parse_date_taken("    :  :     :  :  "), None);
+        assert_eq!(parse_date_taken("2023:02:29 10:00:00"), None);
+        assert_eq!(parse_date_taken("2023:13:01 10:00:00"), None);
+        assert_eq!(parse_date_taken("2023:04:15 24:00:00"), None);
+        assert_eq!(parse_date_taken("2023:04-15 10:00:00"), None);
+    }
+}
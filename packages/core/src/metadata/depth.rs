@@ -0,0 +1,101 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use crate::error::CoreError;
+use super::xmp::{container_item_offset, container_items, contains};
+
+/// Header size inspected for depth XMP (same window as EXIF parsing)
+const HEADER_SIZE: usize = 256 * 1024;
+
+/// Detects portrait-mode / depth photos from the XMP in the file header:
+/// Google Dynamic Depth containers, legacy GDepth metadata and the
+/// Pixel portrait special type.
+///
+/// HEIC auxiliary depth images are not covered - HEIC isn't decoded by core.
+pub(crate) fn is_portrait_header(header: &[u8]) -> bool {
+    if !header.starts_with(&[0xFF, 0xD8]) {
+        return false;
+    }
+    contains(header, b"GDepth:Format")
+        || contains(header, b"GDepth:Data")
+        || contains(header, b"SpecialType-PORTRAIT")
+        || container_items(header).iter().any(|item| item.semantic == "Depth")
+}
+
+/// Copies the depth map of a Dynamic Depth photo to `dest` so it can be
+/// re-edited in other tools. Returns `Ok(None)` if the photo has no
+/// extractable depth item.
+pub fn extract_depth_map(path: &Path, dest: &Path) -> Result<Option<PathBuf>, CoreError> {
+    let mut file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+
+    let mut header = vec![0u8; HEADER_SIZE];
+    let bytes_read = file.read(&mut header)?;
+    header.truncate(bytes_read);
+
+    let items = container_items(&header);
+    let index = match items.iter().position(|item| item.semantic == "Depth") {
+        Some(index) => index,
+        None => return Ok(None),
+    };
+    let offset = match container_item_offset(&items, index, file_len) {
+        Some(offset) => offset,
+        None => return Ok(None),
+    };
+    let length = items[index].length.saturating_sub(items[index].padding);
+
+    file.seek(SeekFrom::Start(offset))?;
+    let mut out = File::create(dest)?;
+    std::io::copy(&mut file.take(length), &mut out)?;
+
+    Ok(Some(dest.to_path_buf()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dynamic_depth_file(depth: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0xFF, 0xD8];
+        bytes.extend_from_slice(format!(
+            "<Container:Directory><rdf:Seq>\
+             <rdf:li><Container:Item Item:Mime=\"image/jpeg\" Item:Semantic=\"Primary\"/></rdf:li>\
+             <rdf:li><Container:Item Item:Mime=\"image/jpeg\" Item:Semantic=\"Depth\" Item:Length=\"{}\"/></rdf:li>\
+             </rdf:Seq></Container:Directory>",
+            depth.len()
+        ).as_bytes());
+        bytes.extend_from_slice(&[0xFF, 0xD9]);
+        bytes.extend_from_slice(depth);
+        bytes
+    }
+
+    #[test]
+    fn test_portrait_detection() {
+        assert!(is_portrait_header(&dynamic_depth_file(b"depth")));
+        assert!(is_portrait_header(b"\xFF\xD8<x GDepth:Format=\"RangeInverse\"/>"));
+        assert!(is_portrait_header(b"\xFF\xD8<x GCamera:SpecialTypeID=\"com.google.android.apps.camera.gallery.specialtype.SpecialType-PORTRAIT\"/>"));
+        assert!(!is_portrait_header(b"\xFF\xD8<x:xmpmeta/>"));
+        assert!(!is_portrait_header(b"\x89PNG GDepth:Format"));
+    }
+
+    #[test]
+    fn test_extract_depth_map() {
+        let temp_dir = std::env::temp_dir().join("fotos_depth_test");
+        if temp_dir.exists() { std::fs::remove_dir_all(&temp_dir).unwrap(); }
+        std::fs::create_dir_all(&temp_dir).unwrap();
+
+        let depth = b"\xFF\xD8fake-depth-jpeg\xFF\xD9";
+        let src = temp_dir.join("PXL_portrait.jpg");
+        std::fs::write(&src, dynamic_depth_file(depth)).unwrap();
+
+        let dest = temp_dir.join("depth.jpg");
+        assert_eq!(extract_depth_map(&src, &dest).unwrap(), Some(dest.clone()));
+        assert_eq!(std::fs::read(&dest).unwrap(), depth);
+
+        let plain = temp_dir.join("plain.jpg");
+        std::fs::write(&plain, b"\xFF\xD8\xFF\xD9").unwrap();
+        assert_eq!(extract_depth_map(&plain, &temp_dir.join("none.jpg")).unwrap(), None);
+
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+    }
+}
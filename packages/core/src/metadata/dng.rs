@@ -0,0 +1,263 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use crate::error::CoreError;
+
+/// Header size inspected for DNG IFDs (same window as EXIF parsing)
+const HEADER_SIZE: usize = 256 * 1024;
+
+const TAG_NEW_SUBFILE_TYPE: u16 = 0x00FE;
+const TAG_IMAGE_WIDTH: u16 = 0x0100;
+const TAG_IMAGE_LENGTH: u16 = 0x0101;
+const TAG_MAKE: u16 = 0x010F;
+const TAG_SUB_IFDS: u16 = 0x014A;
+const TAG_DNG_VERSION: u16 = 0xC612;
+const TAG_DEFAULT_CROP_SIZE: u16 = 0xC620;
+const TAG_OPCODE_LIST_1: u16 = 0xC740;
+const TAG_OPCODE_LIST_2: u16 = 0xC741;
+const TAG_OPCODE_LIST_3: u16 = 0xC74E;
+
+/// Guards against malformed files with IFD loops or absurd entry counts
+const MAX_IFDS: usize = 16;
+const MAX_ENTRIES: u16 = 512;
+
+/// DNG-specific facts not exposed by the generic EXIF reader.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DngInfo {
+    /// Apple ProRAW (DNG written by an iPhone)
+    pub is_proraw: bool,
+    /// Raw rendering relies on DNG opcode lists (lens corrections etc.),
+    /// so the embedded preview is the faithful baseline rendering.
+    pub has_opcodes: bool,
+    /// Dimensions of the main raw image (DefaultCropSize when present).
+    /// IFD0 of a DNG is usually just a reduced-size preview.
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Reads DNG-specific tags. Returns `Ok(None)` for files that aren't DNG.
+pub fn read_dng_info(path: &Path) -> Result<Option<DngInfo>, CoreError> {
+    let mut file = File::open(path)?;
+    let mut header = vec![0u8; HEADER_SIZE];
+    let bytes_read = file.read(&mut header)?;
+    header.truncate(bytes_read);
+    Ok(parse_dng(&header))
+}
+
+/// Parses DNG IFDs from the file header (IFD0 and its SubIFDs).
+pub(crate) fn parse_dng(header: &[u8]) -> Option<DngInfo> {
+    let tiff = Tiff::new(header)?;
+    let ifd0 = tiff.u32_at(4)? as usize;
+    let ifd0_entries = tiff.entries(ifd0)?;
+
+    if !ifd0_entries.iter().any(|e| e.tag == TAG_DNG_VERSION) {
+        return None;
+    }
+
+    let mut info = DngInfo::default();
+    let mut ifds = vec![ifd0_entries];
+
+    if let Some(sub_ifds) = ifds[0].iter().find(|e| e.tag == TAG_SUB_IFDS).cloned() {
+        for offset in tiff.values_u32(&sub_ifds).into_iter().take(MAX_IFDS) {
+            if let Some(entries) = tiff.entries(offset as usize) {
+                ifds.push(entries);
+            }
+        }
+    }
+
+    if let Some(make) = ifds[0].iter().find(|e| e.tag == TAG_MAKE) {
+        info.is_proraw = tiff.ascii(make).map(|m| m.trim().eq_ignore_ascii_case("apple")).unwrap_or(false);
+    }
+
+    for entries in &ifds {
+        if entries.iter().any(|e| matches!(e.tag, TAG_OPCODE_LIST_1 | TAG_OPCODE_LIST_2 | TAG_OPCODE_LIST_3)) {
+            info.has_opcodes = true;
+        }
+
+        // NewSubfileType 0 marks the full-resolution raw image
+        let subfile_type = entries.iter()
+            .find(|e| e.tag == TAG_NEW_SUBFILE_TYPE)
+            .and_then(|e| tiff.values_u32(e).first().copied())
+            .unwrap_or(0);
+        if subfile_type != 0 {
+            continue;
+        }
+
+        let crop = entries.iter()
+            .find(|e| e.tag == TAG_DEFAULT_CROP_SIZE)
+            .map(|e| tiff.values_u32(e))
+            .filter(|v| v.len() >= 2);
+        let (width, height) = match crop {
+            Some(v) => (v[0], v[1]),
+            None => {
+                let width = entries.iter().find(|e| e.tag == TAG_IMAGE_WIDTH).and_then(|e| tiff.values_u32(e).first().copied());
+                let height = entries.iter().find(|e| e.tag == TAG_IMAGE_LENGTH).and_then(|e| tiff.values_u32(e).first().copied());
+                (width.unwrap_or(0), height.unwrap_or(0))
+            }
+        };
+        if width as u64 * height as u64 > info.width as u64 * info.height as u64 {
+            info.width = width;
+            info.height = height;
+        }
+    }
+
+    Some(info)
+}
+
+#[derive(Debug, Clone)]
+struct IfdEntry {
+    tag: u16,
+    field_type: u16,
+    count: u32,
+    /// Offset of the 4-byte value/offset field within the buffer
+    value_pos: usize,
+}
+
+/// Just enough TIFF structure reading for DNG tags, bounded by the buffer.
+struct Tiff<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> Tiff<'a> {
+    fn new(data: &'a [u8]) -> Option<Self> {
+        let little_endian = match data.get(0..4)? {
+            [0x49, 0x49, 0x2A, 0x00] => true,
+            [0x4D, 0x4D, 0x00, 0x2A] => false,
+            _ => return None,
+        };
+        Some(Self { data, little_endian })
+    }
+
+    fn u16_at(&self, pos: usize) -> Option<u16> {
+        let b = self.data.get(pos..pos + 2)?;
+        Some(if self.little_endian { u16::from_le_bytes([b[0], b[1]]) } else { u16::from_be_bytes([b[0], b[1]]) })
+    }
+
+    fn u32_at(&self, pos: usize) -> Option<u32> {
+        let b = self.data.get(pos..pos + 4)?;
+        Some(if self.little_endian {
+            u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+        } else {
+            u32::from_be_bytes([b[0], b[1], b[2], b[3]])
+        })
+    }
+
+    fn entries(&self, ifd_offset: usize) -> Option<Vec<IfdEntry>> {
+        let count = self.u16_at(ifd_offset)?;
+        if count > MAX_ENTRIES {
+            return None;
+        }
+        (0..count as usize)
+            .map(|i| {
+                let pos = ifd_offset + 2 + i * 12;
+                Some(IfdEntry {
+                    tag: self.u16_at(pos)?,
+                    field_type: self.u16_at(pos + 2)?,
+                    count: self.u32_at(pos + 4)?,
+                    value_pos: pos + 8,
+                })
+            })
+            .collect()
+    }
+
+    /// Byte position of the entry's data - inline if it fits in 4 bytes.
+    fn data_pos(&self, entry: &IfdEntry, elem_size: usize) -> Option<usize> {
+        if entry.count as usize * elem_size <= 4 {
+            Some(entry.value_pos)
+        } else {
+            self.u32_at(entry.value_pos).map(|o| o as usize)
+        }
+    }
+
+    /// Integer values of SHORT/LONG entries; RATIONAL values are truncated.
+    fn values_u32(&self, entry: &IfdEntry) -> Vec<u32> {
+        let count = entry.count.min(64) as usize;
+        let elem_size = match entry.field_type {
+            3 => 2,
+            4 | 13 => 4,
+            5 => 8,
+            _ => return Vec::new(),
+        };
+        let pos = match self.data_pos(entry, elem_size) {
+            Some(pos) => pos,
+            None => return Vec::new(),
+        };
+        (0..count)
+            .filter_map(|i| {
+                let p = pos + i * elem_size;
+                match entry.field_type {
+                    3 => self.u16_at(p).map(u32::from),
+                    5 => self.u32_at(p)?.checked_div(self.u32_at(p + 4)?),
+                    _ => self.u32_at(p),
+                }
+            })
+            .collect()
+    }
+
+    fn ascii(&self, entry: &IfdEntry) -> Option<String> {
+        let pos = self.data_pos(entry, 1)?;
+        let bytes = self.data.get(pos..pos + entry.count as usize)?;
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        String::from_utf8(bytes[..end].to_vec()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a little-endian DNG-like TIFF: IFD0 is a 256x171 preview,
+    /// one SubIFD holds the 4032x3024 raw image with an opcode list.
+    fn fake_dng(make: &[u8; 6]) -> Vec<u8> {
+        let mut buf = vec![0u8; 512];
+        buf[0..4].copy_from_slice(&[0x49, 0x49, 0x2A, 0x00]);
+        buf[4..8].copy_from_slice(&8u32.to_le_bytes());
+
+        let put_entry = |buf: &mut Vec<u8>, pos: usize, tag: u16, ty: u16, count: u32, value: u32| {
+            buf[pos..pos + 2].copy_from_slice(&tag.to_le_bytes());
+            buf[pos + 2..pos + 4].copy_from_slice(&ty.to_le_bytes());
+            buf[pos + 4..pos + 8].copy_from_slice(&count.to_le_bytes());
+            buf[pos + 8..pos + 12].copy_from_slice(&value.to_le_bytes());
+        };
+
+        // IFD0 at 8: 6 entries
+        buf[8..10].copy_from_slice(&6u16.to_le_bytes());
+        put_entry(&mut buf, 10, TAG_NEW_SUBFILE_TYPE, 4, 1, 1);
+        put_entry(&mut buf, 22, TAG_IMAGE_WIDTH, 4, 1, 256);
+        put_entry(&mut buf, 34, TAG_IMAGE_LENGTH, 4, 1, 171);
+        put_entry(&mut buf, 46, TAG_MAKE, 2, 6, 300);
+        put_entry(&mut buf, 58, TAG_SUB_IFDS, 4, 1, 200);
+        put_entry(&mut buf, 70, TAG_DNG_VERSION, 1, 4, 0x0000_0401);
+        buf[300..306].copy_from_slice(make);
+
+        // SubIFD at 200: 4 entries
+        buf[200..202].copy_from_slice(&4u16.to_le_bytes());
+        put_entry(&mut buf, 202, TAG_NEW_SUBFILE_TYPE, 4, 1, 0);
+        put_entry(&mut buf, 214, TAG_IMAGE_WIDTH, 4, 1, 4032);
+        put_entry(&mut buf, 226, TAG_IMAGE_LENGTH, 3, 1, 3024);
+        put_entry(&mut buf, 238, TAG_OPCODE_LIST_3, 7, 16, 400);
+        buf
+    }
+
+    #[test]
+    fn test_proraw_detection_and_dimensions() {
+        let info = parse_dng(&fake_dng(b"Apple\0")).expect("should parse as DNG");
+        assert!(info.is_proraw);
+        assert!(info.has_opcodes);
+        assert_eq!((info.width, info.height), (4032, 3024));
+    }
+
+    #[test]
+    fn test_non_apple_dng_and_non_dng() {
+        let info = parse_dng(&fake_dng(b"Leica\0")).unwrap();
+        assert!(!info.is_proraw);
+
+        // Plain TIFF without DNGVersion
+        let mut tiff = fake_dng(b"Apple\0");
+        tiff[70..72].copy_from_slice(&0x0131u16.to_le_bytes());
+        assert_eq!(parse_dng(&tiff), None);
+
+        assert_eq!(parse_dng(b"\xFF\xD8\xFF\xE1"), None);
+    }
+}
@@ -5,9 +5,20 @@ use crate::error::CoreError;
 use crate::types::PhotoMetadata;
 use exif::{In, Tag, Reader, Value};
 
-/// Reads comprehensive EXIF metadata from a photo.
-/// Optimized: reads first 256KB into memory to avoid slow disk seeks on external drives.
+/// Reads comprehensive EXIF metadata from a photo, or container metadata from a video,
+/// completed from a Google Takeout sidecar next to it.
 pub fn read_metadata(path: &Path) -> Result<PhotoMetadata, CoreError> {
+    let mut metadata = if crate::fs::formats::is_video_file(path) {
+        super::video::read_video_metadata(path)?
+    } else {
+        read_image_metadata(path)?
+    };
+    super::takeout::merge_sidecar(path, &mut metadata);
+    Ok(metadata)
+}
+
+/// Optimized: reads first 256KB into memory to avoid slow disk seeks on external drives.
+fn read_image_metadata(path: &Path) -> Result<PhotoMetadata, CoreError> {
     let mut metadata = PhotoMetadata::default();
 
     // Read first 256KB into memory - enough for EXIF header in most cases
@@ -18,6 +29,18 @@ pub fn read_metadata(path: &Path) -> Result<PhotoMetadata, CoreError> {
     let bytes_read = buf_reader.read(&mut header_buf).unwrap_or(0);
     header_buf.truncate(bytes_read);
 
+    // Motion photos (MVIMG / Samsung) carry an MP4 clip after the JPEG
+    let file_len = buf_reader.get_ref().metadata()?.len();
+    metadata.has_motion = super::motion::locate_motion_video(&mut buf_reader, &header_buf, file_len)
+        .ok()
+        .flatten()
+        .is_some();
+    metadata.is_portrait = super::depth::is_portrait_header(&header_buf);
+    let color = super::color::read_color_info(&header_buf);
+    metadata.color_profile = color.profile;
+    metadata.bit_depth = color.bit_depth;
+    metadata.chroma_subsampling = color.subsampling;
+
     // Parse EXIF from memory buffer
     let exif_reader = Reader::new();
     let exif = match exif_reader.read_from_container(&mut Cursor::new(&header_buf)) {
@@ -68,14 +91,16 @@ pub fn read_metadata(path: &Path) -> Result<PhotoMetadata, CoreError> {
         metadata.model = Some(field.display_value().with_unit(&exif).to_string());
     }
     if let Some(field) = exif.get_field(Tag::DateTimeOriginal, In::PRIMARY) {
-        metadata.date_taken = Some(field.display_value().with_unit(&exif).to_string());
+        let raw = field.display_value().with_unit(&exif).to_string();
+        metadata.taken_at = super::date::parse_date_taken(&raw);
+        metadata.date_taken = Some(raw);
     }
 
     // Exposure Parameters
     if let Some(field) = exif.get_field(Tag::PhotographicSensitivity, In::PRIMARY) {
         match &field.value {
             Value::Short(v) => metadata.iso = v.first().map(|&x| x as u32),
-            Value::Long(v) => metadata.iso = v.first().map(|&x| x as u32),
+            Value::Long(v) => metadata.iso = v.first().copied(),
             _ => {}
         }
     }
@@ -95,15 +120,40 @@ pub fn read_metadata(path: &Path) -> Result<PhotoMetadata, CoreError> {
         }
     }
 
+    // No embedded profile, but EXIF declares sRGB (1; 0xFFFF is "uncalibrated")
+    if metadata.color_profile.is_none() {
+        if let Some(field) = exif.get_field(Tag::ColorSpace, In::PRIMARY) {
+            if field.value.get_uint(0) == Some(1) {
+                metadata.color_profile = Some("sRGB".to_string());
+            }
+        }
+    }
+
+    // A scan's EXIF date is the scan date; when it was taken is set by hand
+    metadata.is_scan = super::scan::looks_scanned(&metadata);
+    if metadata.is_scan {
+        metadata.taken_at = None;
+    }
+
     // GPS
     metadata.lat = get_gps_coord(&exif, Tag::GPSLatitude, Tag::GPSLatitudeRef);
     metadata.lon = get_gps_coord(&exif, Tag::GPSLongitude, Tag::GPSLongitudeRef);
+    super::astro::apply_astronomy(&mut metadata);
+
+    // DNG: IFD0 is usually a reduced preview, real dimensions live in the raw SubIFD
+    if let Some(dng) = super::dng::parse_dng(&header_buf) {
+        metadata.is_proraw = dng.is_proraw;
+        if dng.width > 0 && dng.height > 0 {
+            metadata.width = dng.width;
+            metadata.height = dng.height;
+        }
+    }
 
     Ok(metadata)
 }
 
 /// Reads the 'DateTimeOriginal' from EXIF metadata.
-/// - Returns Err(CoreError::Io) if the file cannot be opened.
+/// - Returns Err(CoreError::NotFound / PermissionDenied / Io) if the file cannot be opened.
 /// - Returns Ok(None) if EXIF is missing or corrupted.
 pub fn read_date_taken(path: &Path) -> Result<Option<String>, CoreError> {
     let file = File::open(path)?;
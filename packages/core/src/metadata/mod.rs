@@ -1,2 +1,18 @@
 pub mod exif;
+pub mod motion;
+pub mod depth;
+pub mod dng;
+pub mod astro;
+pub mod date;
+mod color;
+pub mod scan;
+pub mod video;
+pub(crate) mod takeout;
+mod xmp;
 pub use exif::{read_metadata, read_date_taken};
+pub use motion::{find_motion_video_offset, extract_motion_video};
+pub use depth::extract_depth_map;
+pub use dng::{DngInfo, read_dng_info};
+pub use date::{parse_date_taken, truncate_date};
+pub use scan::looks_scanned;
+pub use video::{read_video_metadata, extract_video_still};
@@ -0,0 +1,230 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use crate::error::CoreError;
+use super::xmp::{attr_u64, container_item_offset, container_items, contains};
+
+/// Header size inspected for XMP motion photo hints (same window as EXIF parsing)
+const HEADER_SIZE: usize = 256 * 1024;
+/// Largest plausible size of an MP4 `ftyp` box
+const MAX_FTYP_SIZE: u32 = 256;
+
+/// Locates the MP4 clip embedded after the primary JPEG of a motion photo
+/// (Google MVIMG / Motion Photo, Samsung Motion Photo).
+///
+/// Returns the byte offset where the clip starts, or `Ok(None)` for ordinary photos.
+pub fn find_motion_video_offset(path: &Path) -> Result<Option<u64>, CoreError> {
+    let file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+    let mut reader = BufReader::with_capacity(64 * 1024, file);
+
+    let mut header = vec![0u8; HEADER_SIZE];
+    let bytes_read = reader.read(&mut header)?;
+    header.truncate(bytes_read);
+
+    Ok(locate_motion_video(&mut reader, &header, file_len)?)
+}
+
+/// Copies the embedded clip of a motion photo to `dest`.
+/// Returns `Ok(None)` if the photo has no embedded video.
+pub fn extract_motion_video(path: &Path, dest: &Path) -> Result<Option<PathBuf>, CoreError> {
+    let offset = match find_motion_video_offset(path)? {
+        Some(offset) => offset,
+        None => return Ok(None),
+    };
+
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut out = File::create(dest)?;
+    std::io::copy(&mut file, &mut out)?;
+
+    Ok(Some(dest.to_path_buf()))
+}
+
+/// Finds the clip offset given the already-read file header.
+/// Only touches the rest of the file when the header or trailer hints at a motion photo,
+/// so ordinary JPEGs cost a single extra 4-byte read.
+pub(crate) fn locate_motion_video<R: Read + Seek>(
+    reader: &mut R,
+    header: &[u8],
+    file_len: u64,
+) -> std::io::Result<Option<u64>> {
+    if !header.starts_with(&[0xFF, 0xD8]) {
+        return Ok(None);
+    }
+
+    // 1. Google MVIMG: GCamera:MicroVideoOffset is the clip length counted from the end
+    if let Some(from_end) = attr_u64(header, b"MicroVideoOffset") {
+        if let Some(offset) = file_len.checked_sub(from_end) {
+            if is_ftyp_at(reader, offset)? {
+                return Ok(Some(offset));
+            }
+        }
+    }
+
+    // 2. Motion Photo v1: Container directory item with Semantic="MotionPhoto"
+    let items = container_items(header);
+    if let Some(index) = items.iter().position(|item| item.semantic == "MotionPhoto") {
+        if let Some(offset) = container_item_offset(&items, index, file_len) {
+            if is_ftyp_at(reader, offset)? {
+                return Ok(Some(offset));
+            }
+        }
+    }
+
+    // 3. No usable offset metadata - scan for the MP4 header, but only if something hints at a clip
+    let hinted = contains(header, b"MotionPhoto") || contains(header, b"MicroVideo") || has_samsung_trailer(reader, file_len)?;
+    if hinted {
+        return scan_for_ftyp(reader, file_len);
+    }
+
+    Ok(None)
+}
+
+/// Samsung appends an "SEFT" trailer after the embedded clip.
+fn has_samsung_trailer<R: Read + Seek>(reader: &mut R, file_len: u64) -> std::io::Result<bool> {
+    if file_len < 8 {
+        return Ok(false);
+    }
+    let mut magic = [0u8; 4];
+    reader.seek(SeekFrom::Start(file_len - 4))?;
+    reader.read_exact(&mut magic)?;
+    Ok(&magic == b"SEFT")
+}
+
+/// Checks that an MP4 `ftyp` box starts at `offset`.
+fn is_ftyp_at<R: Read + Seek>(reader: &mut R, offset: u64) -> std::io::Result<bool> {
+    let mut box_header = [0u8; 8];
+    reader.seek(SeekFrom::Start(offset))?;
+    if reader.read_exact(&mut box_header).is_err() {
+        return Ok(false);
+    }
+    Ok(is_ftyp_box(&box_header))
+}
+
+fn is_ftyp_box(box_header: &[u8]) -> bool {
+    let size = u32::from_be_bytes([box_header[0], box_header[1], box_header[2], box_header[3]]);
+    &box_header[4..8] == b"ftyp" && (8..=MAX_FTYP_SIZE).contains(&size)
+}
+
+/// Scans the file for the first plausible `ftyp` box after the JPEG start.
+fn scan_for_ftyp<R: Read + Seek>(reader: &mut R, file_len: u64) -> std::io::Result<Option<u64>> {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut pos = 2u64; // Skip SOI
+    reader.seek(SeekFrom::Start(pos))?;
+
+    while pos + 8 <= file_len {
+        let bytes_read = reader.read(&mut buf)?;
+        if bytes_read < 8 {
+            break;
+        }
+
+        for i in 0..=bytes_read - 8 {
+            if is_ftyp_box(&buf[i..i + 8]) {
+                return Ok(Some(pos + i as u64));
+            }
+        }
+
+        // Overlap chunks so a box header split across reads is still found
+        pos += (bytes_read - 7) as u64;
+        reader.seek(SeekFrom::Start(pos))?;
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn fake_mp4() -> Vec<u8> {
+        let mut mp4 = Vec::new();
+        mp4.extend_from_slice(&24u32.to_be_bytes());
+        mp4.extend_from_slice(b"ftypmp42");
+        mp4.extend_from_slice(&[0u8; 12]);
+        mp4.extend_from_slice(b"moov-and-mdat-payload");
+        mp4
+    }
+
+    fn fake_jpeg(xmp: &str) -> Vec<u8> {
+        let mut jpeg = vec![0xFF, 0xD8];
+        jpeg.extend_from_slice(xmp.as_bytes());
+        jpeg.extend_from_slice(&[0x12, 0x34, 0x56, 0xFF, 0xD9]);
+        jpeg
+    }
+
+    fn locate(bytes: &[u8]) -> Option<u64> {
+        let header = &bytes[..bytes.len().min(HEADER_SIZE)];
+        locate_motion_video(&mut Cursor::new(bytes), header, bytes.len() as u64).unwrap()
+    }
+
+    #[test]
+    fn test_micro_video_offset() {
+        let mp4 = fake_mp4();
+        let mut file = fake_jpeg(&format!(
+            "<x:xmpmeta GCamera:MicroVideo=\"1\" GCamera:MicroVideoOffset=\"{}\"/>",
+            mp4.len()
+        ));
+        let jpeg_len = file.len() as u64;
+        file.extend_from_slice(&mp4);
+
+        assert_eq!(locate(&file), Some(jpeg_len));
+    }
+
+    #[test]
+    fn test_container_directory_length() {
+        let mp4 = fake_mp4();
+        let mut file = fake_jpeg(&format!(
+            "<rdf:li><Container:Item Item:Mime=\"image/jpeg\" Item:Semantic=\"Primary\" Item:Length=\"0\"/></rdf:li>\
+             <rdf:li><Container:Item Item:Mime=\"video/mp4\" Item:Semantic=\"MotionPhoto\" Item:Length=\"{}\"/></rdf:li>",
+            mp4.len()
+        ));
+        let jpeg_len = file.len() as u64;
+        file.extend_from_slice(&mp4);
+
+        assert_eq!(locate(&file), Some(jpeg_len));
+    }
+
+    #[test]
+    fn test_samsung_trailer_scan() {
+        let mut file = fake_jpeg("");
+        file.extend_from_slice(b"MotionPhoto_Data");
+        let video_start = file.len() as u64;
+        file.extend_from_slice(&fake_mp4());
+        file.extend_from_slice(b"\0\0\0\0SEFT");
+
+        assert_eq!(locate(&file), Some(video_start));
+    }
+
+    #[test]
+    fn test_plain_jpeg_has_no_motion() {
+        let file = fake_jpeg("<x:xmpmeta/>");
+        assert_eq!(locate(&file), None);
+
+        // An unhinted ftyp-looking payload must not trigger a full scan
+        let mut file = fake_jpeg("");
+        file.extend_from_slice(&fake_mp4());
+        assert_eq!(locate(&file), None);
+    }
+
+    #[test]
+    fn test_extract_motion_video() {
+        let temp_dir = std::env::temp_dir().join("fotos_motion_test");
+        if temp_dir.exists() { std::fs::remove_dir_all(&temp_dir).unwrap(); }
+        std::fs::create_dir_all(&temp_dir).unwrap();
+
+        let mp4 = fake_mp4();
+        let mut bytes = fake_jpeg(&format!("GCamera:MicroVideoOffset=\"{}\"", mp4.len()));
+        bytes.extend_from_slice(&mp4);
+        let src = temp_dir.join("MVIMG_0001.jpg");
+        std::fs::write(&src, &bytes).unwrap();
+
+        let dest = temp_dir.join("clip.mp4");
+        let extracted = extract_motion_video(&src, &dest).unwrap();
+        assert_eq!(extracted, Some(dest.clone()));
+        assert_eq!(std::fs::read(&dest).unwrap(), mp4);
+
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+    }
+}
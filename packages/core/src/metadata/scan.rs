@@ -0,0 +1,45 @@
+use crate::types::PhotoMetadata;
+
+/// Lower-case fragments of scanner makes and models (and of the scanning
+/// software some drivers write there). "scan" covers Coolscan, CanoScan,
+/// "EPSON Scan", VueScan and plain "Scanner".
+const SCANNER_NAMES: &[&str] = &[
+    "scan", "perfection", "opticfilm", "plustek", "reflecta", "pacific image",
+    "flextight", "imacon", "noritsu", "frontier", "pakon", "silverfast",
+];
+
+/// Whether a photo looks like scanner output rather than a camera capture:
+/// a scanner make or model and none of the exposure data a camera records.
+/// The EXIF date of a scan is when it was scanned, not when it was taken.
+pub fn looks_scanned(metadata: &PhotoMetadata) -> bool {
+    let names = [metadata.make.as_deref(), metadata.model.as_deref()];
+    let scanner = names
+        .into_iter()
+        .flatten()
+        .map(str::to_lowercase)
+        .any(|name| SCANNER_NAMES.iter().any(|fragment| name.contains(fragment)));
+    scanner && metadata.iso.is_none() && metadata.f_number.is_none() && metadata.exposure_time.is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_looks_scanned() {
+        let device = |make: &str, model: &str| PhotoMetadata {
+            make: Some(make.to_string()),
+            model: Some(model.to_string()),
+            ..Default::default()
+        };
+        assert!(looks_scanned(&device("Nikon", "LS-50 ED / Coolscan V ED")));
+        assert!(looks_scanned(&device("EPSON", "Perfection V600")));
+        assert!(looks_scanned(&device("Plustek", "OpticFilm 8200i")));
+        assert!(!looks_scanned(&device("NIKON CORPORATION", "NIKON D850")));
+        assert!(!looks_scanned(&PhotoMetadata::default()));
+
+        // Exposure data means a camera took it, whatever the model says
+        let copy_stand = PhotoMetadata { iso: Some(100), ..device("Canon", "CanoScan 9000F") };
+        assert!(!looks_scanned(&copy_stand));
+    }
+}
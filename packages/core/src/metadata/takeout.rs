@@ -0,0 +1,208 @@
+//! Google Takeout sidecars. Photos exported from Google Photos often carry
+//! no EXIF date or location (they were stripped on upload, or never there),
+//! and what Google knew is in a JSON file next to each photo instead:
+//!
+//! ```json
+//! { "title": "IMG_1234.jpg", "description": "Beach day",
+//!   "photoTakenTime": { "timestamp": "1577836800" },
+//!   "geoData": { "latitude": 43.7, "longitude": 7.26 } }
+//! ```
+//!
+//! The sidecar only fills in what the file itself lacks: the camera's own
+//! EXIF stays authoritative.
+
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::types::PhotoMetadata;
+
+/// Takeout truncates sidecar names to this many characters before ".json"
+const MAX_NAME_CHARS: usize = 46;
+/// Suffix of the sidecars of exports since 2024
+const SUPPLEMENTAL: &str = ".supplemental-metadata";
+/// Larger than any real sidecar; guards against reading a stray huge file
+const MAX_SIDECAR_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Sidecar {
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    photo_taken_time: Option<Timestamp>,
+    #[serde(default)]
+    geo_data: Option<GeoData>,
+    #[serde(default)]
+    geo_data_exif: Option<GeoData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Timestamp {
+    /// Seconds since 1970 in UTC, as a string
+    timestamp: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeoData {
+    latitude: f64,
+    longitude: f64,
+}
+
+impl GeoData {
+    /// Takeout writes 0, 0 for photos without a location
+    fn point(&self) -> Option<(f64, f64)> {
+        let valid = (-90.0..=90.0).contains(&self.latitude) && (-180.0..=180.0).contains(&self.longitude);
+        (valid && (self.latitude, self.longitude) != (0.0, 0.0)).then_some((self.latitude, self.longitude))
+    }
+}
+
+/// Fills in the capture time, location and description `metadata` lacks
+/// from the photo's Takeout sidecar, if it has one.
+pub(crate) fn merge_sidecar(path: &Path, metadata: &mut PhotoMetadata) {
+    let Some(sidecar) = find_sidecar(path).and_then(|file| read_sidecar(&file)) else { return };
+
+    // A scan's date is when it was scanned (or uploaded), not taken
+    if metadata.taken_at.is_none() && !metadata.is_scan {
+        let taken_at = sidecar.photo_taken_time.and_then(|t| t.timestamp.trim().parse::<i64>().ok()).filter(|&t| t > 0);
+        if let Some(taken_at) = taken_at {
+            // Takeout only has UTC, which is the closest to wall-clock time we get
+            metadata.taken_at = Some(taken_at);
+            metadata.date_taken = Some(super::date::exif_date(taken_at));
+        }
+    }
+    if metadata.lat.is_none() || metadata.lon.is_none() {
+        let point = sidecar.geo_data.as_ref().and_then(GeoData::point).or_else(|| sidecar.geo_data_exif.as_ref().and_then(GeoData::point));
+        if let Some((lat, lon)) = point {
+            metadata.lat = Some(lat);
+            metadata.lon = Some(lon);
+        }
+    }
+    if metadata.description.is_none() {
+        metadata.description = sidecar.description.map(|d| d.trim().to_string()).filter(|d| !d.is_empty());
+    }
+}
+
+/// Whether `path` looks like the sidecar of a photo ("IMG_1234.jpg.json"),
+/// rather than a file the import should report as unsupported.
+pub(crate) fn is_sidecar(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+        && path.file_stem().is_some_and(|stem| stem.to_string_lossy().contains('.'))
+}
+
+/// The sidecar of `path`. Takeout names it after the photo with ".json"
+/// (or ".supplemental-metadata.json") appended, truncated when long, with
+/// a duplicate's "(1)" moved to the end ("IMG(1).jpg" → "IMG.jpg(1).json").
+/// Edited copies ("IMG-edited.jpg") share the original's sidecar.
+fn find_sidecar(path: &Path) -> Option<PathBuf> {
+    let dir = path.parent()?;
+    let name = path.file_name()?.to_string_lossy().into_owned();
+    let (stem, ext) = match name.rfind('.') {
+        Some(dot) => (&name[..dot], &name[dot..]),
+        None => (name.as_str(), ""),
+    };
+    let (stem, counter) = split_counter(stem);
+    let stem = stem.strip_suffix("-edited").unwrap_or(stem);
+    let original = format!("{}{}", stem, ext);
+
+    let mut candidates = Vec::new();
+    for base in [original.clone(), format!("{}{}", original, SUPPLEMENTAL)] {
+        candidates.push(format!("{}{}.json", truncate(&base), counter));
+    }
+    // Early exports dropped the photo's extension
+    candidates.push(format!("{}{}.json", stem, counter));
+    candidates.into_iter().map(|candidate| dir.join(candidate)).find(|candidate| candidate.is_file())
+}
+
+/// Splits a duplicate's counter off its name: "IMG(1)" → ("IMG", "(1)")
+fn split_counter(stem: &str) -> (&str, &str) {
+    let counter = stem.strip_suffix(')').and_then(|rest| rest.rfind('(').map(|open| &stem[open..]));
+    match counter {
+        Some(counter) if counter.len() > 2 && counter[1..counter.len() - 1].bytes().all(|b| b.is_ascii_digit()) => {
+            (&stem[..stem.len() - counter.len()], counter)
+        }
+        _ => (stem, ""),
+    }
+}
+
+fn truncate(name: &str) -> &str {
+    match name.char_indices().nth(MAX_NAME_CHARS) {
+        Some((end, _)) => &name[..end],
+        None => name,
+    }
+}
+
+fn read_sidecar(file: &Path) -> Option<Sidecar> {
+    if std::fs::metadata(file).ok()?.len() > MAX_SIDECAR_BYTES {
+        return None;
+    }
+    serde_json::from_slice(&std::fs::read(file).ok()?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const SIDECAR: &str = r#"{
+        "title": "IMG_0001.jpg",
+        "description": " Beach day ",
+        "photoTakenTime": { "timestamp": "1577836800", "formatted": "Jan 1, 2020, 12:00:00 AM UTC" },
+        "geoData": { "latitude": 0.0, "longitude": 0.0, "altitude": 0.0 },
+        "geoDataExif": { "latitude": 43.7, "longitude": 7.26, "altitude": 12.0 }
+    }"#;
+
+    #[test]
+    fn test_sidecar_fills_missing_metadata() {
+        let dir = std::env::temp_dir().join("fotos_takeout_test");
+        if dir.exists() { fs::remove_dir_all(&dir).unwrap(); }
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("IMG_0001.jpg.json"), SIDECAR).unwrap();
+
+        let mut metadata = PhotoMetadata::default();
+        merge_sidecar(&dir.join("IMG_0001.jpg"), &mut metadata);
+        assert_eq!(metadata.taken_at, Some(1_577_836_800));
+        assert_eq!(metadata.date_taken.as_deref(), Some("2020:01:01 00:00:00"));
+        // geoData is empty, so geoDataExif is used
+        assert_eq!((metadata.lat, metadata.lon), (Some(43.7), Some(7.26)));
+        assert_eq!(metadata.description.as_deref(), Some("Beach day"));
+
+        // The file's own EXIF wins
+        let mut metadata = PhotoMetadata { taken_at: Some(5), lat: Some(1.0), lon: Some(2.0), ..Default::default() };
+        merge_sidecar(&dir.join("IMG_0001-edited.jpg"), &mut metadata);
+        assert_eq!((metadata.taken_at, metadata.lat, metadata.lon), (Some(5), Some(1.0), Some(2.0)));
+        assert_eq!(metadata.description.as_deref(), Some("Beach day"));
+
+        let mut metadata = PhotoMetadata::default();
+        merge_sidecar(&dir.join("IMG_0002.jpg"), &mut metadata);
+        assert_eq!(metadata.taken_at, None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_sidecar_names() {
+        let dir = std::env::temp_dir().join("fotos_takeout_names_test");
+        if dir.exists() { fs::remove_dir_all(&dir).unwrap(); }
+        fs::create_dir_all(&dir).unwrap();
+        let long = "PXL_20230415_100300123.PORTRAIT.ORIGINAL.jpg";
+        for sidecar in [
+            "IMG_0001.jpg(1).json",
+            "IMG_0002.jpg.supplemental-metadata.json",
+            "PXL_20230415_100300123.PORTRAIT.ORIGINAL.jpg.s.json",
+            "IMG_0003.json",
+        ] {
+            fs::write(dir.join(sidecar), "{}").unwrap();
+        }
+        let found = |photo: &str| find_sidecar(&dir.join(photo)).map(|f| f.file_name().unwrap().to_string_lossy().into_owned());
+        assert_eq!(found("IMG_0001(1).jpg").as_deref(), Some("IMG_0001.jpg(1).json"));
+        assert_eq!(found("IMG_0002.jpg").as_deref(), Some("IMG_0002.jpg.supplemental-metadata.json"));
+        assert_eq!(found(long).as_deref(), Some("PXL_20230415_100300123.PORTRAIT.ORIGINAL.jpg.s.json"));
+        assert_eq!(found("IMG_0003.jpg").as_deref(), Some("IMG_0003.json"));
+        assert_eq!(found("IMG_0001.jpg"), None);
+
+        assert!(is_sidecar(Path::new("IMG_0001.jpg(1).json")));
+        assert!(!is_sidecar(Path::new("metadata.json")));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
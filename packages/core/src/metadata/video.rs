@@ -0,0 +1,823 @@
+//! Videos imported alongside photos: MP4 and QuickTime (ISO base media
+//! boxes), Matroska (EBML) and AVI (RIFF). Only container headers are read -
+//! duration, frame size, codec, capture time and place - since core has no
+//! video decoder. Thumbnails come from a still the file carries: MP4 cover
+//! art, a Matroska cover attachment, or the first frame of a Motion JPEG
+//! stream. Other videos get a plain frame of their shape, which frontends
+//! mark as a video.
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::error::CoreError;
+use crate::types::{MediaType, PhotoMetadata};
+
+/// Largest header (MP4 `moov`, Matroska `Info`/`Tracks`/`Attachments`, AVI
+/// `hdrl`) read into memory
+const MAX_HEADER: u64 = 64 * 1024 * 1024;
+/// Largest still (cover art or Motion JPEG frame) read
+const MAX_STILL: u64 = 32 * 1024 * 1024;
+/// Seconds from 1904-01-01, the QuickTime epoch, to 1970-01-01
+const QUICKTIME_EPOCH: i64 = 2_082_844_800;
+/// Seconds from 2001-01-01, the Matroska epoch, to 1970-01-01
+const MATROSKA_EPOCH: i64 = 978_307_200;
+/// Top-level chunks of an AVI's `movi` list looked through for a first frame
+const MAX_AVI_CHUNKS: usize = 64;
+
+#[derive(Debug, Default, PartialEq)]
+struct VideoInfo {
+    duration_ms: Option<u64>,
+    width: u32,
+    height: u32,
+    /// EXIF-style orientation from an MP4 track's rotation matrix
+    orientation: u32,
+    codec: Option<String>,
+    /// "YYYY:MM:DD HH:MM:SS", local time where the file has it, else UTC
+    date_taken: Option<String>,
+    location: Option<(f64, f64)>,
+    make: Option<String>,
+    model: Option<String>,
+    still: Option<Still>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Still {
+    /// Cover art already read with the header
+    Bytes(Vec<u8>),
+    /// A Motion JPEG frame: offset and length in the file
+    At(u64, u64),
+}
+
+/// Reads a video's duration, codec, frame size, capture time and place.
+pub fn read_video_metadata(path: &Path) -> Result<PhotoMetadata, CoreError> {
+    let info = read_info(path)?;
+    let mut metadata = PhotoMetadata {
+        media_type: MediaType::Video,
+        duration_ms: info.duration_ms,
+        video_codec: info.codec,
+        width: info.width,
+        height: info.height,
+        orientation: info.orientation,
+        make: info.make,
+        model: info.model,
+        ..Default::default()
+    };
+    if let Some(raw) = info.date_taken {
+        metadata.taken_at = super::date::parse_date_taken(&raw);
+        metadata.date_taken = Some(raw);
+    }
+    if let Some((lat, lon)) = info.location {
+        metadata.lat = Some(lat);
+        metadata.lon = Some(lon);
+    }
+    Ok(metadata)
+}
+
+/// The encoded image (JPEG or PNG) of a still the video carries, for its
+/// thumbnail. Fails for videos without one.
+pub fn extract_video_still(path: &Path) -> Result<Vec<u8>, CoreError> {
+    match read_info(path)?.still {
+        Some(Still::Bytes(bytes)) => Ok(bytes),
+        Some(Still::At(offset, len)) => {
+            let mut file = File::open(path)?;
+            file.seek(SeekFrom::Start(offset))?;
+            let mut bytes = vec![0; len as usize];
+            file.read_exact(&mut bytes)?;
+            Ok(bytes)
+        }
+        None => Err(CoreError::NotFound("no still in video".to_string())),
+    }
+}
+
+fn read_info(path: &Path) -> Result<VideoInfo, CoreError> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut header = [0u8; 12];
+    let read = file.read(&mut header)?;
+    let header = &header[..read];
+    if header.len() == 12 && &header[0..4] == b"RIFF" && &header[8..12] == b"AVI " {
+        return Ok(read_avi(&mut file, len)?);
+    }
+    if header.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) {
+        return Ok(read_matroska(&mut file, len)?);
+    }
+    // QuickTime files may start with `moov`, `mdat` or padding instead of `ftyp`
+    if header.len() >= 8 && matches!(&header[4..8], b"ftyp" | b"moov" | b"mdat" | b"wide" | b"free" | b"skip") {
+        return Ok(read_mp4(&mut file, len)?);
+    }
+    Err(CoreError::InvalidInput(format!("Not a supported video: {}", path.display())))
+}
+
+pub(crate) fn media_type_name(media_type: MediaType) -> &'static str {
+    match media_type {
+        MediaType::Photo => "photo",
+        MediaType::Video => "video",
+    }
+}
+
+/// Unknown names read as photos, as rows from before videos were indexed do.
+pub(crate) fn media_type_from_name(name: &str) -> MediaType {
+    match name {
+        "video" => MediaType::Video,
+        _ => MediaType::Photo,
+    }
+}
+
+/// Common name of a codec given by MP4 sample entry, AVI FourCC or Matroska codec ID.
+fn codec_name(id: &str) -> String {
+    let name = match id.trim_end_matches(['\0', ' ']) {
+        "avc1" | "avc3" | "H264" | "h264" | "X264" | "x264" | "V_MPEG4/ISO/AVC" => "H.264",
+        "hvc1" | "hev1" | "HEVC" | "V_MPEGH/ISO/HEVC" => "HEVC",
+        "av01" | "AV01" | "V_AV1" => "AV1",
+        "vp09" | "VP90" | "V_VP9" => "VP9",
+        "vp08" | "VP80" | "V_VP8" => "VP8",
+        "jpeg" | "mjpa" | "mjpb" | "MJPG" | "mjpg" | "V_MJPEG" => "Motion JPEG",
+        "apch" | "apcn" | "apcs" | "apco" | "ap4h" | "ap4x" => "ProRes",
+        "mp4v" | "XVID" | "xvid" | "DIVX" | "divx" | "DX50" | "FMP4" | "V_MPEG4/ISO/ASP" | "V_MPEG4/ISO/SP" => "MPEG-4",
+        "mp2v" | "V_MPEG2" => "MPEG-2",
+        other => return other.to_string(),
+    };
+    name.to_string()
+}
+
+fn is_motion_jpeg(codec: &Option<String>) -> bool {
+    codec.as_deref() == Some("Motion JPEG")
+}
+
+/// An ISO 8601 date with its zone ("2023-04-15T10:03:00+0200") as an EXIF
+/// date in that zone's local time.
+fn iso_to_exif(iso: &str) -> Option<String> {
+    let iso = iso.trim();
+    let date = format!("{}:{}:{} {}", iso.get(0..4)?, iso.get(5..7)?, iso.get(8..10)?, iso.get(11..19)?);
+    super::date::parse_date_taken(&date).map(|_| date)
+}
+
+/// Latitude and longitude of an ISO 6709 point ("+37.3349-122.0090+010.000/").
+fn parse_iso6709(point: &str) -> Option<(f64, f64)> {
+    let point = point.trim().trim_end_matches('/');
+    let starts: Vec<usize> = point.char_indices().filter(|(_, c)| matches!(c, '+' | '-')).map(|(i, _)| i).collect();
+    if starts.len() < 2 || starts[0] != 0 {
+        return None;
+    }
+    let lat: f64 = point[starts[0]..starts[1]].parse().ok()?;
+    let lon: f64 = point[starts[1]..starts.get(2).copied().unwrap_or(point.len())].parse().ok()?;
+    ((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon)).then_some((lat, lon))
+}
+
+fn be_u16(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn be_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn be_u64(data: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(data.get(at..at + 8)?.try_into().ok()?))
+}
+
+fn le_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+// --- MP4 / QuickTime ---
+
+/// The boxes in `data`, as (type, payload)
+fn boxes(data: &[u8]) -> impl Iterator<Item = ([u8; 4], &[u8])> {
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+        let size = be_u32(data, pos)? as u64;
+        let kind: [u8; 4] = data.get(pos + 4..pos + 8)?.try_into().ok()?;
+        let (size, header) = match size {
+            0 => ((data.len() - pos) as u64, 8),
+            1 => (be_u64(data, pos + 8)?, 16),
+            size => (size, 8),
+        };
+        let end = pos.checked_add(usize::try_from(size).ok()?)?;
+        if size < header as u64 || end > data.len() {
+            return None;
+        }
+        let payload = &data[pos + header..end];
+        pos = end;
+        Some((kind, payload))
+    })
+}
+
+fn child<'a>(data: &'a [u8], kind: &[u8; 4]) -> Option<&'a [u8]> {
+    boxes(data).find(|(k, _)| k == kind).map(|(_, payload)| payload)
+}
+
+fn read_mp4(file: &mut File, len: u64) -> std::io::Result<VideoInfo> {
+    let mut pos = 0;
+    while pos + 8 <= len {
+        file.seek(SeekFrom::Start(pos))?;
+        let mut header = [0u8; 16];
+        let read = file.read(&mut header)?;
+        let (size, header_len) = match be_u32(&header, 0) {
+            Some(0) => (len - pos, 8),
+            Some(1) if read >= 16 => (be_u64(&header, 8).unwrap_or(0), 16),
+            Some(size) => (size as u64, 8),
+            None => break,
+        };
+        if size < header_len {
+            break;
+        }
+        if &header[4..8] == b"moov" {
+            if size > MAX_HEADER {
+                break;
+            }
+            let mut moov = vec![0; (size - header_len) as usize];
+            file.seek(SeekFrom::Start(pos + header_len))?;
+            file.read_exact(&mut moov)?;
+            return Ok(parse_moov(&moov));
+        }
+        pos += size;
+    }
+    Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "no moov box"))
+}
+
+fn parse_moov(moov: &[u8]) -> VideoInfo {
+    let mut info = VideoInfo::default();
+    for (kind, payload) in boxes(moov) {
+        match &kind {
+            b"mvhd" => parse_mvhd(payload, &mut info),
+            b"trak" if info.codec.is_none() => parse_trak(payload, &mut info),
+            b"udta" => parse_udta(payload, &mut info),
+            b"meta" => parse_meta(payload, &mut info),
+            _ => {}
+        }
+    }
+    info
+}
+
+fn parse_mvhd(mvhd: &[u8], info: &mut VideoInfo) {
+    let (created, timescale, duration) = match mvhd.first() {
+        Some(1) => (be_u64(mvhd, 4).map(|t| t as i64), be_u32(mvhd, 20), be_u64(mvhd, 24)),
+        _ => (be_u32(mvhd, 4).map(i64::from), be_u32(mvhd, 12), be_u32(mvhd, 16).map(u64::from)),
+    };
+    if let (Some(timescale), Some(duration)) = (timescale.filter(|&t| t > 0), duration) {
+        info.duration_ms = Some(duration.saturating_mul(1000) / timescale as u64);
+    }
+    // Cameras with an unset clock write 0
+    if let Some(secs) = created.map(|t| t - QUICKTIME_EPOCH).filter(|&secs| secs > 0) {
+        info.date_taken.get_or_insert_with(|| super::date::exif_date(secs));
+    }
+}
+
+/// Frame size, rotation, codec and (for Motion JPEG) first frame of a video track.
+fn parse_trak(trak: &[u8], info: &mut VideoInfo) {
+    let Some(mdia) = child(trak, b"mdia") else { return };
+    let handler = child(mdia, b"hdlr").and_then(|hdlr| hdlr.get(8..12));
+    if handler != Some(b"vide") {
+        return;
+    }
+    if let Some(tkhd) = child(trak, b"tkhd") {
+        let matrix = if tkhd.first() == Some(&1) { 52 } else { 40 };
+        let fixed = |at: usize| be_u32(tkhd, at).map(|v| v >> 16).unwrap_or(0);
+        info.width = fixed(matrix + 36);
+        info.height = fixed(matrix + 40);
+        let (a, b) = (be_u32(tkhd, matrix).map(|v| v as i32), be_u32(tkhd, matrix + 4).map(|v| v as i32));
+        info.orientation = match (a, b) {
+            (Some(0), Some(b)) if b > 0 => 6,
+            (Some(0), Some(b)) if b < 0 => 8,
+            (Some(a), _) if a < 0 => 3,
+            _ => 1,
+        };
+    }
+    let Some(stbl) = child(mdia, b"minf").and_then(|minf| child(minf, b"stbl")) else { return };
+    let Some(entry) = child(stbl, b"stsd").and_then(|stsd| stsd.get(8..)) else { return };
+    if let Some(format) = entry.get(4..8) {
+        info.codec = Some(codec_name(&String::from_utf8_lossy(format)));
+    }
+    if info.width == 0 {
+        info.width = be_u16(entry, 32).unwrap_or(0) as u32;
+        info.height = be_u16(entry, 34).unwrap_or(0) as u32;
+    }
+    if is_motion_jpeg(&info.codec) && info.still.is_none() {
+        let offset = match (child(stbl, b"stco"), child(stbl, b"co64")) {
+            (Some(stco), _) => be_u32(stco, 8).map(u64::from),
+            (None, Some(co64)) => be_u64(co64, 8),
+            _ => None,
+        };
+        let size = child(stbl, b"stsz").and_then(|stsz| match be_u32(stsz, 4) {
+            Some(0) => be_u32(stsz, 12),
+            size => size,
+        });
+        if let (Some(offset), Some(size)) = (offset, size.filter(|&s| s > 0 && s as u64 <= MAX_STILL)) {
+            info.still = Some(Still::At(offset, size as u64));
+        }
+    }
+}
+
+/// QuickTime user data: `©xyz` location, `©mak`/`©mod` camera, and an
+/// iTunes-style `meta` with cover art.
+fn parse_udta(udta: &[u8], info: &mut VideoInfo) {
+    for (kind, payload) in boxes(udta) {
+        // Text entries: 16-bit length and language, then the text
+        let text = || {
+            let len = be_u16(payload, 0)? as usize;
+            payload.get(4..4 + len).map(|t| String::from_utf8_lossy(t).trim().to_string())
+        };
+        match &kind {
+            b"\xA9xyz" => info.location = info.location.or_else(|| text().as_deref().and_then(parse_iso6709)),
+            b"\xA9mak" => info.make = info.make.take().or_else(text),
+            b"\xA9mod" => info.model = info.model.take().or_else(text),
+            b"meta" => parse_meta(payload, info),
+            _ => {}
+        }
+    }
+}
+
+/// Metadata items, by Apple's `keys` (creation date, location, camera) or
+/// iTunes atoms (`covr`).
+fn parse_meta(meta: &[u8], info: &mut VideoInfo) {
+    // A full box in MP4, a plain one in QuickTime
+    let meta = if meta.get(4..8) == Some(b"hdlr") { meta } else { meta.get(4..).unwrap_or_default() };
+    let mut keys = Vec::new();
+    if let Some(entries) = child(meta, b"keys").and_then(|k| k.get(8..)) {
+        keys.extend(boxes(entries).map(|(_, key)| String::from_utf8_lossy(key).into_owned()));
+    }
+    let Some(ilst) = child(meta, b"ilst") else { return };
+    for (kind, item) in boxes(ilst) {
+        let Some(data) = child(item, b"data").and_then(|d| d.get(8..)) else { continue };
+        let text = || String::from_utf8_lossy(data).trim().to_string();
+        let key = keys.get((u32::from_be_bytes(kind) as usize).wrapping_sub(1)).map(String::as_str);
+        match (&kind, key) {
+            (b"covr", _) if info.still.is_none() && (data.len() as u64) <= MAX_STILL => {
+                info.still = Some(Still::Bytes(data.to_vec()));
+            }
+            (_, Some("com.apple.quicktime.creationdate")) => {
+                // Local time beats the UTC of `mvhd`
+                if let Some(date) = iso_to_exif(&text()) {
+                    info.date_taken = Some(date);
+                }
+            }
+            (_, Some("com.apple.quicktime.location.ISO6709")) => info.location = parse_iso6709(&text()).or(info.location),
+            (_, Some("com.apple.quicktime.make")) => info.make = Some(text()),
+            (_, Some("com.apple.quicktime.model")) => info.model = Some(text()),
+            _ => {}
+        }
+    }
+}
+
+// --- Matroska ---
+
+const EBML_SEGMENT: u64 = 0x1853_8067;
+const EBML_SEEK_HEAD: u64 = 0x114D_9B74;
+const EBML_SEEK: u64 = 0x4DBB;
+const EBML_SEEK_ID: u64 = 0x53AB;
+const EBML_SEEK_POSITION: u64 = 0x53AC;
+const EBML_INFO: u64 = 0x1549_A966;
+const EBML_TIMESTAMP_SCALE: u64 = 0x2A_D7B1;
+const EBML_DURATION: u64 = 0x4489;
+const EBML_DATE_UTC: u64 = 0x4461;
+const EBML_TRACKS: u64 = 0x1654_AE6B;
+const EBML_TRACK_ENTRY: u64 = 0xAE;
+const EBML_TRACK_TYPE: u64 = 0x83;
+const EBML_CODEC_ID: u64 = 0x86;
+const EBML_VIDEO: u64 = 0xE0;
+const EBML_PIXEL_WIDTH: u64 = 0xB0;
+const EBML_PIXEL_HEIGHT: u64 = 0xBA;
+const EBML_ATTACHMENTS: u64 = 0x1941_A469;
+const EBML_ATTACHED_FILE: u64 = 0x61A7;
+const EBML_FILE_NAME: u64 = 0x466E;
+const EBML_FILE_MIME_TYPE: u64 = 0x4660;
+const EBML_FILE_DATA: u64 = 0x465C;
+const EBML_CLUSTER: u64 = 0x1F43_B675;
+
+/// An EBML variable-length integer at the start of `data` and its length.
+/// IDs keep their length marker, sizes don't. Unknown sizes (all ones) are `u64::MAX`.
+fn read_vint(data: &[u8], id: bool) -> Option<(u64, usize)> {
+    let first = *data.first()?;
+    let len = first.leading_zeros() as usize + 1;
+    if len > 8 || data.len() < len {
+        return None;
+    }
+    let mut value = if id { first as u64 } else { first as u64 & (0xFF >> len) };
+    for byte in &data[1..len] {
+        value = value << 8 | *byte as u64;
+    }
+    if !id && value == (1 << (7 * len)) - 1 {
+        value = u64::MAX;
+    }
+    Some((value, len))
+}
+
+/// The elements in `data`, as (id, body)
+fn elements(data: &[u8]) -> impl Iterator<Item = (u64, &[u8])> {
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+        let (id, id_len) = read_vint(data.get(pos..)?, true)?;
+        let (size, size_len) = read_vint(data.get(pos + id_len..)?, false)?;
+        let start = pos + id_len + size_len;
+        let end = if size == u64::MAX { data.len() } else { start.checked_add(usize::try_from(size).ok()?)? };
+        let body = data.get(start..end)?;
+        pos = end;
+        Some((id, body))
+    })
+}
+
+fn element(data: &[u8], id: u64) -> Option<&[u8]> {
+    elements(data).find(|(i, _)| *i == id).map(|(_, body)| body)
+}
+
+fn ebml_uint(body: &[u8]) -> u64 {
+    body.iter().take(8).fold(0, |value, byte| value << 8 | *byte as u64)
+}
+
+fn ebml_float(body: &[u8]) -> Option<f64> {
+    match body.len() {
+        4 => Some(f32::from_be_bytes(body.try_into().ok()?) as f64),
+        8 => Some(f64::from_be_bytes(body.try_into().ok()?)),
+        _ => None,
+    }
+}
+
+fn read_matroska(file: &mut File, len: u64) -> std::io::Result<VideoInfo> {
+    let invalid = || std::io::Error::new(std::io::ErrorKind::InvalidData, "not a Matroska file");
+    let mut info = VideoInfo::default();
+    // EBML header, then the segment whose children are walked
+    let (_, pos) = read_element_header(file, 0)?.ok_or_else(invalid)?;
+    let header_end = pos.1;
+    let Some((EBML_SEGMENT, (segment_start, _))) = read_element_header(file, header_end)? else { return Err(invalid()) };
+
+    let mut attachments_at = None;
+    let mut pos = segment_start;
+    while pos < len {
+        let Some((id, (start, end))) = read_element_header(file, pos)? else { break };
+        match id {
+            EBML_SEEK_HEAD | EBML_INFO | EBML_TRACKS | EBML_ATTACHMENTS if end != u64::MAX && end - start <= MAX_HEADER => {
+                let mut body = vec![0; (end - start) as usize];
+                file.seek(SeekFrom::Start(start))?;
+                file.read_exact(&mut body)?;
+                match id {
+                    EBML_SEEK_HEAD => attachments_at = seek_position(&body, EBML_ATTACHMENTS).map(|at| segment_start + at),
+                    EBML_INFO => parse_matroska_info(&body, &mut info),
+                    EBML_TRACKS => parse_matroska_tracks(&body, &mut info),
+                    _ => parse_matroska_attachments(&body, &mut info),
+                }
+            }
+            // Frames follow; cover art stored after them is found through the seek head
+            EBML_CLUSTER => match attachments_at.take() {
+                Some(at) if at > pos && info.still.is_none() => {
+                    pos = at;
+                    continue;
+                }
+                _ => break,
+            },
+            _ => {}
+        }
+        if end == u64::MAX {
+            break;
+        }
+        pos = end;
+    }
+    Ok(info)
+}
+
+/// ID at `pos` and the range of its body, `end` being `u64::MAX` for unknown sizes
+fn read_element_header(file: &mut File, pos: u64) -> std::io::Result<Option<(u64, (u64, u64))>> {
+    file.seek(SeekFrom::Start(pos))?;
+    let mut header = [0u8; 12];
+    let read = file.read(&mut header)?;
+    let header = &header[..read];
+    let Some((id, id_len)) = read_vint(header, true) else { return Ok(None) };
+    let Some((size, size_len)) = read_vint(&header[id_len..], false) else { return Ok(None) };
+    let start = pos + (id_len + size_len) as u64;
+    Ok(Some((id, (start, if size == u64::MAX { u64::MAX } else { start.saturating_add(size) }))))
+}
+
+/// Where the seek head says the element `id` is, relative to the segment's body
+fn seek_position(seek_head: &[u8], id: u64) -> Option<u64> {
+    elements(seek_head).filter(|(i, _)| *i == EBML_SEEK).find_map(|(_, seek)| {
+        let target = element(seek, EBML_SEEK_ID).and_then(|body| read_vint(body, true)).map(|(id, _)| id)?;
+        (target == id).then(|| element(seek, EBML_SEEK_POSITION).map(ebml_uint))?
+    })
+}
+
+fn parse_matroska_info(body: &[u8], info: &mut VideoInfo) {
+    let scale = element(body, EBML_TIMESTAMP_SCALE).map(ebml_uint).unwrap_or(1_000_000);
+    if let Some(duration) = element(body, EBML_DURATION).and_then(ebml_float) {
+        info.duration_ms = Some((duration * scale as f64 / 1_000_000.0).round() as u64);
+    }
+    if let Some(date) = element(body, EBML_DATE_UTC).filter(|d| d.len() == 8) {
+        let nanos = ebml_uint(date) as i64;
+        info.date_taken = Some(super::date::exif_date(MATROSKA_EPOCH + nanos.div_euclid(1_000_000_000)));
+    }
+}
+
+fn parse_matroska_tracks(body: &[u8], info: &mut VideoInfo) {
+    let video = elements(body)
+        .filter(|(id, _)| *id == EBML_TRACK_ENTRY)
+        .map(|(_, track)| track)
+        .find(|track| element(track, EBML_TRACK_TYPE).map(ebml_uint) == Some(1));
+    let Some(track) = video else { return };
+    info.codec = element(track, EBML_CODEC_ID).map(|id| codec_name(&String::from_utf8_lossy(id)));
+    if let Some(video) = element(track, EBML_VIDEO) {
+        info.width = element(video, EBML_PIXEL_WIDTH).map(ebml_uint).unwrap_or(0) as u32;
+        info.height = element(video, EBML_PIXEL_HEIGHT).map(ebml_uint).unwrap_or(0) as u32;
+    }
+}
+
+/// Picks an image attachment, preferring one named like cover art.
+fn parse_matroska_attachments(body: &[u8], info: &mut VideoInfo) {
+    let images: Vec<(String, &[u8])> = elements(body)
+        .filter(|(id, _)| *id == EBML_ATTACHED_FILE)
+        .filter_map(|(_, file)| {
+            let mime = element(file, EBML_FILE_MIME_TYPE)?;
+            if mime != b"image/jpeg" && mime != b"image/png" {
+                return None;
+            }
+            let name = element(file, EBML_FILE_NAME).map(|n| String::from_utf8_lossy(n).to_lowercase()).unwrap_or_default();
+            Some((name, element(file, EBML_FILE_DATA)?))
+        })
+        .collect();
+    let cover = images.iter().find(|(name, _)| name.starts_with("cover")).or(images.first());
+    if let Some((_, data)) = cover {
+        info.still = Some(Still::Bytes(data.to_vec()));
+    }
+}
+
+// --- AVI ---
+
+fn read_avi(file: &mut File, len: u64) -> std::io::Result<VideoInfo> {
+    let mut info = VideoInfo::default();
+    let mut pos = 12;
+    while pos + 12 <= len {
+        let mut header = [0u8; 12];
+        file.seek(SeekFrom::Start(pos))?;
+        file.read_exact(&mut header)?;
+        let size = le_u32(&header, 4).unwrap_or(0) as u64;
+        if &header[0..4] == b"LIST" && &header[8..12] == b"hdrl" && size <= MAX_HEADER {
+            let mut hdrl = vec![0; size.saturating_sub(4) as usize];
+            file.read_exact(&mut hdrl)?;
+            parse_avi_header(&hdrl, &mut info);
+        } else if &header[0..4] == b"LIST" && &header[8..12] == b"movi" {
+            if is_motion_jpeg(&info.codec) {
+                info.still = first_avi_frame(file, pos + 12, pos + 8 + size)?;
+            }
+            break;
+        }
+        pos += 8 + size + (size & 1);
+    }
+    Ok(info)
+}
+
+/// The chunks in `data` (RIFF, little-endian sizes, padded to even lengths), as (id, body)
+fn riff_chunks(data: &[u8]) -> impl Iterator<Item = ([u8; 4], &[u8])> {
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+        let id: [u8; 4] = data.get(pos..pos + 4)?.try_into().ok()?;
+        let size = le_u32(data, pos + 4)? as usize;
+        let body = data.get(pos + 8..pos + 8 + size)?;
+        pos += 8 + size + (size & 1);
+        Some((id, body))
+    })
+}
+
+fn parse_avi_header(hdrl: &[u8], info: &mut VideoInfo) {
+    for (id, body) in riff_chunks(hdrl) {
+        match &id {
+            b"avih" => {
+                let (frame_us, frames) = (le_u32(body, 0).unwrap_or(0) as u64, le_u32(body, 16).unwrap_or(0) as u64);
+                if frame_us > 0 && frames > 0 {
+                    info.duration_ms = Some(frame_us * frames / 1000);
+                }
+                info.width = le_u32(body, 32).unwrap_or(0);
+                info.height = le_u32(body, 36).unwrap_or(0);
+            }
+            b"LIST" if body.starts_with(b"strl") && info.codec.is_none() => {
+                let chunks: Vec<_> = riff_chunks(&body[4..]).collect();
+                let Some((_, strh)) = chunks.iter().find(|(id, _)| id == b"strh") else { continue };
+                if strh.get(0..4) != Some(b"vids") {
+                    continue;
+                }
+                // The bitmap header's compression is more telling than the stream's handler
+                let fourcc = chunks
+                    .iter()
+                    .find(|(id, _)| id == b"strf")
+                    .and_then(|(_, strf)| strf.get(16..20))
+                    .filter(|c| c.iter().any(|&b| b != 0))
+                    .or_else(|| strh.get(4..8));
+                info.codec = fourcc.map(|c| codec_name(&String::from_utf8_lossy(c)));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The first compressed video frame (`##dc`) of a Motion JPEG AVI's `movi` list.
+fn first_avi_frame(file: &mut File, start: u64, end: u64) -> std::io::Result<Option<Still>> {
+    let mut pos = start;
+    for _ in 0..MAX_AVI_CHUNKS {
+        if pos + 10 > end {
+            break;
+        }
+        let mut header = [0u8; 10];
+        file.seek(SeekFrom::Start(pos))?;
+        file.read_exact(&mut header)?;
+        let size = le_u32(&header, 4).unwrap_or(0) as u64;
+        if matches!(&header[2..4], b"dc" | b"db") && header[8..10] == [0xFF, 0xD8] && size <= MAX_STILL {
+            return Ok(Some(Still::At(pos + 8, size)));
+        }
+        pos += 8 + size + (size & 1);
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn mp4_box(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut out = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(payload);
+        out
+    }
+
+    fn tiny_jpeg() -> Vec<u8> {
+        let mut bytes = Vec::new();
+        image::RgbImage::from_pixel(16, 8, image::Rgb([200, 30, 30]))
+            .write_to(&mut std::io::Cursor::new(&mut bytes), image::ImageFormat::Jpeg)
+            .unwrap();
+        bytes
+    }
+
+    fn write_temp(name: &str, bytes: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("fotos_video_test_{}", name));
+        fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    /// A QuickTime-style Motion JPEG file, portrait, with Apple metadata keys
+    fn mjpeg_mov(frame: &[u8]) -> Vec<u8> {
+        let ftyp = mp4_box(b"ftyp", b"qt  \0\0\0\0qt  ");
+        let mut mvhd = vec![0u8; 100];
+        mvhd[4..8].copy_from_slice(&((1_700_000_000 + QUICKTIME_EPOCH) as u32).to_be_bytes());
+        mvhd[12..16].copy_from_slice(&600u32.to_be_bytes());
+        mvhd[16..20].copy_from_slice(&1500u32.to_be_bytes());
+
+        let mut tkhd = vec![0u8; 84];
+        // Rotated 90 degrees: a = 0, b = 1.0, c = -1.0
+        tkhd[44..48].copy_from_slice(&0x0001_0000u32.to_be_bytes());
+        tkhd[48..52].copy_from_slice(&(-0x0001_0000i32).to_be_bytes());
+        tkhd[76..80].copy_from_slice(&(1920u32 << 16).to_be_bytes());
+        tkhd[80..84].copy_from_slice(&(1080u32 << 16).to_be_bytes());
+        let mut hdlr = vec![0u8; 24];
+        hdlr[8..12].copy_from_slice(b"vide");
+        let mut entry = vec![0u8; 78];
+        entry[0..4].copy_from_slice(&78u32.to_be_bytes());
+        entry[4..8].copy_from_slice(b"jpeg");
+        let stsd = [&[0, 0, 0, 0, 0, 0, 0, 1][..], &entry].concat();
+        let stsz = [&[0u8; 4][..], &(frame.len() as u32).to_be_bytes(), &1u32.to_be_bytes()].concat();
+
+        let keys = [
+            &[0u8, 0, 0, 0, 0, 0, 0, 2][..],
+            &mp4_box(b"mdta", b"com.apple.quicktime.creationdate"),
+            &mp4_box(b"mdta", b"com.apple.quicktime.location.ISO6709"),
+        ]
+        .concat();
+        let item = |index: u32, value: &[u8]| {
+            mp4_box(&index.to_be_bytes(), &mp4_box(b"data", &[&[0, 0, 0, 1, 0, 0, 0, 0][..], value].concat()))
+        };
+        let ilst = [item(1, b"2023-11-14T23:13:20+0100"), item(2, b"+48.8584+002.2945+035.000/")].concat();
+        let meta = [mp4_box(b"hdlr", &[0u8; 24]), mp4_box(b"keys", &keys), mp4_box(b"ilst", &ilst)].concat();
+
+        // The frame's offset depends on the moov size: build it with a placeholder first
+        let build = |offset: u32| {
+            let stco = [&[0u8; 4][..], &1u32.to_be_bytes(), &offset.to_be_bytes()].concat();
+            let stbl = [mp4_box(b"stsd", &stsd), mp4_box(b"stsz", &stsz), mp4_box(b"stco", &stco)].concat();
+            let mdia = [mp4_box(b"hdlr", &hdlr), mp4_box(b"minf", &mp4_box(b"stbl", &stbl))].concat();
+            let trak = [mp4_box(b"tkhd", &tkhd), mp4_box(b"mdia", &mdia)].concat();
+            mp4_box(b"moov", &[mp4_box(b"mvhd", &mvhd), mp4_box(b"trak", &trak), mp4_box(b"meta", &meta)].concat())
+        };
+        let offset = (ftyp.len() + build(0).len() + 8) as u32;
+        [ftyp, build(offset), mp4_box(b"mdat", frame)].concat()
+    }
+
+    #[test]
+    fn test_mp4_metadata_and_motion_jpeg_still() {
+        let frame = tiny_jpeg();
+        let path = write_temp("clip.mov", &mjpeg_mov(&frame));
+
+        let metadata = read_video_metadata(&path).unwrap();
+        assert_eq!(metadata.media_type, MediaType::Video);
+        assert_eq!((metadata.duration_ms, metadata.video_codec.as_deref()), (Some(2500), Some("Motion JPEG")));
+        assert_eq!((metadata.width, metadata.height, metadata.orientation), (1920, 1080, 6));
+        // Local time from Apple's creation date, not the UTC of mvhd
+        assert_eq!(metadata.date_taken.as_deref(), Some("2023:11:14 23:13:20"));
+        assert_eq!((metadata.lat, metadata.lon), (Some(48.8584), Some(2.2945)));
+        assert_eq!(extract_video_still(&path).unwrap(), frame);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    fn ebml(id: u64, body: &[u8]) -> Vec<u8> {
+        let id_bytes = id.to_be_bytes();
+        let mut out: Vec<u8> = id_bytes.iter().copied().skip_while(|&b| b == 0).collect();
+        // Sizes as 8-byte vints: marker byte, then seven bytes of length
+        out.push(0x01);
+        out.extend_from_slice(&(body.len() as u64).to_be_bytes()[1..]);
+        out.extend_from_slice(body);
+        out
+    }
+
+    #[test]
+    fn test_matroska_metadata_and_cover() {
+        let cover = tiny_jpeg();
+        let info = [
+            ebml(EBML_TIMESTAMP_SCALE, &[0x0F, 0x42, 0x40]),
+            ebml(EBML_DURATION, &12_345.0f64.to_be_bytes()),
+            ebml(EBML_DATE_UTC, &(700_000_000i64 * 1_000_000_000).to_be_bytes()),
+        ]
+        .concat();
+        let audio = [ebml(EBML_TRACK_TYPE, &[2]), ebml(EBML_CODEC_ID, b"A_OPUS")].concat();
+        let video = [
+            ebml(EBML_TRACK_TYPE, &[1]),
+            ebml(EBML_CODEC_ID, b"V_MPEGH/ISO/HEVC"),
+            ebml(EBML_VIDEO, &[ebml(EBML_PIXEL_WIDTH, &[0x0F, 0x00]), ebml(EBML_PIXEL_HEIGHT, &[0x08, 0x70])].concat()),
+        ]
+        .concat();
+        let tracks = [ebml(EBML_TRACK_ENTRY, &audio), ebml(EBML_TRACK_ENTRY, &video)].concat();
+        let attachment = |name: &[u8], mime: &[u8], data: &[u8]| {
+            ebml(EBML_ATTACHED_FILE, &[ebml(EBML_FILE_NAME, name), ebml(EBML_FILE_MIME_TYPE, mime), ebml(EBML_FILE_DATA, data)].concat())
+        };
+        let attachments = [
+            attachment(b"subs.ttf", b"font/ttf", b"font"),
+            attachment(b"small_cover.png", b"image/png", b"small"),
+            attachment(b"cover.jpg", b"image/jpeg", &cover),
+        ]
+        .concat();
+        // Attachments after the frames, found through the seek head
+        let head_len = 4 + 8 + 3 * (2 + 8 + 4 + 8 + 4 + 8 + 8);
+        let before = [ebml(EBML_INFO, &info), ebml(EBML_TRACKS, &tracks), ebml(EBML_CLUSTER, &[0u8; 64])].concat();
+        let seek = |id: u64, at: u64| {
+            ebml(EBML_SEEK, &[ebml(EBML_SEEK_ID, &(id as u32).to_be_bytes()), ebml(EBML_SEEK_POSITION, &at.to_be_bytes())].concat())
+        };
+        let seek_head = ebml(EBML_SEEK_HEAD, &[
+            seek(EBML_INFO, head_len as u64),
+            seek(EBML_TRACKS, head_len as u64),
+            seek(EBML_ATTACHMENTS, (head_len + before.len()) as u64),
+        ].concat());
+        assert_eq!(seek_head.len(), head_len);
+        let segment = [seek_head, before, ebml(EBML_ATTACHMENTS, &attachments)].concat();
+        let file = [ebml(0x1A45_DFA3, &ebml(0x4282, b"matroska")), ebml(EBML_SEGMENT, &segment)].concat();
+        let path = write_temp("clip.mkv", &file);
+
+        let metadata = read_video_metadata(&path).unwrap();
+        assert_eq!((metadata.duration_ms, metadata.video_codec.as_deref()), (Some(12_345), Some("HEVC")));
+        assert_eq!((metadata.width, metadata.height), (3840, 2160));
+        assert_eq!(metadata.date_taken.as_deref(), Some("2023:03:08 20:26:40"));
+        assert_eq!(extract_video_still(&path).unwrap(), cover);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    fn riff(id: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut out = id.to_vec();
+        out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        out.extend_from_slice(body);
+        if body.len() % 2 == 1 {
+            out.push(0);
+        }
+        out
+    }
+
+    #[test]
+    fn test_avi_metadata_and_first_frame() {
+        let frame = tiny_jpeg();
+        let mut avih = vec![0u8; 56];
+        avih[0..4].copy_from_slice(&40_000u32.to_le_bytes());
+        avih[16..20].copy_from_slice(&250u32.to_le_bytes());
+        avih[32..36].copy_from_slice(&640u32.to_le_bytes());
+        avih[36..40].copy_from_slice(&480u32.to_le_bytes());
+        let mut strh = vec![0u8; 56];
+        strh[0..4].copy_from_slice(b"vids");
+        let mut strf = vec![0u8; 40];
+        strf[16..20].copy_from_slice(b"MJPG");
+        let strl = [&b"strl"[..], &riff(b"strh", &strh), &riff(b"strf", &strf)].concat();
+        let hdrl = [&b"hdrl"[..], &riff(b"avih", &avih), &riff(b"LIST", &strl)].concat();
+        let movi = [&b"movi"[..], &riff(b"01wb", b"audio"), &riff(b"00dc", &frame)].concat();
+        let body = [&b"AVI "[..], &riff(b"LIST", &hdrl), &riff(b"LIST", &movi)].concat();
+        let path = write_temp("clip.avi", &riff(b"RIFF", &body));
+
+        let metadata = read_video_metadata(&path).unwrap();
+        assert_eq!((metadata.duration_ms, metadata.video_codec.as_deref()), (Some(10_000), Some("Motion JPEG")));
+        assert_eq!((metadata.width, metadata.height), (640, 480));
+        assert_eq!(extract_video_still(&path).unwrap(), frame);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_video_helpers() {
+        assert_eq!(parse_iso6709("-33.8568+151.2153/"), Some((-33.8568, 151.2153)));
+        assert_eq!(parse_iso6709("+99.0+000.0/"), None);
+        assert_eq!(codec_name("avc1"), "H.264");
+        assert_eq!(codec_name("xyz1"), "xyz1");
+        assert!(read_video_metadata(&write_temp("notes.mp4", b"not a video at all")).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+//! Minimal XMP scanning over raw header bytes.
+//! We only need a handful of attributes, so no XML parser is pulled in.
+
+/// An item of a Google `Container:Directory` (Motion Photo, Dynamic Depth).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ContainerItem {
+    pub semantic: String,
+    pub mime: String,
+    pub length: u64,
+    pub padding: u64,
+}
+
+/// Lists container items in directory order. The first item is the primary image.
+pub(crate) fn container_items(header: &[u8]) -> Vec<ContainerItem> {
+    let mut items = Vec::new();
+    let mut from = 0;
+    while let Some(at) = find(header, b"<Container:Item", from) {
+        let end = match header[at..].iter().position(|&b| b == b'>') {
+            Some(p) => at + p,
+            None => break,
+        };
+        let element = &header[at..end];
+        items.push(ContainerItem {
+            semantic: attr_str(element, b"Semantic").unwrap_or_default(),
+            mime: attr_str(element, b"Mime").unwrap_or_default(),
+            length: attr_u64(element, b"Length").unwrap_or(0),
+            padding: attr_u64(element, b"Padding").unwrap_or(0),
+        });
+        from = end;
+    }
+    items
+}
+
+/// Byte offset of a secondary container item. Secondary items are appended
+/// after the primary image in directory order, so each one starts at
+/// `file_len` minus the lengths of itself and everything after it.
+pub(crate) fn container_item_offset(items: &[ContainerItem], index: usize, file_len: u64) -> Option<u64> {
+    if index == 0 || index >= items.len() {
+        return None;
+    }
+    let trailing: u64 = items[index..].iter().map(|item| item.length).sum();
+    file_len.checked_sub(trailing)
+}
+
+/// Reads a numeric attribute (`Prefix:Name="123"`) anywhere in `element`.
+pub(crate) fn attr_u64(element: &[u8], name: &[u8]) -> Option<u64> {
+    attr_str(element, name)?.parse().ok()
+}
+
+/// Reads a string attribute (`Prefix:Name="value"`) anywhere in `element`.
+pub(crate) fn attr_str(element: &[u8], name: &[u8]) -> Option<String> {
+    let mut search_from = 0;
+    while let Some(at) = find(element, name, search_from) {
+        search_from = at + name.len();
+        // Must be `:Name="` - skip longer names that merely end with `name`
+        if at == 0 || element[at - 1] != b':' || !element[search_from..].starts_with(b"=\"") {
+            continue;
+        }
+        let value_start = search_from + 2;
+        let value_len = element[value_start..].iter().position(|&b| b == b'"')?;
+        return String::from_utf8(element[value_start..value_start + value_len].to_vec()).ok();
+    }
+    None
+}
+
+pub(crate) fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    if from >= haystack.len() {
+        return None;
+    }
+    haystack[from..]
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|p| p + from)
+}
+
+pub(crate) fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    find(haystack, needle, 0).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_container_items_and_offsets() {
+        let xmp = b"<Container:Directory><rdf:Seq>\
+            <rdf:li><Container:Item Item:Mime=\"image/jpeg\" Item:Semantic=\"Primary\"/></rdf:li>\
+            <rdf:li><Container:Item Item:Semantic=\"Depth\" Item:Mime=\"image/jpeg\" Item:Length=\"300\"/></rdf:li>\
+            <rdf:li><Container:Item Item:Mime=\"video/mp4\" Item:Semantic=\"MotionPhoto\" Item:Length=\"1000\" Item:Padding=\"8\"/></rdf:li>\
+            </rdf:Seq></Container:Directory>";
+
+        let items = container_items(xmp);
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].semantic, "Primary");
+        assert_eq!(items[1].semantic, "Depth");
+        assert_eq!(items[2].padding, 8);
+
+        assert_eq!(container_item_offset(&items, 0, 5000), None);
+        assert_eq!(container_item_offset(&items, 1, 5000), Some(3700));
+        assert_eq!(container_item_offset(&items, 2, 5000), Some(4000));
+    }
+
+    #[test]
+    fn test_attr_requires_exact_name() {
+        let element = b"<x GCamera:MicroVideoOffset=\"42\" Item:Length=\"7\"";
+        assert_eq!(attr_u64(element, b"MicroVideoOffset"), Some(42));
+        assert_eq!(attr_u64(element, b"Offset"), None);
+        assert_eq!(attr_u64(element, b"Length"), Some(7));
+    }
+}
@@ -1,21 +1,286 @@
 use serde::{Serialize, Deserialize};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
 pub struct PhotoId {
     pub id: i64,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
 pub struct ImportResult {
     pub success: u32,
     pub failure: u32,
+    /// Photos skipped because they were deleted from the library before
+    pub skipped_deleted: u32,
+    /// Photos left out by the import's date range (`ImportOptions::taken_after`/`taken_before`)
+    pub skipped_filtered: u32,
+    /// Set for dry runs instead of importing
+    pub plan: Option<ImportPlan>,
+    /// Outcome per file extension (lowercase, "" for none)
+    pub by_extension: Vec<ImportBreakdown>,
+    /// Outcome per source folder, relative to the import root ("." for the root itself)
+    pub by_folder: Vec<ImportBreakdown>,
+    /// Why files failed, for the first `MAX_IMPORT_ERRORS` failures in the
+    /// order they happened; `failure` counts them all
+    pub errors: Vec<ImportError>,
+}
+
+/// Most failures an `ImportResult` details, so a folder of thousands of
+/// unreadable files doesn't make for a huge result
+pub const MAX_IMPORT_ERRORS: usize = 500;
+
+/// One file that failed to import.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct ImportError {
+    pub path: String,
+    pub stage: ImportStage,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct ImportBreakdown {
+    pub key: String,
+    pub success: u32,
+    pub failure: u32,
+}
+
+impl ImportResult {
+    /// Counts one imported (or failed) file in the totals and breakdowns.
+    pub fn record(&mut self, root: &std::path::Path, path: &std::path::Path, ok: bool) {
+        if ok {
+            self.success += 1;
+        } else {
+            self.failure += 1;
+        }
+
+        let extension = path.extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let folder = path.parent()
+            .and_then(|parent| parent.strip_prefix(root).ok())
+            .map(|rel| rel.to_string_lossy().to_string())
+            .filter(|rel| !rel.is_empty())
+            .unwrap_or_else(|| ".".to_string());
+
+        bump(&mut self.by_extension, extension, ok);
+        bump(&mut self.by_folder, folder, ok);
+    }
+
+    /// Counts a failed file, as `record`, keeping what went wrong.
+    pub fn record_failure(&mut self, root: &std::path::Path, path: &std::path::Path, stage: ImportStage, message: String) {
+        self.record(root, path, false);
+        if self.errors.len() < MAX_IMPORT_ERRORS {
+            self.errors.push(ImportError { path: crate::fs::encode_path(path), stage, message });
+        }
+    }
+}
+
+/// Breakdowns stay sorted by key so reports are stable.
+fn bump(entries: &mut Vec<ImportBreakdown>, key: String, ok: bool) {
+    let index = match entries.binary_search_by(|e| e.key.as_str().cmp(&key)) {
+        Ok(index) => index,
+        Err(index) => {
+            entries.insert(index, ImportBreakdown { key, ..Default::default() });
+            index
+        }
+    };
+    if ok {
+        entries[index].success += 1;
+    } else {
+        entries[index].failure += 1;
+    }
+}
+
+/// What an import would do, reported by a dry run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct ImportPlan {
+    /// Photos that would be added
+    pub new_files: u32,
+    /// Photos already in the index (same path, or same hash at another path)
+    pub duplicates: u32,
+    /// Photos deleted from the library before, skipped unless re-import is requested
+    pub previously_deleted: u32,
+    /// Files skipped because their format isn't supported
+    pub unsupported: u32,
+    /// Photos left out by the import's filters (`ImportOptions`)
+    pub filtered: u32,
+    /// Supported files that can't be read and would fail to import
+    pub unreadable: u32,
+    /// Total size of the new photos
+    pub new_bytes: u64,
+    /// Rough import duration estimate
+    pub estimated_seconds: u64,
+}
+
+/// Why a thumbnail or cache directory can't be written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, uniffi::Enum)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum CacheProblem {
+    /// A read-only volume, or one the app isn't allowed to write to
+    ReadOnly,
+    /// No space left, or over quota
+    Full,
+    /// Missing and can't be created, e.g. an unplugged drive
+    Unusable,
+}
+
+/// A cache directory thumbnails and previews can't be written to, found
+/// when the library is opened (see `check_cache_dir`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct CacheUnavailable {
+    pub path: String,
+    pub problem: CacheProblem,
+    /// The OS error, for bug reports
+    pub detail: String,
+}
+
+/// A step of importing one file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, uniffi::Enum)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ImportStage {
+    /// Reading EXIF and other metadata, usually bound by the source drive
+    Metadata,
+    /// Perceptual or file hash
+    Hash,
+    /// Decoding and resizing, usually bound by the CPU
+    Thumbnail,
+    /// Writing to the library database
+    Index,
+}
+
+/// Import speed over the last few seconds, reported periodically while an
+/// import runs so a slow source drive can be told from a busy CPU.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct ImportThroughput {
+    pub files_per_sec: f64,
+    pub mb_per_sec: f64,
+    /// Where the time went, slowest stage first
+    pub stage_breakdown: Vec<StageTime>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct StageTime {
+    pub stage: ImportStage,
+    /// Time spent in the stage per file, in milliseconds
+    pub ms_per_file: f64,
+    /// Share of the time spent on files, 0 to 1
+    pub share: f64,
+}
+
+/// Outcome of a thumbnail integrity spot-check.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct ThumbnailVerifyReport {
+    pub checked: u32,
+    /// Corrupt thumbnails that were regenerated
+    pub repaired: u32,
+    /// Corrupt thumbnails that could not be regenerated (removed from cache)
+    pub failed: u32,
+    /// Where the next spot-check should continue; 0 once the library wrapped around
+    pub next_offset: u32,
+}
+
+/// Outcome of a `migrate_legacy_thumbnails` batch.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct LegacyThumbnailReport {
+    /// Moved into the sharded layout
+    pub migrated: u32,
+    /// Deleted: already regenerated, corrupt, or of a photo no longer indexed
+    pub removed: u32,
+    /// Legacy files left for later batches
+    pub remaining: u32,
+}
+
+/// Outcome of `PhotoIndex::verify`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct LibraryVerifyReport {
+    pub checked: u32,
+    /// Photos whose original is gone, now flagged missing
+    pub missing: Vec<i64>,
+    /// Photos flagged missing before whose original is back
+    pub found: u32,
+    /// Cached thumbnails of no photo in the library or the trash
+    pub orphaned_thumbnails: u32,
+    pub orphaned_bytes: u64,
+    /// Orphaned thumbnails deleted
+    pub removed: u32,
+}
+
+/// Outcome of `PhotoIndex::audit_originals`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct OriginalsAuditReport {
+    pub checked: u32,
+    /// Originals whose content still matches their checksum
+    pub verified: u32,
+    /// Originals checksummed for the first time, or again because their
+    /// size or modification time changed (edited or replaced)
+    pub recorded: u32,
+    /// Photos whose original's content changed while its size and
+    /// modification time didn't, now flagged corrupt
+    pub corrupt: Vec<i64>,
+    /// Originals that couldn't be read, e.g. missing; `verify` deals with those
+    pub unreadable: u32,
+}
+
+/// How often a photo was opened, see `PhotoIndex::record_view`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct PhotoViews {
+    pub view_count: u32,
+    /// Unix time of the latest view, `None` if never viewed
+    pub last_viewed_at: Option<i64>,
+}
+
+/// A photo's auto-enhance edit, see `PhotoIndex::set_enhance`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct PhotoEnhance {
+    pub recipe: crate::image::AutoEnhance,
+    /// Whether the photo is shown enhanced; the recipe is kept either way
+    pub enabled: bool,
+}
+
+/// Outcome of the orientation maintenance pass, see `normalize_orientation`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct OrientationReport {
+    /// Photos with an EXIF orientation other than 1
+    pub checked: u32,
+    /// Cached thumbnails whose shape didn't match the photo's and were regenerated
+    pub thumbnails_fixed: u32,
+    /// Originals rewritten with upright pixels and orientation 1
+    pub rewritten: u32,
+    /// Photos whose pixels can't be rewritten (RAW, PNG, WebP)
+    pub skipped: u32,
+    pub failed: u32,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
 pub struct PhotoMetadata {
     pub make: Option<String>,
     pub model: Option<String>,
+    /// Capture date as found in EXIF, kept for reference
     pub date_taken: Option<String>,
+    /// `date_taken` parsed: local wall-clock seconds since 1970 (EXIF has no
+    /// zone). `None` means "date unknown" - missing, or a placeholder like
+    /// "0000:00:00 00:00:00".
+    pub taken_at: Option<i64>,
+    /// Set when `taken_at` was entered by hand rather than read from the
+    /// file: how much of it the user actually knows.
+    pub date_precision: Option<DatePrecision>,
     pub width: u32,
     pub height: u32,
     pub lat: Option<f64>,
@@ -24,16 +289,439 @@ pub struct PhotoMetadata {
     pub f_number: Option<f32>,
     pub exposure_time: Option<String>,
     pub orientation: u32,
+    /// User rotation on top of the EXIF orientation, degrees clockwise (0, 90, 180, 270).
+    /// Non-destructive: the original file is never touched.
+    pub rotation: u32,
+    /// Motion photo with an embedded video clip (Android MVIMG / Samsung)
+    pub has_motion: bool,
+    /// Portrait-mode photo carrying depth data (Dynamic Depth / GDepth XMP)
+    pub is_portrait: bool,
+    /// Apple ProRAW DNG
+    pub is_proraw: bool,
+    /// Film or print scan (scanner make, no exposure data). `date_taken` is
+    /// then the scan date and `taken_at` stays unset until dated by hand.
+    pub is_scan: bool,
+    /// Daylight at capture time: "day", "golden_hour", "blue_hour" or "night"
+    pub light: Option<String>,
+    /// Moon phase at capture time: 0.0 new, 0.5 full
+    pub moon_phase: Option<f64>,
+    /// Embedded ICC profile description ("Display P3"), or "sRGB" when only
+    /// EXIF declares it
+    pub color_profile: Option<String>,
+    /// Bits per channel of the original (JPEG and PNG)
+    pub bit_depth: Option<u32>,
+    /// JPEG chroma subsampling, e.g. "4:2:0"
+    pub chroma_subsampling: Option<String>,
+    #[serde(default)]
+    pub media_type: MediaType,
+    /// Length of a video
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+    /// Video codec by its common name ("H.264", "HEVC", "Motion JPEG")
+    #[serde(default)]
+    pub video_codec: Option<String>,
+    /// Caption, e.g. from a Google Takeout sidecar
+    #[serde(default)]
+    pub description: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
 pub struct PhotoInfo {
     pub id: PhotoId,
     pub path: String, // String is more portable for FFI
+    /// Perceptual hash, alike for photos that look alike (see `compute_hash`)
     pub hash: String,
+    /// BLAKE3 of the original's bytes, for exact duplicates and integrity
+    /// checks. `None` until the file has been read in full.
+    #[serde(default)]
+    pub checksum: Option<String>,
     pub metadata: PhotoMetadata,
     pub thumb_path: Option<String>,
+    /// Size of the original as of its import or last rescan, 0 if unknown
     pub file_size: u64,
     pub created_at: Option<i64>, // Unix timestamp
     pub modified_at: Option<i64>, // Unix timestamp
+    /// The original wasn't found by the last `PhotoIndex::verify`
+    #[serde(default)]
+    pub missing: bool,
+    /// The original's content changed without it being edited, found by
+    /// `PhotoIndex::audit_originals`
+    #[serde(default)]
+    pub corrupt: bool,
+}
+
+/// Aggregates over a selection of photos, for the UI footer.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct SelectionSummary {
+    pub count: u32,
+    /// Size of the originals as last indexed or rescanned (unknown sizes count as 0)
+    pub total_bytes: u64,
+    /// Earliest and latest capture time, "2022:03:04 10:00:00", including
+    /// dates set by hand
+    pub date_start: Option<String>,
+    pub date_end: Option<String>,
+    /// Distinct "Make Model" names, sorted
+    pub cameras: Vec<String>,
+    /// How many of the photos have GPS coordinates
+    pub geotagged: u32,
+}
+
+/// Which photos a view shows and in what order. Unset filters match everything.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct PhotoQuery {
+    pub place_id: Option<i64>,
+    pub weather_condition: Option<String>,
+    pub light: Option<String>,
+    /// Capture date range, inclusive, as `taken_at` values. Undated photos
+    /// never match a range.
+    pub taken_from: Option<i64>,
+    pub taken_to: Option<i64>,
+    /// "Make Model" as listed in `SelectionSummary::cameras`
+    pub camera: Option<String>,
+    pub has_gps: Option<bool>,
+    pub iso_min: Option<u32>,
+    pub iso_max: Option<u32>,
+    /// Shape as displayed: "landscape", "portrait" or "square"
+    pub orientation: Option<String>,
+    /// File extensions, case-insensitive ("jpg", "dng"); empty matches all
+    pub file_types: Vec<String>,
+    /// ICC profile description, case-insensitive ("display p3")
+    pub color_profile: Option<String>,
+    /// Only originals with at least this many bits per channel
+    pub min_bit_depth: Option<u32>,
+    /// "4:4:4", "4:2:2", "4:2:0"...
+    pub chroma_subsampling: Option<String>,
+    pub is_scan: Option<bool>,
+    /// Photos whose original the last `verify` couldn't find
+    pub missing: Option<bool>,
+    /// Photos whose original `audit_originals` found corrupted
+    #[uniffi(default = None)]
+    pub corrupt: Option<bool>,
+    /// Only photos, or only videos
+    #[uniffi(default = None)]
+    pub media_type: Option<MediaType>,
+    /// Photos opened at least once (`true`) or never looked at (`false`)
+    #[uniffi(default = None)]
+    pub viewed: Option<bool>,
+    pub sort: QuerySort,
+    /// Reverses `sort` (newest or largest first). Undated photos always come
+    /// last when sorting by date.
+    pub descending: bool,
+}
+
+/// Order of `PhotoIndex::query` results. Ties fall back to import order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, uniffi::Enum)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum QuerySort {
+    #[default]
+    DateTaken,
+    /// Size of the original as indexed
+    FileSize,
+    Path,
+    /// Times opened, then latest view; "most viewed" with `descending`
+    ViewCount,
+}
+
+/// File format of `PhotoIndex::export_manifest`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, uniffi::Enum)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ManifestFormat {
+    Csv,
+    Json,
+}
+
+/// Whether an indexed file is a still photo or a video.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, uniffi::Enum)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum MediaType {
+    #[default]
+    Photo,
+    Video,
+}
+
+/// How precisely a hand-entered capture date is known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, uniffi::Enum)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum DatePrecision {
+    Year,
+    Month,
+    Day,
+}
+
+/// A photo that looks like another, and how much: the Hamming distance
+/// between their perceptual hashes (0 = identical, 64 = opposite).
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct SimilarPhoto {
+    pub photo: PhotoInfo,
+    pub distance: u32,
+}
+
+/// Photos that look alike - bursts, re-edits, resized copies - for the
+/// duplicate report.
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct DuplicateGroup {
+    /// In import order
+    pub photos: Vec<PhotoInfo>,
+}
+
+/// One stretch of a timeline scrubber: the dated photos of a day, month or year.
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct TimelineBucket {
+    /// `taken_at` of the bucket's first second (midnight, the 1st, or January 1st)
+    pub start: i64,
+    pub count: u32,
+    /// The bucket's earliest photo, to show as its thumbnail
+    pub cover: PhotoInfo,
+}
+
+/// A year of the library at a glance, for the shareable "year in review".
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct YearInReview {
+    pub year: i32,
+    pub photo_count: u32,
+    /// Distinct days with at least one photo
+    pub days_with_photos: u32,
+    pub geotagged: u32,
+    /// Photos per month, January first
+    pub months: Vec<u32>,
+    /// Saved places by photos taken there, most first
+    pub top_places: Vec<ReviewCount>,
+    /// "Make Model" by photos, most first
+    pub top_cameras: Vec<ReviewCount>,
+    /// One photo from each of the busiest days, in date order
+    pub highlights: Vec<ReviewHighlight>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct ReviewCount {
+    pub name: String,
+    pub count: u32,
+}
+
+/// A busy day of the year and the photo picked to stand for it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct ReviewHighlight {
+    pub photo_id: i64,
+    pub taken_at: i64,
+    /// Photos taken that day
+    pub day_count: u32,
+}
+
+/// Undated photos that probably belong together - same folder, consecutive
+/// file numbers - with a date guessed from the dated photos either side.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct UndatedCluster {
+    /// Folder the photos are in, in index path form
+    pub folder: String,
+    /// In file-number order
+    pub photo_ids: Vec<i64>,
+    /// Proposed `taken_at`, when a dated neighbor exists
+    pub suggested_taken_at: Option<i64>,
+    /// How far the proposal can be trusted: the closer the neighbors'
+    /// dates, the finer
+    pub suggested_precision: Option<DatePrecision>,
+}
+
+/// Order of a page from `PhotoIndex::list_page`. Ties fall back to import order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, uniffi::Enum)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum PhotoSort {
+    /// Oldest capture date first; undated photos last
+    #[default]
+    DateTaken,
+    /// Newest capture date first; undated photos still last
+    DateTakenDescending,
+    /// Order the photos were imported in
+    Imported,
+    /// Largest pixel count first
+    Dimensions,
+}
+
+/// Photos either side of the one shown in the detail view, nearest first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct Neighbors {
+    pub before: Vec<PhotoInfo>,
+    pub after: Vec<PhotoInfo>,
+}
+
+/// Photo ids changed since a revision, see `PhotoIndex::changes_since`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct PhotoChanges {
+    /// Revision these changes bring the caller up to
+    pub revision: u64,
+    pub added: Vec<i64>,
+    pub updated: Vec<i64>,
+    pub removed: Vec<i64>,
+}
+
+/// A photo to add with `PhotoIndex::insert_batch`.
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct NewPhoto {
+    pub path: String,
+    pub hash: String,
+    pub metadata: PhotoMetadata,
+}
+
+/// Coordinates of a photo, used to undo a `set_location` batch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct PhotoLocation {
+    pub id: i64,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct GeoPoint {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+/// A user-named place ("Home", "Office").
+/// A circle of `radius_m` around `lat`/`lon`, or a polygon when `polygon` is non-empty
+/// (the center is then only used for display).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct Place {
+    pub id: i64,
+    pub name: String,
+    pub lat: f64,
+    pub lon: f64,
+    pub radius_m: f64,
+    pub polygon: Vec<GeoPoint>,
+}
+
+/// A user-curated collection of photos. Photos can be in any number of albums.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct PhotoAlbum {
+    pub id: i64,
+    pub name: String,
+    pub photo_count: u32,
+    /// First photo added, shown as the album cover
+    pub cover_photo_id: Option<i64>,
+    pub created_at: i64,
+}
+
+/// A photo in the trash, with when it was put there (unix seconds).
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct TrashedPhoto {
+    pub photo: PhotoInfo,
+    pub trashed_at: i64,
+}
+
+/// A photo handed over by a native picker, waiting in the intake queue until
+/// `drain_intake_queue` imports it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct IntakeItem {
+    /// The picker's identifier for the asset (PHAsset local identifier, content URI)
+    pub identifier: String,
+    /// Where the bridge copied the payload, in index path form
+    pub payload_path: String,
+    pub queued_at: i64,
+    /// Failed import attempts so far
+    pub attempts: u32,
+}
+
+/// An import of a folder, journaled so one interrupted by a crash or a
+/// cancel continues where it stopped the next time the folder is imported.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct ImportSession {
+    pub id: i64,
+    /// The imported folder, as passed to the import
+    pub root: String,
+    pub started_at: i64,
+    /// Files handled so far (imported, failed or skipped)
+    pub done: u32,
+    /// Files still to do
+    pub pending: u32,
+    /// Whether this continues an earlier, unfinished import of `root`
+    /// (always, for sessions listed as unfinished)
+    pub resumed: bool,
+}
+
+/// Outcome of `rescan`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct RescanReport {
+    /// Files not indexed at their path before, now imported
+    pub added: u32,
+    /// Files whose size or modification time changed, re-read in place
+    pub modified: u32,
+    /// Indexed files that are gone, now flagged missing
+    pub missing: u32,
+    pub unchanged: u32,
+    /// New or modified files that couldn't be read
+    pub failed: u32,
+}
+
+/// Historical weather at capture time, attached by an optional enrichment pass.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+#[cfg_attr(feature = "typescript", derive(schemars::JsonSchema))]
+pub struct Weather {
+    pub temperature_c: Option<f64>,
+    /// Normalized condition: clear, cloudy, fog, drizzle, rain, snow, thunderstorm
+    pub condition: String,
+    /// Which provider supplied the data (e.g. "open-meteo")
+    pub source: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_import_result_breakdowns() {
+        let root = Path::new("/import");
+        let mut result = ImportResult::default();
+        result.record(root, Path::new("/import/a.CR3"), false);
+        result.record(root, Path::new("/import/trip/b.cr3"), false);
+        result.record(root, Path::new("/import/trip/c.jpg"), true);
+
+        assert_eq!((result.success, result.failure), (1, 2));
+        assert_eq!(result.by_extension, vec![
+            ImportBreakdown { key: "cr3".to_string(), success: 0, failure: 2 },
+            ImportBreakdown { key: "jpg".to_string(), success: 1, failure: 0 },
+        ]);
+        assert_eq!(result.by_folder, vec![
+            ImportBreakdown { key: ".".to_string(), success: 0, failure: 1 },
+            ImportBreakdown { key: "trip".to_string(), success: 1, failure: 1 },
+        ]);
+    }
+
+    #[test]
+    fn test_import_errors_are_capped() {
+        let root = Path::new("/import");
+        let mut result = ImportResult::default();
+        for i in 0..MAX_IMPORT_ERRORS + 10 {
+            result.record_failure(root, Path::new(&format!("/import/{}.jpg", i)), ImportStage::Hash, "Unreadable".to_string());
+        }
+        assert_eq!(result.failure as usize, MAX_IMPORT_ERRORS + 10);
+        assert_eq!(result.errors.len(), MAX_IMPORT_ERRORS);
+        assert_eq!(result.errors[0], ImportError { path: "/import/0.jpg".to_string(), stage: ImportStage::Hash, message: "Unreadable".to_string() });
+    }
 }
@@ -0,0 +1,187 @@
+//! TypeScript declarations of the types core serializes to frontends, generated
+//! from their JSON schemas so field names can't drift from the Rust side.
+//! `cargo test --features typescript` checks the desktop's copy is current;
+//! set `UPDATE_TYPES=1` to rewrite it.
+
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::schema::{InstanceType, Schema, SchemaObject, SingleOrVec};
+use schemars::JsonSchema;
+
+use crate::config::{ImportOptions, PhotoCoreConfig};
+use crate::error::UserMessage;
+use crate::types::*;
+
+/// Declarations for every serialized core type, in name order.
+pub fn core_declarations() -> String {
+    let mut gen = SchemaSettings::draft07().into_generator();
+    add::<PhotoInfo>(&mut gen);
+    add::<PhotoQuery>(&mut gen);
+    add::<PhotoChanges>(&mut gen);
+    add::<PhotoLocation>(&mut gen);
+    add::<PhotoSort>(&mut gen);
+    add::<NewPhoto>(&mut gen);
+    add::<Neighbors>(&mut gen);
+    add::<SelectionSummary>(&mut gen);
+    add::<TimelineBucket>(&mut gen);
+    add::<UndatedCluster>(&mut gen);
+    add::<SimilarPhoto>(&mut gen);
+    add::<DuplicateGroup>(&mut gen);
+    add::<YearInReview>(&mut gen);
+    add::<Place>(&mut gen);
+    add::<PhotoAlbum>(&mut gen);
+    add::<TrashedPhoto>(&mut gen);
+    add::<Weather>(&mut gen);
+    add::<ImportResult>(&mut gen);
+    add::<ImportThroughput>(&mut gen);
+    add::<ImportSession>(&mut gen);
+    add::<RescanReport>(&mut gen);
+    add::<ThumbnailVerifyReport>(&mut gen);
+    add::<LegacyThumbnailReport>(&mut gen);
+    add::<OrientationReport>(&mut gen);
+    add::<LibraryVerifyReport>(&mut gen);
+    add::<OriginalsAuditReport>(&mut gen);
+    add::<PhotoViews>(&mut gen);
+    add::<PhotoEnhance>(&mut gen);
+    add::<ManifestFormat>(&mut gen);
+    add::<CacheUnavailable>(&mut gen);
+    add::<UserMessage>(&mut gen);
+    add::<PhotoCoreConfig>(&mut gen);
+    add::<ImportOptions>(&mut gen);
+    render(gen.definitions())
+}
+
+fn add<T: JsonSchema>(gen: &mut SchemaGenerator) {
+    gen.subschema_for::<T>();
+}
+
+/// Renders schema `definitions` as exported interfaces and type aliases.
+/// `Option` fields become optional (`name?: T`) as elsewhere in the frontend,
+/// though they arrive as `null`.
+pub fn render(definitions: &schemars::Map<String, Schema>) -> String {
+    let mut names: Vec<&String> = definitions.keys().collect();
+    names.sort();
+
+    let mut out = String::from("// Generated from the Rust types in packages/core - do not edit.\n");
+    for name in names {
+        let Schema::Object(schema) = &definitions[name] else { continue };
+        out.push('\n');
+        push_doc(&mut out, schema, "");
+        match &schema.object {
+            Some(object) if !object.properties.is_empty() => {
+                out.push_str(&format!("export interface {} {{\n", name));
+                for (field, property) in &object.properties {
+                    let Schema::Object(property) = property else { continue };
+                    push_doc(&mut out, property, "    ");
+                    let optional = if object.required.contains(field) { "" } else { "?" };
+                    out.push_str(&format!("    {}{}: {};\n", field, optional, type_of(property)));
+                }
+                out.push_str("}\n");
+            }
+            _ => out.push_str(&format!("export type {} = {};\n", name, type_of(schema))),
+        }
+    }
+    out
+}
+
+fn push_doc(out: &mut String, schema: &SchemaObject, indent: &str) {
+    let Some(description) = schema.metadata.as_ref().and_then(|m| m.description.as_ref()) else { return };
+    let text = description.split_whitespace().collect::<Vec<_>>().join(" ");
+    out.push_str(&format!("{}/** {} */\n", indent, text));
+}
+
+/// The TypeScript type of `schema`, leaving out `null` (see `render`).
+fn type_of(schema: &SchemaObject) -> String {
+    if let Some(reference) = &schema.reference {
+        return reference.trim_start_matches("#/definitions/").to_string();
+    }
+    if let Some(values) = &schema.enum_values {
+        return values.iter().map(|v| v.to_string().replace('"', "'")).collect::<Vec<_>>().join(" | ");
+    }
+    if let Some(subschemas) = &schema.subschemas {
+        // A documented reference is wrapped in allOf, an optional one in anyOf with null
+        let parts = subschemas.all_of.iter().chain(&subschemas.any_of).chain(&subschemas.one_of).flatten();
+        let types: Vec<String> = parts
+            .filter_map(|part| match part {
+                Schema::Object(part) if !is_null(part) => Some(type_of(part)),
+                _ => None,
+            })
+            .collect();
+        return types.join(" | ");
+    }
+
+    let instance_types: Vec<InstanceType> = match &schema.instance_type {
+        Some(SingleOrVec::Single(single)) => vec![**single],
+        Some(SingleOrVec::Vec(types)) => types.iter().copied().filter(|t| *t != InstanceType::Null).collect(),
+        None => Vec::new(),
+    };
+    let types: Vec<String> = instance_types
+        .into_iter()
+        .map(|instance_type| match instance_type {
+            InstanceType::String => "string".to_string(),
+            InstanceType::Integer | InstanceType::Number => "number".to_string(),
+            InstanceType::Boolean => "boolean".to_string(),
+            InstanceType::Array => match schema.array.as_ref().and_then(|a| a.items.as_ref()) {
+                Some(SingleOrVec::Single(item)) => match item.as_ref() {
+                    Schema::Object(item) => format!("{}[]", wrap(type_of(item))),
+                    Schema::Bool(_) => "unknown[]".to_string(),
+                },
+                _ => "unknown[]".to_string(),
+            },
+            InstanceType::Object => match schema.object.as_ref().and_then(|o| o.additional_properties.as_deref()) {
+                Some(Schema::Object(value)) => format!("Record<string, {}>", type_of(value)),
+                _ => "Record<string, unknown>".to_string(),
+            },
+            InstanceType::Null => "null".to_string(),
+        })
+        .collect();
+    if types.is_empty() {
+        "unknown".to_string()
+    } else {
+        types.join(" | ")
+    }
+}
+
+fn is_null(schema: &SchemaObject) -> bool {
+    matches!(&schema.instance_type, Some(SingleOrVec::Single(t)) if **t == InstanceType::Null)
+}
+
+/// Parenthesizes a union before `[]` is appended.
+fn wrap(ty: String) -> String {
+    if ty.contains(" | ") {
+        format!("({})", ty)
+    } else {
+        ty
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DESKTOP_TYPES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../desktop/src/generated/core.ts");
+
+    #[test]
+    fn test_desktop_types_are_current() {
+        let generated = core_declarations();
+        if std::env::var_os("UPDATE_TYPES").is_some() {
+            std::fs::write(DESKTOP_TYPES, &generated).unwrap();
+        }
+        let current = std::fs::read_to_string(DESKTOP_TYPES).unwrap_or_default();
+        assert!(
+            current == generated,
+            "{} is out of date; run `UPDATE_TYPES=1 cargo test --features typescript` in packages/core",
+            DESKTOP_TYPES
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let generated = core_declarations();
+        assert!(generated.contains("export interface PhotoInfo {\n    id: PhotoId;\n"));
+        assert!(generated.contains("    thumb_path?: string;\n"));
+        assert!(generated.contains("export type QuerySort = 'date_taken' | 'path' | 'file_size' | 'view_count';"));
+        assert!(generated.contains("    date_precision?: DatePrecision;\n"));
+        assert!(generated.contains("    params: Record<string, string>;\n"));
+        assert!(generated.contains("    photo_ids: number[];\n"));
+    }
+}
@@ -0,0 +1,258 @@
+//! Watching folders for new photos and importing them as they arrive.
+//!
+//! Folders are polled rather than subscribed to through OS notifications:
+//! a poll of a photo folder is cheap next to importing what's in it, works
+//! the same on every platform and on network drives, and sees files that
+//! appeared while the app wasn't looking. A new file is imported once its
+//! size and modification time hold still between two polls, so photos
+//! still being copied in aren't read half-written.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::config::{ImportOptions, PhotoCoreConfig};
+use crate::error::CoreError;
+use crate::fs::{encode_path, file_stats, scan_photos};
+use crate::index::PhotoIndex;
+use crate::types::ImportResult;
+use crate::{flush_import_batch, prepare_import, PreparedImport};
+
+/// Time between polls of a watched folder
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// How often the polling thread checks whether it was stopped
+const STOP_CHECK: Duration = Duration::from_millis(100);
+
+/// Told about each batch of photos a `FolderWatcher` imports, on the
+/// watcher's thread.
+#[uniffi::export(with_foreign)]
+pub trait WatchListener: Send + Sync {
+    fn imported(&self, root: String, result: ImportResult);
+}
+
+/// A folder being watched, see `start_watch`. Watching ends with
+/// `stop_watch` or when the last reference is dropped.
+#[derive(uniffi::Object)]
+pub struct FolderWatcher {
+    root: String,
+    stopped: Arc<AtomicBool>,
+    thread: Mutex<Option<std::thread::JoinHandle<()>>>,
+}
+
+/// Starts importing photos added under `root` from now on. Files already
+/// there are left to a regular import or `rescan`.
+#[uniffi::export]
+pub fn start_watch(
+    root: String,
+    index: Arc<PhotoIndex>,
+    config: PhotoCoreConfig,
+    listener: Arc<dyn WatchListener>,
+) -> Result<Arc<FolderWatcher>, CoreError> {
+    FolderWatcher::start(root, index, config, listener, POLL_INTERVAL)
+}
+
+#[uniffi::export]
+impl FolderWatcher {
+    pub fn root(&self) -> String {
+        self.root.clone()
+    }
+
+    /// Stops watching, waiting for an import in progress to finish.
+    pub fn stop_watch(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        let thread = self.thread.lock().ok().and_then(|mut thread| thread.take());
+        if let Some(thread) = thread {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl FolderWatcher {
+    fn start(
+        root: String,
+        index: Arc<PhotoIndex>,
+        config: PhotoCoreConfig,
+        listener: Arc<dyn WatchListener>,
+        interval: Duration,
+    ) -> Result<Arc<Self>, CoreError> {
+        let root_path = PathBuf::from(&root);
+        if !root_path.is_dir() {
+            return Err(CoreError::NotFound(format!("Not a folder: {}", root)));
+        }
+        let mut poller = Poller::new(&root_path)?;
+        let stopped = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let (root, stopped) = (root.clone(), stopped.clone());
+            std::thread::spawn(move || {
+                while sleep_unless_stopped(interval, &stopped) {
+                    let ready = match poller.poll(&root_path) {
+                        Ok(ready) => ready,
+                        Err(e) => {
+                            println!("[Watch] polling {} failed: {}", root, e);
+                            continue;
+                        }
+                    };
+                    if ready.is_empty() {
+                        continue;
+                    }
+                    match import_files(&root_path, &ready, &index, &config) {
+                        Ok(result) => listener.imported(root.clone(), result),
+                        Err(e) => println!("[Watch] importing into {} failed: {}", root, e),
+                    }
+                }
+            })
+        };
+        Ok(Arc::new(Self { root, stopped, thread: Mutex::new(Some(thread)) }))
+    }
+}
+
+impl Drop for FolderWatcher {
+    fn drop(&mut self) {
+        self.stop_watch();
+    }
+}
+
+/// Sleeps for `duration`, or less if `stopped` is set. Returns false if it was.
+fn sleep_unless_stopped(duration: Duration, stopped: &AtomicBool) -> bool {
+    let mut slept = Duration::ZERO;
+    while slept < duration {
+        if stopped.load(Ordering::Relaxed) {
+            return false;
+        }
+        let step = STOP_CHECK.min(duration - slept);
+        std::thread::sleep(step);
+        slept += step;
+    }
+    !stopped.load(Ordering::Relaxed)
+}
+
+/// What a watcher knows about its folder between polls.
+struct Poller {
+    /// Files seen before, imported or there from the start
+    known: std::collections::HashSet<PathBuf>,
+    /// New files with their size and modification time at the last poll
+    arriving: HashMap<PathBuf, Option<(u64, i64)>>,
+}
+
+impl Poller {
+    fn new(root: &Path) -> Result<Self, CoreError> {
+        Ok(Self { known: scan_photos(root)?.into_iter().collect(), arriving: HashMap::new() })
+    }
+
+    /// New files that have held still since the last poll.
+    fn poll(&mut self, root: &Path) -> Result<Vec<PathBuf>, CoreError> {
+        let mut ready = Vec::new();
+        let mut arriving = HashMap::new();
+        for path in scan_photos(root)? {
+            if self.known.contains(&path) {
+                continue;
+            }
+            let stats = file_stats(&path);
+            if stats.is_some() && self.arriving.get(&path) == Some(&stats) {
+                self.known.insert(path.clone());
+                ready.push(path);
+            } else {
+                arriving.insert(path, stats);
+            }
+        }
+        self.arriving = arriving;
+        Ok(ready)
+    }
+}
+
+/// Imports `paths` under `root` as an import would, skipping ones already indexed.
+fn import_files(root: &Path, paths: &[PathBuf], index: &PhotoIndex, config: &PhotoCoreConfig) -> Result<ImportResult, CoreError> {
+    let options = ImportOptions::default();
+    let indexed = index.paths_exist(paths.iter().map(|path| encode_path(path)).collect())?;
+    let mut result = ImportResult::default();
+    let mut pending = Vec::new();
+    for (path, indexed) in paths.iter().zip(indexed) {
+        if indexed {
+            continue;
+        }
+        match prepare_import(path, index, config, &options)? {
+            PreparedImport::Ready(photo) => pending.push((path, *photo)),
+            PreparedImport::SkippedDeleted => result.skipped_deleted += 1,
+            PreparedImport::Filtered => result.skipped_filtered += 1,
+            PreparedImport::Failed(stage, message) => result.record_failure(root, path, stage, message),
+        }
+    }
+    flush_import_batch(index, &mut pending, root, &mut result);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[derive(Default)]
+    struct Imports(Mutex<Vec<ImportResult>>);
+
+    impl WatchListener for Imports {
+        fn imported(&self, _root: String, result: ImportResult) {
+            self.0.lock().unwrap().push(result);
+        }
+    }
+
+    #[test]
+    fn test_watch_imports_new_photos() {
+        let temp_dir = std::env::temp_dir().join("fotos_watch_test");
+        let src_dir = temp_dir.join("src");
+        if temp_dir.exists() { fs::remove_dir_all(&temp_dir).unwrap(); }
+        fs::create_dir_all(src_dir.join("sub")).unwrap();
+        let save = |path: PathBuf, shade: u8| ::image::RgbImage::from_pixel(8, 8, ::image::Rgb([shade, 0, 0])).save(path).unwrap();
+        save(src_dir.join("before.png"), 0);
+
+        let index = PhotoIndex::open(temp_dir.join("test.db").to_string_lossy().to_string()).unwrap();
+        let config = PhotoCoreConfig {
+            thumbnail_dir: temp_dir.join("thumbs").to_string_lossy().to_string(),
+            thumbnail_size: 64,
+        };
+        let imports = Arc::new(Imports::default());
+        let root = src_dir.to_string_lossy().to_string();
+        let watcher = FolderWatcher::start(root, index.clone(), config.clone(), imports.clone(), Duration::from_millis(50)).unwrap();
+
+        save(src_dir.join("sub").join("new.png"), 1);
+        let deadline = std::time::Instant::now() + Duration::from_secs(10);
+        while index.count().unwrap() == 0 && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        watcher.stop_watch();
+
+        // Only the file added while watching
+        assert_eq!(index.count().unwrap(), 1);
+        assert!(index.get_by_path(encode_path(&src_dir.join("sub").join("new.png"))).unwrap().is_some());
+        let results = imports.0.lock().unwrap();
+        assert_eq!(results.iter().map(|r| r.success).sum::<u32>(), 1);
+
+        // Nothing is imported after stopping
+        save(src_dir.join("after.png"), 2);
+        std::thread::sleep(Duration::from_millis(200));
+        assert_eq!(index.count().unwrap(), 1);
+
+        assert!(start_watch("/no/such/folder".to_string(), index, config, imports.clone()).is_err());
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[test]
+    fn test_poller_waits_for_files_to_settle() {
+        let temp_dir = std::env::temp_dir().join("fotos_watch_poll_test");
+        if temp_dir.exists() { fs::remove_dir_all(&temp_dir).unwrap(); }
+        fs::create_dir_all(&temp_dir).unwrap();
+        let mut poller = Poller::new(&temp_dir).unwrap();
+
+        let file = temp_dir.join("copying.jpg");
+        fs::write(&file, b"half").unwrap();
+        assert!(poller.poll(&temp_dir).unwrap().is_empty());
+        fs::write(&file, b"half and the rest").unwrap();
+        assert!(poller.poll(&temp_dir).unwrap().is_empty());
+        assert_eq!(poller.poll(&temp_dir).unwrap(), vec![file.clone()]);
+        // Reported once
+        assert!(poller.poll(&temp_dir).unwrap().is_empty());
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+}
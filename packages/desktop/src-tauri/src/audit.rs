@@ -0,0 +1,103 @@
+//! Opt-in check of originals against bit rot: once a day a rotating sample
+//! of photos is re-read and compared with the checksums core keeps (see
+//! `fotos_core::PhotoIndex::audit_originals`), and the report announced to
+//! the frontend with an "originals-audit" event. The setting and the last
+//! report are kept as JSON in the app data dir.
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use fotos_core::OriginalsAuditReport;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::errors::CommandError;
+use crate::paths::LibraryPaths;
+
+/// Photos re-read per run; a library of 20,000 is covered every few months
+const SAMPLE_SIZE: u32 = 200;
+const INTERVAL_SECS: i64 = 24 * 60 * 60;
+/// How often the background thread looks whether a run is due
+const POLL: Duration = Duration::from_secs(15 * 60);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditState {
+    pub enabled: bool,
+    /// Unix time of the last run, scheduled or not
+    pub last_run: Option<i64>,
+    pub last_report: Option<OriginalsAuditReport>,
+}
+
+pub struct OriginalsAudit {
+    file: PathBuf,
+    state: Mutex<AuditState>,
+    /// Held while a run reads files, so runs don't overlap
+    running: Mutex<()>,
+}
+
+impl OriginalsAudit {
+    pub fn restore(file: PathBuf) -> Self {
+        let state = std::fs::read(&file)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        Self { file, state: Mutex::new(state), running: Mutex::new(()) }
+    }
+
+    pub fn state(&self) -> AuditState {
+        self.state.lock().map(|state| state.clone()).unwrap_or_default()
+    }
+
+    pub fn set_enabled(&self, enabled: bool) -> Result<AuditState, CommandError> {
+        self.update(|state| state.enabled = enabled)
+    }
+
+    /// Checks a sample of originals now and keeps the report.
+    pub fn run(&self, library: &LibraryPaths) -> Result<OriginalsAuditReport, CommandError> {
+        let _running = self.running.lock().map_err(|e| e.to_string())?;
+        let index = library.index()?;
+        let report = index.audit_originals(SAMPLE_SIZE)?;
+        self.update(|state| {
+            state.last_run = Some(unix_now());
+            state.last_report = Some(report.clone());
+        })?;
+        Ok(report)
+    }
+
+    fn due(&self) -> bool {
+        let state = self.state();
+        state.enabled && state.last_run.is_none_or(|last| unix_now() - last >= INTERVAL_SECS)
+    }
+
+    fn update(&self, change: impl FnOnce(&mut AuditState)) -> Result<AuditState, CommandError> {
+        let mut state = self.state.lock().map_err(|e| e.to_string())?;
+        change(&mut state);
+        std::fs::write(&self.file, serde_json::to_vec(&*state).map_err(|e| e.to_string())?)?;
+        Ok(state.clone())
+    }
+}
+
+/// Runs the audit whenever it's enabled and due, for the life of the app.
+pub fn schedule<R: Runtime>(app: AppHandle<R>) {
+    std::thread::spawn(move || loop {
+        let audit = app.state::<OriginalsAudit>();
+        if audit.due() {
+            match audit.run(&app.state::<LibraryPaths>()) {
+                Ok(report) => {
+                    println!("[Audit] {} checked, {} corrupt", report.checked, report.corrupt.len());
+                    let _ = app.emit("originals-audit", report);
+                }
+                Err(e) => println!("[Audit] failed: {}", e.fallback),
+            }
+        }
+        std::thread::sleep(POLL);
+    });
+}
+
+fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,117 @@
+//! Security-scoped bookmarks for import sources. In the macOS sandbox, access
+//! to a folder the user picked only lasts until the app quits; a bookmark
+//! saved at import time lets the next launch regain it, so originals keep
+//! opening. Elsewhere paths stay accessible and nothing is stored.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Bookmarks per import source path, persisted as JSON in the app data dir.
+pub struct Bookmarks {
+    file: PathBuf,
+    entries: Mutex<BTreeMap<String, Vec<u8>>>,
+}
+
+impl Bookmarks {
+    /// Loads the saved bookmarks and resolves them, regaining access to every
+    /// source that still exists. Stale bookmarks (moved or renamed folders)
+    /// are refreshed; ones that no longer resolve are dropped.
+    pub fn restore(file: PathBuf) -> Self {
+        let saved: BTreeMap<String, Vec<u8>> = std::fs::read(&file)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+
+        let mut entries = BTreeMap::new();
+        for (path, bookmark) in saved {
+            match platform::resolve(&bookmark) {
+                Some((resolved, stale)) => {
+                    let bookmark = if stale { platform::create(&resolved).unwrap_or(bookmark) } else { bookmark };
+                    entries.insert(resolved, bookmark);
+                }
+                None => println!("[Bookmarks] dropping unresolvable source: {}", path),
+            }
+        }
+
+        let bookmarks = Self { file, entries: Mutex::new(entries) };
+        bookmarks.save();
+        bookmarks
+    }
+
+    /// Bookmarks an import source while the user's grant is still valid.
+    pub fn remember(&self, path: &str) {
+        let Some(bookmark) = platform::create(path) else {
+            return;
+        };
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(path.to_string(), bookmark);
+        }
+        self.save();
+    }
+
+    fn save(&self) {
+        let Ok(entries) = self.entries.lock() else {
+            return;
+        };
+        if entries.is_empty() && !self.file.exists() {
+            return;
+        }
+        if let Ok(json) = serde_json::to_vec(&*entries) {
+            if let Err(e) = std::fs::write(&self.file, json) {
+                println!("[Bookmarks] failed to save: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use objc2::runtime::Bool;
+    use objc2_foundation::{NSData, NSString, NSURLBookmarkCreationOptions, NSURLBookmarkResolutionOptions, NSURL};
+
+    pub fn create(path: &str) -> Option<Vec<u8>> {
+        let url = NSURL::fileURLWithPath(&NSString::from_str(path));
+        let data = unsafe {
+            url.bookmarkDataWithOptions_includingResourceValuesForKeys_relativeToURL_error(
+                NSURLBookmarkCreationOptions::WithSecurityScope,
+                None,
+                None,
+            )
+        }
+        .ok()?;
+        Some(data.to_vec())
+    }
+
+    /// Resolved path and whether the bookmark should be recreated. Access
+    /// is started here and kept for the lifetime of the app.
+    pub fn resolve(bookmark: &[u8]) -> Option<(String, bool)> {
+        let data = NSData::with_bytes(bookmark);
+        let mut stale = Bool::NO;
+        let url = unsafe {
+            NSURL::URLByResolvingBookmarkData_options_relativeToURL_bookmarkDataIsStale_error(
+                &data,
+                NSURLBookmarkResolutionOptions::WithSecurityScope,
+                None,
+                &mut stale,
+            )
+        }
+        .ok()?;
+        if !unsafe { url.startAccessingSecurityScopedResource() } {
+            return None;
+        }
+        let path = url.path()?.to_string();
+        Some((path, stale.as_bool()))
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod platform {
+    pub fn create(_path: &str) -> Option<Vec<u8>> {
+        None
+    }
+
+    pub fn resolve(_bookmark: &[u8]) -> Option<(String, bool)> {
+        None
+    }
+}
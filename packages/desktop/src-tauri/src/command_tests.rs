@@ -0,0 +1,570 @@
+//! Headless tests of the command layer: commands are invoked through Tauri's
+//! IPC on the mock runtime, against a throwaway library. Events the commands
+//! emit go to the mock window and are dropped.
+
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+use tauri::test::{get_ipc_response, mock_builder, mock_context, noop_assets, MockRuntime, INVOKE_KEY};
+use tauri::webview::InvokeRequest;
+use tauri::{App, Manager, WebviewWindow, WebviewWindowBuilder};
+
+use crate::{audit, bookmarks, diagnostics, fallback_cache, formats, paths, startup, viewport, watch};
+
+/// A 1x1 PNG
+const PNG: &[u8] = &[
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52, 0x00, 0x00,
+    0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x02, 0x00, 0x00, 0x00, 0x90, 0x77, 0x53, 0xde, 0x00, 0x00, 0x00,
+    0x0c, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0xf8, 0xdf, 0xc0, 0x00, 0x00, 0x04, 0x01, 0x01, 0x80, 0xc5,
+    0x2a, 0x18, 0x5d, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+];
+
+struct TestLibrary {
+    dir: PathBuf,
+    // The webview only lives as long as its app
+    app: App<MockRuntime>,
+    webview: WebviewWindow<MockRuntime>,
+}
+
+impl TestLibrary {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("fotos_command_test_{}", name));
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+        let library = paths::LibraryPaths::open(&dir.join("data"), &dir.join("cache"), None).unwrap();
+
+        let app = mock_builder()
+            .manage(library)
+            .manage(bookmarks::Bookmarks::restore(dir.join("bookmarks.json")))
+            .manage(viewport::VisibleHints::default())
+            .manage(formats::ExtraExtensions::restore(dir.join("formats.json")))
+            .manage(watch::Watchers::new(dir.join("watched.json")))
+            .manage(fotos_core::TempManager::open(&dir.join("cache").join("tmp")).unwrap())
+            .manage(diagnostics::ImportErrors::default())
+            .manage(fallback_cache::FallbackCache::restore(dir.join("fallback_cache.json")))
+            .manage(audit::OriginalsAudit::restore(dir.join("audit.json")))
+            .manage(startup::StartupCheck::restore(dir.join("startup.json")))
+            .invoke_handler(tauri::generate_handler![
+                crate::import_photos,
+                crate::get_import_errors,
+                crate::rescan_folder,
+                crate::get_extra_extensions,
+                crate::set_extra_extensions,
+                crate::start_watch,
+                crate::stop_watch,
+                crate::list_watches,
+                crate::list_photos,
+                crate::query_photos,
+                crate::record_view,
+                crate::get_photo_views,
+                crate::delete_photos_from_app,
+                crate::move_to_trash,
+                crate::restore_from_trash,
+                crate::list_trash,
+                crate::empty_trash,
+                crate::regenerate_thumbnails,
+                crate::hint_visible_range,
+                crate::verify_library,
+                crate::export_year_in_review,
+                crate::export_manifest,
+                crate::clear_cache,
+                crate::backup_library,
+                crate::restore_library,
+                crate::get_enhanced_preview,
+                crate::get_photo_enhance,
+                crate::set_enhance_enabled,
+                crate::get_fallback_cache,
+                crate::set_fallback_cache,
+                crate::get_originals_audit,
+                crate::set_originals_audit,
+                crate::run_originals_audit,
+                crate::get_startup_report,
+                crate::get_startup_check,
+                crate::set_startup_check,
+                crate::discard_import_session,
+                crate::read_file_bytes,
+            ])
+            .build(mock_context(noop_assets()))
+            .unwrap();
+        app.manage(paths::Grants::follow(&app.asset_protocol_scope()));
+        let webview = WebviewWindowBuilder::new(&app, "main", Default::default()).build().unwrap();
+        Self { dir, app, webview }
+    }
+
+    /// A folder of `count` photos to import
+    fn source(&self, count: usize) -> PathBuf {
+        let source = self.dir.join("source");
+        std::fs::create_dir_all(&source).unwrap();
+        for i in 0..count {
+            // Bytes after the image's end make each file a photo of its own
+            std::fs::write(source.join(format!("IMG_{:04}.png", i)), [PNG, &i.to_le_bytes()].concat()).unwrap();
+        }
+        source
+    }
+
+    /// What the dialog plugin does with a file or folder the user picks
+    fn pick(&self, path: &Path) {
+        let scope = self.app.asset_protocol_scope();
+        if path.is_dir() { scope.allow_directory(path, true) } else { scope.allow_file(path) }.unwrap();
+    }
+
+    fn invoke(&self, cmd: &str, args: Value) -> Result<Value, Value> {
+        let request = InvokeRequest {
+            cmd: cmd.into(),
+            callback: tauri::ipc::CallbackFn(0),
+            error: tauri::ipc::CallbackFn(1),
+            url: "tauri://localhost".parse().unwrap(),
+            body: tauri::ipc::InvokeBody::Json(args),
+            headers: Default::default(),
+            invoke_key: INVOKE_KEY.to_string(),
+        };
+        get_ipc_response(&self.webview, request).map(|body| body.deserialize().unwrap())
+    }
+
+    fn import(&self, root: &Path) -> Value {
+        self.invoke("import_photos", json!({ "rootPath": root })).unwrap()
+    }
+
+    fn photo_ids(&self) -> Vec<i64> {
+        let photos = self.invoke("list_photos", json!({})).unwrap();
+        photos.as_array().unwrap().iter().map(|p| p["id"]["id"].as_i64().unwrap()).collect()
+    }
+}
+
+impl Drop for TestLibrary {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[test]
+fn test_import_counts_and_skips_duplicates() {
+    let library = TestLibrary::new("import");
+    let source = library.source(3);
+
+    let first = library.import(&source);
+    assert_eq!((first["success"].as_u64(), first["failure"].as_u64()), (Some(3), Some(0)));
+    assert_eq!(library.photo_ids().len(), 3);
+    // Sizes come from the index
+    let photos = library.invoke("list_photos", json!({})).unwrap();
+    assert_eq!(photos[0]["file_size"].as_u64(), Some(PNG.len() as u64 + 8));
+
+    // Already indexed files are skipped, not counted again
+    let second = library.import(&source);
+    assert_eq!((second["success"].as_u64(), second["failure"].as_u64()), (Some(0), Some(0)));
+    assert_eq!(library.photo_ids().len(), 3);
+
+    let dry_run = library.invoke("import_photos", json!({ "rootPath": source, "dryRun": true })).unwrap();
+    assert_eq!(dry_run["plan"]["duplicates"].as_u64(), Some(3));
+}
+
+#[test]
+fn test_import_in_parallel() {
+    let library = TestLibrary::new("parallel");
+    let source = library.source(12);
+
+    let result = library.invoke("import_photos", json!({ "rootPath": source, "filters": { "concurrency": 4 } })).unwrap();
+    assert_eq!((result["success"].as_u64(), result["failure"].as_u64()), (Some(12), Some(0)));
+    assert_eq!(library.photo_ids().len(), 12);
+    // Every file was journaled as done, so the session finished
+    let again = library.import(&source);
+    assert_eq!(again["success"].as_u64(), Some(0));
+}
+
+#[test]
+fn test_import_filters() {
+    let library = TestLibrary::new("filters");
+    let source = library.source(2);
+    std::fs::create_dir_all(source.join("nested")).unwrap();
+    std::fs::write(source.join("nested").join("IMG_9999.png"), PNG).unwrap();
+
+    let only_raw = json!({ "rootPath": source, "dryRun": true, "filters": { "extensions": ["cr3"] } });
+    let plan = &library.invoke("import_photos", only_raw).unwrap()["plan"];
+    assert_eq!((plan["new_files"].as_u64(), plan["filtered"].as_u64()), (Some(0), Some(3)));
+
+    let top_level = library.invoke("import_photos", json!({ "rootPath": source, "filters": { "recursive": false } })).unwrap();
+    assert_eq!(top_level["success"].as_u64(), Some(2));
+
+    // Undated photos are outside any date range
+    let dated = json!({ "rootPath": source, "filters": { "taken_after": 0 } });
+    let result = library.invoke("import_photos", dated).unwrap();
+    assert_eq!((result["success"].as_u64(), result["skipped_filtered"].as_u64()), (Some(0), Some(1)));
+    assert_eq!(library.photo_ids().len(), 2);
+}
+
+#[test]
+fn test_import_error_log() {
+    let library = TestLibrary::new("import_errors");
+    let source = library.source(1);
+    std::fs::write(source.join("broken.jpg"), b"not a jpeg").unwrap();
+
+    let result = library.import(&source);
+    assert_eq!((result["success"].as_u64(), result["failure"].as_u64()), (Some(1), Some(1)));
+    let errors = library.invoke("get_import_errors", json!({})).unwrap();
+    assert_eq!(errors, result["errors"]);
+    let error = &errors[0];
+    assert!(error["path"].as_str().unwrap().ends_with("broken.jpg"));
+    assert!(error["stage"].is_string() && !error["message"].as_str().unwrap().is_empty());
+
+    // The next import replaces the log
+    std::fs::remove_file(source.join("broken.jpg")).unwrap();
+    library.import(&source);
+    assert_eq!(library.invoke("get_import_errors", json!({})).unwrap(), json!([]));
+}
+
+#[test]
+fn test_deleted_photos_stay_deleted_on_reimport() {
+    let library = TestLibrary::new("delete");
+    let source = library.source(2);
+    library.import(&source);
+
+    let ids = library.photo_ids();
+    let deleted = library.invoke("delete_photos_from_app", json!({ "ids": [ids[0]] })).unwrap();
+    assert_eq!(deleted["deleted_count"].as_u64(), Some(1));
+    assert_eq!(library.photo_ids(), vec![ids[1]]);
+    // Removed from the library only - the original stays
+    assert_eq!(std::fs::read_dir(&source).unwrap().count(), 2);
+
+    let reimport = library.import(&source);
+    assert_eq!(reimport["skipped_deleted"].as_u64(), Some(1));
+    assert_eq!(library.photo_ids().len(), 1);
+
+    let forced = library.invoke("import_photos", json!({ "rootPath": source, "reimportDeleted": true })).unwrap();
+    assert_eq!(forced["success"].as_u64(), Some(1));
+    assert_eq!(library.photo_ids().len(), 2);
+}
+
+#[test]
+fn test_trash_restore_and_empty() {
+    let library = TestLibrary::new("trash");
+    let source = library.source(2);
+    library.import(&source);
+
+    let ids = library.photo_ids();
+    assert_eq!(library.invoke("move_to_trash", json!({ "ids": ids })).unwrap().as_u64(), Some(2));
+    assert!(library.photo_ids().is_empty());
+    let trash = library.invoke("list_trash", json!({})).unwrap();
+    assert_eq!(trash.as_array().unwrap().len(), 2);
+    assert!(trash[0]["photo"]["thumb_path"].is_string());
+
+    library.invoke("restore_from_trash", json!({ "ids": [ids[0]] })).unwrap();
+    assert_eq!(library.photo_ids(), vec![ids[0]]);
+
+    let emptied = library.invoke("empty_trash", json!({})).unwrap();
+    assert_eq!(emptied["deleted_count"].as_u64(), Some(1));
+    assert!(library.invoke("list_trash", json!({})).unwrap().as_array().unwrap().is_empty());
+    // Like any deletion, the original stays on disk
+    assert_eq!(std::fs::read_dir(&source).unwrap().count(), 2);
+}
+
+#[test]
+fn test_regenerate_thumbnails() {
+    let library = TestLibrary::new("regenerate");
+    library.import(&library.source(2));
+    let thumbs = library.dir.join("data").join("thumbnails");
+    std::fs::remove_dir_all(&thumbs).unwrap();
+
+    // The last photo is on screen; ids the library doesn't have are ignored
+    let last = *library.photo_ids().last().unwrap();
+    library.invoke("hint_visible_range", json!({ "ids": [last, -1] })).unwrap();
+
+    library.invoke("regenerate_thumbnails", json!({})).unwrap();
+    let photos = library.invoke("list_photos", json!({})).unwrap();
+    assert!(photos.as_array().unwrap().iter().all(|p| p["thumb_path"].is_string()));
+}
+
+#[test]
+fn test_rescan_folder() {
+    let library = TestLibrary::new("rescan");
+    let source = library.source(2);
+    library.import(&source);
+    std::fs::remove_file(source.join("IMG_0001.png")).unwrap();
+    std::fs::write(source.join("IMG_0002.png"), PNG).unwrap();
+
+    let report = library.invoke("rescan_folder", json!({ "rootPath": source })).unwrap();
+    assert_eq!((report["added"].as_u64(), report["missing"].as_u64(), report["unchanged"].as_u64()), (Some(1), Some(1), Some(1)));
+    assert_eq!(library.photo_ids().len(), 3);
+}
+
+#[test]
+fn test_extra_extensions() {
+    let library = TestLibrary::new("formats");
+    let source = library.source(1);
+    std::fs::write(source.join("scan.jxl"), PNG).unwrap();
+    let new_files = || library.invoke("import_photos", json!({ "rootPath": source, "dryRun": true })).unwrap()["plan"]["new_files"].as_u64();
+    assert_eq!(new_files(), Some(1));
+
+    let supported = library.invoke("set_extra_extensions", json!({ "extensions": [".JXL"] })).unwrap();
+    assert!(supported.as_array().unwrap().contains(&json!("jxl")));
+    assert_eq!(library.invoke("get_extra_extensions", json!({})).unwrap(), json!(["jxl"]));
+    assert_eq!(new_files(), Some(2));
+    assert!(library.dir.join("formats.json").exists());
+
+    assert!(library.invoke("set_extra_extensions", json!({ "extensions": ["../x"] })).is_err());
+    library.invoke("set_extra_extensions", json!({ "extensions": [] })).unwrap();
+    assert_eq!(new_files(), Some(1));
+}
+
+#[test]
+fn test_originals_audit() {
+    let library = TestLibrary::new("audit");
+    let source = library.source(2);
+    library.import(&source);
+    assert_eq!(library.invoke("get_originals_audit", json!({})).unwrap()["enabled"], false);
+
+    // Checksums are taken at import
+    let report = library.invoke("run_originals_audit", json!({})).unwrap();
+    assert_eq!((report["checked"].as_u64(), report["recorded"].as_u64()), (Some(2), Some(0)));
+    assert_eq!((report["verified"].as_u64(), report["corrupt"].clone()), (Some(2), json!([])));
+
+    let state = library.invoke("set_originals_audit", json!({ "enabled": true })).unwrap();
+    assert_eq!((state["enabled"].clone(), state["last_report"].clone()), (json!(true), report));
+    assert!(state["last_run"].is_i64());
+    // Kept for the next launch
+    let saved: Value = serde_json::from_slice(&std::fs::read(library.dir.join("audit.json")).unwrap()).unwrap();
+    assert_eq!(saved, state);
+}
+
+#[test]
+fn test_startup_check() {
+    let library = TestLibrary::new("startup");
+    let source = library.source(2);
+    library.import(&source);
+    assert_eq!(library.invoke("get_startup_report", json!({})).unwrap(), Value::Null);
+    // An import that was interrupted halfway
+    let index = fotos_core::PhotoIndex::open(library.dir.join("data").join("fotos.db").to_string_lossy().into_owned()).unwrap();
+    let session = index.begin_import_session("/Volumes/Card".into(), vec!["a.jpg".into(), "b.jpg".into()]).unwrap();
+
+    let run = || {
+        let check = library.webview.state::<startup::StartupCheck>();
+        check.run(&library.webview.state::<paths::LibraryPaths>(), 3);
+        library.invoke("get_startup_report", json!({})).unwrap()
+    };
+    let report = run();
+    assert_eq!((report["database_problems"].clone(), report["cache_unavailable"].clone()), (json!([]), Value::Null));
+    assert_eq!(report["temp_swept"], 3);
+    assert_eq!(report["unfinished_imports"][0]["root"], "/Volumes/Card");
+
+    library.invoke("discard_import_session", json!({ "id": session.id })).unwrap();
+    assert_eq!(run()["unfinished_imports"], json!([]));
+
+    assert_eq!(library.invoke("get_startup_check", json!({})).unwrap()["enabled"], true);
+    let settings = library.invoke("set_startup_check", json!({ "enabled": false })).unwrap();
+    let saved: Value = serde_json::from_slice(&std::fs::read(library.dir.join("startup.json")).unwrap()).unwrap();
+    assert_eq!((settings["enabled"].clone(), saved), (json!(false), settings));
+}
+
+#[test]
+fn test_photo_views() {
+    let library = TestLibrary::new("views");
+    let source = library.source(3);
+    library.import(&source);
+    let ids = library.photo_ids();
+
+    for id in [ids[1], ids[1], ids[2]] {
+        assert_eq!(library.invoke("record_view", json!({ "id": id })).unwrap(), true);
+    }
+    assert_eq!(library.invoke("record_view", json!({ "id": 999 })).unwrap(), false);
+    let views = library.invoke("get_photo_views", json!({ "id": ids[1] })).unwrap();
+    assert_eq!(views["view_count"], 2);
+    assert!(views["last_viewed_at"].is_i64());
+
+    let query_ids = |query: Value| -> Vec<i64> {
+        let photos = library.invoke("query_photos", json!({ "query": query })).unwrap();
+        photos.as_array().unwrap().iter().map(|p| p["id"]["id"].as_i64().unwrap()).collect()
+    };
+    assert_eq!(query_ids(json!({ "viewed": true, "sort": "view_count", "descending": true })), vec![ids[1], ids[2]]);
+    assert_eq!(query_ids(json!({ "viewed": false })), vec![ids[0]]);
+}
+
+#[test]
+fn test_watch_commands() {
+    let library = TestLibrary::new("watch");
+    let source = library.source(1);
+
+    library.invoke("start_watch", json!({ "rootPath": source })).unwrap();
+    // Watching twice is a no-op
+    library.invoke("start_watch", json!({ "rootPath": source })).unwrap();
+    assert_eq!(library.invoke("list_watches", json!({})).unwrap(), json!([source]));
+    assert!(library.dir.join("watched.json").exists());
+    assert!(library.invoke("start_watch", json!({ "rootPath": library.dir.join("missing") })).is_err());
+
+    // Photos added while watching are imported on a later poll
+    std::fs::write(source.join("IMG_9999.png"), PNG).unwrap();
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(15);
+    while library.photo_ids().is_empty() && std::time::Instant::now() < deadline {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    assert_eq!(library.photo_ids().len(), 1);
+
+    assert_eq!(library.invoke("stop_watch", json!({ "rootPath": source })).unwrap(), json!(true));
+    assert_eq!(library.invoke("stop_watch", json!({ "rootPath": source })).unwrap(), json!(false));
+    assert_eq!(library.invoke("list_watches", json!({})).unwrap(), json!([]));
+}
+
+#[test]
+fn test_verify_library() {
+    let library = TestLibrary::new("verify");
+    let source = library.source(2);
+    library.import(&source);
+    std::fs::remove_file(source.join("IMG_0001.png")).unwrap();
+    let orphan = library.dir.join("data").join("thumbnails").join("00").join("0000000000000000.jpg");
+    std::fs::create_dir_all(orphan.parent().unwrap()).unwrap();
+    std::fs::write(&orphan, PNG).unwrap();
+
+    let report = library.invoke("verify_library", json!({ "removeOrphans": false })).unwrap();
+    assert_eq!(report["missing"].as_array().unwrap().len(), 1);
+    assert_eq!(report["orphaned_thumbnails"], 1);
+    let photos = library.invoke("list_photos", json!({})).unwrap();
+    assert_eq!(photos.as_array().unwrap().iter().filter(|p| p["missing"] == true).count(), 1);
+
+    library.invoke("verify_library", json!({ "removeOrphans": true })).unwrap();
+    assert!(!orphan.exists());
+}
+
+#[test]
+fn test_export_year_in_review() {
+    let library = TestLibrary::new("review");
+    library.import(&library.source(1));
+    let dest = library.dir.join("export");
+    std::fs::create_dir_all(&dest).unwrap();
+    // Only into a folder the user chose
+    assert!(library.invoke("export_year_in_review", json!({ "year": 2023, "destDir": dest })).is_err());
+    library.pick(&dest);
+
+    let page = library.invoke("export_year_in_review", json!({ "year": 2023, "destDir": dest })).unwrap();
+    let page = PathBuf::from(page.as_str().unwrap());
+    assert!(std::fs::read_to_string(&page).unwrap().contains("2023 in photos"));
+    let review: Value = serde_json::from_slice(&std::fs::read(page.with_file_name("review.json")).unwrap()).unwrap();
+    // The test photo has no capture date
+    assert_eq!(review["photo_count"].as_u64(), Some(0));
+}
+
+#[test]
+fn test_export_manifest() {
+    let library = TestLibrary::new("manifest");
+    let source = library.source(2);
+    library.import(&source);
+
+    let dest = library.dir.join("manifest.csv");
+    library.pick(&dest);
+    let listed = library.invoke("export_manifest", json!({ "query": {}, "destPath": dest })).unwrap();
+    assert_eq!(listed, 2);
+    let csv = std::fs::read_to_string(&dest).unwrap();
+    assert_eq!(csv.lines().count(), 3);
+    assert!(csv.contains("IMG_0001.png"));
+
+    let dest = library.dir.join("manifest.json");
+    library.invoke("export_manifest", json!({ "query": { "has_gps": true }, "destPath": dest })).unwrap();
+    let entries: Value = serde_json::from_slice(&std::fs::read(&dest).unwrap()).unwrap();
+    assert_eq!(entries, json!([]));
+    // Only manifest formats can be written
+    let config = library.dir.join("settings.conf");
+    assert!(library.invoke("export_manifest", json!({ "query": {}, "destPath": config })).is_err());
+    // And only where the user chose to save
+    let elsewhere = library.dir.join("data").join("manifest.csv");
+    assert!(library.invoke("export_manifest", json!({ "query": {}, "destPath": elsewhere })).is_err());
+}
+
+#[test]
+fn test_backup_restore_and_reset_snapshot() {
+    let library = TestLibrary::new("backup");
+    library.import(&library.source(2));
+    let backup = library.dir.join("before.db");
+    library.pick(&backup);
+    library.invoke("backup_library", json!({ "destPath": backup })).unwrap();
+
+    library.import(&library.source(3));
+    assert_eq!(library.photo_ids().len(), 3);
+    library.invoke("restore_library", json!({ "sourcePath": backup })).unwrap();
+    assert_eq!(library.photo_ids().len(), 2);
+
+    // Resetting the library keeps a snapshot it can be restored from
+    library.invoke("clear_cache", json!({ "category": "database" })).unwrap();
+    assert!(library.photo_ids().is_empty());
+    let snapshots: Vec<PathBuf> = std::fs::read_dir(library.dir.join("data").join("backups"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    let latest = snapshots.iter().max().unwrap();
+    library.invoke("restore_library", json!({ "sourcePath": latest })).unwrap();
+    assert_eq!(library.photo_ids().len(), 2);
+
+    assert!(library.invoke("backup_library", json!({ "destPath": library.dir.join("notes.txt") })).is_err());
+}
+
+#[test]
+fn test_file_reads_limited_to_library() {
+    let library = TestLibrary::new("reads");
+    let source = library.source(1);
+    library.import(&source);
+    let photo = source.join("IMG_0000.png");
+    assert!(library.invoke("read_file_bytes", json!({ "path": photo })).is_ok());
+
+    // A photo outside the library can't be read until the user picks it
+    let outside = library.dir.join("outside");
+    std::fs::create_dir_all(&outside).unwrap();
+    let other = outside.join("other.png");
+    std::fs::write(&other, PNG).unwrap();
+    assert!(library.invoke("read_file_bytes", json!({ "path": other })).is_err());
+    library.pick(&outside);
+    assert_eq!(library.invoke("read_file_bytes", json!({ "path": other })).unwrap(), json!(PNG));
+}
+
+#[test]
+fn test_previews_leave_no_temp_files() {
+    let library = TestLibrary::new("temp");
+    let source = library.source(1);
+    library.import(&source);
+    let id = library.photo_ids()[0];
+
+    let preview = library.invoke("get_enhanced_preview", json!({ "id": id })).unwrap();
+    assert!(Path::new(preview.as_str().unwrap()).is_file());
+    assert_eq!(std::fs::read_dir(library.dir.join("cache").join("tmp")).unwrap().count(), 0);
+}
+
+#[test]
+fn test_enhance_toggle_is_kept() {
+    let library = TestLibrary::new("enhance");
+    library.import(&library.source(1));
+    let id = library.photo_ids()[0];
+    assert_eq!(library.invoke("get_photo_enhance", json!({ "id": id })).unwrap(), Value::Null);
+    assert_eq!(library.invoke("set_enhance_enabled", json!({ "id": id, "enabled": true })).unwrap(), false);
+
+    library.invoke("get_enhanced_preview", json!({ "id": id })).unwrap();
+    assert_eq!(library.invoke("get_photo_enhance", json!({ "id": id })).unwrap()["enabled"], true);
+    assert_eq!(library.invoke("set_enhance_enabled", json!({ "id": id, "enabled": false })).unwrap(), true);
+    let enhance = library.invoke("get_photo_enhance", json!({ "id": id })).unwrap();
+    assert_eq!(enhance["enabled"], false);
+    assert!(enhance["recipe"]["white_point"].is_number());
+}
+
+#[test]
+fn test_unwritable_cache_uses_fallback() {
+    let library = TestLibrary::new("fallback_cache");
+    // A file where the cache directory should be, like a drive that's gone
+    let blocked = library.dir.join("blocked");
+    std::fs::write(&blocked, b"").unwrap();
+    let fallback = library.dir.join("fallback");
+
+    let paths = paths::LibraryPaths::open(&library.dir.join("data"), &blocked.join("cache"), None).unwrap();
+    let problem = paths.cache_unavailable.unwrap();
+    assert_eq!(problem.problem, fotos_core::CacheProblem::Unusable);
+    assert!(!paths.using_fallback_cache);
+
+    let paths = paths::LibraryPaths::open(&library.dir.join("data"), &blocked.join("cache"), Some(&fallback)).unwrap();
+    assert!(paths.cache_unavailable.is_none() && paths.using_fallback_cache);
+    assert!(Path::new(&paths.thumb_dir).starts_with(&fallback) && Path::new(&paths.tile_dir).is_dir());
+    // A usable cache stays where it is
+    let paths = paths::LibraryPaths::open(&library.dir.join("data"), &library.dir.join("cache"), Some(&fallback)).unwrap();
+    assert!(!paths.using_fallback_cache && paths.thumb_dir.ends_with("thumbnails"));
+
+    let refused = library.invoke("set_fallback_cache", json!({ "dir": blocked.join("cache") })).unwrap_err();
+    assert_eq!(refused["key"], "error.cache_unavailable");
+    library.invoke("set_fallback_cache", json!({ "dir": fallback })).unwrap();
+    assert_eq!(library.invoke("get_fallback_cache", json!({})).unwrap(), json!(fallback));
+    assert!(library.dir.join("fallback_cache.json").exists());
+    library.invoke("set_fallback_cache", json!({ "dir": null })).unwrap();
+    assert_eq!(library.invoke("get_fallback_cache", json!({})).unwrap(), json!(null));
+}
@@ -0,0 +1,136 @@
+//! Bug report material: versions, platform capabilities, library health and
+//! recent errors, with the user's home directory and name redacted.
+
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::Mutex;
+
+use fotos_core::ImportError;
+use serde_json::json;
+
+use crate::paths::LibraryPaths;
+use crate::storage::StorageBreakdown;
+
+/// How many recent errors are kept for the report
+const MAX_RECENT_ERRORS: usize = 50;
+/// Originals checked for readability - enough to spot a revoked permission
+const ORIGINALS_SAMPLE: usize = 200;
+
+/// Errors the webview reported since launch, oldest first.
+#[derive(Default)]
+pub struct RecentErrors(Mutex<VecDeque<String>>);
+
+impl RecentErrors {
+    pub fn record(&self, message: String) {
+        if let Ok(mut errors) = self.0.lock() {
+            if errors.len() == MAX_RECENT_ERRORS {
+                errors.pop_front();
+            }
+            errors.push_back(message);
+        }
+    }
+
+    fn snapshot(&self) -> Vec<String> {
+        self.0.lock().map(|errors| errors.iter().cloned().collect()).unwrap_or_default()
+    }
+}
+
+/// Per-file errors of the last import, replaced by each import.
+#[derive(Default)]
+pub struct ImportErrors(Mutex<Vec<ImportError>>);
+
+impl ImportErrors {
+    pub fn replace(&self, errors: Vec<ImportError>) {
+        if let Ok(mut last) = self.0.lock() {
+            *last = errors;
+        }
+    }
+
+    pub fn snapshot(&self) -> Vec<ImportError> {
+        self.0.lock().map(|errors| errors.clone()).unwrap_or_default()
+    }
+}
+
+/// Collects the report as pretty-printed JSON, ready to paste into an issue.
+pub fn collect(app_version: &str, library: &LibraryPaths, errors: &RecentErrors) -> String {
+    let database = match library.index() {
+        Ok(index) => {
+            let photos = index.list().unwrap_or_default();
+            json!({
+                "photos": photos.len(),
+                "integrity_problems": index.integrity_check().unwrap_or_else(|e| vec![e.to_string()]),
+                "unreadable_originals": photos
+                    .iter()
+                    .take(ORIGINALS_SAMPLE)
+                    .filter(|photo| std::fs::File::open(fotos_core::decode_path(&photo.path)).is_err())
+                    .count(),
+            })
+        }
+        Err(e) => json!({ "error": e.to_string() }),
+    };
+
+    let report = json!({
+        "versions": {
+            "app": app_version,
+            "core": fotos_core::get_version(),
+            "tauri": tauri::VERSION,
+        },
+        "platform": {
+            "os": std::env::consts::OS,
+            "arch": std::env::consts::ARCH,
+            "native_quick_look": native_quick_look(),
+        },
+        "database": database,
+        "storage": StorageBreakdown::measure(library),
+        "permissions": {
+            "library_writable": writable(Path::new(&library.preview_dir)),
+            "thumbnails_writable": writable(Path::new(&library.thumb_dir)),
+            "tiles_writable": writable(Path::new(&library.tile_dir)),
+        },
+        "paths": library,
+        "recent_errors": errors.snapshot(),
+    });
+
+    redact(&serde_json::to_string_pretty(&report).unwrap_or_default())
+}
+
+/// Whether the quick preview uses the OS previewer rather than falling back
+/// to the default viewer (see `file_service::quick_look`).
+fn native_quick_look() -> bool {
+    if cfg!(target_os = "macos") {
+        return true;
+    }
+    cfg!(target_os = "linux") && on_path("gdbus")
+}
+
+fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+/// Probes with a throwaway file, since permission bits don't tell the whole
+/// story on sandboxed or network volumes.
+fn writable(dir: &Path) -> bool {
+    let probe = dir.join(".fotos-write-test");
+    let ok = std::fs::write(&probe, b"").is_ok();
+    let _ = std::fs::remove_file(&probe);
+    ok
+}
+
+/// Replaces the home directory with `~` and the user name with `<user>`.
+fn redact(report: &str) -> String {
+    let mut redacted = report.to_string();
+    for var in ["HOME", "USERPROFILE"] {
+        if let Some(home) = std::env::var(var).ok().filter(|home| home.len() > 1) {
+            // Windows paths appear with escaped backslashes inside JSON strings
+            redacted = redacted.replace(&home.replace('\\', "\\\\"), "~");
+        }
+    }
+    for var in ["USER", "USERNAME"] {
+        if let Some(user) = std::env::var(var).ok().filter(|user| user.len() > 2) {
+            redacted = redacted.replace(&user, "<user>");
+        }
+    }
+    redacted
+}
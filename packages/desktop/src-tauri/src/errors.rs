@@ -0,0 +1,51 @@
+//! Errors for commands whose failures are shown to the user. Instead of a bare
+//! English string the webview gets a message key and parameters it can
+//! localize, plus the English text as a fallback.
+
+use std::path::Path;
+
+use fotos_core::{CoreError, UserMessage};
+
+#[derive(Debug, serde::Serialize)]
+pub struct CommandError {
+    #[serde(flatten)]
+    pub message: UserMessage,
+    pub fallback: String,
+}
+
+impl CommandError {
+    /// For `map_err`: a core error that happened while working on `file`.
+    pub fn with_file(file: &Path) -> impl Fn(CoreError) -> CommandError + '_ {
+        move |err| CommandError {
+            message: err.user_message(Some(file)),
+            fallback: format!("{} ({})", err, file.display()),
+        }
+    }
+}
+
+impl From<CoreError> for CommandError {
+    fn from(err: CoreError) -> Self {
+        CommandError { message: err.user_message(None), fallback: err.to_string() }
+    }
+}
+
+impl From<std::io::Error> for CommandError {
+    fn from(err: std::io::Error) -> Self {
+        CoreError::from(err).into()
+    }
+}
+
+/// Errors that are already plain text, such as path validation
+impl From<String> for CommandError {
+    fn from(detail: String) -> Self {
+        let mut message = UserMessage { key: "error.generic".to_string(), ..Default::default() };
+        message.params.insert("detail".to_string(), detail.clone());
+        CommandError { message, fallback: detail }
+    }
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.fallback)
+    }
+}
@@ -0,0 +1,40 @@
+//! Where thumbnails, map tiles and temp files go when the usual locations
+//! can't be written to, e.g. a home folder on a read-only or full volume.
+//! Picked in settings and kept as JSON in the app data dir; `LibraryPaths`
+//! switches to it at the next launch if the usual locations fail their check.
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use fotos_core::CoreError;
+
+use crate::errors::CommandError;
+
+pub struct FallbackCache {
+    file: PathBuf,
+    dir: Mutex<Option<String>>,
+}
+
+impl FallbackCache {
+    pub fn restore(file: PathBuf) -> Self {
+        let dir = std::fs::read(&file).ok().and_then(|bytes| serde_json::from_slice(&bytes).ok());
+        Self { file, dir: Mutex::new(dir) }
+    }
+
+    pub fn get(&self) -> Option<String> {
+        self.dir.lock().ok().and_then(|dir| dir.clone())
+    }
+
+    /// Saves `dir` as the fallback, or forgets it when `None`. A directory
+    /// that fails the same check as the usual locations is refused.
+    pub fn set(&self, dir: Option<String>) -> Result<(), CommandError> {
+        if let Some(problem) = dir.clone().and_then(fotos_core::check_cache_dir) {
+            return Err(CoreError::CacheUnavailable(format!("{}: {}", problem.path, problem.detail)).into());
+        }
+        std::fs::write(&self.file, serde_json::to_vec(&dir).map_err(|e| e.to_string())?)?;
+        if let Ok(mut current) = self.dir.lock() {
+            *current = dir;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+//! Hands originals over to the OS: file manager and native quick preview.
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Shows the file selected in Finder / Explorer / the file manager.
+pub fn reveal_in_file_manager(path: &Path) -> Result<(), String> {
+    tauri_plugin_opener::reveal_item_in_dir(path).map_err(|e| e.to_string())
+}
+
+/// Opens the OS-native quick preview for `path`: Quick Look on macOS,
+/// GNOME Sushi on Linux. Windows has no built-in quick preview, so there
+/// (and when the previewer is missing) the file opens in its default viewer.
+pub fn quick_look(path: &Path) -> Result<(), String> {
+    if cfg!(target_os = "macos") {
+        return spawn_detached(Command::new("qlmanage").arg("-p").arg(path));
+    }
+    if cfg!(target_os = "linux") {
+        let shown = Command::new("gdbus")
+            .args(["call", "--session",
+                "--dest", "org.gnome.NautilusPreviewer",
+                "--object-path", "/org/gnome/NautilusPreviewer",
+                "--method", "org.gnome.NautilusPreviewer.ShowFile"])
+            .arg(file_uri(path))
+            .args(["0", "false"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|status| status.success())
+            .unwrap_or(false);
+        if shown {
+            return Ok(());
+        }
+    }
+    tauri_plugin_opener::open_path(path, None::<&str>).map_err(|e| e.to_string())
+}
+
+/// The preview stays open after the command returns; reap it in the background.
+fn spawn_detached(command: &mut Command) -> Result<(), String> {
+    let mut child = command
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// `file://` URI with everything outside the unreserved set percent-encoded.
+fn file_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => uri.push(byte as char),
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
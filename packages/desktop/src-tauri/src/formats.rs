@@ -0,0 +1,37 @@
+//! File extensions the user imports beyond core's defaults, such as TIFF or
+//! JPEG XL. Kept as JSON in the app data dir and handed to core at launch,
+//! so scans, pasted-image sniffing and the file picker go by the same list.
+
+use std::path::PathBuf;
+
+use crate::errors::CommandError;
+
+pub struct ExtraExtensions {
+    file: PathBuf,
+}
+
+impl ExtraExtensions {
+    /// Applies the saved extensions. A file core rejects (edited by hand) is
+    /// ignored, leaving the defaults.
+    pub fn restore(file: PathBuf) -> Self {
+        let saved: Option<Vec<String>> = std::fs::read(&file)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok());
+        if let Some(Err(e)) = saved.map(fotos_core::set_extra_extensions) {
+            println!("[Formats] ignoring saved extensions: {}", e);
+        }
+        Self { file }
+    }
+
+    /// The extensions added by the user; core lists them after its defaults.
+    pub fn get(&self) -> Vec<String> {
+        fotos_core::supported_extensions().split_off(fotos_core::fs::formats::DEFAULT_EXTENSIONS.len())
+    }
+
+    /// Replaces the user's extensions and saves them. Returns every supported extension.
+    pub fn set(&self, extensions: Vec<String>) -> Result<Vec<String>, CommandError> {
+        let supported = fotos_core::set_extra_extensions(extensions)?;
+        std::fs::write(&self.file, serde_json::to_vec(&self.get()).map_err(|e| e.to_string())?)?;
+        Ok(supported)
+    }
+}
@@ -0,0 +1,45 @@
+//! The HTTP client shared by everything the app downloads: map tiles, weather
+//! lookups and images saved from the web.
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Largest map tile accepted
+pub const MAX_TILE_BYTES: usize = 5 * 1024 * 1024;
+/// Largest image accepted by `import_from_url`
+pub const MAX_IMAGE_BYTES: usize = 100 * 1024 * 1024;
+
+/// One client for the app, so connections are pooled across requests.
+pub fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            // Tile servers such as OpenStreetMap's refuse anonymous clients
+            .user_agent(concat!("Fotos/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .expect("static client configuration is valid")
+    })
+}
+
+/// Downloads `url`, failing on error statuses and on bodies over `max_bytes`
+/// (checked as the body arrives, since Content-Length may be missing or wrong).
+pub async fn download(url: &str, max_bytes: usize) -> Result<Vec<u8>, String> {
+    let mut response = client()
+        .get(url)
+        .send().await.map_err(|e| e.to_string())?
+        .error_for_status().map_err(|e| e.to_string())?;
+    let too_large = || format!("{} is larger than {} MB", url, max_bytes / (1024 * 1024));
+    if response.content_length().is_some_and(|len| len > max_bytes as u64) {
+        return Err(too_large());
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+        if body.len() + chunk.len() > max_bytes {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
@@ -1,5 +1,27 @@
-use fotos_core::{PhotoCoreConfig, PhotoIndex, ImportResult, PhotoInfo};
+use fotos_core::{PhotoCoreConfig, PhotoIndex, ImportResult, ImportStage, PhotoInfo, PhotoLocation, PhotoSort, Place, PhotoAlbum, DatePrecision, UndatedCluster};
 use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::Manager;
+
+mod audit;
+mod bookmarks;
+#[cfg(test)]
+mod command_tests;
+mod diagnostics;
+mod errors;
+mod fallback_cache;
+mod file_service;
+mod formats;
+mod http;
+mod open_with;
+mod paths;
+mod shared_index;
+mod startup;
+mod storage;
+mod weather;
+mod viewport;
+mod watch;
+use errors::CommandError;
+use weather::WeatherProvider;
 
 // Global cancellation flag for import operations
 static IMPORT_CANCELLED: AtomicBool = AtomicBool::new(false);
@@ -19,64 +41,221 @@ fn get_core_version() -> String {
     fotos_core::get_version()
 }
 
+/// Library locations, for display and "reveal in folder" in settings
+#[tauri::command]
+fn get_library_paths(library: tauri::State<'_, paths::LibraryPaths>) -> paths::LibraryPaths {
+    library.inner().clone()
+}
+
+/// The directory caches move to when their usual one can't be written to
+#[tauri::command]
+fn get_fallback_cache(fallback: tauri::State<'_, fallback_cache::FallbackCache>) -> Option<String> {
+    fallback.get()
+}
+
+/// Sets or clears (`None`) the fallback cache directory, used from the next launch
+#[tauri::command]
+fn set_fallback_cache(dir: Option<String>, fallback: tauri::State<'_, fallback_cache::FallbackCache>) -> Result<(), CommandError> {
+    fallback.set(dir)
+}
+
+/// Bytes used by originals, caches and the database, for the settings storage chart
+#[tauri::command]
+async fn get_storage_breakdown(library: tauri::State<'_, paths::LibraryPaths>) -> Result<storage::StorageBreakdown, String> {
+    Ok(storage::StorageBreakdown::measure(&library))
+}
+
+/// Redacted JSON report for bug reports: versions, capabilities, library health, recent errors
+#[tauri::command]
+async fn run_diagnostics(
+    app: tauri::AppHandle,
+    library: tauri::State<'_, paths::LibraryPaths>,
+    errors: tauri::State<'_, diagnostics::RecentErrors>,
+) -> Result<String, String> {
+    Ok(diagnostics::collect(&app.package_info().version.to_string(), &library, &errors))
+}
+
+/// Errors caught in the webview, kept for `run_diagnostics`
+#[tauri::command]
+fn log_error(message: String, errors: tauri::State<'_, diagnostics::RecentErrors>) {
+    errors.record(message);
+}
+
 #[tauri::command]
-async fn list_photos(db_path: String, thumb_dir: String) -> Result<Vec<PhotoInfo>, String> {
+async fn list_photos(library: tauri::State<'_, paths::LibraryPaths>) -> Result<Vec<PhotoInfo>, String> {
+    let db_path = library.db_path.clone();
+    let thumb_dir = library.thumb_dir.clone();
     // Ensure parent directory exists
     if let Some(parent) = std::path::Path::new(&db_path).parent() {
         std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
     }
 
-    let index = PhotoIndex::open(db_path)
+    let index = library.index()
         .map_err(|e| e.to_string())?;
     
     let mut photos = index.list().map_err(|e| e.to_string())?;
-    
-    // Populate thumb_path and file_size
-    let thumbnailer = fotos_core::Thumbnailer::new(std::path::PathBuf::from(&thumb_dir));
-    let spec = fotos_core::ThumbnailSpec { width: 256, height: 256 };
-    for photo in &mut photos {
-        let source_path = std::path::Path::new(&photo.path);
-
-        // Get thumbnail path
-        match thumbnailer.get_cached_path(source_path, &spec) {
-            Ok(Some(path)) => {
-                photo.thumb_path = Some(path.to_string_lossy().to_string());
-            }
-            Ok(None) => {
-                photo.thumb_path = None;
-            }
-            Err(_) => {
-                photo.thumb_path = None;
-            }
-        }
+    fill_thumb_paths(&mut photos, &thumb_dir);
 
-        // Get file size
-        if let Ok(metadata) = std::fs::metadata(source_path) {
-            photo.file_size = metadata.len();
-        }
+    Ok(photos)
+}
+
+/// One page of the library for the virtualized grid
+#[tauri::command]
+async fn list_photos_page(
+    offset: u32,
+    limit: u32,
+    sort: PhotoSort,
+    library: tauri::State<'_, paths::LibraryPaths>,
+) -> Result<Vec<PhotoInfo>, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    let mut photos = index.list_page(offset, limit, sort).map_err(|e| e.to_string())?;
+    fill_thumb_paths(&mut photos, &library.thumb_dir);
+    Ok(photos)
+}
+
+/// Photos that look like `id` (resized copies, bursts, edits), closest first
+#[tauri::command]
+async fn find_similar_photos(
+    id: i64,
+    max_distance: u32,
+    library: tauri::State<'_, paths::LibraryPaths>,
+) -> Result<Vec<fotos_core::SimilarPhoto>, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    let mut similar = index.find_similar(id, max_distance).map_err(|e| e.to_string())?;
+    for entry in &mut similar {
+        fill_thumb_paths(std::slice::from_mut(&mut entry.photo), &library.thumb_dir);
     }
+    Ok(similar)
+}
 
+/// The `top_k` photos most like `id`, for "more like this"
+#[tauri::command]
+async fn find_similar_to(
+    id: i64,
+    top_k: u32,
+    library: tauri::State<'_, paths::LibraryPaths>,
+) -> Result<Vec<fotos_core::SimilarPhoto>, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    let mut similar = index.find_similar_to(id, top_k).map_err(|e| e.to_string())?;
+    for entry in &mut similar {
+        fill_thumb_paths(std::slice::from_mut(&mut entry.photo), &library.thumb_dir);
+    }
+    Ok(similar)
+}
+
+/// Groups of near-duplicate photos across the library, with thumbs
+#[tauri::command]
+async fn get_duplicate_report(
+    max_distance: u32,
+    library: tauri::State<'_, paths::LibraryPaths>,
+) -> Result<Vec<fotos_core::DuplicateGroup>, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    let mut groups = index.duplicate_report(max_distance).map_err(|e| e.to_string())?;
+    for group in &mut groups {
+        fill_thumb_paths(&mut group.photos, &library.thumb_dir);
+    }
+    Ok(groups)
+}
+
+/// Geotagged photos inside the map viewport, with thumbs
+#[tauri::command]
+async fn list_photos_in_bounds(
+    min_lat: f64,
+    min_lon: f64,
+    max_lat: f64,
+    max_lon: f64,
+    library: tauri::State<'_, paths::LibraryPaths>,
+) -> Result<Vec<PhotoInfo>, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    let mut photos = index.list_in_bounds(min_lat, min_lon, max_lat, max_lon).map_err(|e| e.to_string())?;
+    fill_thumb_paths(&mut photos, &library.thumb_dir);
     Ok(photos)
 }
 
+/// Photo counts per day, month or year with cover thumbnails, for the timeline scrubber
+#[tauri::command]
+async fn get_timeline(
+    granularity: DatePrecision,
+    library: tauri::State<'_, paths::LibraryPaths>,
+) -> Result<Vec<fotos_core::TimelineBucket>, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    let mut buckets = index.timeline(granularity).map_err(|e| e.to_string())?;
+    for bucket in &mut buckets {
+        fill_thumb_paths(std::slice::from_mut(&mut bucket.cover), &library.thumb_dir);
+    }
+    Ok(buckets)
+}
+
 #[tauri::command]
-async fn import_photos(
-    window: tauri::Window,
+async fn count_photos(library: tauri::State<'_, paths::LibraryPaths>) -> Result<u64, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    index.count().map_err(|e| e.to_string())
+}
+
+/// Populates thumb_path, which the index doesn't store
+fn fill_thumb_paths(photos: &mut [PhotoInfo], thumb_dir: &str) {
+    let thumbnailer = fotos_core::Thumbnailer::new(std::path::PathBuf::from(thumb_dir));
+    let spec = fotos_core::ThumbnailSpec { width: 256, height: 256, rotation: 0 };
+    for photo in photos {
+        let source_path = fotos_core::decode_path(&photo.path);
+        let spec = fotos_core::ThumbnailSpec { rotation: photo.metadata.rotation, ..spec };
+        photo.thumb_path = match thumbnailer.get_cached_path(&source_path, &spec) {
+            Ok(Some(path)) => Some(path.to_string_lossy().to_string()),
+            Ok(None) | Err(_) => None,
+        };
+    }
+}
+
+/// Photos either side of the one in the detail view, with thumbs, for the filmstrip
+#[tauri::command]
+async fn get_neighbors(
+    id: i64,
+    query: fotos_core::PhotoQuery,
+    before: u32,
+    after: u32,
+    library: tauri::State<'_, paths::LibraryPaths>,
+) -> Result<fotos_core::Neighbors, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    let mut neighbors = index.neighbors(id, query, before, after).map_err(|e| e.to_string())?;
+    fill_thumb_paths(&mut neighbors.before, &library.thumb_dir);
+    fill_thumb_paths(&mut neighbors.after, &library.thumb_dir);
+    Ok(neighbors)
+}
+
+#[tauri::command]
+async fn import_photos<R: tauri::Runtime>(
+    window: tauri::Window<R>,
     root_path: String,
-    db_path: String,
-    thumb_dir: String,
-) -> Result<ImportResult, String> {
+    dry_run: Option<bool>,
+    reimport_deleted: Option<bool>,
+    filters: Option<fotos_core::ImportOptions>,
+    library: tauri::State<'_, paths::LibraryPaths>,
+    bookmarks: tauri::State<'_, bookmarks::Bookmarks>,
+) -> Result<ImportResult, CommandError> {
+    let db_path = library.db_path.clone();
+    let thumb_dir = library.thumb_dir.clone();
+    // Extensions, subfolders, file size and date range; the other options come as arguments
+    let filters = filters.unwrap_or_default();
+    // Dry run: report what would happen, without creating thumbnails or index entries
+    if dry_run.unwrap_or(false) {
+        let index = library.index()?;
+        let config = PhotoCoreConfig { thumbnail_dir: thumb_dir, thumbnail_size: 256 };
+        let options = fotos_core::ImportOptions { dry_run: true, ..filters };
+        return Ok(fotos_core::run_import_pipeline(root_path, index, config, options)?);
+    }
+
     // Reset cancellation flag at start
     IMPORT_CANCELLED.store(false, Ordering::SeqCst);
+    bookmarks.remember(&root_path);
 
     // Ensure parent directories exist
     if let Some(parent) = std::path::Path::new(&db_path).parent() {
-        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        std::fs::create_dir_all(parent)?;
     }
-    std::fs::create_dir_all(&thumb_dir).map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&thumb_dir)?;
 
-    let index = PhotoIndex::open(db_path)
-        .map_err(|e| e.to_string())?;
+    let index = library.index()
+        ?;
 
     let config = PhotoCoreConfig {
         thumbnail_dir: thumb_dir,
@@ -85,83 +264,497 @@ async fn import_photos(
 
     let root_path_buf = std::path::Path::new(&root_path);
 
-    // Support both single file and directory import
+    // Support both single file and directory import. A folder import is
+    // journaled so one interrupted by a crash picks up where it stopped.
     let photos = if root_path_buf.is_file() {
         vec![root_path_buf.to_path_buf()]
     } else {
-        fotos_core::scan_photos(root_path_buf).map_err(|e| e.to_string())?
+        fotos_core::scan_photos_with(root_path_buf, &filters)?
+    };
+    let indexed = index.paths_exist(photos.iter().map(|p| fotos_core::encode_path(p)).collect())?;
+    let (photos, session): (Vec<_>, _) = if root_path_buf.is_file() {
+        (photos.into_iter().zip(indexed).collect(), None)
+    } else {
+        // Only files still to import are journaled; indexed ones are just counted as skipped
+        let new_paths = photos.iter().zip(&indexed).filter(|(_, indexed)| !**indexed).map(|(p, _)| fotos_core::encode_path(p)).collect();
+        let session = index.begin_import_session(root_path.clone(), new_paths)?;
+        if session.resumed {
+            println!("[Import] Resuming: {} of {} files done before", session.done, session.done + session.pending);
+        }
+        let todo: std::collections::HashSet<String> = index.pending_import_files(session.id)?.into_iter().collect();
+        let photos = photos.into_iter().zip(indexed).filter(|(p, indexed)| *indexed || todo.contains(&fotos_core::encode_path(p))).collect();
+        (photos, Some(session))
     };
     let total = photos.len();
+    let sizes: Vec<u64> = photos.iter()
+        .map(|(p, _)| std::fs::metadata(p).map(|m| m.len()).unwrap_or(0))
+        .collect();
+    let total_bytes: u64 = sizes.iter().sum();
+
+    // One worker per core unless the import asks for a number
+    let workers = match filters.concurrency {
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n as usize,
+    };
+    let reimport_deleted = reimport_deleted.unwrap_or(false);
 
     let mut result = ImportResult::default();
+    let mut last_error = None;
     let mut skipped = 0usize;
-    for (i, path) in photos.into_iter().enumerate() {
-        // Check for cancellation
-        if IMPORT_CANCELLED.load(Ordering::SeqCst) {
-            println!("[Import] CANCELLED at {}/{}", i + 1, total);
-            use tauri::Emitter;
-            let _ = window.emit("import-cancelled", serde_json::json!({
-                "current": i,
-                "total": total,
-                "success": result.success,
-                "failure": result.failure
-            }));
-            break;
+    let mut throughput = Throughput::new();
+    let mut bytes_processed = 0u64;
+    let mut handled = 0usize;
+    // Workers take the next file as they free up and do its metadata, hash,
+    // thumbnail and insert; results come back in completion order and are
+    // counted, journaled and reported here, on one thread
+    let next = std::sync::atomic::AtomicUsize::new(0);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::scope(|scope| -> Result<(), CommandError> {
+        for _ in 0..workers.min(total) {
+            let sender = sender.clone();
+            let (next, photos, index, config, filters) = (&next, &photos, &index, &config, &filters);
+            scope.spawn(move || {
+                while !IMPORT_CANCELLED.load(Ordering::SeqCst) {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some((path, indexed)) = photos.get(i) else { break };
+                    // Skip if already imported (fast path - avoid expensive metadata/hash/thumbnail work)
+                    let imported = (!indexed).then(|| {
+                        let mut stages = StageTimings::default();
+                        (import_file(index, config, path, reimport_deleted, filters, &mut stages), stages)
+                    });
+                    if sender.send((i, imported)).is_err() {
+                        break;
+                    }
+                }
+            });
         }
+        drop(sender);
+
+        use tauri::Emitter;
+        for (i, imported) in receiver {
+            let path = &photos[i].0;
+            let path_str = fotos_core::encode_path(path);
+            handled += 1;
+            bytes_processed += sizes[i];
+            throughput.record(handled as u64, bytes_processed);
 
-        let path_str = path.to_string_lossy().to_string();
+            let Some((imported, stages)) = imported else {
+                skipped += 1;
+                // Emit progress but mark as skipped
+                let _ = window.emit("import-progress", serde_json::json!({
+                    "current": handled,
+                    "total": total,
+                    "success": result.success,
+                    "failure": result.failure,
+                    "skipped": skipped,
+                    "last_path": path_str,
+                    "bytes_processed": bytes_processed,
+                    "total_bytes": total_bytes,
+                    "eta_seconds": throughput.eta_seconds(total_bytes.saturating_sub(bytes_processed))
+                }));
+                continue;
+            };
+            throughput.stages.add(stages);
+
+            match imported {
+                Ok(FileImport::SkippedDeleted) => {
+                    println!("[Import] SKIPPED (deleted before): {}", path_str);
+                    result.skipped_deleted += 1;
+                },
+                Ok(FileImport::Filtered) => {
+                    println!("[Import] SKIPPED (outside date range): {}", path_str);
+                    result.skipped_filtered += 1;
+                },
+                Ok(FileImport::Imported) => {
+                    println!("[Import] SUCCESS: {}", path_str);
+                    result.record(root_path_buf, path, true);
+                },
+                Err(e) => {
+                    println!("[Import] FAILED: {} - {}", path_str, e.error);
+                    result.record_failure(root_path_buf, path, e.stage, e.error.to_string());
+                    last_error = Some(CommandError::with_file(path)(e.error));
+                },
+            }
+            if let Some(session) = &session {
+                index.mark_import_files_done(session.id, vec![path_str.clone()])?;
+            }
 
-        // Skip if already imported (fast path - avoid expensive metadata/hash/thumbnail work)
-        if let Ok(Some(_)) = index.get_by_path(path_str.clone()) {
-            skipped += 1;
-            // Emit progress but mark as skipped
-            use tauri::Emitter;
+            // Emit progress every photo
+            if let Some(report) = throughput.report() {
+                let _ = window.emit("import-throughput", report);
+            }
             let _ = window.emit("import-progress", serde_json::json!({
-                "current": i + 1,
+                "current": handled,
                 "total": total,
                 "success": result.success,
                 "failure": result.failure,
-                "skipped": skipped,
-                "last_path": path_str
+                "last_path": path_str,
+                "last_error": last_error,
+                "bytes_processed": bytes_processed,
+                "total_bytes": total_bytes,
+                "eta_seconds": throughput.eta_seconds(total_bytes.saturating_sub(bytes_processed))
             }));
-            continue;
         }
+        Ok(())
+    })?;
 
-        // Use a block to ensure we can handle errors per-file
-        let file_result = (|| -> Result<(), String> {
-            let metadata = fotos_core::read_metadata(&path).map_err(|e| e.to_string())?;
-            let hash = fotos_core::compute_hash(&path).map_err(|e| e.to_string())?;
-            // Thumbnail generation may fail if no EXIF thumbnail - that's OK, frontend uses original
-            let _ = fotos_core::generate_thumbnail(&path, &config);
-            index.insert(path_str.clone(), hash.clone(), metadata).map_err(|e| e.to_string())?;
-            Ok(())
-        })();
+    // Files the workers already finished count; the rest are left for next time
+    let cancelled = handled < total && IMPORT_CANCELLED.load(Ordering::SeqCst);
+    if cancelled {
+        println!("[Import] CANCELLED at {}/{}", handled, total);
+        use tauri::Emitter;
+        let _ = window.emit("import-cancelled", serde_json::json!({
+            "current": handled,
+            "total": total,
+            "success": result.success,
+            "failure": result.failure
+        }));
+    }
 
-        match file_result {
-            Ok(_) => {
-                println!("[Import] SUCCESS: {}", path_str);
-                result.success += 1;
-            },
+    // A cancelled import keeps its session, so importing the folder again resumes it
+    if let Some(session) = session {
+        if !cancelled {
+            index.finish_import_session(session.id)?;
+        }
+    }
+    window.state::<diagnostics::ImportErrors>().replace(result.errors.clone());
+
+    Ok(result)
+}
+
+/// What `import_file` did with a file
+enum FileImport {
+    Imported,
+    /// The user deleted this photo before
+    SkippedDeleted,
+    /// Taken outside the import's date range
+    Filtered,
+}
+
+/// A file that failed to import, and the stage it failed in
+struct FailedImport {
+    stage: ImportStage,
+    error: fotos_core::CoreError,
+}
+
+/// Imports one file.
+fn import_file(
+    index: &PhotoIndex,
+    config: &PhotoCoreConfig,
+    path: &std::path::Path,
+    reimport_deleted: bool,
+    filters: &fotos_core::ImportOptions,
+    stages: &mut StageTimings,
+) -> Result<FileImport, FailedImport> {
+    let at = |stage| move |error| FailedImport { stage, error };
+    let metadata = stages.time(ImportStage::Metadata, || fotos_core::read_metadata(path)).map_err(at(ImportStage::Metadata))?;
+    if !filters.accepts_date(metadata.taken_at) {
+        return Ok(FileImport::Filtered);
+    }
+    let hash = stages.time(ImportStage::Hash, || fotos_core::compute_hash(path)).map_err(at(ImportStage::Hash))?;
+    if !reimport_deleted && index.is_deleted(fotos_core::encode_path(path), hash.clone()).map_err(at(ImportStage::Index))? {
+        return Ok(FileImport::SkippedDeleted);
+    }
+    // Thumbnail generation may fail if no EXIF thumbnail - that's OK, frontend uses original
+    let _ = stages.time(ImportStage::Thumbnail, || fotos_core::generate_thumbnail(path, config));
+    stages.time(ImportStage::Index, || index.insert(fotos_core::encode_path(path), hash, metadata)).map_err(at(ImportStage::Index))?;
+    stages.files += 1;
+    Ok(FileImport::Imported)
+}
+
+/// Why files of the last folder or clipboard import failed, at most
+/// `fotos_core::MAX_IMPORT_ERRORS` of them
+#[tauri::command]
+fn get_import_errors(import_errors: tauri::State<'_, diagnostics::ImportErrors>) -> Vec<fotos_core::ImportError> {
+    import_errors.snapshot()
+}
+
+/// Imports photos added to `root_path` from now on, across launches, until
+/// `stop_watch`. Each batch is announced with a "watch-imported" event.
+#[tauri::command]
+async fn start_watch<R: tauri::Runtime>(
+    root_path: String,
+    app: tauri::AppHandle<R>,
+    library: tauri::State<'_, paths::LibraryPaths>,
+    bookmarks: tauri::State<'_, bookmarks::Bookmarks>,
+    watchers: tauri::State<'_, watch::Watchers>,
+) -> Result<(), CommandError> {
+    bookmarks.remember(&root_path);
+    watchers.start(root_path, &app, &library)
+}
+
+/// Stops watching `root_path`. Returns false if it wasn't watched.
+#[tauri::command]
+async fn stop_watch(root_path: String, watchers: tauri::State<'_, watch::Watchers>) -> Result<bool, CommandError> {
+    Ok(watchers.stop(&root_path))
+}
+
+/// Folders being watched, for settings
+#[tauri::command]
+fn list_watches(watchers: tauri::State<'_, watch::Watchers>) -> Vec<String> {
+    watchers.list()
+}
+
+/// Every file extension imported as a photo, for file pickers
+#[tauri::command]
+fn get_supported_extensions() -> Vec<String> {
+    fotos_core::supported_extensions()
+}
+
+/// The extensions the user added to the defaults, for settings
+#[tauri::command]
+fn get_extra_extensions(formats: tauri::State<'_, formats::ExtraExtensions>) -> Vec<String> {
+    formats.get()
+}
+
+/// Imports files with `extensions` too (e.g. "heic", "jxl"), remembered
+/// across launches. Returns every supported extension.
+#[tauri::command]
+fn set_extra_extensions(
+    extensions: Vec<String>,
+    formats: tauri::State<'_, formats::ExtraExtensions>,
+) -> Result<Vec<String>, CommandError> {
+    formats.set(extensions)
+}
+
+/// Catches the library up with a folder imported before: new files are
+/// imported, changed ones re-read and gone ones flagged missing. Far quicker
+/// than importing the folder again, since unchanged files aren't read.
+#[tauri::command]
+async fn rescan_folder(
+    root_path: String,
+    library: tauri::State<'_, paths::LibraryPaths>,
+) -> Result<fotos_core::RescanReport, CommandError> {
+    let index = library.index()?;
+    let config = PhotoCoreConfig {
+        thumbnail_dir: library.thumb_dir.clone(),
+        thumbnail_size: 256,
+    };
+    Ok(fotos_core::rescan(root_path, index, config)?)
+}
+
+/// Imports what was on the clipboard when the user pasted. Image data
+/// (screenshots, images copied from other apps) is saved to the library's
+/// pasted folder first; copied file paths are imported where they are.
+/// Pasting is explicit, so previously deleted photos come back.
+#[tauri::command]
+async fn import_from_clipboard(
+    images: Vec<Vec<u8>>,
+    file_paths: Vec<String>,
+    library: tauri::State<'_, paths::LibraryPaths>,
+    import_errors: tauri::State<'_, diagnostics::ImportErrors>,
+) -> Result<ImportResult, CommandError> {
+    let index = library.index()?;
+    let config = PhotoCoreConfig {
+        thumbnail_dir: library.thumb_dir.clone(),
+        thumbnail_size: 256,
+    };
+    let pasted_dir = std::path::PathBuf::from(&library.pasted_dir);
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+
+    let mut result = ImportResult::default();
+    let mut files = Vec::new();
+    for (i, bytes) in images.iter().enumerate() {
+        let Some(extension) = paths::image_extension(bytes) else {
+            println!("[Import] FAILED: clipboard image {} - unsupported format", i);
+            result.failure += 1;
+            continue;
+        };
+        let path = pasted_dir.join(format!("paste-{}-{}.{}", stamp, i, extension));
+        std::fs::write(&path, bytes).map_err(|e| CommandError::with_file(&path)(e.into()))?;
+        // Pasting the same screenshot twice shouldn't add it twice
+        let hash = fotos_core::compute_hash(&path).map_err(CommandError::with_file(&path))?;
+        if index.get_by_hash(hash)?.is_some() {
+            let _ = std::fs::remove_file(&path);
+            continue;
+        }
+        files.push(path);
+    }
+    for path in &file_paths {
+        match paths::photo_file(path) {
+            Ok(path) => {
+                if index.get_by_path(fotos_core::encode_path(&path))?.is_none() {
+                    files.push(path);
+                }
+            }
             Err(e) => {
-                println!("[Import] FAILED: {} - {}", path_str, e);
+                println!("[Import] FAILED: {} - {}", path, e);
                 result.failure += 1;
-            },
+            }
         }
+    }
 
-        // Emit progress every photo
-        use tauri::Emitter;
-        let _ = window.emit("import-progress", serde_json::json!({
-            "current": i + 1,
-            "total": total,
-            "success": result.success,
-            "failure": result.failure,
-            "last_path": path_str
-        }));
+    for path in files {
+        let root = path.parent().unwrap_or(&pasted_dir).to_path_buf();
+        match import_file(&index, &config, &path, true, &Default::default(), &mut Default::default()) {
+            Ok(_) => {
+                println!("[Import] SUCCESS: {}", path.display());
+                result.record(&root, &path, true);
+            }
+            Err(e) => {
+                println!("[Import] FAILED: {} - {}", path.display(), e.error);
+                result.record_failure(&root, &path, e.stage, e.error.to_string());
+            }
+        }
     }
+    import_errors.replace(result.errors.clone());
 
     Ok(result)
 }
 
+/// Saves an image from the web into the library and records the URL it came
+/// from, e.g. to keep reference images. Like pasting, it's explicit, so a
+/// previously deleted image comes back; one already in the library is returned as is.
+#[tauri::command]
+async fn import_from_url(url: String, library: tauri::State<'_, paths::LibraryPaths>) -> Result<PhotoInfo, CommandError> {
+    paths::check_http_url(&url)?;
+    let bytes = http::download(&url, http::MAX_IMAGE_BYTES).await?;
+    let extension = paths::image_extension(&bytes).ok_or_else(|| format!("Unsupported image format: {}", url))?;
+
+    let index = library.index()?;
+    let config = PhotoCoreConfig {
+        thumbnail_dir: library.thumb_dir.clone(),
+        thumbnail_size: 256,
+    };
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let path = std::path::Path::new(&library.pasted_dir).join(format!("web-{}.{}", stamp, extension));
+    std::fs::write(&path, &bytes).map_err(|e| CommandError::with_file(&path)(e.into()))?;
+
+    let hash = fotos_core::compute_hash(&path).map_err(CommandError::with_file(&path))?;
+    let mut photo = match index.get_by_hash(hash)? {
+        Some(existing) => {
+            let _ = std::fs::remove_file(&path);
+            existing
+        }
+        None => {
+            import_file(&index, &config, &path, true, &Default::default(), &mut Default::default())
+                .map_err(|e| CommandError::with_file(&path)(e.error))?;
+            index.get_by_path(fotos_core::encode_path(&path))?
+                .ok_or_else(|| format!("Imported photo missing from the index: {}", path.display()))?
+        }
+    };
+    if index.get_source_url(photo.id.id)?.is_none() {
+        index.set_source_url(photo.id.id, url)?;
+    }
+    fill_thumb_paths(std::slice::from_mut(&mut photo), &library.thumb_dir);
+    Ok(photo)
+}
+
+/// What the frontend puts on the clipboard: either file paths, or one photo as PNG
+#[derive(serde::Serialize, Default)]
+struct ClipboardPayload {
+    paths: Vec<String>,
+    png: Option<Vec<u8>>,
+}
+
+/// Prepares photos for pasting into chat apps or documents. `as_image` renders
+/// the first photo (clipboards hold a single image), downscaled to
+/// `max_dimension` for huge originals; otherwise all file paths are returned.
+#[tauri::command]
+async fn copy_photos_to_clipboard(
+    ids: Vec<i64>,
+    as_image: bool,
+    max_dimension: Option<u32>,
+    library: tauri::State<'_, paths::LibraryPaths>,
+) -> Result<ClipboardPayload, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    let mut payload = ClipboardPayload::default();
+    let mut first_rotation = None;
+    for photo in index.get_by_ids(ids).map_err(|e| e.to_string())? {
+        first_rotation.get_or_insert(photo.metadata.rotation);
+        payload.paths.push(photo.path);
+    }
+
+    if as_image {
+        let first = payload.paths.first().ok_or("No photos to copy")?;
+        let source = paths::photo_file(first)?;
+        let rotation = first_rotation.unwrap_or(0);
+        payload.png = Some(fotos_core::render_png(&source, rotation, max_dimension).map_err(|e| e.to_string())?);
+        payload.paths.clear();
+    }
+
+    Ok(payload)
+}
+
+/// Rotates a photo non-destructively and swaps its thumbnail for the rotated one,
+/// so the grid, filmstrip and exports all pick up the new rotation
+#[tauri::command]
+async fn rotate_photo(
+    id: i64,
+    degrees: i32,
+    library: tauri::State<'_, paths::LibraryPaths>,
+) -> Result<PhotoInfo, CommandError> {
+    let index = library.index()?;
+    let previous = index.get_by_id(id)?
+        .ok_or_else(|| format!("Unknown photo {}", id))?;
+    let mut photo = index.rotate(id, degrees)?
+        .ok_or_else(|| format!("Unknown photo {}", id))?;
+
+    let thumbnailer = fotos_core::Thumbnailer::new(std::path::PathBuf::from(&library.thumb_dir));
+    let source = fotos_core::decode_path(&photo.path);
+    let spec = fotos_core::ThumbnailSpec { width: 256, height: 256, rotation: photo.metadata.rotation };
+    // No thumbnail (e.g. RAW without a preview) is fine - the frontend shows the original
+    let _ = thumbnailer.get_or_create(&source, &spec);
+    let old_spec = fotos_core::ThumbnailSpec { rotation: previous.metadata.rotation, ..spec };
+    if old_spec != spec {
+        if let Ok(Some(old_thumb)) = thumbnailer.get_cached_path(&source, &old_spec) {
+            let _ = std::fs::remove_file(old_thumb);
+        }
+    }
+
+    fill_thumb_paths(std::slice::from_mut(&mut photo), &library.thumb_dir);
+    Ok(photo)
+}
+
+/// Counts a view of a photo opened in the detail view. Cheap enough to call
+/// on every open; false for an unknown photo.
+#[tauri::command]
+async fn record_view(id: i64, library: tauri::State<'_, paths::LibraryPaths>) -> Result<bool, CommandError> {
+    let index = library.index()?;
+    Ok(index.record_view(id)?)
+}
+
+/// How often a photo was viewed and when last
+#[tauri::command]
+async fn get_photo_views(id: i64, library: tauri::State<'_, paths::LibraryPaths>) -> Result<fotos_core::PhotoViews, CommandError> {
+    let index = library.index()?;
+    Ok(index.view_stats(id)?)
+}
+
+/// Native quick preview (Quick Look etc.) of an original
+#[tauri::command]
+async fn quick_look(
+    path: String,
+    library: tauri::State<'_, paths::LibraryPaths>,
+    grants: tauri::State<'_, paths::Grants>,
+) -> Result<(), String> {
+    let path = paths::library_photo(&path, &library, &grants)?;
+    file_service::quick_look(&path)
+}
+
+/// Shows an original in the file manager
+#[tauri::command]
+async fn reveal_in_file_manager(
+    path: String,
+    library: tauri::State<'_, paths::LibraryPaths>,
+    grants: tauri::State<'_, paths::Grants>,
+) -> Result<(), String> {
+    let path = paths::library_photo(&path, &library, &grants)?;
+    file_service::reveal_in_file_manager(&path)
+}
+
+/// Photos the OS asked us to open since the last call
+#[tauri::command]
+fn take_opened_files(pending: tauri::State<'_, open_with::PendingFiles>) -> Vec<String> {
+    pending.take()
+}
+
 /// Delete result struct
 #[derive(serde::Serialize, Default)]
 struct DeleteResult {
@@ -170,50 +763,207 @@ struct DeleteResult {
     errors: Vec<String>,
 }
 
+/// Import throughput over a rolling window, so ETAs follow the current speed
+/// (e.g. a run of already-imported files, or a slower folder of RAWs).
+struct Throughput {
+    /// (when, files handled, bytes handled)
+    samples: std::collections::VecDeque<(std::time::Instant, u64, u64)>,
+    stages: StageTimings,
+    last_report: Option<std::time::Instant>,
+}
+
+impl Throughput {
+    const WINDOW: std::time::Duration = std::time::Duration::from_secs(20);
+    /// Don't report an ETA from too little data
+    const MIN_SPAN: std::time::Duration = std::time::Duration::from_secs(2);
+    /// Time between "import-throughput" events
+    const REPORT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+    fn new() -> Self {
+        Self { samples: std::collections::VecDeque::new(), stages: StageTimings::default(), last_report: None }
+    }
+
+    fn record(&mut self, files_processed: u64, bytes_processed: u64) {
+        let now = std::time::Instant::now();
+        self.samples.push_back((now, files_processed, bytes_processed));
+        while let Some(&(at, _, _)) = self.samples.front() {
+            if now.duration_since(at) > Self::WINDOW && self.samples.len() > 2 {
+                self.samples.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn eta_seconds(&self, remaining_bytes: u64) -> Option<u64> {
+        let (_, bytes_per_sec) = self.rates()?;
+        if bytes_per_sec <= 0.0 {
+            return None;
+        }
+        Some((remaining_bytes as f64 / bytes_per_sec).round() as u64)
+    }
+
+    /// Files and bytes per second over the window
+    fn rates(&self) -> Option<(f64, f64)> {
+        let (&(first_at, first_files, first_bytes), &(last_at, last_files, last_bytes)) = (self.samples.front()?, self.samples.back()?);
+        let span = last_at.duration_since(first_at);
+        if span < Self::MIN_SPAN {
+            return None;
+        }
+        let seconds = span.as_secs_f64();
+        Some(((last_files - first_files) as f64 / seconds, last_bytes.saturating_sub(first_bytes) as f64 / seconds))
+    }
+
+    /// The current speed, at most once per `REPORT_INTERVAL`.
+    fn report(&mut self) -> Option<fotos_core::ImportThroughput> {
+        let now = std::time::Instant::now();
+        if self.last_report.is_some_and(|at| now.duration_since(at) < Self::REPORT_INTERVAL) {
+            return None;
+        }
+        let (files_per_sec, bytes_per_sec) = self.rates()?;
+        self.last_report = Some(now);
+        Some(fotos_core::ImportThroughput {
+            files_per_sec,
+            mb_per_sec: bytes_per_sec / (1024.0 * 1024.0),
+            stage_breakdown: self.stages.breakdown(),
+        })
+    }
+}
+
+/// Time spent in each import stage, over the files imported so far and
+/// summed across workers.
+#[derive(Default)]
+struct StageTimings {
+    spent: Vec<(ImportStage, std::time::Duration)>,
+    files: u64,
+}
+
+impl StageTimings {
+    fn time<T>(&mut self, stage: ImportStage, work: impl FnOnce() -> T) -> T {
+        let start = std::time::Instant::now();
+        let result = work();
+        self.spend(stage, start.elapsed());
+        result
+    }
+
+    fn spend(&mut self, stage: ImportStage, elapsed: std::time::Duration) {
+        match self.spent.iter_mut().find(|(s, _)| *s == stage) {
+            Some((_, spent)) => *spent += elapsed,
+            None => self.spent.push((stage, elapsed)),
+        }
+    }
+
+    /// Adds the time a worker spent on one file
+    fn add(&mut self, other: StageTimings) {
+        for (stage, elapsed) in other.spent {
+            self.spend(stage, elapsed);
+        }
+        self.files += other.files;
+    }
+
+    fn breakdown(&self) -> Vec<fotos_core::StageTime> {
+        let total: f64 = self.spent.iter().map(|(_, spent)| spent.as_secs_f64()).sum();
+        let mut breakdown: Vec<_> = self.spent.iter()
+            .map(|&(stage, spent)| fotos_core::StageTime {
+                stage,
+                ms_per_file: spent.as_secs_f64() * 1000.0 / self.files.max(1) as f64,
+                share: if total > 0.0 { spent.as_secs_f64() / total } else { 0.0 },
+            })
+            .collect();
+        breakdown.sort_by(|a, b| b.share.total_cmp(&a.share));
+        breakdown
+    }
+}
+
 /// Delete photos from app only (DB + thumbnails), keep original files
 #[tauri::command]
 async fn delete_photos_from_app(
     ids: Vec<i64>,
-    db_path: String,
-    thumb_dir: String,
+    library: tauri::State<'_, paths::LibraryPaths>,
 ) -> Result<DeleteResult, String> {
-    let index = PhotoIndex::open(db_path).map_err(|e| e.to_string())?;
-
-    let thumbnailer = fotos_core::Thumbnailer::new(std::path::PathBuf::from(&thumb_dir));
-    let spec = fotos_core::ThumbnailSpec { width: 256, height: 256 };
-
-    let mut result = DeleteResult::default();
+    let thumb_dir = library.thumb_dir.clone();
+    let index = library.index().map_err(|e| e.to_string())?;
 
     // Delete each photo from DB and remove its thumbnail
     let deleted_photos = index.delete_by_ids(ids).map_err(|e| e.to_string())?;
+    Ok(remove_thumbnails(deleted_photos, &thumb_dir))
+}
+
+/// Reports photos deleted from the index, removing their thumbnails
+fn remove_thumbnails(deleted_photos: Vec<PhotoInfo>, thumb_dir: &str) -> DeleteResult {
+    let thumbnailer = fotos_core::Thumbnailer::new(std::path::PathBuf::from(thumb_dir));
+    let spec = fotos_core::ThumbnailSpec { width: 256, height: 256, rotation: 0 };
 
+    let mut result = DeleteResult::default();
     for photo in deleted_photos {
         result.deleted_paths.push(photo.path.clone());
         result.deleted_count += 1;
 
         // Try to delete thumbnail
-        let source_path = std::path::Path::new(&photo.path);
-        if let Ok(Some(thumb_path)) = thumbnailer.get_cached_path(source_path, &spec) {
+        let source_path = fotos_core::decode_path(&photo.path);
+        let spec = fotos_core::ThumbnailSpec { rotation: photo.metadata.rotation, ..spec };
+        if let Ok(Some(thumb_path)) = thumbnailer.get_cached_path(&source_path, &spec) {
             if let Err(e) = std::fs::remove_file(&thumb_path) {
                 result.errors.push(format!("Failed to delete thumbnail {}: {}", thumb_path.display(), e));
             }
         }
     }
+    result
+}
 
-    Ok(result)
+/// Days a photo stays in the trash before `purge_trash` deletes it for good
+const TRASH_RETENTION_DAYS: u32 = 30;
+
+/// Move photos to the trash (originals and thumbnails are kept)
+#[tauri::command]
+async fn move_to_trash(ids: Vec<i64>, library: tauri::State<'_, paths::LibraryPaths>) -> Result<u32, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    index.move_to_trash(ids).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn restore_from_trash(ids: Vec<i64>, library: tauri::State<'_, paths::LibraryPaths>) -> Result<u32, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    index.restore_from_trash(ids).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn list_trash(library: tauri::State<'_, paths::LibraryPaths>) -> Result<Vec<fotos_core::TrashedPhoto>, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    let mut trash = index.list_trash().map_err(|e| e.to_string())?;
+    for item in &mut trash {
+        fill_thumb_paths(std::slice::from_mut(&mut item.photo), &library.thumb_dir);
+    }
+    Ok(trash)
+}
+
+/// Permanently remove everything in the trash from the library (originals are kept)
+#[tauri::command]
+async fn empty_trash(library: tauri::State<'_, paths::LibraryPaths>) -> Result<DeleteResult, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    let purged = index.empty_trash().map_err(|e| e.to_string())?;
+    Ok(remove_thumbnails(purged, &library.thumb_dir))
+}
+
+/// Startup maintenance: permanently remove photos trashed over `TRASH_RETENTION_DAYS` ago
+#[tauri::command]
+async fn purge_trash(library: tauri::State<'_, paths::LibraryPaths>) -> Result<DeleteResult, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    let purged = index.purge_trash(TRASH_RETENTION_DAYS).map_err(|e| e.to_string())?;
+    Ok(remove_thumbnails(purged, &library.thumb_dir))
 }
 
 /// Delete photos completely (DB + thumbnails + original files)
 #[tauri::command]
 async fn delete_photos_completely(
     ids: Vec<i64>,
-    db_path: String,
-    thumb_dir: String,
+    library: tauri::State<'_, paths::LibraryPaths>,
 ) -> Result<DeleteResult, String> {
-    let index = PhotoIndex::open(db_path).map_err(|e| e.to_string())?;
+    let thumb_dir = library.thumb_dir.clone();
+    let index = library.index().map_err(|e| e.to_string())?;
 
     let thumbnailer = fotos_core::Thumbnailer::new(std::path::PathBuf::from(&thumb_dir));
-    let spec = fotos_core::ThumbnailSpec { width: 256, height: 256 };
+    let spec = fotos_core::ThumbnailSpec { width: 256, height: 256, rotation: 0 };
 
     let mut result = DeleteResult::default();
 
@@ -225,15 +975,16 @@ async fn delete_photos_completely(
         result.deleted_count += 1;
 
         // Try to delete thumbnail
-        let source_path = std::path::Path::new(&photo.path);
-        if let Ok(Some(thumb_path)) = thumbnailer.get_cached_path(source_path, &spec) {
+        let source_path = fotos_core::decode_path(&photo.path);
+        let spec = fotos_core::ThumbnailSpec { rotation: photo.metadata.rotation, ..spec };
+        if let Ok(Some(thumb_path)) = thumbnailer.get_cached_path(&source_path, &spec) {
             if let Err(e) = std::fs::remove_file(&thumb_path) {
                 result.errors.push(format!("Failed to delete thumbnail {}: {}", thumb_path.display(), e));
             }
         }
 
         // Delete original file
-        if let Err(e) = std::fs::remove_file(&photo.path) {
+        if let Err(e) = std::fs::remove_file(fotos_core::decode_path(&photo.path)) {
             result.errors.push(format!("Failed to delete original {}: {}", photo.path, e));
         }
     }
@@ -241,25 +992,270 @@ async fn delete_photos_completely(
     Ok(result)
 }
 
+/// Geotag photos dropped onto the map (or clear with null coordinates).
+/// Returns the previous locations, to be passed to `restore_locations` for undo.
+#[tauri::command]
+async fn set_location(
+    ids: Vec<i64>,
+    lat: Option<f64>,
+    lon: Option<f64>,
+    library: tauri::State<'_, paths::LibraryPaths>,
+) -> Result<Vec<PhotoLocation>, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    index.set_location(ids, lat, lon).map_err(|e| e.to_string())
+}
+
+/// Undo a `set_location` batch
+#[tauri::command]
+async fn restore_locations(locations: Vec<PhotoLocation>, library: tauri::State<'_, paths::LibraryPaths>) -> Result<(), String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    index.restore_locations(locations).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn add_place(place: Place, library: tauri::State<'_, paths::LibraryPaths>) -> Result<i64, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    index.add_place(place).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn list_places(library: tauri::State<'_, paths::LibraryPaths>) -> Result<Vec<Place>, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    index.list_places().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn delete_place(id: i64, library: tauri::State<'_, paths::LibraryPaths>) -> Result<bool, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    index.delete_place(id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn create_album(name: String, library: tauri::State<'_, paths::LibraryPaths>) -> Result<i64, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    index.create_album(name).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn rename_album(id: i64, name: String, library: tauri::State<'_, paths::LibraryPaths>) -> Result<bool, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    index.rename_album(id, name).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn delete_album(id: i64, library: tauri::State<'_, paths::LibraryPaths>) -> Result<bool, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    index.delete_album(id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn list_albums(library: tauri::State<'_, paths::LibraryPaths>) -> Result<Vec<PhotoAlbum>, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    index.list_albums().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn add_to_album(album_id: i64, photo_ids: Vec<i64>, library: tauri::State<'_, paths::LibraryPaths>) -> Result<u32, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    index.add_to_album(album_id, photo_ids).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn remove_from_album(album_id: i64, photo_ids: Vec<i64>, library: tauri::State<'_, paths::LibraryPaths>) -> Result<u32, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    index.remove_from_album(album_id, photo_ids).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn list_album_photos(album_id: i64, library: tauri::State<'_, paths::LibraryPaths>) -> Result<Vec<PhotoInfo>, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    let mut photos = index.list_album_photos(album_id).map_err(|e| e.to_string())?;
+    fill_thumb_paths(&mut photos, &library.thumb_dir);
+    Ok(photos)
+}
+
+#[tauri::command]
+async fn places_for_photo(photo_id: i64, library: tauri::State<'_, paths::LibraryPaths>) -> Result<Vec<Place>, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    index.places_for_photo(photo_id).map_err(|e| e.to_string())
+}
+
+/// Filter the library by a named place
+#[tauri::command]
+async fn list_photos_by_place(place_id: i64, library: tauri::State<'_, paths::LibraryPaths>) -> Result<Vec<PhotoInfo>, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    index.list_by_place(place_id).map_err(|e| e.to_string())
+}
+
+/// Optional enrichment pass: attach historical weather to geotagged, dated photos.
+/// Returns how many photos were enriched. Network errors stop the pass; the
+/// remaining photos are picked up next time.
 #[tauri::command]
-async fn clear_app_data(thumb_dir: String, db_path: String) -> Result<(), String> {
+async fn enrich_weather(library: tauri::State<'_, paths::LibraryPaths>) -> Result<u32, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    let provider = weather::OpenMeteo::new();
+
+    let mut enriched = 0;
+    for photo in index.list_missing_weather().map_err(|e| e.to_string())? {
+        let (lat, lon) = match (photo.metadata.lat, photo.metadata.lon) {
+            (Some(lat), Some(lon)) => (lat, lon),
+            _ => continue,
+        };
+        let (date, hour) = match photo.metadata.date_taken.as_deref().and_then(weather::split_date_taken) {
+            Some(parts) => parts,
+            None => continue,
+        };
+        if let Some(found) = provider.lookup(lat, lon, &date, hour).await? {
+            index.set_weather(photo.id.id, found).map_err(|e| e.to_string())?;
+            enriched += 1;
+        }
+    }
+
+    Ok(enriched)
+}
+
+/// Search photos by weather condition ("snow", "rain", ...)
+#[tauri::command]
+async fn list_photos_by_weather(condition: String, library: tauri::State<'_, paths::LibraryPaths>) -> Result<Vec<PhotoInfo>, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    index.list_by_weather(condition).map_err(|e| e.to_string())
+}
+
+/// Filtered, sorted listing (date range, camera, GPS, ISO, shape, file type)
+#[tauri::command]
+async fn query_photos(query: fotos_core::PhotoQuery, library: tauri::State<'_, paths::LibraryPaths>) -> Result<Vec<PhotoInfo>, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    let mut photos = index.query(query).map_err(|e| e.to_string())?;
+    fill_thumb_paths(&mut photos, &library.thumb_dir);
+    Ok(photos)
+}
+
+/// Full-text search over camera, path and capture date
+#[tauri::command]
+async fn search_photos(query: String, library: tauri::State<'_, paths::LibraryPaths>) -> Result<Vec<PhotoInfo>, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    let mut photos = index.search(query).map_err(|e| e.to_string())?;
+    fill_thumb_paths(&mut photos, &library.thumb_dir);
+    Ok(photos)
+}
+
+/// Undated photos grouped for manual dating, with proposed dates
+#[tauri::command]
+async fn list_undated_clusters(library: tauri::State<'_, paths::LibraryPaths>) -> Result<Vec<UndatedCluster>, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    index.undated_clusters().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn set_approximate_date(
+    ids: Vec<i64>,
+    taken_at: i64,
+    precision: DatePrecision,
+    library: tauri::State<'_, paths::LibraryPaths>,
+) -> Result<u32, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    index.set_approximate_date(ids, taken_at, precision).map_err(|e| e.to_string())
+}
+
+/// Photographer filters: "day", "golden_hour", "blue_hour" or "night"
+#[tauri::command]
+async fn list_photos_by_light(light: String, library: tauri::State<'_, paths::LibraryPaths>) -> Result<Vec<PhotoInfo>, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    index.list_by_light(light).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_revision(library: tauri::State<'_, paths::LibraryPaths>) -> Result<u64, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    index.revision().map_err(|e| e.to_string())
+}
+
+/// Ids added/updated/removed since `revision`, for reconciling the grid after background jobs
+#[tauri::command]
+async fn changes_since(revision: u64, library: tauri::State<'_, paths::LibraryPaths>) -> Result<fotos_core::PhotoChanges, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    index.changes_since(revision).map_err(|e| e.to_string())
+}
+
+/// Footer stats for a selection, aggregated in Rust so rows don't have to go to JS
+#[tauri::command]
+async fn summarize_selection(ids: Vec<i64>, library: tauri::State<'_, paths::LibraryPaths>) -> Result<fotos_core::SelectionSummary, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    index.summarize_selection(ids).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn clear_app_data(library: tauri::State<'_, paths::LibraryPaths>) -> Result<(), String> {
+    let thumb_dir = library.thumb_dir.clone();
     // Clear thumbnails
     if std::path::Path::new(&thumb_dir).exists() {
         std::fs::remove_dir_all(&thumb_dir).map_err(|e| e.to_string())?;
     }
     std::fs::create_dir_all(&thumb_dir).map_err(|e| e.to_string())?;
 
-    // Clear database
-    if std::path::Path::new(&db_path).exists() {
-        std::fs::remove_file(&db_path).map_err(|e| e.to_string())?;
-    }
+    // Clear database, with its WAL files
+    storage::CacheCategory::Database.clear(&library)?;
 
     // Note: map_tiles directory is preserved
     Ok(())
 }
 
+/// Saves a copy of the library database to `dest_path` (chosen by the user)
 #[tauri::command]
-async fn regenerate_thumbnails(window: tauri::Window, db_path: String, thumb_dir: String) -> Result<(), String> {
+async fn backup_library(
+    dest_path: String,
+    library: tauri::State<'_, paths::LibraryPaths>,
+    grants: tauri::State<'_, paths::Grants>,
+) -> Result<(), CommandError> {
+    let dest = paths::export_file(&dest_path, &["db"], &grants)?;
+    let index = library.index()?;
+    index.backup_to(dest.to_string_lossy().into_owned()).map_err(CommandError::with_file(&dest))?;
+    Ok(())
+}
+
+/// Replaces the library with a backup from `backup_library` or the backups
+/// folder. The current library is snapshotted first, so a restore can be undone.
+#[tauri::command]
+async fn restore_library(source_path: String, library: tauri::State<'_, paths::LibraryPaths>) -> Result<(), CommandError> {
+    let source = std::path::PathBuf::from(&source_path);
+    if !source.is_file() {
+        return Err(format!("Not a file: {}", source.display()).into());
+    }
+    storage::snapshot_library(&library)?;
+    let index = library.index()?;
+    index.restore_from(source_path).map_err(CommandError::with_file(&source))?;
+    Ok(())
+}
+
+/// Bytes a `clear_cache` would free and what the user gives up, for the confirmation
+#[tauri::command]
+async fn describe_clear(category: storage::CacheCategory, library: tauri::State<'_, paths::LibraryPaths>) -> Result<storage::ClearInfo, String> {
+    Ok(category.describe(&library))
+}
+
+/// Clears one cache category (or resets the database), returning the bytes freed
+#[tauri::command]
+async fn clear_cache(category: storage::CacheCategory, library: tauri::State<'_, paths::LibraryPaths>) -> Result<u64, String> {
+    category.clear(&library)
+}
+
+/// Tells background thumbnailing which photos are on screen, so they're done first
+#[tauri::command]
+fn hint_visible_range(ids: Vec<i64>, hints: tauri::State<'_, viewport::VisibleHints>) {
+    hints.set(ids);
+}
+
+/// Regenerates every thumbnail in library order, doing the photos the grid
+/// reports visible first. Each hinted photo done early is announced with
+/// "thumbnail-ready" so the grid can swap in its thumbnail.
+#[tauri::command]
+async fn regenerate_thumbnails<R: tauri::Runtime>(
+    window: tauri::Window<R>,
+    library: tauri::State<'_, paths::LibraryPaths>,
+    hints: tauri::State<'_, viewport::VisibleHints>,
+) -> Result<(), String> {
+    let db_path = library.db_path.clone();
+    let thumb_dir = library.thumb_dir.clone();
     
     // Ensure parent directories exist
     if let Some(parent) = std::path::Path::new(&db_path).parent() {
@@ -267,61 +1263,206 @@ async fn regenerate_thumbnails(window: tauri::Window, db_path: String, thumb_dir
     }
     std::fs::create_dir_all(&thumb_dir).map_err(|e| e.to_string())?;
 
-    let index = PhotoIndex::open(db_path.clone())
+    let index = library.index()
         .map_err(|e| e.to_string())?;
     
-    let photos = index.list().map_err(|e| e.to_string())?;
-    let total = photos.len();
+    // Streamed rather than listed: this walks the whole library
+    let total = index.count().map_err(|e| e.to_string())?;
     
-    let config = PhotoCoreConfig {
-        thumbnail_dir: thumb_dir,
-        thumbnail_size: 256,
-    };
+    let thumbnailer = fotos_core::Thumbnailer::new(std::path::PathBuf::from(&thumb_dir));
 
+    let mut current = 0;
     let mut success = 0;
     let mut failure = 0;
 
-    for (i, photo) in photos.iter().enumerate() {
-        let path = std::path::PathBuf::from(&photo.path);
-        
-        let file_result = fotos_core::generate_thumbnail(&path, &config);
+    index.for_each(|photo| {
+        for hinted in index.get_by_ids(hints.take())? {
+            let spec = fotos_core::ThumbnailSpec { width: 256, height: 256, rotation: hinted.metadata.rotation };
+            if let Ok(thumb) = thumbnailer.get_or_create(&fotos_core::decode_path(&hinted.path), &spec) {
+                use tauri::Emitter;
+                let _ = window.emit("thumbnail-ready", serde_json::json!({
+                    "id": hinted.id.id,
+                    "thumb_path": thumb.to_string_lossy(),
+                }));
+            }
+        }
+
+        let path = fotos_core::decode_path(&photo.path);
+        let spec = fotos_core::ThumbnailSpec { width: 256, height: 256, rotation: photo.metadata.rotation };
+
+        // Cheap for a photo a hint already covered: the cached file is reused
+        let file_result = thumbnailer.generate(&path, &spec);
 
         match file_result {
             Ok(_) => success += 1,
             Err(_) => failure += 1,
         }
+        current += 1;
         
         // Emit progress
         use tauri::Emitter;
         let _ = window.emit("import-progress", serde_json::json!({
-            "current": i + 1,
+            "current": current,
             "total": total,
             "success": success,
             "failure": failure,
             "last_path": photo.path
         }));
-    }
+        Ok(())
+    }).map_err(|e| e.to_string())?;
     
     Ok(())
 }
 
+/// Maintenance: spot-check a slice of cached thumbnails and regenerate corrupt ones
+#[tauri::command]
+async fn verify_thumbnails(
+    offset: u32,
+    limit: u32,
+    library: tauri::State<'_, paths::LibraryPaths>,
+) -> Result<fotos_core::ThumbnailVerifyReport, String> {
+    let thumb_dir = library.thumb_dir.clone();
+    let index = library.index().map_err(|e| e.to_string())?;
+    let config = PhotoCoreConfig {
+        thumbnail_dir: thumb_dir,
+        thumbnail_size: 256,
+    };
+    fotos_core::verify_thumbnails(index, config, offset, limit).map_err(|e| e.to_string())
+}
+
+/// Maintenance: flag photos whose originals are gone and find (optionally delete) orphaned thumbnails
+#[tauri::command]
+async fn verify_library(
+    remove_orphans: bool,
+    library: tauri::State<'_, paths::LibraryPaths>,
+) -> Result<fotos_core::LibraryVerifyReport, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    let config = PhotoCoreConfig {
+        thumbnail_dir: library.thumb_dir.clone(),
+        thumbnail_size: 256,
+    };
+    index.verify(config, remove_orphans).map_err(|e| e.to_string())
+}
+
+/// Whether originals are checked against bit rot in the background, and the last report
+#[tauri::command]
+fn get_originals_audit(audit: tauri::State<'_, audit::OriginalsAudit>) -> audit::AuditState {
+    audit.state()
+}
+
+/// Turns the daily check of originals against bit rot on or off
+#[tauri::command]
+fn set_originals_audit(enabled: bool, audit: tauri::State<'_, audit::OriginalsAudit>) -> Result<audit::AuditState, CommandError> {
+    audit.set_enabled(enabled)
+}
+
+/// Maintenance: re-read a sample of originals now and flag the corrupted ones
+#[tauri::command]
+async fn run_originals_audit(
+    audit: tauri::State<'_, audit::OriginalsAudit>,
+    library: tauri::State<'_, paths::LibraryPaths>,
+) -> Result<fotos_core::OriginalsAuditReport, CommandError> {
+    audit.run(&library)
+}
+
+/// This launch's startup check, `None` until it ran or when it's turned off
+#[tauri::command]
+fn get_startup_report(check: tauri::State<'_, startup::StartupCheck>) -> Option<startup::StartupReport> {
+    check.report()
+}
+
+#[tauri::command]
+fn get_startup_check(check: tauri::State<'_, startup::StartupCheck>) -> startup::StartupSettings {
+    check.settings()
+}
+
+/// Turns the quick health check at launch on or off
+#[tauri::command]
+fn set_startup_check(enabled: bool, check: tauri::State<'_, startup::StartupCheck>) -> Result<startup::StartupSettings, CommandError> {
+    check.set_enabled(enabled)
+}
+
+/// Gives up on an interrupted import instead of resuming it; what it
+/// imported so far stays in the library
+#[tauri::command]
+fn discard_import_session(id: i64, library: tauri::State<'_, paths::LibraryPaths>) -> Result<(), CommandError> {
+    let index = library.index()?;
+    index.finish_import_session(id)?;
+    Ok(())
+}
+
+/// Maintenance: move a batch of thumbnails from the old flat cache layout into the sharded one
+#[tauri::command]
+async fn migrate_legacy_thumbnails(
+    limit: u32,
+    library: tauri::State<'_, paths::LibraryPaths>,
+) -> Result<fotos_core::LegacyThumbnailReport, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    let config = PhotoCoreConfig {
+        thumbnail_dir: library.thumb_dir.clone(),
+        thumbnail_size: 256,
+    };
+    fotos_core::migrate_legacy_thumbnails(index, config, limit).map_err(|e| e.to_string())
+}
+
+/// Maintenance: fix thumbnails of EXIF-rotated photos, optionally rewriting JPEG originals upright
+#[tauri::command]
+async fn normalize_orientation(
+    rewrite_pixels: bool,
+    library: tauri::State<'_, paths::LibraryPaths>,
+) -> Result<fotos_core::OrientationReport, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    let config = PhotoCoreConfig {
+        thumbnail_dir: library.thumb_dir.clone(),
+        thumbnail_size: 256,
+    };
+    fotos_core::normalize_orientation(index, config, rewrite_pixels).map_err(|e| e.to_string())
+}
+
+/// Thumbnail-ready bitmap for photo ids `first_id..first_id + count` (see
+/// `fotos_core::thumbnail_ready_map`), sent as raw bytes so the grid gets an
+/// ArrayBuffer rather than a JSON array
+#[tauri::command]
+async fn thumbnail_ready_map(
+    first_id: i64,
+    count: u32,
+    library: tauri::State<'_, paths::LibraryPaths>,
+) -> Result<tauri::ipc::Response, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    let config = PhotoCoreConfig {
+        thumbnail_dir: library.thumb_dir.clone(),
+        thumbnail_size: 256,
+    };
+    let map = fotos_core::thumbnail_ready_map(index, config, first_id, count).map_err(|e| e.to_string())?;
+    Ok(tauri::ipc::Response::new(map))
+}
+
 #[tauri::command]
-async fn read_file_bytes(path: String) -> Result<Vec<u8>, String> {
+async fn read_file_bytes(
+    path: String,
+    library: tauri::State<'_, paths::LibraryPaths>,
+    grants: tauri::State<'_, paths::Grants>,
+) -> Result<Vec<u8>, String> {
+    let path = paths::library_photo(&path, &library, &grants)?;
     std::fs::read(&path).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-async fn get_raw_preview(path: String, cache_dir: String) -> Result<String, String> {
-    let source_path = std::path::Path::new(&path);
+async fn get_raw_preview(
+    path: String,
+    library: tauri::State<'_, paths::LibraryPaths>,
+    grants: tauri::State<'_, paths::Grants>,
+    temp: tauri::State<'_, fotos_core::TempManager>,
+) -> Result<String, String> {
+    let cache_dir = library.preview_dir.clone();
+    let source = paths::library_photo(&path, &library, &grants)?;
+    let source_path = source.as_path();
 
     // Create a unique cache filename based on the source path
-    let file_name = source_path.file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("preview");
-    let hash = fotos_core::compute_hash(source_path).map_err(|e| e.to_string())?;
-    let preview_path = std::path::PathBuf::from(&cache_dir)
-        .join("raw_previews")
-        .join(format!("{}_{}.jpg", file_name, &hash[..16]));
+    let preview_path = paths::resolve_within(
+        std::path::Path::new(&cache_dir),
+        &std::path::Path::new(storage::RAW_PREVIEWS).join(derived_cache_name(source_path, "jpg")?),
+    )?;
 
     // Return cached preview if it exists
     if preview_path.exists() {
@@ -331,23 +1472,198 @@ async fn get_raw_preview(path: String, cache_dir: String) -> Result<String, Stri
     // Extract and cache the preview
     let preview_bytes = fotos_core::extract_raw_preview(source_path).map_err(|e| e.to_string())?;
 
-    // Ensure directory exists
-    if let Some(parent) = preview_path.parent() {
-        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    // Written aside and moved into place, so a crash can't leave a partial preview cached
+    let job = temp.job("raw-preview").map_err(|e| e.to_string())?;
+    std::fs::write(job.path("preview.jpg"), &preview_bytes).map_err(|e| e.to_string())?;
+    job.persist("preview.jpg", &preview_path).map_err(|e| e.to_string())?;
+
+    Ok(preview_path.to_string_lossy().to_string())
+}
+
+/// Cache file name for a derived asset of `source`: "<stem>_<key>.<ext>",
+/// keyed on its path, size and modification time so a cache hit doesn't
+/// read the file, and a changed original gets a new name.
+fn derived_cache_name(source_path: &std::path::Path, ext: &str) -> Result<String, String> {
+    use std::hash::{Hash, Hasher};
+
+    let file_name = source_path.file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("preview");
+    let stats = fotos_core::file_stats(source_path).ok_or_else(|| format!("Cannot read {}", source_path.display()))?;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    fotos_core::encode_path(source_path).hash(&mut hasher);
+    stats.hash(&mut hasher);
+    Ok(format!("{}_{:016x}.{}", file_name, hasher.finish(), ext))
+}
+
+/// Render a photo's auto-enhanced preview (levels, white balance, saturation),
+/// upright like its thumbnail, so the viewer can toggle between the original
+/// and the suggestion. The recipe is computed and stored, switched on, the
+/// first time; after that the stored one is used.
+#[tauri::command]
+async fn get_enhanced_preview(
+    id: i64,
+    library: tauri::State<'_, paths::LibraryPaths>,
+    temp: tauri::State<'_, fotos_core::TempManager>,
+) -> Result<String, String> {
+    let index = library.index().map_err(|e| e.to_string())?;
+    let photo = index.get_by_id(id).map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Unknown photo {}", id))?;
+    let source = paths::photo_file(&photo.path)?;
+    let source_path = source.as_path();
+    let rotation = photo.metadata.rotation % 360;
+    let name = derived_cache_name(source_path, "jpg")?;
+    let name = if rotation == 0 { name } else { format!("r{}_{}", rotation, name) };
+    let preview_path = paths::resolve_within(
+        std::path::Path::new(&library.preview_dir),
+        &std::path::Path::new(storage::ENHANCED_PREVIEWS).join(name),
+    )?;
+
+    let recipe = match index.get_enhance(id).map_err(|e| e.to_string())? {
+        Some(enhance) => enhance.recipe,
+        None => {
+            let recipe = fotos_core::compute_auto_enhance(source_path).map_err(|e| e.to_string())?;
+            index.set_enhance(id, fotos_core::PhotoEnhance { recipe, enabled: true }).map_err(|e| e.to_string())?;
+            recipe
+        }
+    };
+
+    // Return cached preview if it exists
+    if preview_path.exists() {
+        return Ok(preview_path.to_string_lossy().to_string());
     }
 
-    // Write to cache
-    std::fs::write(&preview_path, &preview_bytes).map_err(|e| e.to_string())?;
+    let job = temp.job("enhanced-preview").map_err(|e| e.to_string())?;
+    fotos_core::apply_auto_enhance(source_path, &job.path("enhanced.jpg"), &recipe, rotation).map_err(|e| e.to_string())?;
+    job.persist("enhanced.jpg", &preview_path).map_err(|e| e.to_string())?;
 
     Ok(preview_path.to_string_lossy().to_string())
 }
 
+/// A photo's auto-enhance edit, None if it was never enhanced
 #[tauri::command]
-async fn get_cached_tile(cache_dir: String, z: u32, x: u32, y: u32) -> Result<Option<String>, String> {
-    let tile_path = std::path::PathBuf::from(&cache_dir)
-        .join(z.to_string())
-        .join(x.to_string())
-        .join(format!("{}.png", y));
+async fn get_photo_enhance(id: i64, library: tauri::State<'_, paths::LibraryPaths>) -> Result<Option<fotos_core::PhotoEnhance>, CommandError> {
+    let index = library.index()?;
+    Ok(index.get_enhance(id)?)
+}
+
+/// Switches a photo's stored auto-enhance on or off. False if it was never
+/// enhanced (`get_enhanced_preview` computes the recipe).
+#[tauri::command]
+async fn set_enhance_enabled(id: i64, enabled: bool, library: tauri::State<'_, paths::LibraryPaths>) -> Result<bool, CommandError> {
+    let index = library.index()?;
+    let Some(enhance) = index.get_enhance(id)? else { return Ok(false) };
+    Ok(index.set_enhance(id, fotos_core::PhotoEnhance { enabled, ..enhance })?)
+}
+
+/// Extract the video clip of a motion photo so the viewer can play it.
+/// Returns None for photos without an embedded clip.
+#[tauri::command]
+async fn get_motion_video(
+    path: String,
+    library: tauri::State<'_, paths::LibraryPaths>,
+    grants: tauri::State<'_, paths::Grants>,
+    temp: tauri::State<'_, fotos_core::TempManager>,
+) -> Result<Option<String>, String> {
+    let cache_dir = library.preview_dir.clone();
+    let source = paths::library_photo(&path, &library, &grants)?;
+    let source_path = source.as_path();
+    let clip_path = paths::resolve_within(
+        std::path::Path::new(&cache_dir),
+        &std::path::Path::new(storage::MOTION_VIDEOS).join(derived_cache_name(source_path, "mp4")?),
+    )?;
+
+    if clip_path.exists() {
+        return Ok(Some(clip_path.to_string_lossy().to_string()));
+    }
+
+    let job = temp.job("motion-video").map_err(|e| e.to_string())?;
+    if fotos_core::extract_motion_video(source_path, &job.path("clip.mp4")).map_err(|e| e.to_string())?.is_none() {
+        return Ok(None);
+    }
+    job.persist("clip.mp4", &clip_path).map_err(|e| e.to_string())?;
+    Ok(Some(clip_path.to_string_lossy().to_string()))
+}
+
+/// Export the depth map of a portrait photo to `dest_path` (chosen by the user),
+/// for re-editing bokeh in other tools. Returns None if there is no depth map.
+#[tauri::command]
+async fn export_depth_map(
+    path: String,
+    dest_path: String,
+    library: tauri::State<'_, paths::LibraryPaths>,
+    grants: tauri::State<'_, paths::Grants>,
+) -> Result<Option<String>, CommandError> {
+    let source = paths::library_photo(&path, &library, &grants)?;
+    let dest = paths::export_file(&dest_path, &["jpg", "jpeg"], &grants)?;
+    let exported = fotos_core::extract_depth_map(&source, &dest)
+        .map_err(CommandError::with_file(&dest))?;
+    Ok(exported.map(|p| p.to_string_lossy().to_string()))
+}
+
+/// Writes a manifest of the photos matching `query` to `dest_path` (chosen
+/// by the user), as CSV or JSON by its extension, without copying any
+/// images. Returns how many photos it lists.
+#[tauri::command]
+async fn export_manifest(
+    query: fotos_core::PhotoQuery,
+    dest_path: String,
+    library: tauri::State<'_, paths::LibraryPaths>,
+    grants: tauri::State<'_, paths::Grants>,
+) -> Result<u32, CommandError> {
+    let dest = paths::export_file(&dest_path, &["csv", "json"], &grants)?;
+    let format = match dest.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref() {
+        Some("json") => fotos_core::ManifestFormat::Json,
+        _ => fotos_core::ManifestFormat::Csv,
+    };
+    let index = library.index()?;
+    index.export_manifest(query, format, dest.to_string_lossy().into_owned()).map_err(CommandError::with_file(&dest))
+}
+
+/// Writes a shareable "year in review" for `year` into a new folder inside
+/// `dest_dir`: index.html, review.json with the underlying numbers, and the
+/// highlight photos as thumbnails (no originals or file paths leave the library).
+/// Returns the path of index.html.
+#[tauri::command]
+async fn export_year_in_review(
+    year: i32,
+    dest_dir: String,
+    library: tauri::State<'_, paths::LibraryPaths>,
+    grants: tauri::State<'_, paths::Grants>,
+) -> Result<String, CommandError> {
+    let dest_dir = paths::export_dir(&dest_dir, &grants)?;
+    let index = library.index()?;
+    let review = index.year_in_review(year)?;
+
+    let bundle = dest_dir.join(format!("Fotos {} in review", year));
+    let photos_dir = bundle.join("photos");
+    std::fs::create_dir_all(&photos_dir).map_err(|e| CommandError::with_file(&photos_dir)(e.into()))?;
+
+    let thumbnailer = fotos_core::Thumbnailer::new(std::path::PathBuf::from(&library.thumb_dir));
+    for highlight in &review.highlights {
+        let Some(photo) = index.get_by_id(highlight.photo_id)? else { continue };
+        let source = fotos_core::decode_path(&photo.path);
+        let spec = fotos_core::ThumbnailSpec { width: 256, height: 256, rotation: photo.metadata.rotation };
+        // A missing original just leaves a gap in the page
+        if let Ok(thumb) = thumbnailer.get_or_create(&source, &spec) {
+            let dest = photos_dir.join(format!("{}.jpg", highlight.photo_id));
+            std::fs::copy(&thumb, &dest).map_err(|e| CommandError::with_file(&dest)(e.into()))?;
+        }
+    }
+
+    let json = serde_json::to_vec_pretty(&review).map_err(|e| e.to_string())?;
+    let json_path = bundle.join("review.json");
+    std::fs::write(&json_path, json).map_err(|e| CommandError::with_file(&json_path)(e.into()))?;
+    let html_path = bundle.join("index.html");
+    std::fs::write(&html_path, fotos_core::render_year_in_review(review))
+        .map_err(|e| CommandError::with_file(&html_path)(e.into()))?;
+    Ok(html_path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+async fn get_cached_tile(z: u32, x: u32, y: u32, library: tauri::State<'_, paths::LibraryPaths>) -> Result<Option<String>, String> {
+    let cache_dir = library.tile_dir.clone();
+    let tile_path = paths::tile_path(&cache_dir, z, x, y)?;
 
     if tile_path.exists() {
         Ok(Some(tile_path.to_string_lossy().to_string()))
@@ -357,28 +1673,30 @@ async fn get_cached_tile(cache_dir: String, z: u32, x: u32, y: u32) -> Result<Op
 }
 
 #[tauri::command]
-async fn download_tile(cache_dir: String, z: u32, x: u32, y: u32, url: String) -> Result<String, String> {
-    let tile_path = std::path::PathBuf::from(&cache_dir)
-        .join(z.to_string())
-        .join(x.to_string())
-        .join(format!("{}.png", y));
+async fn download_tile(
+    z: u32,
+    x: u32,
+    y: u32,
+    url: String,
+    library: tauri::State<'_, paths::LibraryPaths>,
+    temp: tauri::State<'_, fotos_core::TempManager>,
+) -> Result<String, String> {
+    let cache_dir = library.tile_dir.clone();
+    let tile_path = paths::tile_path(&cache_dir, z, x, y)?;
+    paths::check_http_url(&url)?;
 
     // Check if already cached
     if tile_path.exists() {
         return Ok(tile_path.to_string_lossy().to_string());
     }
 
-    // Create directory structure
-    if let Some(parent) = tile_path.parent() {
-        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
-    }
-
     // Download tile
-    let response = reqwest::get(&url).await.map_err(|e| e.to_string())?;
-    let bytes = response.bytes().await.map_err(|e| e.to_string())?;
+    let bytes = http::download(&url, http::MAX_TILE_BYTES).await?;
 
     // Save to cache
-    std::fs::write(&tile_path, &bytes).map_err(|e| e.to_string())?;
+    let job = temp.job("tile").map_err(|e| e.to_string())?;
+    std::fs::write(job.path("tile"), &bytes).map_err(|e| e.to_string())?;
+    job.persist("tile", &tile_path).map_err(|e| e.to_string())?;
 
     Ok(tile_path.to_string_lossy().to_string())
 }
@@ -388,21 +1706,149 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
+        .setup(|app| {
+            let data_dir = app.path().app_data_dir()?;
+            let cache_dir = app.path().app_cache_dir()?;
+            let fallback_cache = fallback_cache::FallbackCache::restore(data_dir.join("fallback_cache.json"));
+            let library = paths::LibraryPaths::open(&data_dir, &cache_dir, fallback_cache.get().as_deref().map(std::path::Path::new))?;
+            if let Some(problem) = &library.cache_unavailable {
+                println!("[Cache] {} unavailable ({:?}): {}", problem.path, problem.problem, problem.detail);
+            }
+            // Clears out temp files of a run that didn't get to clean up
+            app.manage(fotos_core::TempManager::open(std::path::Path::new(&library.temp_dir))?);
+            app.manage(library);
+            app.manage(fallback_cache);
+            // Before anything scans or opens files
+            app.manage(formats::ExtraExtensions::restore(data_dir.join("formats.json")));
+            // Before anything reads originals: regain sandbox access to import sources
+            app.manage(bookmarks::Bookmarks::restore(data_dir.join("bookmarks.json")));
+            app.manage(open_with::PendingFiles::default());
+            // Before the dialogs pick anything or the OS opens files with the app
+            app.manage(paths::Grants::follow(&app.asset_protocol_scope()));
+            app.manage(diagnostics::RecentErrors::default());
+            app.manage(diagnostics::ImportErrors::default());
+            app.manage(viewport::VisibleHints::default());
+            let watchers = watch::Watchers::new(data_dir.join("watched.json"));
+            watchers.restore(app.handle(), &app.state::<paths::LibraryPaths>());
+            app.manage(watchers);
+            app.manage(audit::OriginalsAudit::restore(data_dir.join("audit.json")));
+            audit::schedule(app.handle().clone());
+            app.manage(startup::StartupCheck::restore(data_dir.join("startup.json")));
+            startup::schedule(app.handle().clone());
+            open_with::deliver(app.handle(), open_with::from_args(std::env::args()));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             get_core_version,
+            get_library_paths,
+            get_fallback_cache,
+            set_fallback_cache,
+            get_storage_breakdown,
+            run_diagnostics,
+            log_error,
             import_photos,
+            get_import_errors,
+            rescan_folder,
+            get_supported_extensions,
+            get_extra_extensions,
+            set_extra_extensions,
+            start_watch,
+            stop_watch,
+            list_watches,
+            import_from_clipboard,
+            import_from_url,
+            copy_photos_to_clipboard,
+            quick_look,
+            reveal_in_file_manager,
+            take_opened_files,
             cancel_import,
             list_photos,
+            list_photos_page,
+            count_photos,
+            get_timeline,
+            list_photos_in_bounds,
+            find_similar_photos,
+            find_similar_to,
+            get_duplicate_report,
+            query_photos,
+            search_photos,
+            list_undated_clusters,
+            set_approximate_date,
+            get_neighbors,
+            rotate_photo,
+            record_view,
+            get_photo_views,
             clear_app_data,
+            describe_clear,
+            clear_cache,
+            backup_library,
+            restore_library,
             regenerate_thumbnails,
+            hint_visible_range,
+            verify_thumbnails,
+            migrate_legacy_thumbnails,
+            verify_library,
+            get_originals_audit,
+            set_originals_audit,
+            run_originals_audit,
+            get_startup_report,
+            get_startup_check,
+            set_startup_check,
+            discard_import_session,
+            thumbnail_ready_map,
+            normalize_orientation,
             read_file_bytes,
             get_raw_preview,
+            get_enhanced_preview,
+            get_photo_enhance,
+            set_enhance_enabled,
+            get_motion_video,
+            export_depth_map,
+            export_year_in_review,
+            export_manifest,
             get_cached_tile,
             download_tile,
             delete_photos_from_app,
-            delete_photos_completely
+            delete_photos_completely,
+            move_to_trash,
+            restore_from_trash,
+            list_trash,
+            empty_trash,
+            purge_trash,
+            set_location,
+            restore_locations,
+            add_place,
+            list_places,
+            delete_place,
+            create_album,
+            rename_album,
+            delete_album,
+            list_albums,
+            add_to_album,
+            remove_from_album,
+            list_album_photos,
+            places_for_photo,
+            list_photos_by_place,
+            enrich_weather,
+            list_photos_by_weather,
+            list_photos_by_light,
+            get_revision,
+            changes_since,
+            summarize_selection
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_app, _event| {
+            // macOS delivers "Open with" as an event rather than launch arguments
+            #[cfg(target_os = "macos")]
+            if let tauri::RunEvent::Opened { urls } = _event {
+                let files = urls.iter()
+                    .filter_map(|url| url.to_file_path().ok())
+                    .filter_map(|path| paths::photo_file(&path.to_string_lossy()).ok())
+                    .map(|path| path.to_string_lossy().to_string())
+                    .collect();
+                open_with::deliver(_app, files);
+            }
+        });
 }
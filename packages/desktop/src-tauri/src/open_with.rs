@@ -0,0 +1,39 @@
+//! "Open with Fotos": photos handed to the app by the OS - launch arguments
+//! on Windows and Linux, open events on macOS.
+
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+
+use crate::paths;
+
+/// Files opened by the OS that the webview hasn't picked up yet. The first
+/// launch's files arrive before the frontend listens, so they wait here and
+/// "open-files" only signals that there is something to take.
+#[derive(Default)]
+pub struct PendingFiles(Mutex<Vec<String>>);
+
+impl PendingFiles {
+    pub fn take(&self) -> Vec<String> {
+        self.0.lock().map(|mut files| std::mem::take(&mut *files)).unwrap_or_default()
+    }
+}
+
+/// Supported photos among the launch arguments (the first is the executable).
+pub fn from_args(args: impl Iterator<Item = String>) -> Vec<String> {
+    args.skip(1)
+        .filter_map(|arg| paths::photo_file(&arg).ok())
+        .map(|path| fotos_core::encode_path(&path))
+        .collect()
+}
+
+/// Queues opened files, lets the webview read them and tells it to fetch them.
+pub fn deliver<R: tauri::Runtime>(app: &tauri::AppHandle<R>, files: Vec<String>) {
+    if files.is_empty() {
+        return;
+    }
+    app.state::<paths::Grants>().open(&files.iter().map(|file| fotos_core::decode_path(file)).collect::<Vec<_>>());
+    if let Ok(mut pending) = app.state::<PendingFiles>().0.lock() {
+        pending.extend(files);
+    }
+    let _ = app.emit("open-files", ());
+}
@@ -0,0 +1,266 @@
+//! Validation for paths and tile coordinates coming from the webview.
+//! Commands must not be usable to read or write outside the places they are meant for.
+
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::shared_index::SharedIndex;
+
+/// Deepest zoom level any tile server we use provides
+const MAX_TILE_ZOOM: u32 = 22;
+
+/// Where the library and its caches live, derived once at startup from the
+/// app's data and cache directories. Commands take these from managed state
+/// instead of trusting paths sent by the webview.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LibraryPaths {
+    pub db_path: String,
+    pub thumb_dir: String,
+    /// Decoded RAW previews, enhanced previews and extracted clips
+    pub preview_dir: String,
+    /// Images pasted from the clipboard or saved from the web, which have no file of their own
+    pub pasted_dir: String,
+    /// Map tiles - kept in the cache dir so clearing the library preserves them
+    pub tile_dir: String,
+    /// Database snapshots taken before the library is reset or restored
+    pub backup_dir: String,
+    /// Root of the `fotos_core::TempManager`
+    pub temp_dir: String,
+    /// Set when neither the usual cache locations nor the fallback can be
+    /// written to: thumbnails and tiles aren't saved until that's fixed
+    pub cache_unavailable: Option<fotos_core::CacheUnavailable>,
+    /// Whether the caches are in the user's fallback location (see `fallback_cache`)
+    pub using_fallback_cache: bool,
+    /// The database at `db_path`, open for as long as the app runs
+    #[serde(skip)]
+    index: std::sync::Arc<SharedIndex>,
+}
+
+impl LibraryPaths {
+    /// Creates the directories so commands can assume they exist. Thumbnails,
+    /// tiles and temp files move to `fallback` if their usual directories
+    /// can't be written to; only the library's own data (database, pasted
+    /// originals, backups) must be writable.
+    pub fn open(data_dir: &Path, cache_dir: &Path, fallback: Option<&Path>) -> std::io::Result<Self> {
+        let pasted_dir = data_dir.join("pasted");
+        let backup_dir = data_dir.join("backups");
+        std::fs::create_dir_all(&pasted_dir)?;
+        std::fs::create_dir_all(&backup_dir)?;
+
+        let check = |dir: &Path| fotos_core::check_cache_dir(dir.to_string_lossy().into_owned());
+        let usual = (data_dir.join("thumbnails"), cache_dir.to_path_buf());
+        let problem = check(&usual.0).or_else(|| check(&usual.1));
+        let usable_fallback = fallback.filter(|dir| problem.is_some() && check(dir).is_none());
+        let (thumb_dir, cache_root) = match usable_fallback {
+            Some(dir) => (dir.join("thumbnails"), dir.to_path_buf()),
+            None => usual,
+        };
+        let tile_dir = cache_root.join("tiles");
+        let temp_dir = match (&problem, usable_fallback) {
+            // Temp files still need a home for previews and downloads to work
+            (Some(_), None) => std::env::temp_dir().join("fotos"),
+            _ => cache_root.join("tmp"),
+        };
+        if problem.is_none() || usable_fallback.is_some() {
+            std::fs::create_dir_all(&thumb_dir)?;
+            std::fs::create_dir_all(&tile_dir)?;
+        }
+        Ok(Self {
+            db_path: data_dir.join("fotos.db").to_string_lossy().into_owned(),
+            thumb_dir: thumb_dir.to_string_lossy().into_owned(),
+            preview_dir: data_dir.to_string_lossy().into_owned(),
+            pasted_dir: pasted_dir.to_string_lossy().into_owned(),
+            tile_dir: tile_dir.to_string_lossy().into_owned(),
+            backup_dir: backup_dir.to_string_lossy().into_owned(),
+            temp_dir: temp_dir.to_string_lossy().into_owned(),
+            cache_unavailable: problem.filter(|_| usable_fallback.is_none()),
+            using_fallback_cache: usable_fallback.is_some(),
+            index: Default::default(),
+        })
+    }
+
+    /// The library's index, shared by every command (see `SharedIndex`).
+    pub fn index(&self) -> Result<std::sync::Arc<fotos_core::PhotoIndex>, fotos_core::CoreError> {
+        self.index.get(&self.db_path)
+    }
+
+    /// Closes the shared index, before the database file is deleted or replaced.
+    pub fn close_index(&self) {
+        self.index.close();
+    }
+}
+
+/// Joins `relative` onto `root`, rejecting absolute paths and `..` so the
+/// result can't escape `root`.
+pub fn resolve_within(root: &Path, relative: &Path) -> Result<PathBuf, String> {
+    if relative.as_os_str().is_empty() {
+        return Err("Empty cache path".to_string());
+    }
+    for component in relative.components() {
+        if !matches!(component, Component::Normal(_)) {
+            return Err(format!("Path escapes its cache directory: {}", relative.display()));
+        }
+    }
+    Ok(root.join(relative))
+}
+
+/// Cache location of a map tile, with z/x/y checked against the tile grid.
+pub fn tile_path(cache_dir: &str, z: u32, x: u32, y: u32) -> Result<PathBuf, String> {
+    if z > MAX_TILE_ZOOM {
+        return Err(format!("Invalid zoom level {}", z));
+    }
+    let tiles_per_side = 1u32 << z;
+    if x >= tiles_per_side || y >= tiles_per_side {
+        return Err(format!("Invalid tile {}/{}/{}", z, x, y));
+    }
+    resolve_within(
+        Path::new(cache_dir),
+        &Path::new(&z.to_string()).join(x.to_string()).join(format!("{}.png", y)),
+    )
+}
+
+/// File extension for pasted image data, sniffed from its signature.
+/// Only formats the importer handles are accepted, extra ones included
+/// (see `fotos_core::supported_extensions`).
+pub fn image_extension(bytes: &[u8]) -> Option<&'static str> {
+    let extension = if bytes.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+        "png"
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        "jpg"
+    } else if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        "webp"
+    } else if bytes.starts_with(b"II*\0") || bytes.starts_with(b"MM\0*") {
+        "tif"
+    } else if bytes.starts_with(&[0xFF, 0x0A]) || bytes.starts_with(b"\0\0\0\x0CJXL \r\n\x87\n") {
+        "jxl"
+    } else {
+        return None;
+    };
+    fotos_core::is_supported_extension(extension).then_some(extension)
+}
+
+/// Downloads (map tiles, images saved from the web) are only fetched over HTTP(S).
+pub fn check_http_url(url: &str) -> Result<(), String> {
+    if url.starts_with("https://") || url.starts_with("http://") {
+        Ok(())
+    } else {
+        Err(format!("Unsupported URL: {}", url))
+    }
+}
+
+/// An existing photo file: a regular file with an extension the importer handles.
+/// For files the OS hands the app; ones the webview asks for go through `library_photo`.
+/// `path` may be in the index's encoded form (see `fotos_core::encode_path`).
+pub fn photo_file(path: &str) -> Result<PathBuf, String> {
+    let path = std::fs::canonicalize(fotos_core::decode_path(path)).map_err(|e| e.to_string())?;
+    if !path.is_file() || !fotos_core::is_supported_image(&path) {
+        return Err(format!("Not a photo: {}", path.display()));
+    }
+    Ok(path)
+}
+
+/// A photo file the webview may read: one in the index, or one the user
+/// picked or opened with the app (see `Grants`). Keeps file-reading commands
+/// from serving arbitrary files.
+pub fn library_photo(path: &str, library: &LibraryPaths, grants: &Grants) -> Result<PathBuf, String> {
+    let file = photo_file(path)?;
+    if grants.covers(&file) {
+        return Ok(file);
+    }
+    let index = library.index().map_err(|e| e.to_string())?;
+    // Indexed under the path it was imported by, which may not be the canonical one
+    let indexed = index.paths_exist(vec![path.to_string(), fotos_core::encode_path(&file)]).map_err(|e| e.to_string())?;
+    if !indexed.contains(&true) {
+        return Err(format!("Not in the library: {}", file.display()));
+    }
+    Ok(file)
+}
+
+/// An output file the user picked for an export: in a folder they chose in a
+/// dialog, and with one of `extensions`, so an export can't overwrite e.g. a
+/// config file.
+pub fn export_file(path: &str, extensions: &[&str], grants: &Grants) -> Result<PathBuf, String> {
+    let path = PathBuf::from(path);
+    let ext = path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+    if !extensions.contains(&ext.as_str()) {
+        return Err(format!("Export must be a {} file", extensions.join("/")));
+    }
+    if !path.parent().is_some_and(|dir| grants.is_chosen_dir(dir)) {
+        return Err(format!("Not a location chosen for the export: {}", path.display()));
+    }
+    Ok(path)
+}
+
+/// A folder the user chose to export into.
+pub fn export_dir(path: &str, grants: &Grants) -> Result<PathBuf, String> {
+    let path = PathBuf::from(path);
+    if !grants.is_chosen_dir(&path) {
+        return Err(format!("Not a location chosen for the export: {}", path.display()));
+    }
+    Ok(path)
+}
+
+/// Files and folders the user picked in a dialog or opened with the app. The
+/// dialog plugin adds each pick to the asset protocol scope, and this follows
+/// the scope's events, so unlike a path sent by the webview a grant can't be
+/// made up. Lasts until the app quits.
+#[derive(Debug, Clone, Default)]
+pub struct Grants {
+    picked: Arc<Mutex<Vec<PathBuf>>>,
+    /// Handed over by the OS ("Open with"); readable, but not export targets
+    opened: Arc<Mutex<Vec<PathBuf>>>,
+}
+
+impl Grants {
+    /// Records every path allowed on `scope` from now on.
+    pub fn follow(scope: &tauri::scope::fs::Scope) -> Self {
+        let grants = Self::default();
+        let picked = grants.picked.clone();
+        scope.listen(move |event| {
+            if let tauri::scope::fs::Event::PathAllowed(path) = event {
+                if let Ok(mut picked) = picked.lock() {
+                    picked.push(canonical(path));
+                }
+            }
+        });
+        grants
+    }
+
+    /// Lets the webview read files the OS opened with the app.
+    pub fn open(&self, files: &[PathBuf]) {
+        if let Ok(mut opened) = self.opened.lock() {
+            opened.extend(files.iter().map(|file| canonical(file)));
+        }
+    }
+
+    /// Whether `path` was picked or opened itself, or is inside a picked folder.
+    fn covers(&self, path: &Path) -> bool {
+        let path = canonical(path);
+        self.picked.lock().is_ok_and(|picked| picked.iter().any(|p| path.starts_with(p)))
+            || self.opened.lock().is_ok_and(|opened| opened.contains(&path))
+    }
+
+    /// Whether `dir` was picked as a folder, or is where a picked file (e.g.
+    /// the target of a save dialog) goes.
+    fn is_chosen_dir(&self, dir: &Path) -> bool {
+        let dir = canonical(dir);
+        self.picked.lock().is_ok_and(|picked| {
+            picked.iter().any(|p| *p == dir || (!p.is_dir() && p.parent() == Some(dir.as_path())))
+        })
+    }
+}
+
+/// `path` with symlinks and `..` resolved as far as it exists; a save
+/// dialog's target doesn't yet, but its folder does.
+fn canonical(path: &Path) -> PathBuf {
+    if let Ok(path) = std::fs::canonicalize(path) {
+        return path;
+    }
+    match (path.parent().and_then(|dir| std::fs::canonicalize(dir).ok()), path.file_name()) {
+        (Some(dir), Some(name)) => dir.join(name),
+        _ => path.to_path_buf(),
+    }
+}
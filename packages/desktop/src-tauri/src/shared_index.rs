@@ -0,0 +1,41 @@
+//! The library's `PhotoIndex`, opened once and shared by every command, so
+//! its reader pool and query cache live as long as the app instead of one
+//! IPC call.
+
+use std::sync::{Arc, Mutex};
+
+use fotos_core::{CoreError, PhotoIndex};
+
+#[derive(Default)]
+pub struct SharedIndex {
+    index: Mutex<Option<Arc<PhotoIndex>>>,
+}
+
+impl SharedIndex {
+    /// The open index, opening the database at `db_path` on first use.
+    pub fn get(&self, db_path: &str) -> Result<Arc<PhotoIndex>, CoreError> {
+        let mut index = self.index.lock().map_err(|e| CoreError::Database(e.to_string()))?;
+        if let Some(index) = index.as_ref() {
+            return Ok(index.clone());
+        }
+        let opened = PhotoIndex::open(db_path.to_string())?;
+        *index = Some(opened.clone());
+        Ok(opened)
+    }
+
+    /// Lets go of the index before its database is deleted or replaced; the
+    /// next `get` opens it again. Commands still running keep theirs until
+    /// they finish.
+    pub fn close(&self) {
+        if let Ok(mut index) = self.index.lock() {
+            *index = None;
+        }
+    }
+}
+
+impl std::fmt::Debug for SharedIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let open = self.index.lock().map(|index| index.is_some()).unwrap_or(false);
+        f.debug_struct("SharedIndex").field("open", &open).finish()
+    }
+}
@@ -0,0 +1,128 @@
+//! Quick health check right after launch: the database's `quick_check`,
+//! whether the caches can be written to, the temp files an earlier run left
+//! behind and imports that were interrupted. The report is announced to the
+//! frontend with a "startup-report" event so it can offer recovery (restore
+//! a backup, pick a fallback cache, resume an import) up front instead of
+//! failing somewhere mid-use. The check can be turned off in settings, kept
+//! as JSON in the app data dir.
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use fotos_core::{CacheUnavailable, ImportSession};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::errors::CommandError;
+use crate::paths::LibraryPaths;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StartupSettings {
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+}
+
+impl Default for StartupSettings {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StartupReport {
+    /// What `quick_check` found, or why the database couldn't be opened;
+    /// empty when it's healthy
+    pub database_problems: Vec<String>,
+    pub cache_unavailable: Option<CacheUnavailable>,
+    /// Temp files of an earlier run that were cleared out
+    pub temp_swept: u32,
+    /// Imports to offer resuming, oldest first
+    pub unfinished_imports: Vec<ImportSession>,
+    pub duration_ms: u64,
+}
+
+pub struct StartupCheck {
+    file: PathBuf,
+    settings: Mutex<StartupSettings>,
+    /// This launch's report, once the check ran
+    report: Mutex<Option<StartupReport>>,
+}
+
+impl StartupCheck {
+    pub fn restore(file: PathBuf) -> Self {
+        let settings = std::fs::read(&file)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        Self { file, settings: Mutex::new(settings), report: Mutex::new(None) }
+    }
+
+    pub fn settings(&self) -> StartupSettings {
+        self.settings.lock().map(|settings| settings.clone()).unwrap_or_default()
+    }
+
+    pub fn set_enabled(&self, enabled: bool) -> Result<StartupSettings, CommandError> {
+        let mut settings = self.settings.lock().map_err(|e| e.to_string())?;
+        settings.enabled = enabled;
+        std::fs::write(&self.file, serde_json::to_vec(&*settings).map_err(|e| e.to_string())?)?;
+        Ok(settings.clone())
+    }
+
+    pub fn report(&self) -> Option<StartupReport> {
+        self.report.lock().ok().and_then(|report| report.clone())
+    }
+
+    /// Checks the library now and keeps the report.
+    pub fn run(&self, library: &LibraryPaths, temp_swept: u32) -> StartupReport {
+        let started = Instant::now();
+        let mut report = StartupReport {
+            cache_unavailable: library
+                .cache_unavailable
+                .clone()
+                .or_else(|| fotos_core::check_cache_dir(library.thumb_dir.clone())),
+            temp_swept,
+            ..Default::default()
+        };
+        match library.index() {
+            Ok(index) => {
+                report.database_problems = index.integrity_check().unwrap_or_else(|e| vec![e.to_string()]);
+                // A damaged database can't be trusted to list them
+                if report.database_problems.is_empty() {
+                    report.unfinished_imports = index.unfinished_import_sessions().unwrap_or_default();
+                }
+            }
+            Err(e) => report.database_problems.push(e.to_string()),
+        }
+        report.duration_ms = started.elapsed().as_millis() as u64;
+        if let Ok(mut kept) = self.report.lock() {
+            *kept = Some(report.clone());
+        }
+        report
+    }
+}
+
+/// Runs the check once in the background if it's enabled, so a large
+/// library doesn't hold up the window.
+pub fn schedule<R: Runtime>(app: AppHandle<R>) {
+    std::thread::spawn(move || {
+        let check = app.state::<StartupCheck>();
+        if !check.settings().enabled {
+            return;
+        }
+        let swept = app.state::<fotos_core::TempManager>().swept();
+        let report = check.run(&app.state::<LibraryPaths>(), swept);
+        println!(
+            "[Startup] checked in {} ms: {} database problems, {} temp files swept, {} unfinished imports",
+            report.duration_ms,
+            report.database_problems.len(),
+            report.temp_swept,
+            report.unfinished_imports.len()
+        );
+        let _ = app.emit("startup-report", report);
+    });
+}
@@ -0,0 +1,177 @@
+//! Disk usage of the library and its caches, per category, for the settings
+//! screen, and clearing them one category at a time.
+
+use std::path::{Path, PathBuf};
+
+use crate::paths::LibraryPaths;
+
+/// Cache subdirectories of `LibraryPaths::preview_dir`
+pub const RAW_PREVIEWS: &str = "raw_previews";
+pub const ENHANCED_PREVIEWS: &str = "enhanced_previews";
+pub const MOTION_VIDEOS: &str = "motion_videos";
+
+/// Bytes used per category. Originals only counts files the app owns (pasted
+/// images); photos imported from elsewhere stay where the user keeps them.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct StorageBreakdown {
+    pub originals: u64,
+    pub thumbnails: u64,
+    pub raw_previews: u64,
+    pub enhanced_previews: u64,
+    pub motion_videos: u64,
+    pub tiles: u64,
+    /// The database including its WAL and shared-memory files
+    pub database: u64,
+    /// Snapshots taken before resets and restores
+    pub backups: u64,
+}
+
+impl StorageBreakdown {
+    pub fn measure(library: &LibraryPaths) -> Self {
+        let preview_dir = Path::new(&library.preview_dir);
+        let database = CacheCategory::Database.describe(library).bytes;
+        Self {
+            originals: dir_size(Path::new(&library.pasted_dir)),
+            thumbnails: dir_size(Path::new(&library.thumb_dir)),
+            raw_previews: dir_size(&preview_dir.join(RAW_PREVIEWS)),
+            enhanced_previews: dir_size(&preview_dir.join(ENHANCED_PREVIEWS)),
+            motion_videos: dir_size(&preview_dir.join(MOTION_VIDEOS)),
+            tiles: dir_size(Path::new(&library.tile_dir)),
+            database,
+            backups: dir_size(Path::new(&library.backup_dir)),
+        }
+    }
+}
+
+/// What can be cleared on its own from settings
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheCategory {
+    Thumbnails,
+    /// RAW previews, enhanced previews and extracted Live Photo clips
+    Previews,
+    Tiles,
+    Database,
+}
+
+/// Shown in the confirmation before clearing a category
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ClearInfo {
+    pub bytes: u64,
+    pub consequence: String,
+}
+
+impl CacheCategory {
+    fn files(self, library: &LibraryPaths) -> Vec<PathBuf> {
+        let preview_dir = Path::new(&library.preview_dir);
+        match self {
+            Self::Thumbnails => vec![PathBuf::from(&library.thumb_dir)],
+            Self::Previews => [RAW_PREVIEWS, ENHANCED_PREVIEWS, MOTION_VIDEOS]
+                .iter()
+                .map(|dir| preview_dir.join(dir))
+                .collect(),
+            Self::Tiles => vec![PathBuf::from(&library.tile_dir)],
+            Self::Database => ["", "-wal", "-shm"]
+                .iter()
+                .map(|suffix| PathBuf::from(format!("{}{}", library.db_path, suffix)))
+                .collect(),
+        }
+    }
+
+    fn consequence(self) -> &'static str {
+        match self {
+            Self::Thumbnails => "Thumbnails are regenerated from the originals, which can take a while for large libraries.",
+            Self::Previews => "RAW and enhanced previews and Live Photo clips are recreated the next time a photo is opened.",
+            Self::Tiles => "Map tiles are downloaded again as the map is browsed, so the map needs a connection until then.",
+            Self::Database => "The library forgets all imported photos, places and rotations. Photo files on disk are not touched; import them again to rebuild the library. A backup is kept in the library's backups folder.",
+        }
+    }
+
+    pub fn describe(self, library: &LibraryPaths) -> ClearInfo {
+        ClearInfo {
+            bytes: self.files(library).iter().map(|path| size(path)).sum(),
+            consequence: self.consequence().to_string(),
+        }
+    }
+
+    /// Deletes the category's files and returns the bytes freed. Cache
+    /// directories are recreated empty so commands can keep assuming they exist.
+    pub fn clear(self, library: &LibraryPaths) -> Result<u64, String> {
+        if matches!(self, Self::Database) {
+            snapshot_library(library)?;
+            // The next command opens the fresh database instead of the deleted one
+            library.close_index();
+        }
+        let mut freed = 0;
+        for path in self.files(library) {
+            let bytes = size(&path);
+            if path.is_dir() {
+                std::fs::remove_dir_all(&path).map_err(|e| e.to_string())?;
+                std::fs::create_dir_all(&path).map_err(|e| e.to_string())?;
+            } else if path.exists() {
+                std::fs::remove_file(&path).map_err(|e| e.to_string())?;
+            }
+            freed += bytes;
+        }
+        Ok(freed)
+    }
+}
+
+/// Automatic snapshots kept in `LibraryPaths::backup_dir`; older ones are deleted
+const MAX_SNAPSHOTS: usize = 5;
+
+/// Backs the database up into the backup folder before it's reset or
+/// replaced, so a mistaken reset can be undone. Returns the snapshot, or
+/// None when there's no library yet.
+pub fn snapshot_library(library: &LibraryPaths) -> Result<Option<PathBuf>, String> {
+    if !Path::new(&library.db_path).exists() {
+        return Ok(None);
+    }
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let backup_dir = Path::new(&library.backup_dir);
+    let snapshot = backup_dir.join(format!("fotos-{}.db", stamp));
+    let index = library.index().map_err(|e| e.to_string())?;
+    index.backup_to(snapshot.to_string_lossy().into_owned()).map_err(|e| e.to_string())?;
+
+    // Millisecond stamps of equal length sort oldest first
+    let mut snapshots: Vec<PathBuf> = std::fs::read_dir(backup_dir)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            name.starts_with("fotos-") && name.ends_with(".db")
+        })
+        .collect();
+    snapshots.sort();
+    for old in snapshots.iter().rev().skip(MAX_SNAPSHOTS) {
+        let _ = std::fs::remove_file(old);
+    }
+    Ok(Some(snapshot))
+}
+
+fn size(path: &Path) -> u64 {
+    if path.is_dir() { dir_size(path) } else { file_size(path) }
+}
+
+fn file_size(path: &Path) -> u64 {
+    std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+/// Total size of the files below `dir`. Symlinks aren't followed and missing
+/// or unreadable entries count as empty.
+pub fn dir_size(dir: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(kind) if kind.is_dir() => dir_size(&entry.path()),
+            Ok(kind) if kind.is_file() => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            _ => 0,
+        })
+        .sum()
+}
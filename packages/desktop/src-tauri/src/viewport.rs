@@ -0,0 +1,24 @@
+//! Viewport hints for background thumbnailing: the grid reports which photos
+//! are on screen, and backfill passes generate those before carrying on in
+//! library order, so scrolling reveals thumbnails sooner.
+
+use std::sync::Mutex;
+
+/// Photos on screen as of the grid's last report, not yet thumbnailed by a
+/// backfill pass. Each report replaces the previous one: photos scrolled past
+/// are left to the sequential backfill.
+#[derive(Default)]
+pub struct VisibleHints(Mutex<Vec<i64>>);
+
+impl VisibleHints {
+    pub fn set(&self, ids: Vec<i64>) {
+        if let Ok(mut hints) = self.0.lock() {
+            *hints = ids;
+        }
+    }
+
+    /// The hinted photos, clearing the hint.
+    pub fn take(&self) -> Vec<i64> {
+        self.0.lock().map(|mut hints| std::mem::take(&mut *hints)).unwrap_or_default()
+    }
+}
@@ -0,0 +1,94 @@
+//! Watched folders: photos added to them are imported automatically (see
+//! `fotos_core::start_watch`) and announced to the frontend with a
+//! "watch-imported" event. The folders are kept as JSON in the app data dir
+//! and watched again at the next launch.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use fotos_core::{FolderWatcher, ImportResult, PhotoCoreConfig, WatchListener};
+use tauri::{AppHandle, Emitter, Runtime};
+
+use crate::errors::CommandError;
+use crate::paths::LibraryPaths;
+
+pub struct Watchers {
+    file: PathBuf,
+    active: Mutex<BTreeMap<String, Arc<FolderWatcher>>>,
+}
+
+/// Forwards a watcher's imports to the frontend.
+struct EmitImported<R: Runtime>(AppHandle<R>);
+
+impl<R: Runtime> WatchListener for EmitImported<R> {
+    fn imported(&self, root: String, result: ImportResult) {
+        println!("[Watch] {}: {} imported, {} failed", root, result.success, result.failure);
+        let _ = self.0.emit("watch-imported", serde_json::json!({ "root": root, "result": result }));
+    }
+}
+
+impl Watchers {
+    pub fn new(file: PathBuf) -> Self {
+        Self { file, active: Mutex::new(BTreeMap::new()) }
+    }
+
+    /// Watches the folders saved by the last session. Ones that are gone are dropped.
+    pub fn restore<R: Runtime>(&self, app: &AppHandle<R>, library: &LibraryPaths) {
+        let saved: Vec<String> = std::fs::read(&self.file)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        for root in saved {
+            if let Err(e) = self.start(root.clone(), app, library) {
+                println!("[Watch] not watching {} any more: {}", root, e.fallback);
+            }
+        }
+        self.save();
+    }
+
+    /// Starts watching `root`, unless it already is.
+    pub fn start<R: Runtime>(&self, root: String, app: &AppHandle<R>, library: &LibraryPaths) -> Result<(), CommandError> {
+        let mut active = self.active.lock().map_err(|e| e.to_string())?;
+        if active.contains_key(&root) {
+            return Ok(());
+        }
+        let index = library.index()?;
+        let config = PhotoCoreConfig {
+            thumbnail_dir: library.thumb_dir.clone(),
+            thumbnail_size: 256,
+        };
+        let watcher = fotos_core::start_watch(root.clone(), index, config, Arc::new(EmitImported(app.clone())))?;
+        active.insert(root, watcher);
+        drop(active);
+        self.save();
+        Ok(())
+    }
+
+    /// Stops watching `root`. Returns false if it wasn't watched.
+    pub fn stop(&self, root: &str) -> bool {
+        let watcher = self.active.lock().ok().and_then(|mut active| active.remove(root));
+        let Some(watcher) = watcher else {
+            return false;
+        };
+        watcher.stop_watch();
+        self.save();
+        true
+    }
+
+    pub fn list(&self) -> Vec<String> {
+        self.active.lock().map(|active| active.keys().cloned().collect()).unwrap_or_default()
+    }
+
+    fn save(&self) {
+        let roots = self.list();
+        if roots.is_empty() && !self.file.exists() {
+            return;
+        }
+        if let Ok(json) = serde_json::to_vec(&roots) {
+            if let Err(e) = std::fs::write(&self.file, json) {
+                println!("[Watch] failed to save watched folders: {}", e);
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! Historical weather lookup for the optional enrichment pass.
+
+use fotos_core::Weather;
+
+/// Source of historical weather. Implement this to plug in another service or an offline dataset.
+pub trait WeatherProvider {
+    /// Weather at `lat`/`lon` on `date` (YYYY-MM-DD, local time) around `hour`.
+    async fn lookup(&self, lat: f64, lon: f64, date: &str, hour: usize) -> Result<Option<Weather>, String>;
+}
+
+/// Open-Meteo historical archive (free, no API key).
+pub struct OpenMeteo {
+    client: reqwest::Client,
+}
+
+impl OpenMeteo {
+    pub fn new() -> Self {
+        Self { client: crate::http::client().clone() }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct ArchiveResponse {
+    hourly: Hourly,
+}
+
+#[derive(serde::Deserialize)]
+struct Hourly {
+    temperature_2m: Vec<Option<f64>>,
+    weather_code: Vec<Option<u32>>,
+}
+
+impl WeatherProvider for OpenMeteo {
+    async fn lookup(&self, lat: f64, lon: f64, date: &str, hour: usize) -> Result<Option<Weather>, String> {
+        let url = format!(
+            "https://archive-api.open-meteo.com/v1/archive?latitude={}&longitude={}&start_date={}&end_date={}&hourly=temperature_2m,weather_code&timezone=auto",
+            lat, lon, date, date
+        );
+        let bytes = self.client.get(&url)
+            .send().await.map_err(|e| e.to_string())?
+            .error_for_status().map_err(|e| e.to_string())?
+            .bytes().await.map_err(|e| e.to_string())?;
+        let response: ArchiveResponse = serde_json::from_slice(&bytes).map_err(|e| e.to_string())?;
+
+        let code = match response.hourly.weather_code.get(hour).copied().flatten() {
+            Some(code) => code,
+            None => return Ok(None),
+        };
+        Ok(Some(Weather {
+            temperature_c: response.hourly.temperature_2m.get(hour).copied().flatten(),
+            condition: condition_from_wmo(code).to_string(),
+            source: "open-meteo".to_string(),
+        }))
+    }
+}
+
+/// Maps WMO weather interpretation codes to the normalized conditions stored in the index.
+fn condition_from_wmo(code: u32) -> &'static str {
+    match code {
+        0 | 1 => "clear",
+        2 | 3 => "cloudy",
+        45 | 48 => "fog",
+        51..=57 => "drizzle",
+        61..=67 | 80..=82 => "rain",
+        71..=77 | 85 | 86 => "snow",
+        95..=99 => "thunderstorm",
+        _ => "cloudy",
+    }
+}
+
+/// Splits an EXIF `date_taken` ("2023-01-15 10:30:00") into date and hour.
+pub fn split_date_taken(date_taken: &str) -> Option<(String, usize)> {
+    let date = date_taken.get(0..10)?.replace(':', "-");
+    let hour = date_taken.get(11..13)?.parse().ok()?;
+    Some((date, hour))
+}
@@ -0,0 +1,494 @@
+// Generated from the Rust types in packages/core - do not edit.
+
+/** Auto-enhance adjustments computed from an image. Pure data so callers can store it (see `PhotoIndex::set_enhance`), toggle it, or re-apply it later. */
+export interface AutoEnhance {
+    /** Luminance level mapped to black (0-255) */
+    black_point: number;
+    /** Luminance level mapped to white (0-255) */
+    white_point: number;
+    /** Gray-world white balance gains */
+    gain_r: number;
+    gain_g: number;
+    gain_b: number;
+    /** Saturation multiplier (1.0 = unchanged) */
+    saturation: number;
+}
+
+/** Why a thumbnail or cache directory can't be written to. */
+export type CacheProblem = 'read_only' | 'full' | 'unusable';
+
+/** A cache directory thumbnails and previews can't be written to, found when the library is opened (see `check_cache_dir`). */
+export interface CacheUnavailable {
+    path: string;
+    problem: CacheProblem;
+    /** The OS error, for bug reports */
+    detail: string;
+}
+
+/** How precisely a hand-entered capture date is known. */
+export type DatePrecision = 'year' | 'month' | 'day';
+
+/** Photos that look alike - bursts, re-edits, resized copies - for the duplicate report. */
+export interface DuplicateGroup {
+    /** In import order */
+    photos: PhotoInfo[];
+}
+
+export interface GeoPoint {
+    lat: number;
+    lon: number;
+}
+
+export interface ImportBreakdown {
+    key: string;
+    success: number;
+    failure: number;
+}
+
+/** One file that failed to import. */
+export interface ImportError {
+    path: string;
+    stage: ImportStage;
+    message: string;
+}
+
+export interface ImportOptions {
+    /** Only scan and report what an import would do (`ImportResult::plan`); nothing is written. */
+    dry_run?: boolean;
+    /** Re-import photos that were deliberately deleted from the library (skipped by default) */
+    reimport_deleted?: boolean;
+    /** Threads reading metadata, hashing and thumbnailing in parallel; 0 or 1 imports one file at a time. Index inserts stay batched either way. */
+    concurrency?: number;
+    /** Only files with these extensions (any case, no dot), e.g. the RAW ones; empty imports every supported format */
+    extensions?: string[];
+    /** Look in subfolders too (the default); off imports only the folder's own files */
+    recursive?: boolean;
+    /** Only photos taken at or after this time, in local seconds since 1970 like `PhotoMetadata::taken_at`. Photos without a date are left out when either bound is set. */
+    taken_after?: number;
+    /** Only photos taken before this time (exclusive), as `taken_after` */
+    taken_before?: number;
+    /** Only files of at least this many bytes, to leave out icons and the like */
+    min_file_size?: number;
+}
+
+/** What an import would do, reported by a dry run. */
+export interface ImportPlan {
+    /** Photos that would be added */
+    new_files: number;
+    /** Photos already in the index (same path, or same hash at another path) */
+    duplicates: number;
+    /** Photos deleted from the library before, skipped unless re-import is requested */
+    previously_deleted: number;
+    /** Files skipped because their format isn't supported */
+    unsupported: number;
+    /** Photos left out by the import's filters (`ImportOptions`) */
+    filtered: number;
+    /** Supported files that can't be read and would fail to import */
+    unreadable: number;
+    /** Total size of the new photos */
+    new_bytes: number;
+    /** Rough import duration estimate */
+    estimated_seconds: number;
+}
+
+export interface ImportResult {
+    success: number;
+    failure: number;
+    /** Photos skipped because they were deleted from the library before */
+    skipped_deleted: number;
+    /** Photos left out by the import's date range (`ImportOptions::taken_after`/`taken_before`) */
+    skipped_filtered: number;
+    /** Set for dry runs instead of importing */
+    plan?: ImportPlan;
+    /** Outcome per file extension (lowercase, "" for none) */
+    by_extension: ImportBreakdown[];
+    /** Outcome per source folder, relative to the import root ("." for the root itself) */
+    by_folder: ImportBreakdown[];
+    /** Why files failed, for the first `MAX_IMPORT_ERRORS` failures in the order they happened; `failure` counts them all */
+    errors: ImportError[];
+}
+
+/** An import of a folder, journaled so one interrupted by a crash or a cancel continues where it stopped the next time the folder is imported. */
+export interface ImportSession {
+    id: number;
+    /** The imported folder, as passed to the import */
+    root: string;
+    started_at: number;
+    /** Files handled so far (imported, failed or skipped) */
+    done: number;
+    /** Files still to do */
+    pending: number;
+    /** Whether this continues an earlier, unfinished import of `root` (always, for sessions listed as unfinished) */
+    resumed: boolean;
+}
+
+/** A step of importing one file. */
+export type ImportStage = 'metadata' | 'hash' | 'thumbnail' | 'index';
+
+/** Import speed over the last few seconds, reported periodically while an import runs so a slow source drive can be told from a busy CPU. */
+export interface ImportThroughput {
+    files_per_sec: number;
+    mb_per_sec: number;
+    /** Where the time went, slowest stage first */
+    stage_breakdown: StageTime[];
+}
+
+/** Outcome of a `migrate_legacy_thumbnails` batch. */
+export interface LegacyThumbnailReport {
+    /** Moved into the sharded layout */
+    migrated: number;
+    /** Deleted: already regenerated, corrupt, or of a photo no longer indexed */
+    removed: number;
+    /** Legacy files left for later batches */
+    remaining: number;
+}
+
+/** Outcome of `PhotoIndex::verify`. */
+export interface LibraryVerifyReport {
+    checked: number;
+    /** Photos whose original is gone, now flagged missing */
+    missing: number[];
+    /** Photos flagged missing before whose original is back */
+    found: number;
+    /** Cached thumbnails of no photo in the library or the trash */
+    orphaned_thumbnails: number;
+    orphaned_bytes: number;
+    /** Orphaned thumbnails deleted */
+    removed: number;
+}
+
+/** File format of `PhotoIndex::export_manifest`. */
+export type ManifestFormat = 'csv' | 'json';
+
+/** Whether an indexed file is a still photo or a video. */
+export type MediaType = 'photo' | 'video';
+
+/** Photos either side of the one shown in the detail view, nearest first. */
+export interface Neighbors {
+    before: PhotoInfo[];
+    after: PhotoInfo[];
+}
+
+/** A photo to add with `PhotoIndex::insert_batch`. */
+export interface NewPhoto {
+    path: string;
+    hash: string;
+    metadata: PhotoMetadata;
+}
+
+/** Outcome of the orientation maintenance pass, see `normalize_orientation`. */
+export interface OrientationReport {
+    /** Photos with an EXIF orientation other than 1 */
+    checked: number;
+    /** Cached thumbnails whose shape didn't match the photo's and were regenerated */
+    thumbnails_fixed: number;
+    /** Originals rewritten with upright pixels and orientation 1 */
+    rewritten: number;
+    /** Photos whose pixels can't be rewritten (RAW, PNG, WebP) */
+    skipped: number;
+    failed: number;
+}
+
+/** Outcome of `PhotoIndex::audit_originals`. */
+export interface OriginalsAuditReport {
+    checked: number;
+    /** Originals whose content still matches their checksum */
+    verified: number;
+    /** Originals checksummed for the first time, or again because their size or modification time changed (edited or replaced) */
+    recorded: number;
+    /** Photos whose original's content changed while its size and modification time didn't, now flagged corrupt */
+    corrupt: number[];
+    /** Originals that couldn't be read, e.g. missing; `verify` deals with those */
+    unreadable: number;
+}
+
+/** A user-curated collection of photos. Photos can be in any number of albums. */
+export interface PhotoAlbum {
+    id: number;
+    name: string;
+    photo_count: number;
+    /** First photo added, shown as the album cover */
+    cover_photo_id?: number;
+    created_at: number;
+}
+
+/** Photo ids changed since a revision, see `PhotoIndex::changes_since`. */
+export interface PhotoChanges {
+    /** Revision these changes bring the caller up to */
+    revision: number;
+    added: number[];
+    updated: number[];
+    removed: number[];
+}
+
+export interface PhotoCoreConfig {
+    thumbnail_dir: string;
+    thumbnail_size: number;
+}
+
+/** A photo's auto-enhance edit, see `PhotoIndex::set_enhance`. */
+export interface PhotoEnhance {
+    recipe: AutoEnhance;
+    /** Whether the photo is shown enhanced; the recipe is kept either way */
+    enabled: boolean;
+}
+
+export interface PhotoId {
+    id: number;
+}
+
+export interface PhotoInfo {
+    id: PhotoId;
+    path: string;
+    /** Perceptual hash, alike for photos that look alike (see `compute_hash`) */
+    hash: string;
+    /** BLAKE3 of the original's bytes, for exact duplicates and integrity checks. `None` until the file has been read in full. */
+    checksum?: string;
+    metadata: PhotoMetadata;
+    thumb_path?: string;
+    /** Size of the original as of its import or last rescan, 0 if unknown */
+    file_size: number;
+    created_at?: number;
+    modified_at?: number;
+    /** The original wasn't found by the last `PhotoIndex::verify` */
+    missing?: boolean;
+    /** The original's content changed without it being edited, found by `PhotoIndex::audit_originals` */
+    corrupt?: boolean;
+}
+
+/** Coordinates of a photo, used to undo a `set_location` batch. */
+export interface PhotoLocation {
+    id: number;
+    lat?: number;
+    lon?: number;
+}
+
+export interface PhotoMetadata {
+    make?: string;
+    model?: string;
+    /** Capture date as found in EXIF, kept for reference */
+    date_taken?: string;
+    /** `date_taken` parsed: local wall-clock seconds since 1970 (EXIF has no zone). `None` means "date unknown" - missing, or a placeholder like "0000:00:00 00:00:00". */
+    taken_at?: number;
+    /** Set when `taken_at` was entered by hand rather than read from the file: how much of it the user actually knows. */
+    date_precision?: DatePrecision;
+    width: number;
+    height: number;
+    lat?: number;
+    lon?: number;
+    iso?: number;
+    f_number?: number;
+    exposure_time?: string;
+    orientation: number;
+    /** User rotation on top of the EXIF orientation, degrees clockwise (0, 90, 180, 270). Non-destructive: the original file is never touched. */
+    rotation: number;
+    /** Motion photo with an embedded video clip (Android MVIMG / Samsung) */
+    has_motion: boolean;
+    /** Portrait-mode photo carrying depth data (Dynamic Depth / GDepth XMP) */
+    is_portrait: boolean;
+    /** Apple ProRAW DNG */
+    is_proraw: boolean;
+    /** Film or print scan (scanner make, no exposure data). `date_taken` is then the scan date and `taken_at` stays unset until dated by hand. */
+    is_scan: boolean;
+    /** Daylight at capture time: "day", "golden_hour", "blue_hour" or "night" */
+    light?: string;
+    /** Moon phase at capture time: 0.0 new, 0.5 full */
+    moon_phase?: number;
+    /** Embedded ICC profile description ("Display P3"), or "sRGB" when only EXIF declares it */
+    color_profile?: string;
+    /** Bits per channel of the original (JPEG and PNG) */
+    bit_depth?: number;
+    /** JPEG chroma subsampling, e.g. "4:2:0" */
+    chroma_subsampling?: string;
+    media_type?: MediaType;
+    /** Length of a video */
+    duration_ms?: number;
+    /** Video codec by its common name ("H.264", "HEVC", "Motion JPEG") */
+    video_codec?: string;
+    /** Caption, e.g. from a Google Takeout sidecar */
+    description?: string;
+}
+
+/** Which photos a view shows and in what order. Unset filters match everything. */
+export interface PhotoQuery {
+    place_id?: number;
+    weather_condition?: string;
+    light?: string;
+    /** Capture date range, inclusive, as `taken_at` values. Undated photos never match a range. */
+    taken_from?: number;
+    taken_to?: number;
+    /** "Make Model" as listed in `SelectionSummary::cameras` */
+    camera?: string;
+    has_gps?: boolean;
+    iso_min?: number;
+    iso_max?: number;
+    /** Shape as displayed: "landscape", "portrait" or "square" */
+    orientation?: string;
+    /** File extensions, case-insensitive ("jpg", "dng"); empty matches all */
+    file_types?: string[];
+    /** ICC profile description, case-insensitive ("display p3") */
+    color_profile?: string;
+    /** Only originals with at least this many bits per channel */
+    min_bit_depth?: number;
+    /** "4:4:4", "4:2:2", "4:2:0"... */
+    chroma_subsampling?: string;
+    is_scan?: boolean;
+    /** Photos whose original the last `verify` couldn't find */
+    missing?: boolean;
+    /** Photos whose original `audit_originals` found corrupted */
+    corrupt?: boolean;
+    /** Only photos, or only videos */
+    media_type?: MediaType;
+    /** Photos opened at least once (`true`) or never looked at (`false`) */
+    viewed?: boolean;
+    sort?: QuerySort;
+    /** Reverses `sort` (newest or largest first). Undated photos always come last when sorting by date. */
+    descending?: boolean;
+}
+
+/** Order of a page from `PhotoIndex::list_page`. Ties fall back to import order. */
+export type PhotoSort = 'date_taken' | 'date_taken_descending' | 'imported' | 'dimensions';
+
+/** How often a photo was opened, see `PhotoIndex::record_view`. */
+export interface PhotoViews {
+    view_count: number;
+    /** Unix time of the latest view, `None` if never viewed */
+    last_viewed_at?: number;
+}
+
+/** A user-named place ("Home", "Office"). A circle of `radius_m` around `lat`/`lon`, or a polygon when `polygon` is non-empty (the center is then only used for display). */
+export interface Place {
+    id: number;
+    name: string;
+    lat: number;
+    lon: number;
+    radius_m: number;
+    polygon: GeoPoint[];
+}
+
+/** Order of `PhotoIndex::query` results. Ties fall back to import order. */
+export type QuerySort = 'date_taken' | 'path' | 'file_size' | 'view_count';
+
+/** Outcome of `rescan`. */
+export interface RescanReport {
+    /** Files not indexed at their path before, now imported */
+    added: number;
+    /** Files whose size or modification time changed, re-read in place */
+    modified: number;
+    /** Indexed files that are gone, now flagged missing */
+    missing: number;
+    unchanged: number;
+    /** New or modified files that couldn't be read */
+    failed: number;
+}
+
+export interface ReviewCount {
+    name: string;
+    count: number;
+}
+
+/** A busy day of the year and the photo picked to stand for it. */
+export interface ReviewHighlight {
+    photo_id: number;
+    taken_at: number;
+    /** Photos taken that day */
+    day_count: number;
+}
+
+/** Aggregates over a selection of photos, for the UI footer. */
+export interface SelectionSummary {
+    count: number;
+    /** Size of the originals as last indexed or rescanned (unknown sizes count as 0) */
+    total_bytes: number;
+    /** Earliest and latest capture time, "2022:03:04 10:00:00", including dates set by hand */
+    date_start?: string;
+    date_end?: string;
+    /** Distinct "Make Model" names, sorted */
+    cameras: string[];
+    /** How many of the photos have GPS coordinates */
+    geotagged: number;
+}
+
+/** A photo that looks like another, and how much: the Hamming distance between their perceptual hashes (0 = identical, 64 = opposite). */
+export interface SimilarPhoto {
+    photo: PhotoInfo;
+    distance: number;
+}
+
+export interface StageTime {
+    stage: ImportStage;
+    /** Time spent in the stage per file, in milliseconds */
+    ms_per_file: number;
+    /** Share of the time spent on files, 0 to 1 */
+    share: number;
+}
+
+/** Outcome of a thumbnail integrity spot-check. */
+export interface ThumbnailVerifyReport {
+    checked: number;
+    /** Corrupt thumbnails that were regenerated */
+    repaired: number;
+    /** Corrupt thumbnails that could not be regenerated (removed from cache) */
+    failed: number;
+    /** Where the next spot-check should continue; 0 once the library wrapped around */
+    next_offset: number;
+}
+
+/** One stretch of a timeline scrubber: the dated photos of a day, month or year. */
+export interface TimelineBucket {
+    /** `taken_at` of the bucket's first second (midnight, the 1st, or January 1st) */
+    start: number;
+    count: number;
+    /** The bucket's earliest photo, to show as its thumbnail */
+    cover: PhotoInfo;
+}
+
+/** A photo in the trash, with when it was put there (unix seconds). */
+export interface TrashedPhoto {
+    photo: PhotoInfo;
+    trashed_at: number;
+}
+
+/** Undated photos that probably belong together - same folder, consecutive file numbers - with a date guessed from the dated photos either side. */
+export interface UndatedCluster {
+    /** Folder the photos are in, in index path form */
+    folder: string;
+    /** In file-number order */
+    photo_ids: number[];
+    /** Proposed `taken_at`, when a dated neighbor exists */
+    suggested_taken_at?: number;
+    /** How far the proposal can be trusted: the closer the neighbors' dates, the finer */
+    suggested_precision?: DatePrecision;
+}
+
+/** An error as the user should see it: a stable message key plus parameters, so frontends can show "Disk full while copying IMG_2012.CR3" in the user's language instead of raw IO jargon. Keys: `error.not_found`, `error.permission_denied`, `error.storage_full`, `error.decode`, `error.database`, `error.invalid_input`, `error.io`, `error.cache_unavailable`. Parameters: `file` (file name only, when known) and `detail` (the untranslated technical message, for the curious and for bug reports). */
+export interface UserMessage {
+    key: string;
+    params: Record<string, string>;
+}
+
+/** Historical weather at capture time, attached by an optional enrichment pass. */
+export interface Weather {
+    temperature_c?: number;
+    /** Normalized condition: clear, cloudy, fog, drizzle, rain, snow, thunderstorm */
+    condition: string;
+    /** Which provider supplied the data (e.g. "open-meteo") */
+    source: string;
+}
+
+/** A year of the library at a glance, for the shareable "year in review". */
+export interface YearInReview {
+    year: number;
+    photo_count: number;
+    /** Distinct days with at least one photo */
+    days_with_photos: number;
+    geotagged: number;
+    /** Photos per month, January first */
+    months: number[];
+    /** Saved places by photos taken there, most first */
+    top_places: ReviewCount[];
+    /** "Make Model" by photos, most first */
+    top_cameras: ReviewCount[];
+    /** One photo from each of the busiest days, in date order */
+    highlights: ReviewHighlight[];
+}
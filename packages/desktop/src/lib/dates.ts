@@ -0,0 +1,20 @@
+import type { PhotoMetadata } from "../types";
+
+/**
+ * Capture time as a local Date. The core stores wall-clock time as if it
+ * were UTC (EXIF has no zone), so the UTC fields are read back as local.
+ * Undated photos and placeholder dates give null.
+ */
+export function photoDate(metadata: PhotoMetadata | undefined | null): Date | null {
+    const takenAt = metadata?.taken_at;
+    if (takenAt == null) return null;
+    const utc = new Date(takenAt * 1000);
+    return new Date(
+        utc.getUTCFullYear(),
+        utc.getUTCMonth(),
+        utc.getUTCDate(),
+        utc.getUTCHours(),
+        utc.getUTCMinutes(),
+        utc.getUTCSeconds(),
+    );
+}
@@ -0,0 +1,27 @@
+import type { CommandError } from "../types";
+
+// English texts for error keys from the backend; other languages add their own table
+const MESSAGES: Record<string, string> = {
+    "error.not_found": "{file} could not be found",
+    "error.permission_denied": "No permission to access {file}",
+    "error.storage_full": "Disk full while writing {file}",
+    "error.decode": "{file} could not be read as an image",
+    "error.database": "The library database could not be updated",
+    "error.invalid_input": "Invalid request: {detail}",
+    "error.io": "Could not read or write {file}",
+    "error.cache_unavailable": "The thumbnail cache can't be written to. Choose another cache location in Settings",
+    "error.generic": "{detail}",
+};
+
+function isCommandError(e: unknown): e is CommandError {
+    return typeof e === "object" && e !== null && "key" in e && "params" in e;
+}
+
+/** Text to show for an error thrown by `invoke`: localized for keyed errors, as-is for plain strings. */
+export function errorMessage(e: unknown): string {
+    if (!isCommandError(e)) return String(e);
+    const template = MESSAGES[e.key];
+    if (!template) return e.fallback;
+    const params: Record<string, string> = { file: "the file", ...e.params };
+    return template.replace(/\{(\w+)\}/g, (_, name) => params[name] ?? "");
+}
@@ -0,0 +1,18 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { PhotoInfo } from "../types";
+
+/**
+ * Geotagged photos inside a map viewport, e.g. from maplibre's `getBounds()`.
+ * Longitudes are wrapped into -180..180, so a view across the antimeridian
+ * arrives with west greater than east, which the index handles.
+ */
+export async function photosInBounds(south: number, west: number, north: number, east: number): Promise<PhotoInfo[]> {
+    const wrap = (lon: number) => (lon >= -180 && lon <= 180 ? lon : ((((lon + 180) % 360) + 360) % 360) - 180);
+    const wholeWorld = east - west >= 360;
+    return invoke("list_photos_in_bounds", {
+        minLat: south,
+        minLon: wholeWorld ? -180 : wrap(west),
+        maxLat: north,
+        maxLon: wholeWorld ? 180 : wrap(east),
+    });
+}
@@ -0,0 +1,35 @@
+import type { ImportOptions } from "../types";
+
+const IMPORT_FILTERS_KEY = "fotos-import-filters";
+
+/** The filters set in settings for folder imports, sent as `import_photos`' `filters`. */
+export type ImportFilters = Pick<ImportOptions, "extensions" | "recursive" | "taken_after" | "taken_before" | "min_file_size">;
+
+export function loadImportFilters(): ImportFilters {
+    if (typeof localStorage === "undefined") return {};
+    try {
+        return JSON.parse(localStorage.getItem(IMPORT_FILTERS_KEY) ?? "{}");
+    } catch {
+        return {};
+    }
+}
+
+export function saveImportFilters(filters: ImportFilters) {
+    if (typeof localStorage !== "undefined") {
+        localStorage.setItem(IMPORT_FILTERS_KEY, JSON.stringify(filters));
+    }
+}
+
+/**
+ * A "YYYY-MM-DD" day as the core's wall-clock seconds (see `photoDate`),
+ * or undefined for an empty input.
+ */
+export function dayToTakenAt(day: string): number | undefined {
+    if (!day) return undefined;
+    const [year, month, date] = day.split("-").map(Number);
+    return Date.UTC(year, month - 1, date) / 1000;
+}
+
+export function takenAtToDay(takenAt: number | undefined | null): string {
+    return takenAt == null ? "" : new Date(takenAt * 1000).toISOString().slice(0, 10);
+}
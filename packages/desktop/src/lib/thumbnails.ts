@@ -0,0 +1,15 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/**
+ * Which photos with ids `firstId..firstId + count` have a cached thumbnail,
+ * fetched as one bitmap. The returned lookup is false outside the range, so
+ * the grid can show a placeholder without touching the thumbnail path.
+ */
+export async function thumbnailsReady(firstId: number, count: number): Promise<(id: number) => boolean> {
+    const buffer: ArrayBuffer = await invoke("thumbnail_ready_map", { firstId, count });
+    const bits = new Uint8Array(buffer);
+    return (id) => {
+        const i = id - firstId;
+        return i >= 0 && i < count && (bits[i >> 3] & (1 << (i & 7))) !== 0;
+    };
+}
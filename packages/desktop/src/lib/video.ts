@@ -0,0 +1,15 @@
+import type { PhotoMetadata } from "../types";
+
+export function isVideo(metadata: PhotoMetadata | undefined | null): boolean {
+    return metadata?.media_type === "video";
+}
+
+/** A video's length as "m:ss", or "h:mm:ss" from an hour up. */
+export function formatDuration(ms: number | null | undefined): string {
+    if (ms == null) return "";
+    const total = Math.round(ms / 1000);
+    const seconds = String(total % 60).padStart(2, "0");
+    const minutes = Math.floor(total / 60) % 60;
+    const hours = Math.floor(total / 3600);
+    return hours > 0 ? `${hours}:${String(minutes).padStart(2, "0")}:${seconds}` : `${minutes}:${seconds}`;
+}
@@ -0,0 +1,46 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/** Grid cells on screen, by photo id */
+const visible = new Set<number>();
+let observer: IntersectionObserver | undefined;
+let timer: ReturnType<typeof setTimeout> | undefined;
+
+/** Reports the visible photos once scrolling settles, so background thumbnailing does them first */
+function report() {
+    clearTimeout(timer);
+    timer = setTimeout(() => {
+        invoke("hint_visible_range", { ids: [...visible] }).catch(() => {});
+    }, 150);
+}
+
+/**
+ * Svelte action for a grid cell showing photo `id`: tracks whether it's on
+ * screen and keeps the backend's viewport hint current.
+ */
+export function trackVisible(node: HTMLElement, id: number) {
+    observer ??= new IntersectionObserver((entries) => {
+        for (const entry of entries) {
+            const photoId = Number((entry.target as HTMLElement).dataset.photoId);
+            if (entry.isIntersecting) {
+                visible.add(photoId);
+            } else {
+                visible.delete(photoId);
+            }
+        }
+        report();
+    });
+    node.dataset.photoId = String(id);
+    observer.observe(node);
+
+    return {
+        update(newId: number) {
+            visible.delete(Number(node.dataset.photoId));
+            node.dataset.photoId = String(newId);
+        },
+        destroy() {
+            observer?.unobserve(node);
+            visible.delete(Number(node.dataset.photoId));
+            report();
+        },
+    };
+}
@@ -1,31 +1,72 @@
-export interface PhotoId {
-    id: number;
-}
+// Types serialized by fotos-core are generated from the Rust definitions into
+// ./generated/core.ts (see packages/core/src/typescript.rs). Only the shapes
+// the desktop backend defines itself are written by hand here.
+import type { CacheUnavailable, ImportSession, PhotoInfo as CorePhotoInfo, UserMessage } from "./generated/core";
 
-export interface PhotoMetadata {
-    width: number;
-    height: number;
-    date_taken?: string;
-    iso?: number;
-    f_number?: number;
-    exposure_time?: string;
-    make?: string;
-    model?: string;
-    lat?: number;
-    lon?: number;
-}
+export type * from "./generated/core";
 
-export interface PhotoInfo {
-    id: PhotoId;
-    path: string;
-    hash: string;
-    metadata: PhotoMetadata;
-    thumb_path?: string;
-    file_size: number;
-    created_at?: number;
-    modified_at?: number;
+export interface PhotoInfo extends CorePhotoInfo {
     // For grouped RAW+JPEG pairs
     rawPath?: string;
     hasRaw?: boolean;
     isRawOnly?: boolean;
 }
+
+export interface StorageBreakdown {
+    originals: number;
+    thumbnails: number;
+    raw_previews: number;
+    enhanced_previews: number;
+    motion_videos: number;
+    tiles: number;
+    database: number;
+    backups: number;
+}
+
+/** Error thrown by commands whose failures are shown to the user */
+export interface CommandError extends UserMessage {
+    fallback: string;
+}
+
+/** Payload of the `import-progress` event; the byte counts and ETA only come from folder imports */
+export interface ImportProgress {
+    current: number;
+    total: number;
+    success: number;
+    failure: number;
+    skipped?: number;
+    last_path: string;
+    last_error?: CommandError;
+    bytes_processed?: number;
+    total_bytes?: number;
+    eta_seconds?: number;
+}
+
+export interface ClearInfo {
+    bytes: number;
+    consequence: string;
+}
+
+export interface LibraryPaths {
+    db_path: string;
+    thumb_dir: string;
+    preview_dir: string;
+    pasted_dir: string;
+    tile_dir: string;
+    backup_dir: string;
+    temp_dir: string;
+    /** Set when no cache location can be written to; thumbnails aren't saved */
+    cache_unavailable?: CacheUnavailable;
+    using_fallback_cache: boolean;
+}
+
+/** Payload of the `startup-report` event, the quick health check at launch */
+export interface StartupReport {
+    /** What SQLite's quick_check found; empty when the database is healthy */
+    database_problems: string[];
+    cache_unavailable?: CacheUnavailable;
+    /** Temp files an earlier run left behind, already cleared out */
+    temp_swept: number;
+    unfinished_imports: ImportSession[];
+    duration_ms: number;
+}